@@ -6,4 +6,6 @@
 // for full license information.
 
 pub use buttplug_client::*;
+#[cfg(feature = "usb-manager")]
+pub use buttplug_server_hwmgr_usb::*;
 pub use buttplug_transport_websocket_tungstenite::*;
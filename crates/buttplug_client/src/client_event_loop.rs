@@ -28,9 +28,12 @@ use buttplug_core::{
 };
 use dashmap::DashMap;
 use log::*;
-use std::sync::{
-  Arc,
-  atomic::{AtomicBool, Ordering},
+use std::{
+  collections::HashSet,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
 };
 use tokio::{
   select,
@@ -99,6 +102,9 @@ where
   /// Receives incoming messages from client instances.
   from_client_receiver: mpsc::Receiver<ButtplugClientRequest>,
   sorter: ClientMessageSorter,
+  /// Indexes of devices the server reconnected, which should be reported as reconnected instead
+  /// of added once the next device list arrives.
+  reconnecting_devices: HashSet<u32>,
 }
 
 impl<ConnectorType> ButtplugClientEventLoop<ConnectorType>
@@ -129,6 +135,7 @@ where
       from_connector_receiver,
       connector,
       sorter: ClientMessageSorter::default(),
+      reconnecting_devices: HashSet::new(),
     }
   }
 
@@ -217,10 +224,15 @@ where
           if self.device_map.contains_key(&dev.1.device_index()) {
             continue;
           }
-          trace!("Device added, updating map and sending to client");
           let info = dev.1.clone();
           let device = self.create_client_device(&info);
-          self.send_client_event(ButtplugClientEvent::DeviceAdded(device));
+          if self.reconnecting_devices.remove(&info.device_index()) {
+            trace!("Device reconnected, updating map and sending to client");
+            self.send_client_event(ButtplugClientEvent::DeviceReconnected(device));
+          } else {
+            trace!("Device added, updating map and sending to client");
+            self.send_client_event(ButtplugClientEvent::DeviceAdded(device));
+          }
         }
         let new_indexes: Vec<u32> = list.devices().iter().map(|x| x.1.device_index()).collect();
         let disconnected_indexes: Vec<u32> = self
@@ -248,6 +260,33 @@ where
             ));
         }
       }
+      ButtplugServerMessageV4::EmergencyStopState(msg) => {
+        trace!("Emergency stop state event received, forwarding to client.");
+        self.send_client_event(ButtplugClientEvent::EmergencyStopStateChanged(
+          msg.engaged(),
+        ));
+      }
+      ButtplugServerMessageV4::DeviceReconnected(msg) => {
+        trace!("Device reconnection received, waiting for device list.");
+        self.reconnecting_devices.insert(msg.device_index());
+      }
+      ButtplugServerMessageV4::DeviceConnectionFailed(msg) => {
+        trace!("Device connection failure received, forwarding to client.");
+        self.send_client_event(ButtplugClientEvent::DeviceConnectionFailed {
+          name: msg.device_name().clone(),
+          address: msg.device_address().clone(),
+          protocols: msg.protocols().clone(),
+          error_code: msg.error_code(),
+          error_message: msg.error_message().clone(),
+        });
+      }
+      ButtplugServerMessageV4::HardwareManagerStatus(msg) => {
+        trace!("Hardware manager status received, forwarding to client.");
+        self.send_client_event(ButtplugClientEvent::HardwareManagerStatusChanged {
+          name: msg.manager_name().clone(),
+          status: msg.status(),
+        });
+      }
       ButtplugServerMessageV4::Error(e) => {
         self.send_client_event(ButtplugClientEvent::Error(e.into()));
       }
@@ -14,7 +14,16 @@ use crate::{ButtplugClientMessageSender, ButtplugClientResultFuture, device::Cli
 use buttplug_core::message::{InputType, InputTypeReading};
 use buttplug_core::{
   errors::ButtplugDeviceError,
-  message::{ButtplugServerMessageV4, DeviceFeature, DeviceMessageInfoV4, OutputType, StopCmdV4},
+  message::{
+    ApplyPresetCmdV4,
+    ButtplugServerMessageV4,
+    DeviceFeature,
+    DeviceMessageInfoV4,
+    OutputType,
+    PresetOutputV4,
+    StopCmdV4,
+    StorePresetCmdV4,
+  },
   util::stream::convert_broadcast_receiver_to_stream,
 };
 use futures::{FutureExt, Stream, future};
@@ -311,6 +320,22 @@ impl ButtplugClientDevice {
       .send_message_expect_ok(StopCmdV4::new(Some(self.index), None, inputs, outputs).into())
   }
 
+  /// Sets the device's outputs to one of the named presets in its user configuration on the
+  /// server.
+  pub fn apply_preset(&self, name: &str) -> ButtplugClientResultFuture {
+    self
+      .event_loop_sender
+      .send_message_expect_ok(ApplyPresetCmdV4::new(self.index, name).into())
+  }
+
+  /// Stores a named preset in the device's user configuration on the server, replacing any preset
+  /// with the same name. Values use the same step ranges as output commands.
+  pub fn store_preset(&self, name: &str, outputs: &[PresetOutputV4]) -> ButtplugClientResultFuture {
+    self
+      .event_loop_sender
+      .send_message_expect_ok(StorePresetCmdV4::new(self.index, name, outputs).into())
+  }
+
   pub(crate) fn set_device_connected(&self, connected: bool) {
     self.device_connected.store(connected, Ordering::Relaxed);
   }
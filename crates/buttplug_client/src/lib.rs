@@ -24,6 +24,8 @@ use buttplug_core::{
     BUTTPLUG_CURRENT_API_MINOR_VERSION,
     ButtplugClientMessageV4,
     ButtplugServerMessageV4,
+    DeviceConnectionErrorCode,
+    HardwareManagerStatus,
     InputType,
     PingV0,
     RequestDeviceListV0,
@@ -134,6 +136,9 @@ pub enum ButtplugClientEvent {
   /// Emitted when a device has been removed from the server. Includes a
   /// [ButtplugClientDevice] object representing the device.
   DeviceRemoved(ButtplugClientDevice),
+  /// Emitted instead of [DeviceAdded](ButtplugClientEvent::DeviceAdded) when the server
+  /// automatically reconnected a device that had been removed. The device keeps its old index.
+  DeviceReconnected(ButtplugClientDevice),
   /// Emitted when a client has not pinged the server in a sufficient amount of
   /// time.
   PingTimeout,
@@ -141,6 +146,24 @@ pub enum ButtplugClientEvent {
   ServerConnect,
   /// Emitted when a client connector detects that the server has disconnected.
   ServerDisconnect,
+  /// Emitted when the server emergency stop is engaged (true) or reset (false). While engaged, the
+  /// server rejects all output commands.
+  EmergencyStopStateChanged(bool),
+  /// Emitted when the server found a device but could not connect to it, along with the protocols
+  /// it tried and why connecting failed.
+  DeviceConnectionFailed {
+    name: String,
+    address: String,
+    protocols: Vec<String>,
+    error_code: DeviceConnectionErrorCode,
+    error_message: String,
+  },
+  /// Emitted when one of the server's hardware managers (Bluetooth, HID, serial, etc...) changes
+  /// status, and for every hardware manager when scanning starts.
+  HardwareManagerStatusChanged {
+    name: String,
+    status: HardwareManagerStatus,
+  },
   /// Emitted when an error that cannot be matched to a request is received from
   /// the server.
   Error(ButtplugError),
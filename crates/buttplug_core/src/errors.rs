@@ -172,6 +172,10 @@ pub enum ButtplugDeviceError {
   ProtocolInputNotSupported(InputType),
   /// Device does not support {0}
   OutputNotSupported(OutputType),
+  /// Emergency stop is engaged, output commands are rejected until it is reset
+  EmergencyStopEngaged,
+  /// Device {0} feature {1} is locked by another client
+  DeviceFeatureLocked(u32, u32),
 }
 
 /// Unknown errors occur in exceptional circumstances where no other error type
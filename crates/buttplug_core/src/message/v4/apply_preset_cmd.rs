@@ -0,0 +1,62 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use crate::message::{
+  ButtplugDeviceMessage,
+  ButtplugMessage,
+  ButtplugMessageError,
+  ButtplugMessageValidator,
+};
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+/// Sets a device's outputs to one of the named presets in its user configuration.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct ApplyPresetCmdV4 {
+  #[serde(rename = "Id")]
+  id: u32,
+  #[serde(rename = "DeviceIndex")]
+  #[getset(get_copy = "pub")]
+  device_index: u32,
+  #[serde(rename = "PresetName")]
+  #[getset(get = "pub")]
+  preset_name: String,
+}
+
+impl ApplyPresetCmdV4 {
+  pub fn new(device_index: u32, preset_name: &str) -> Self {
+    Self {
+      id: 1,
+      device_index,
+      preset_name: preset_name.to_owned(),
+    }
+  }
+}
+
+impl ButtplugMessage for ApplyPresetCmdV4 {
+  fn id(&self) -> u32 {
+    self.id
+  }
+  fn set_id(&mut self, id: u32) {
+    self.id = id;
+  }
+}
+
+impl ButtplugDeviceMessage for ApplyPresetCmdV4 {
+  fn device_index(&self) -> u32 {
+    self.device_index
+  }
+  fn set_device_index(&mut self, device_index: u32) {
+    self.device_index = device_index;
+  }
+}
+
+impl ButtplugMessageValidator for ApplyPresetCmdV4 {
+  fn is_valid(&self) -> Result<(), ButtplugMessageError> {
+    self.is_not_system_id(self.id)
+  }
+}
@@ -0,0 +1,161 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use crate::{
+  errors::ButtplugDeviceError,
+  message::{ButtplugMessage, ButtplugMessageError, ButtplugMessageValidator},
+};
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+/// Broad reason a device connection failed, so clients can react to specific failures without
+/// parsing the error message.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceConnectionErrorCode {
+  /// The failure doesn't fit any of the other codes.
+  #[default]
+  Unknown,
+  /// The hardware could not be connected to, or stopped responding while connecting.
+  Connection,
+  /// The operating system denied access to the hardware.
+  Permission,
+  /// The hardware connected, but protocol identification or initialization failed.
+  Protocol,
+  /// The device configuration for the hardware is invalid.
+  Configuration,
+}
+
+impl From<&ButtplugDeviceError> for DeviceConnectionErrorCode {
+  fn from(error: &ButtplugDeviceError) -> Self {
+    match error {
+      ButtplugDeviceError::DeviceConnectionError(_)
+      | ButtplugDeviceError::DeviceCommunicationError(_)
+      | ButtplugDeviceError::DeviceNotConnected(_)
+      | ButtplugDeviceError::InvalidEndpoint(_) => Self::Connection,
+      ButtplugDeviceError::DevicePermissionError(_) => Self::Permission,
+      ButtplugDeviceError::ProtocolAttributesNotFound(_)
+      | ButtplugDeviceError::ProtocolNotImplemented(_)
+      | ButtplugDeviceError::ProtocolSpecificError(..)
+      | ButtplugDeviceError::ProtocolRequirementError(_)
+      | ButtplugDeviceError::DeviceSpecificError(_)
+      | ButtplugDeviceError::UnhandledCommand(_) => Self::Protocol,
+      ButtplugDeviceError::DeviceConfigurationError(_) => Self::Configuration,
+      _ => Self::Unknown,
+    }
+  }
+}
+
+/// Sent to all clients when the server found a device it has a configuration for, but could not
+/// connect to or initialize it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Getters, CopyGetters, Serialize, Deserialize)]
+pub struct DeviceConnectionFailedV4 {
+  #[serde(rename = "Id")]
+  id: u32,
+  #[serde(rename = "DeviceName")]
+  #[getset(get = "pub")]
+  device_name: String,
+  #[serde(rename = "DeviceAddress")]
+  #[getset(get = "pub")]
+  device_address: String,
+  /// Protocols the server tried to use for the device.
+  #[serde(rename = "Protocols")]
+  #[getset(get = "pub")]
+  protocols: Vec<String>,
+  #[serde(rename = "ErrorCode")]
+  #[getset(get_copy = "pub")]
+  error_code: DeviceConnectionErrorCode,
+  /// Human readable description of the failure.
+  #[serde(rename = "ErrorMessage")]
+  #[getset(get = "pub")]
+  error_message: String,
+}
+
+/// Readable description of a connection failure. `ButtplugDeviceError`'s `Display` only prints the
+/// variant name, so the failures that can happen while connecting get spelled out here.
+fn connection_error_message(error: &ButtplugDeviceError) -> String {
+  match error {
+    ButtplugDeviceError::DeviceConnectionError(msg) => format!("Device connection error: {msg}"),
+    ButtplugDeviceError::DeviceCommunicationError(msg) => {
+      format!("Device communication error: {msg}")
+    }
+    ButtplugDeviceError::DeviceNotConnected(msg) => format!("Device {msg} not connected"),
+    ButtplugDeviceError::InvalidEndpoint(endpoint) => {
+      format!("Device does not have endpoint {endpoint}")
+    }
+    ButtplugDeviceError::DevicePermissionError(msg) => format!("Device permission error: {msg}"),
+    ButtplugDeviceError::ProtocolNotImplemented(protocol) => {
+      format!("Protocol {protocol} not implemented in library")
+    }
+    ButtplugDeviceError::ProtocolSpecificError(protocol, msg) => {
+      format!("{protocol} protocol specific error: {msg}")
+    }
+    ButtplugDeviceError::DeviceSpecificError(msg) => format!("Device type specific error: {msg}"),
+    ButtplugDeviceError::UnhandledCommand(msg) => {
+      format!("Device does not handle command type: {msg}")
+    }
+    ButtplugDeviceError::DeviceConfigurationError(msg) => {
+      format!("Device configuration error: {msg}")
+    }
+    ButtplugDeviceError::ProtocolAttributesNotFound(msg)
+    | ButtplugDeviceError::ProtocolRequirementError(msg) => msg.clone(),
+    error => format!("{error:?}"),
+  }
+}
+
+impl DeviceConnectionFailedV4 {
+  pub fn new(
+    device_name: &str,
+    device_address: &str,
+    protocols: &[String],
+    error: &ButtplugDeviceError,
+  ) -> Self {
+    Self {
+      id: 0,
+      device_name: device_name.to_owned(),
+      device_address: device_address.to_owned(),
+      protocols: protocols.to_vec(),
+      error_code: error.into(),
+      error_message: connection_error_message(error),
+    }
+  }
+}
+
+impl ButtplugMessage for DeviceConnectionFailedV4 {
+  fn id(&self) -> u32 {
+    self.id
+  }
+  fn set_id(&mut self, id: u32) {
+    self.id = id;
+  }
+}
+
+impl ButtplugMessageValidator for DeviceConnectionFailedV4 {
+  fn is_valid(&self) -> Result<(), ButtplugMessageError> {
+    self.is_system_id(self.id)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::message::ButtplugServerMessageV4;
+
+  #[test]
+  fn test_device_connection_failed_serialize() {
+    let msg = ButtplugServerMessageV4::DeviceConnectionFailed(DeviceConnectionFailedV4::new(
+      "Test Device",
+      "test-address",
+      &["lovense".to_owned()],
+      &ButtplugDeviceError::DevicePermissionError("Access denied".to_owned()),
+    ));
+    let js = serde_json::to_string(&msg).expect("Infallible serialization.");
+    assert_eq!(
+      js,
+      "{\"DeviceConnectionFailed\":{\"Id\":0,\"DeviceName\":\"Test Device\",\"DeviceAddress\":\"test-address\",\"Protocols\":[\"lovense\"],\"ErrorCode\":\"Permission\",\"ErrorMessage\":\"Device permission error: Access denied\"}}"
+    );
+  }
+}
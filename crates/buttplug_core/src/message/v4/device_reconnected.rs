@@ -0,0 +1,46 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use crate::message::{ButtplugMessage, ButtplugMessageError, ButtplugMessageValidator};
+use getset::CopyGetters;
+use serde::{Deserialize, Serialize};
+
+/// Sent to all clients when a device that dropped out was automatically reconnected, right before
+/// the [DeviceList](crate::message::DeviceListV4) that includes it again. The device keeps the
+/// index it had before disconnecting.
+#[derive(Debug, Default, Clone, PartialEq, Eq, CopyGetters, Serialize, Deserialize)]
+pub struct DeviceReconnectedV4 {
+  #[serde(rename = "Id")]
+  id: u32,
+  #[serde(rename = "DeviceIndex")]
+  #[getset(get_copy = "pub")]
+  device_index: u32,
+}
+
+impl DeviceReconnectedV4 {
+  pub fn new(device_index: u32) -> Self {
+    Self {
+      id: 0,
+      device_index,
+    }
+  }
+}
+
+impl ButtplugMessage for DeviceReconnectedV4 {
+  fn id(&self) -> u32 {
+    self.id
+  }
+  fn set_id(&mut self, id: u32) {
+    self.id = id;
+  }
+}
+
+impl ButtplugMessageValidator for DeviceReconnectedV4 {
+  fn is_valid(&self) -> Result<(), ButtplugMessageError> {
+    self.is_system_id(self.id)
+  }
+}
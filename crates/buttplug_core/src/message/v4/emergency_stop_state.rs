@@ -0,0 +1,42 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use crate::message::{ButtplugMessage, ButtplugMessageError, ButtplugMessageValidator};
+use getset::CopyGetters;
+use serde::{Deserialize, Serialize};
+
+/// Sent to all clients whenever the server emergency stop is engaged or reset. While engaged, all
+/// output commands are rejected.
+#[derive(Debug, Default, Clone, PartialEq, Eq, CopyGetters, Serialize, Deserialize)]
+pub struct EmergencyStopStateV4 {
+  #[serde(rename = "Id")]
+  id: u32,
+  #[serde(rename = "Engaged")]
+  #[getset(get_copy = "pub")]
+  engaged: bool,
+}
+
+impl EmergencyStopStateV4 {
+  pub fn new(engaged: bool) -> Self {
+    Self { id: 0, engaged }
+  }
+}
+
+impl ButtplugMessage for EmergencyStopStateV4 {
+  fn id(&self) -> u32 {
+    self.id
+  }
+  fn set_id(&mut self, id: u32) {
+    self.id = id;
+  }
+}
+
+impl ButtplugMessageValidator for EmergencyStopStateV4 {
+  fn is_valid(&self) -> Result<(), ButtplugMessageError> {
+    self.is_system_id(self.id)
+  }
+}
@@ -0,0 +1,87 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use crate::message::{ButtplugMessage, ButtplugMessageError, ButtplugMessageValidator};
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+/// Whether one of the server's hardware managers (Bluetooth, HID, serial, etc...) can currently be
+/// used to find devices.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HardwareManagerStatus {
+  /// Ready to scan, but not currently scanning.
+  Available,
+  /// Cannot find devices right now, e.g. the Bluetooth adapter is missing or a port is busy.
+  Unavailable(String),
+  /// Currently scanning for devices.
+  Scanning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum HardwareManagerState {
+  Available,
+  Unavailable,
+  Scanning,
+}
+
+/// Sent to all clients whenever the status of one of the server's hardware managers changes, and
+/// for every hardware manager whenever scanning starts.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+pub struct HardwareManagerStatusV4 {
+  #[serde(rename = "Id")]
+  id: u32,
+  #[serde(rename = "ManagerName")]
+  #[getset(get = "pub")]
+  manager_name: String,
+  #[serde(rename = "Status")]
+  status: HardwareManagerState,
+  #[serde(rename = "Reason", default, skip_serializing_if = "Option::is_none")]
+  reason: Option<String>,
+}
+
+impl HardwareManagerStatusV4 {
+  pub fn new(manager_name: &str, status: &HardwareManagerStatus) -> Self {
+    let (status, reason) = match status {
+      HardwareManagerStatus::Available => (HardwareManagerState::Available, None),
+      HardwareManagerStatus::Unavailable(reason) => {
+        (HardwareManagerState::Unavailable, Some(reason.clone()))
+      }
+      HardwareManagerStatus::Scanning => (HardwareManagerState::Scanning, None),
+    };
+    Self {
+      id: 0,
+      manager_name: manager_name.to_owned(),
+      status,
+      reason,
+    }
+  }
+
+  pub fn status(&self) -> HardwareManagerStatus {
+    match self.status {
+      HardwareManagerState::Available => HardwareManagerStatus::Available,
+      HardwareManagerState::Unavailable => {
+        HardwareManagerStatus::Unavailable(self.reason.clone().unwrap_or_default())
+      }
+      HardwareManagerState::Scanning => HardwareManagerStatus::Scanning,
+    }
+  }
+}
+
+impl ButtplugMessage for HardwareManagerStatusV4 {
+  fn id(&self) -> u32 {
+    self.id
+  }
+  fn set_id(&mut self, id: u32) {
+    self.id = id;
+  }
+}
+
+impl ButtplugMessageValidator for HardwareManagerStatusV4 {
+  fn is_valid(&self) -> Result<(), ButtplugMessageError> {
+    self.is_system_id(self.id)
+  }
+}
@@ -5,8 +5,13 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
+mod apply_preset_cmd;
+mod device_connection_failed;
 mod device_list;
 mod device_message_info;
+mod device_reconnected;
+mod emergency_stop_state;
+mod hardware_manager_status;
 mod input_cmd;
 mod input_reading;
 mod output_cmd;
@@ -14,10 +19,16 @@ mod request_server_info;
 mod server_info;
 mod spec_enums;
 mod stop_cmd;
+mod store_preset_cmd;
 
 pub use {
+  apply_preset_cmd::ApplyPresetCmdV4,
+  device_connection_failed::{DeviceConnectionErrorCode, DeviceConnectionFailedV4},
   device_list::DeviceListV4,
   device_message_info::DeviceMessageInfoV4,
+  device_reconnected::DeviceReconnectedV4,
+  emergency_stop_state::EmergencyStopStateV4,
+  hardware_manager_status::{HardwareManagerStatus, HardwareManagerStatusV4},
   input_cmd::{InputCmdV4, InputCommandType},
   input_reading::{InputReadingV4, InputTypeReading, InputValue},
   output_cmd::{OutputCmdV4, OutputCommand, OutputHwPositionWithDuration, OutputValue},
@@ -25,4 +36,5 @@ pub use {
   server_info::ServerInfoV4,
   spec_enums::{ButtplugClientMessageV4, ButtplugDeviceMessageNameV4, ButtplugServerMessageV4},
   stop_cmd::StopCmdV4,
+  store_preset_cmd::{PresetOutputV4, StorePresetCmdV4},
 };
@@ -23,7 +23,16 @@ use crate::message::{
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
 
-use super::{DeviceListV4, InputReadingV4};
+use super::{
+  ApplyPresetCmdV4,
+  DeviceConnectionFailedV4,
+  DeviceListV4,
+  DeviceReconnectedV4,
+  EmergencyStopStateV4,
+  HardwareManagerStatusV4,
+  InputReadingV4,
+  StorePresetCmdV4,
+};
 
 /// Represents all client-to-server messages in v4 of the Buttplug Spec
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -40,6 +49,9 @@ pub enum ButtplugClientMessageV4 {
   StopCmd(StopCmdV4),
   OutputCmd(OutputCmdV4),
   InputCmd(InputCmdV4),
+  // Preset commands
+  ApplyPresetCmd(ApplyPresetCmdV4),
+  StorePresetCmd(StorePresetCmdV4),
 }
 
 impl ButtplugMessageFinalizer for ButtplugClientMessageV4 {
@@ -57,8 +69,13 @@ pub enum ButtplugServerMessageV4 {
   // Device enumeration messages
   DeviceList(DeviceListV4),
   ScanningFinished(ScanningFinishedV0),
+  DeviceConnectionFailed(DeviceConnectionFailedV4),
+  DeviceReconnected(DeviceReconnectedV4),
   // Sensor commands
   InputReading(InputReadingV4),
+  // Server status messages
+  EmergencyStopState(EmergencyStopStateV4),
+  HardwareManagerStatus(HardwareManagerStatusV4),
 }
 
 impl ButtplugMessageFinalizer for ButtplugServerMessageV4 {
@@ -0,0 +1,115 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use crate::message::{
+  ButtplugDeviceMessage,
+  ButtplugMessage,
+  ButtplugMessageError,
+  ButtplugMessageValidator,
+  OutputType,
+};
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+/// Level for one feature output in a preset, using the same step range as OutputCmd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct PresetOutputV4 {
+  #[serde(rename = "FeatureIndex")]
+  feature_index: u32,
+  #[serde(rename = "OutputType")]
+  output_type: OutputType,
+  #[serde(rename = "Value")]
+  value: i32,
+}
+
+impl PresetOutputV4 {
+  pub fn new(feature_index: u32, output_type: OutputType, value: i32) -> Self {
+    Self {
+      feature_index,
+      output_type,
+      value,
+    }
+  }
+}
+
+/// Adds or replaces a named preset in a device's user configuration.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct StorePresetCmdV4 {
+  #[serde(rename = "Id")]
+  id: u32,
+  #[serde(rename = "DeviceIndex")]
+  #[getset(get_copy = "pub")]
+  device_index: u32,
+  #[serde(rename = "PresetName")]
+  #[getset(get = "pub")]
+  preset_name: String,
+  #[serde(rename = "Outputs")]
+  #[getset(get = "pub")]
+  outputs: Vec<PresetOutputV4>,
+}
+
+impl StorePresetCmdV4 {
+  pub fn new(device_index: u32, preset_name: &str, outputs: &[PresetOutputV4]) -> Self {
+    Self {
+      id: 1,
+      device_index,
+      preset_name: preset_name.to_owned(),
+      outputs: outputs.to_vec(),
+    }
+  }
+}
+
+impl ButtplugMessage for StorePresetCmdV4 {
+  fn id(&self) -> u32 {
+    self.id
+  }
+  fn set_id(&mut self, id: u32) {
+    self.id = id;
+  }
+}
+
+impl ButtplugDeviceMessage for StorePresetCmdV4 {
+  fn device_index(&self) -> u32 {
+    self.device_index
+  }
+  fn set_device_index(&mut self, device_index: u32) {
+    self.device_index = device_index;
+  }
+}
+
+impl ButtplugMessageValidator for StorePresetCmdV4 {
+  fn is_valid(&self) -> Result<(), ButtplugMessageError> {
+    self.is_not_system_id(self.id)?;
+    if self.preset_name.is_empty() {
+      return Err(ButtplugMessageError::InvalidMessageContents(
+        "Preset names cannot be empty".to_owned(),
+      ));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::message::ButtplugClientMessageV4;
+
+  #[test]
+  fn test_store_preset_cmd_serialize() {
+    let msg = ButtplugClientMessageV4::StorePresetCmd(StorePresetCmdV4::new(
+      0,
+      "Low",
+      &[PresetOutputV4::new(1, OutputType::Vibrate, 5)],
+    ));
+    let js = serde_json::to_string(&msg).expect("Infallible serialization.");
+    assert_eq!(
+      js,
+      "{\"StorePresetCmd\":{\"Id\":1,\"DeviceIndex\":0,\"PresetName\":\"Low\",\"Outputs\":[{\"FeatureIndex\":1,\"OutputType\":\"Vibrate\",\"Value\":5}]}}"
+    );
+  }
+}
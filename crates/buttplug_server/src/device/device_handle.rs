@@ -10,7 +10,11 @@
 //! DeviceHandle provides the interface for sending commands to devices.
 //! It owns the device state directly and handles all command processing.
 
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use std::{
+  collections::BTreeMap,
+  sync::Arc,
+  time::{Duration, Instant},
+};
 
 use buttplug_core::{
   ButtplugResultFuture,
@@ -23,20 +27,24 @@ use buttplug_core::{
     DeviceMessageInfoV4,
     InputCommandType,
     InputType,
+    OutputCmdV4,
+    OutputCommand,
     OutputType,
     OutputValue,
     StopCmdV4,
   },
-  util::stream::convert_broadcast_receiver_to_stream,
+  util::{async_manager, stream::convert_broadcast_receiver_to_stream},
 };
 use buttplug_server_device_config::{
   DeviceConfigurationManager,
+  DevicePreset,
   ServerDeviceDefinition,
   UserDeviceIdentifier,
 };
 use dashmap::DashMap;
 use futures::future::{self, BoxFuture, FutureExt};
 use tokio::sync::{
+  broadcast,
   mpsc::{Sender, channel},
   oneshot,
 };
@@ -47,18 +55,24 @@ use crate::{
   ButtplugServerResultFuture,
   message::{
     ButtplugServerDeviceMessage,
+    TryFromDeviceAttributes,
     checked_input_cmd::CheckedInputCmdV4,
     checked_output_cmd::CheckedOutputCmdV4,
     server_device_attributes::ServerDeviceAttributes,
     spec_enums::ButtplugDeviceCommandMessageUnionV4,
   },
+  recorder::{RecordedEvent, SessionRecorder},
 };
 
 use super::{
   InternalDeviceEvent,
   device_task::{DeviceTaskConfig, spawn_device_task},
   hardware::{Hardware, HardwareCommand, HardwareConnector, HardwareEvent},
+  output_arbiter::OutputArbiter,
+  output_mixer::{DEFAULT_OUTPUT_LAYER, LayerExpiry, OutputMixer, OutputMixerConfig},
+  output_values::output_max_value,
   protocol::{ProtocolHandler, ProtocolKeepaliveStrategy, ProtocolSpecializer},
+  safety_policy::{SAFETY_RAMP_INTERVAL, SafetyFollowUp, SafetyPolicy, SafetyViolation},
 };
 
 /// Commands that can be sent to a device through its handle.
@@ -108,10 +122,13 @@ pub struct DeviceHandle {
   last_output_command: Arc<DashMap<Uuid, CheckedOutputCmdV4>>,
   stop_commands: Arc<Vec<ButtplugDeviceCommandMessageUnionV4>>,
   internal_hw_msg_sender: Sender<Vec<HardwareCommand>>,
+  safety_policy: Option<Arc<SafetyPolicy>>,
+  mixer: Arc<OutputMixer>,
 }
 
 impl DeviceHandle {
   /// Create a new DeviceHandle with direct ownership of device state
+  #[allow(clippy::too_many_arguments)]
   pub(crate) fn new(
     hardware: Arc<Hardware>,
     handler: Arc<dyn ProtocolHandler>,
@@ -119,7 +136,11 @@ impl DeviceHandle {
     identifier: UserDeviceIdentifier,
     stop_commands: Vec<ButtplugDeviceCommandMessageUnionV4>,
     internal_hw_msg_sender: Sender<Vec<HardwareCommand>>,
+    safety_event_sender: broadcast::Sender<SafetyViolation>,
+    mixer_config: OutputMixerConfig,
   ) -> Self {
+    let safety_policy =
+      SafetyPolicy::new(&identifier, &definition, safety_event_sender).map(Arc::new);
     Self {
       hardware,
       handler,
@@ -129,6 +150,8 @@ impl DeviceHandle {
       last_output_command: Arc::new(DashMap::new()),
       stop_commands: Arc::new(stop_commands),
       internal_hw_msg_sender,
+      safety_policy,
+      mixer: Arc::new(OutputMixer::new(mixer_config)),
     }
   }
 
@@ -222,6 +245,112 @@ impl DeviceHandle {
     }
   }
 
+  /// Sets the output layer for a source, such as a background pattern running alongside a game.
+  /// Layers are mixed with output commands sent through [DeviceHandle::parse_message] using the
+  /// device manager's blend mode, and higher priorities win in priority override mode. Callers are
+  /// responsible for checking the emergency stop.
+  pub(crate) fn parse_layered_output(
+    &self,
+    source: &str,
+    priority: u32,
+    cmd: &CheckedOutputCmdV4,
+  ) -> ButtplugServerResultFuture {
+    let (mixed, new_layer) = self.mixer.set_layer(
+      source,
+      priority,
+      cmd,
+      output_max_value(&self.definition, cmd),
+    );
+    if let (Some(layer), Some(timeout)) = (new_layer, self.mixer.layer_timeout()) {
+      let handle = self.clone();
+      let source = source.to_owned();
+      let cmd = cmd.clone();
+      // One timer per layer, which keeps going for as long as the layer keeps being updated.
+      buttplug_core::spawn!("DeviceOutputLayerExpiry", async move {
+        let mut deadline = Instant::now() + timeout;
+        loop {
+          async_manager::sleep(deadline.saturating_duration_since(Instant::now())).await;
+          match handle.mixer.expire_layer(&source, &cmd, layer) {
+            LayerExpiry::Extended(next) => deadline = next,
+            LayerExpiry::Expired(cmd) => {
+              if let Some(cmd) = cmd
+                && let Err(e) = handle.send_mixed_output(&cmd).await
+              {
+                error!("Error updating output after layer expired: {:?}", e);
+              }
+              break;
+            }
+          }
+        }
+      });
+    }
+    self.send_mixed_output(&mixed)
+  }
+
+  /// Sets every output in a preset on behalf of a session. The commands go through the arbiter
+  /// like the session's own output commands would. Presets are checked against the device's
+  /// features when they are configured, but are checked again here in case the device definition
+  /// changed since.
+  pub(crate) fn apply_preset(
+    &self,
+    arbiter: &OutputArbiter,
+    session: u32,
+    preset: &DevicePreset,
+  ) -> ButtplugServerResultFuture {
+    let commands: Result<Vec<_>, ButtplugError> = preset
+      .outputs()
+      .iter()
+      .map(|output| {
+        let command = OutputCommand::from_output_type(output.output_type(), output.value())?;
+        CheckedOutputCmdV4::try_from_device_attributes(
+          OutputCmdV4::new(self.definition.index(), output.feature_index(), command),
+          &self.legacy_attributes,
+        )
+      })
+      .collect();
+    let commands: Result<Vec<_>, ButtplugError> = commands.and_then(|commands| {
+      commands
+        .iter()
+        .map(|cmd| {
+          arbiter
+            .arbitrate(session, cmd, output_max_value(&self.definition, cmd))
+            .map_err(ButtplugError::from)
+        })
+        .collect()
+    });
+    let fut_vec: Vec<_> = match commands {
+      Ok(commands) => commands
+        .into_iter()
+        .map(|cmd| self.parse_message(cmd.into()))
+        .collect(),
+      Err(e) => return future::ready(Err(e)).boxed(),
+    };
+    async move {
+      for fut in fut_vec {
+        fut.await?;
+      }
+      Ok(message::OkV0::default().into())
+    }
+    .boxed()
+  }
+
+  /// Removes a source's output layers, sending whatever the remaining layers mix to.
+  pub fn clear_output_layer(&self, source: &str) -> ButtplugServerResultFuture {
+    let fut_vec: Vec<_> = self
+      .mixer
+      .clear_layer(source)
+      .iter()
+      .map(|cmd| self.send_mixed_output(cmd))
+      .collect();
+    async move {
+      for fut in fut_vec {
+        fut.await?;
+      }
+      Ok(message::OkV0::default().into())
+    }
+    .boxed()
+  }
+
   pub fn stop(&self, stop_cmd: &StopCmdV4) -> ButtplugServerResultFuture {
     // Other generic messages
     self.handle_stop_device_cmd(stop_cmd)
@@ -259,8 +388,20 @@ impl DeviceHandle {
   // --- Private command handling methods ---
 
   fn handle_outputcmd_v4(&self, msg: &CheckedOutputCmdV4) -> ButtplugServerResultFuture {
+    self.parse_layered_output(DEFAULT_OUTPUT_LAYER, 0, msg)
+  }
+
+  /// Sends a mixed output command through safety limits and the protocol handler.
+  fn send_mixed_output(&self, msg: &CheckedOutputCmdV4) -> ButtplugServerResultFuture {
+    let msg = if let Some(policy) = &self.safety_policy {
+      let (msg, follow_up) = policy.enforce(msg);
+      self.schedule_safety_follow_up(policy, &msg, follow_up);
+      msg
+    } else {
+      msg.clone()
+    };
     if let Some(last_msg) = self.last_output_command.get(&msg.feature_id())
-      && *last_msg == *msg
+      && *last_msg == msg
     {
       trace!("No commands generated for incoming device packet, skipping and returning success.");
       return future::ready(Ok(message::OkV0::default().into())).boxed();
@@ -268,7 +409,56 @@ impl DeviceHandle {
     self
       .last_output_command
       .insert(msg.feature_id(), msg.clone());
-    self.handle_generic_command_result(self.handler.handle_output_cmd(msg))
+    self.handle_generic_command_result(self.handler.handle_output_cmd(&msg))
+  }
+
+  /// Spawns the ramp and on-time tasks the safety policy asked for. Both send their commands
+  /// straight to the device task, and stop once a newer command supersedes them.
+  fn schedule_safety_follow_up(
+    &self,
+    policy: &Arc<SafetyPolicy>,
+    msg: &CheckedOutputCmdV4,
+    follow_up: SafetyFollowUp,
+  ) {
+    // Commands are queued without waiting, since the policy calls this with the output's state
+    // locked.
+    let send_command = {
+      let handler = self.handler.clone();
+      let last_output_command = self.last_output_command.clone();
+      let sender = self.internal_hw_msg_sender.clone();
+      move |cmd: CheckedOutputCmdV4| {
+        let commands = match handler.handle_output_cmd(&cmd) {
+          Ok(commands) => commands,
+          Err(e) => {
+            error!("Error generating safety limited command: {:?}", e);
+            return false;
+          }
+        };
+        last_output_command.insert(cmd.feature_id(), cmd);
+        sender.try_send(commands).is_ok()
+      }
+    };
+    if let Some(generation) = follow_up.ramp {
+      let policy = policy.clone();
+      let msg = msg.clone();
+      let send_command = send_command.clone();
+      buttplug_core::spawn!("DeviceSafetyRamp", async move {
+        loop {
+          async_manager::sleep(SAFETY_RAMP_INTERVAL).await;
+          if !policy.ramp_step(&msg, generation, &send_command) {
+            break;
+          }
+        }
+      });
+    }
+    if let Some((limit, generation)) = follow_up.on_timer {
+      let policy = policy.clone();
+      let msg = msg.clone();
+      buttplug_core::spawn!("DeviceSafetyOnTimer", async move {
+        async_manager::sleep(limit).await;
+        policy.on_time_expired(&msg, generation, send_command);
+      });
+    }
   }
 
   fn handle_hardware_commands(&self, commands: Vec<HardwareCommand>) -> ButtplugServerResultFuture {
@@ -295,6 +485,10 @@ impl DeviceHandle {
   fn handle_stop_device_cmd(&self, msg: &StopCmdV4) -> ButtplugServerResultFuture {
     let mut fut_vec = vec![];
     if msg.outputs() {
+      if let Some(policy) = &self.safety_policy {
+        policy.cancel_all();
+      }
+      self.mixer.clear();
       self
         .stop_commands
         .iter()
@@ -302,6 +496,9 @@ impl DeviceHandle {
     }
     if msg.inputs() {
       self.definition.features().iter().for_each(|(i, f)| {
+        if Some(*i) == self.definition.emergency_stop_button() {
+          return;
+        }
         if let Some(inputs) = f.input() {
           if inputs.can_subscribe() {
             fut_vec.push(
@@ -399,6 +596,10 @@ impl DeviceHandle {
     feature_id: Uuid,
     input_type: InputType,
   ) -> ButtplugServerResultFuture {
+    // Clients can't turn off the emergency stop button.
+    if Some(feature_index) == self.definition.emergency_stop_button() {
+      return future::ready(Ok(message::OkV0::new(1).into())).boxed();
+    }
     let device = self.hardware.clone();
     let handler = self.handler.clone();
     async move {
@@ -430,11 +631,15 @@ impl std::fmt::Debug for DeviceHandle {
 /// 4. Spawns the device communication task
 /// 5. Spawns the device event forwarding task
 /// 6. Returns a DeviceHandle for interacting with the device
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn build_device_handle(
   device_config_manager: Arc<DeviceConfigurationManager>,
   mut hardware_connector: Box<dyn HardwareConnector>,
   protocol_specializers: Vec<ProtocolSpecializer>,
   device_event_sender: tokio::sync::mpsc::Sender<InternalDeviceEvent>,
+  safety_event_sender: broadcast::Sender<SafetyViolation>,
+  mixer_config: OutputMixerConfig,
+  recorder: Option<SessionRecorder>,
 ) -> Result<DeviceHandle, ButtplugDeviceError> {
   // At this point, we know we've got hardware that is waiting to connect, and enough protocol
   // info to actually do something after we connect. So go ahead and connect.
@@ -467,7 +672,15 @@ pub(super) async fn build_device_handle(
   }
 
   let mut protocol_identifier_stage = protocol_identifier.unwrap();
-  let hardware = Arc::new(hardware_out.unwrap());
+  let mut hardware_out = hardware_out.unwrap();
+  if let Some(recorder) = recorder {
+    recorder.record(RecordedEvent::DeviceConnected {
+      name: hardware_out.name().clone(),
+      address: hardware_out.address().clone(),
+    });
+    hardware_out.set_recorder(recorder);
+  }
+  let hardware = Arc::new(hardware_out);
 
   let (identifier, mut protocol_initializer) = protocol_identifier_stage
     .identify(hardware.clone(), hardware_connector.specifier())
@@ -503,17 +716,6 @@ pub(super) async fn build_device_handle(
     hardware.message_gap()
   };
 
-  spawn_device_task(
-    hardware.clone(),
-    handler.clone(),
-    DeviceTaskConfig {
-      message_gap: device_wait_duration,
-      requires_keepalive: hardware.requires_keepalive(),
-      keepalive_strategy: handler.keepalive_strategy(),
-    },
-    internal_hw_msg_recv,
-  );
-
   // Generate stop commands for this device
   let mut stop_commands: Vec<ButtplugDeviceCommandMessageUnionV4> = vec![];
   for feature in definition.features().values() {
@@ -564,18 +766,11 @@ pub(super) async fn build_device_handle(
     }
   }
 
-  // Create the DeviceHandle
-  let device_handle = DeviceHandle::new(
-    hardware,
-    handler,
-    definition.clone(),
-    identifier,
-    stop_commands,
-    internal_hw_msg_sender,
-  );
-
   // If we need a keepalive with a packet replay, set this up via stopping the device on connect.
-  if ((requires_keepalive
+  // The stop is written before the device task starts, so it can't be batched together with (or
+  // land after) the first command a client sends.
+  let mut initial_commands: Vec<HardwareCommand> = vec![];
+  if (requires_keepalive
     && matches!(
       strategy,
       ProtocolKeepaliveStrategy::HardwareRequiredRepeatLastPacketStrategy
@@ -583,14 +778,55 @@ pub(super) async fn build_device_handle(
     || matches!(
       strategy,
       ProtocolKeepaliveStrategy::RepeatLastPacketStrategyWithTiming(_)
-    ))
-    && let Err(e) = device_handle.stop(&StopCmdV4::default()).await
+    )
   {
-    return Err(ButtplugDeviceError::DeviceConnectionError(format!(
-      "Error setting up keepalive: {e}"
-    )));
+    for stop_cmd in &stop_commands {
+      let ButtplugDeviceCommandMessageUnionV4::OutputCmd(stop_cmd) = stop_cmd else {
+        continue;
+      };
+      let commands = handler.handle_output_cmd(stop_cmd).map_err(|e| {
+        ButtplugDeviceError::DeviceConnectionError(format!("Error setting up keepalive: {e}"))
+      })?;
+      for command in commands {
+        initial_commands.retain(|existing| !command.overlaps(existing));
+        initial_commands.push(command);
+      }
+    }
+  }
+  let mut keepalive_packet = None;
+  for command in initial_commands {
+    hardware.parse_message(&command).await.map_err(|e| {
+      ButtplugDeviceError::DeviceConnectionError(format!("Error setting up keepalive: {e}"))
+    })?;
+    if let HardwareCommand::Write(write_cmd) = command {
+      keepalive_packet = Some(write_cmd);
+    }
   }
 
+  spawn_device_task(
+    hardware.clone(),
+    handler.clone(),
+    DeviceTaskConfig {
+      message_gap: device_wait_duration,
+      requires_keepalive: hardware.requires_keepalive(),
+      keepalive_strategy: handler.keepalive_strategy(),
+      keepalive_packet,
+    },
+    internal_hw_msg_recv,
+  );
+
+  // Create the DeviceHandle
+  let device_handle = DeviceHandle::new(
+    hardware,
+    handler,
+    definition.clone(),
+    identifier,
+    stop_commands,
+    internal_hw_msg_sender,
+    safety_event_sender,
+    mixer_config,
+  );
+
   // Spawn the device event forwarding task.
   // This task listens to device events (disconnections, notifications) and forwards them
   // to the device manager event loop via the provided sender.
@@ -635,5 +871,23 @@ pub(super) async fn build_device_handle(
     }
   });
 
+  // The emergency stop button stays subscribed for as long as the device is connected.
+  if let Some(feature_index) = definition.emergency_stop_button() {
+    let feature_id = definition.features()[&feature_index].id();
+    if let Err(e) = device_handle
+      .handle_input_subscribe_cmd(
+        definition.index(),
+        feature_index,
+        feature_id,
+        InputType::Button,
+      )
+      .await
+    {
+      return Err(ButtplugDeviceError::DeviceConnectionError(format!(
+        "Error subscribing to emergency stop button: {e}"
+      )));
+    }
+  }
+
   Ok(device_handle)
 }
@@ -18,6 +18,8 @@ use buttplug_core::util::async_manager;
 use futures::future;
 use tokio::{select, sync::mpsc::Receiver, time::Instant};
 
+use crate::metrics;
+
 use super::{
   hardware::{Hardware, HardwareCommand, HardwareEvent, HardwareWriteCmd},
   protocol::{ProtocolHandler, ProtocolKeepaliveStrategy},
@@ -31,6 +33,8 @@ pub struct DeviceTaskConfig {
   pub requires_keepalive: bool,
   /// The keepalive strategy from the protocol handler
   pub keepalive_strategy: ProtocolKeepaliveStrategy,
+  /// Packet already written to the device to replay until a command replaces it
+  pub keepalive_packet: Option<HardwareWriteCmd>,
 }
 
 /// Spawn the device communication task.
@@ -84,7 +88,7 @@ async fn run_device_task(
       strategy,
       ProtocolKeepaliveStrategy::RepeatLastPacketStrategyWithTiming(_)
     );
-  let mut keepalive_packet: Option<HardwareWriteCmd> = None;
+  let mut keepalive_packet = config.keepalive_packet.filter(|_| track_keepalive);
 
   // Batching state: pending commands and when to flush them
   let mut pending_commands: VecDeque<HardwareCommand> = VecDeque::new();
@@ -123,6 +127,7 @@ async fn run_device_task(
         if device_wait_duration.is_none() {
           // No batching - send immediately
           trace!("No wait duration, sending commands immediately: {:?}", commands);
+          metrics::device_commands_sent(hardware.name(), hardware.address(), commands.len());
           for cmd in commands {
             let _ = hardware.parse_message(&cmd).await;
             if track_keepalive {
@@ -139,10 +144,16 @@ async fn run_device_task(
             batch_deadline = Some(Instant::now() + device_wait_duration.unwrap());
           } else {
             // Subsequent batches - deduplicate each command against existing
+            let mut coalesced = 0;
             for command in commands {
+              let queued = pending_commands.len();
               pending_commands.retain(|existing| !command.overlaps(existing));
+              coalesced += queued - pending_commands.len();
               pending_commands.push_back(command);
             }
+            if coalesced > 0 {
+              metrics::device_commands_coalesced(hardware.name(), hardware.address(), coalesced);
+            }
           }
         }
       }
@@ -150,6 +161,7 @@ async fn run_device_task(
       // Priority 2: Batch deadline reached - flush pending commands
       _ = batch_fut => {
         debug!("Batch deadline reached, sending {} commands", pending_commands.len());
+        metrics::device_commands_sent(hardware.name(), hardware.address(), pending_commands.len());
         while let Some(cmd) = pending_commands.pop_front() {
           let _ = hardware.parse_message(&cmd).await;
           if track_keepalive {
@@ -163,33 +175,28 @@ async fn run_device_task(
 
       // Priority 3: Keepalive timer
       _ = keepalive_fut => {
-        let result = match &strategy {
+        let packet = match &strategy {
           ProtocolKeepaliveStrategy::RepeatLastPacketStrategyWithTiming(duration) => {
             if hardware.time_since_last_write().await > *duration {
-              if let Some(ref packet) = keepalive_packet {
-                hardware.write_value(packet).await
-              } else {
+              if keepalive_packet.is_none() {
                 warn!("No keepalive packet available, device may disconnect.");
-                Ok(())
               }
+              keepalive_packet.as_ref()
             } else {
-              Ok(())
+              None
             }
           }
-          ProtocolKeepaliveStrategy::HardwareRequiredRepeatPacketStrategy(packet) => {
-            hardware.write_value(packet).await
-          }
+          ProtocolKeepaliveStrategy::HardwareRequiredRepeatPacketStrategy(packet) => Some(packet),
           ProtocolKeepaliveStrategy::HardwareRequiredRepeatLastPacketStrategy => {
-            if let Some(ref packet) = keepalive_packet {
-              hardware.write_value(packet).await
-            } else {
-              Ok(())
-            }
+            keepalive_packet.as_ref()
           }
         };
-        if let Err(e) = result {
-          warn!("Error writing keepalive packet: {:?}", e);
-          break;
+        if let Some(packet) = packet {
+          metrics::device_keepalive_write(hardware.name(), hardware.address());
+          if let Err(e) = hardware.write_value(packet).await {
+            warn!("Error writing keepalive packet: {:?}", e);
+            break;
+          }
         }
       }
 
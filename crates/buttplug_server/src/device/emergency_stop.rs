@@ -0,0 +1,83 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Latched emergency stop shared between the device manager and its event loop.
+
+use super::{DeviceHandle, output_arbiter::OutputArbiter};
+use buttplug_core::message::{ButtplugServerMessageV4, EmergencyStopStateV4, StopCmdV4};
+use dashmap::DashMap;
+use futures::future;
+use std::sync::{
+  Arc,
+  atomic::{AtomicBool, Ordering},
+};
+use tokio::sync::broadcast;
+
+/// Unlike StopCmd, the emergency stop stays engaged after stopping devices. Output commands are
+/// rejected until it is explicitly reset.
+pub(crate) struct EmergencyStop {
+  engaged: AtomicBool,
+  devices: Arc<DashMap<u32, DeviceHandle>>,
+  output_sender: broadcast::Sender<ButtplugServerMessageV4>,
+  arbiter: Arc<OutputArbiter>,
+}
+
+impl EmergencyStop {
+  pub(crate) fn new(
+    devices: Arc<DashMap<u32, DeviceHandle>>,
+    output_sender: broadcast::Sender<ButtplugServerMessageV4>,
+    arbiter: Arc<OutputArbiter>,
+  ) -> Self {
+    Self {
+      engaged: AtomicBool::new(false),
+      devices,
+      output_sender,
+      arbiter,
+    }
+  }
+
+  pub(crate) fn is_engaged(&self) -> bool {
+    self.engaged.load(Ordering::Acquire)
+  }
+
+  /// Latches the emergency stop and stops all outputs on every connected device. Devices are
+  /// stopped again even if the stop was already engaged.
+  pub(crate) async fn engage(&self) {
+    if !self.engaged.swap(true, Ordering::AcqRel) {
+      warn!("Emergency stop engaged, rejecting output commands until reset.");
+      self.send_state(true);
+    }
+    self.arbiter.clear_all();
+    let fut_vec: Vec<_> = self
+      .devices
+      .iter()
+      .map(|dev| dev.value().stop(&StopCmdV4::new(None, None, false, true)))
+      .collect();
+    for result in future::join_all(fut_vec).await {
+      if let Err(e) = result {
+        error!("Error stopping device during emergency stop: {:?}", e);
+      }
+    }
+  }
+
+  pub(crate) fn reset(&self) {
+    if self.engaged.swap(false, Ordering::AcqRel) {
+      info!("Emergency stop reset, accepting output commands.");
+      self.send_state(false);
+    }
+  }
+
+  fn send_state(&self, engaged: bool) {
+    if self
+      .output_sender
+      .send(EmergencyStopStateV4::new(engaged).into())
+      .is_err()
+    {
+      debug!("Server not currently available, dropping emergency stop state event.");
+    }
+  }
+}
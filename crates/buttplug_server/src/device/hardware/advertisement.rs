@@ -0,0 +1,600 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Bluetooth LE devices that are talked to through advertisements instead of connections.
+//!
+//! Some cheap devices never accept a connection. They're controlled by broadcasting advertisements
+//! with manufacturer data they listen for, and some report state (like battery level) in their own
+//! advertisements. All radio access goes through a [BleAdvertisementAdapter], so the hardware and
+//! comm manager here can sit on top of whatever Bluetooth stack a platform has.
+//!
+//! Manufacturer data is passed to and from protocols in its on-air layout, the 2 byte little endian
+//! company id followed by the data. Writes to [Endpoint::Tx] are broadcast, and the latest
+//! manufacturer data the device advertised can be read or subscribed to on [Endpoint::Rx].
+
+use super::{
+  GenericHardwareSpecializer,
+  Hardware,
+  HardwareConnector,
+  HardwareEvent,
+  HardwareInternal,
+  HardwareReadCmd,
+  HardwareReading,
+  HardwareSpecializer,
+  HardwareSubscribeCmd,
+  HardwareUnsubscribeCmd,
+  HardwareWriteCmd,
+  communication::{
+    HardwareCommunicationManager,
+    HardwareCommunicationManagerBuilder,
+    HardwareCommunicationManagerEvent,
+  },
+};
+use async_trait::async_trait;
+use buttplug_core::{
+  ButtplugResultFuture,
+  errors::ButtplugDeviceError,
+  message::HardwareManagerStatus,
+};
+use buttplug_server_device_config::{
+  BluetoothLEAdvertisementSpecifier,
+  Endpoint,
+  ProtocolCommunicationSpecifier,
+};
+use futures::future::{self, BoxFuture, FutureExt};
+use getset::Getters;
+use std::{
+  collections::HashMap,
+  fmt::{self, Debug},
+  sync::{
+    Arc,
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
+};
+use tokio::{
+  select,
+  sync::{broadcast, mpsc::Sender},
+};
+use tokio_util::sync::CancellationToken;
+
+/// Prefix for the addresses of devices added with
+/// [BleAdvertisementCommunicationManagerBuilder::broadcast_device], which have no address of their
+/// own.
+pub const BROADCAST_DEVICE_ADDRESS_PREFIX: &str = "ble-broadcast:";
+
+/// An advertisement heard by a [BleAdvertisementAdapter].
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct BleAdvertisement {
+  address: String,
+  /// Advertised local name, empty if the device didn't send one.
+  name: String,
+  manufacturer_data: HashMap<u16, Vec<u8>>,
+}
+
+impl BleAdvertisement {
+  pub fn new(address: &str, name: &str, manufacturer_data: &HashMap<u16, Vec<u8>>) -> Self {
+    Self {
+      address: address.to_owned(),
+      name: name.to_owned(),
+      manufacturer_data: manufacturer_data.clone(),
+    }
+  }
+}
+
+/// Access to a Bluetooth LE radio for listening to and sending advertisements.
+#[async_trait]
+pub trait BleAdvertisementAdapter: Send + Sync {
+  /// Whether there's a radio available to use.
+  fn is_available(&self) -> bool;
+  /// Whether the radio can broadcast advertisements, or is only able to listen.
+  fn can_advertise(&self) -> bool;
+  /// Receiver for advertisements heard while listening.
+  fn advertisements(&self) -> broadcast::Receiver<BleAdvertisement>;
+  async fn start_listening(&self) -> Result<(), ButtplugDeviceError>;
+  async fn stop_listening(&self) -> Result<(), ButtplugDeviceError>;
+  /// Starts broadcasting manufacturer data, replacing whatever was being broadcast under the same
+  /// handle. Each device uses its address as its handle.
+  async fn advertise(
+    &self,
+    handle: &str,
+    company: u16,
+    data: &[u8],
+  ) -> Result<(), ButtplugDeviceError>;
+  async fn stop_advertising(&self, handle: &str) -> Result<(), ButtplugDeviceError>;
+}
+
+/// Packs manufacturer data into the layout protocols see, with the company id first.
+fn encode_manufacturer_data(company: u16, data: &[u8]) -> Vec<u8> {
+  let mut packet = company.to_le_bytes().to_vec();
+  packet.extend_from_slice(data);
+  packet
+}
+
+/// Manufacturer data from an advertisement, in company id order so devices that advertise more
+/// than one are handled the same way every time.
+fn sorted_manufacturer_data(advertisement: &BleAdvertisement) -> Vec<Vec<u8>> {
+  let mut companies: Vec<&u16> = advertisement.manufacturer_data().keys().collect();
+  companies.sort();
+  companies
+    .into_iter()
+    .map(|company| encode_manufacturer_data(*company, &advertisement.manufacturer_data()[company]))
+    .collect()
+}
+
+pub struct BleAdvertisementHardwareConnector {
+  advertisement: BleAdvertisement,
+  adapter: Arc<dyn BleAdvertisementAdapter>,
+}
+
+impl BleAdvertisementHardwareConnector {
+  pub fn new(advertisement: &BleAdvertisement, adapter: Arc<dyn BleAdvertisementAdapter>) -> Self {
+    Self {
+      advertisement: advertisement.clone(),
+      adapter,
+    }
+  }
+}
+
+impl Debug for BleAdvertisementHardwareConnector {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("BleAdvertisementHardwareConnector")
+      .field("advertisement", &self.advertisement)
+      .finish()
+  }
+}
+
+#[async_trait]
+impl HardwareConnector for BleAdvertisementHardwareConnector {
+  fn specifier(&self) -> ProtocolCommunicationSpecifier {
+    ProtocolCommunicationSpecifier::BluetoothLEAdvertisement(
+      BluetoothLEAdvertisementSpecifier::new_from_device(
+        self.advertisement.name(),
+        self.advertisement.manufacturer_data(),
+      ),
+    )
+  }
+
+  async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
+    let hardware_internal =
+      BleAdvertisementHardware::new(&self.advertisement, self.adapter.clone());
+    let hardware = Hardware::new(
+      self.advertisement.name(),
+      self.advertisement.address(),
+      &[Endpoint::Rx, Endpoint::Tx],
+      &None,
+      false,
+      Box::new(hardware_internal),
+    );
+    Ok(Box::new(GenericHardwareSpecializer::new(hardware)))
+  }
+}
+
+fn check_endpoint(endpoint: Endpoint, expected: Endpoint) -> Result<(), ButtplugDeviceError> {
+  if endpoint == expected {
+    Ok(())
+  } else {
+    Err(ButtplugDeviceError::InvalidEndpoint(endpoint.to_string()))
+  }
+}
+
+async fn watch_device_advertisements(
+  address: String,
+  mut receiver: broadcast::Receiver<BleAdvertisement>,
+  last_manufacturer_data: Arc<Mutex<Option<Vec<u8>>>>,
+  subscribed: Arc<AtomicBool>,
+  event_sender: broadcast::Sender<HardwareEvent>,
+  cancellation_token: CancellationToken,
+) {
+  loop {
+    let advertisement = select! {
+      _ = cancellation_token.cancelled() => return,
+      advertisement = receiver.recv() => match advertisement {
+        Ok(advertisement) => advertisement,
+        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+        Err(broadcast::error::RecvError::Closed) => return,
+      }
+    };
+    if *advertisement.address() != address {
+      continue;
+    }
+    for data in sorted_manufacturer_data(&advertisement) {
+      *last_manufacturer_data
+        .lock()
+        .expect("Lock is never held across panics") = Some(data.clone());
+      if subscribed.load(Ordering::Relaxed) {
+        // If nothing is listening, there's no one to tell.
+        let _ = event_sender.send(HardwareEvent::Notification(
+          address.clone(),
+          Endpoint::Rx,
+          data,
+        ));
+      }
+    }
+  }
+}
+
+pub struct BleAdvertisementHardware {
+  address: String,
+  adapter: Arc<dyn BleAdvertisementAdapter>,
+  last_manufacturer_data: Arc<Mutex<Option<Vec<u8>>>>,
+  subscribed: Arc<AtomicBool>,
+  event_sender: broadcast::Sender<HardwareEvent>,
+  cancellation_token: CancellationToken,
+}
+
+impl BleAdvertisementHardware {
+  pub fn new(advertisement: &BleAdvertisement, adapter: Arc<dyn BleAdvertisementAdapter>) -> Self {
+    let address = advertisement.address().clone();
+    // Devices we only broadcast to have never advertised anything, so may not have any data yet.
+    let last_manufacturer_data =
+      Arc::new(Mutex::new(sorted_manufacturer_data(advertisement).pop()));
+    let subscribed = Arc::new(AtomicBool::new(false));
+    let (event_sender, _) = broadcast::channel(256);
+    let cancellation_token = CancellationToken::new();
+    buttplug_core::spawn!(
+      "BleAdvertisementHardware advertisement watcher",
+      watch_device_advertisements(
+        address.clone(),
+        adapter.advertisements(),
+        last_manufacturer_data.clone(),
+        subscribed.clone(),
+        event_sender.clone(),
+        cancellation_token.child_token(),
+      )
+    );
+    Self {
+      address,
+      adapter,
+      last_manufacturer_data,
+      subscribed,
+      event_sender,
+      cancellation_token,
+    }
+  }
+}
+
+impl HardwareInternal for BleAdvertisementHardware {
+  fn event_stream(&self) -> broadcast::Receiver<HardwareEvent> {
+    self.event_sender.subscribe()
+  }
+
+  fn disconnect(&self) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.cancellation_token.cancel();
+    let adapter = self.adapter.clone();
+    let address = self.address.clone();
+    async move { adapter.stop_advertising(&address).await }.boxed()
+  }
+
+  fn read_value(
+    &self,
+    msg: &HardwareReadCmd,
+  ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
+    if let Err(err) = check_endpoint(msg.endpoint(), Endpoint::Rx) {
+      return future::ready(Err(err)).boxed();
+    }
+    let result = self
+      .last_manufacturer_data
+      .lock()
+      .expect("Lock is never held across panics")
+      .clone()
+      .map(|data| HardwareReading::new(Endpoint::Rx, &data))
+      .ok_or_else(|| {
+        ButtplugDeviceError::DeviceCommunicationError(format!(
+          "No manufacturer data advertised by {} yet",
+          self.address
+        ))
+      });
+    future::ready(result).boxed()
+  }
+
+  fn write_value(
+    &self,
+    msg: &HardwareWriteCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    if let Err(err) = check_endpoint(msg.endpoint(), Endpoint::Tx) {
+      return future::ready(Err(err)).boxed();
+    }
+    let data = msg.data().clone();
+    if data.len() < 2 {
+      return future::ready(Err(ButtplugDeviceError::DeviceCommunicationError(format!(
+        "Advertisement data must start with a 2 byte company id, got {} bytes",
+        data.len()
+      ))))
+      .boxed();
+    }
+    if !self.adapter.can_advertise() {
+      // Devices heard through a listen-only adapter can still report state, but there's no way to
+      // send them anything.
+      return future::ready(Err(ButtplugDeviceError::DeviceCommunicationError(format!(
+        "Adapter cannot advertise, cannot write to {}",
+        self.address
+      ))))
+      .boxed();
+    }
+    let adapter = self.adapter.clone();
+    let address = self.address.clone();
+    async move {
+      let company = u16::from_le_bytes([data[0], data[1]]);
+      adapter.advertise(&address, company, &data[2..]).await
+    }
+    .boxed()
+  }
+
+  fn subscribe(
+    &self,
+    msg: &HardwareSubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    let result = check_endpoint(msg.endpoint(), Endpoint::Rx);
+    if result.is_ok() {
+      self.subscribed.store(true, Ordering::Relaxed);
+    }
+    future::ready(result).boxed()
+  }
+
+  fn unsubscribe(
+    &self,
+    msg: &HardwareUnsubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    let result = check_endpoint(msg.endpoint(), Endpoint::Rx);
+    if result.is_ok() {
+      self.subscribed.store(false, Ordering::Relaxed);
+    }
+    future::ready(result).boxed()
+  }
+}
+
+impl Drop for BleAdvertisementHardware {
+  fn drop(&mut self) {
+    self.cancellation_token.cancel();
+  }
+}
+
+pub struct BleAdvertisementCommunicationManagerBuilder {
+  adapter: Arc<dyn BleAdvertisementAdapter>,
+  broadcast_devices: Vec<String>,
+}
+
+impl BleAdvertisementCommunicationManagerBuilder {
+  pub fn new(adapter: Arc<dyn BleAdvertisementAdapter>) -> Self {
+    Self {
+      adapter,
+      broadcast_devices: vec![],
+    }
+  }
+
+  /// Adds a device that only listens for advertisements and never sends its own, so can't be found
+  /// by listening. It's reported as found under this name whenever scanning starts, which is what
+  /// protocol configurations will need to match.
+  pub fn broadcast_device(mut self, name: &str) -> Self {
+    self.broadcast_devices.push(name.to_owned());
+    self
+  }
+}
+
+impl HardwareCommunicationManagerBuilder for BleAdvertisementCommunicationManagerBuilder {
+  fn finish(
+    &mut self,
+    sender: Sender<HardwareCommunicationManagerEvent>,
+  ) -> Box<dyn HardwareCommunicationManager> {
+    Box::new(BleAdvertisementCommunicationManager::new(
+      sender,
+      self.adapter.clone(),
+      &self.broadcast_devices,
+    ))
+  }
+}
+
+async fn report_advertised_devices(
+  sender: Sender<HardwareCommunicationManagerEvent>,
+  adapter: Arc<dyn BleAdvertisementAdapter>,
+  mut receiver: broadcast::Receiver<BleAdvertisement>,
+  reported: Arc<Mutex<HashMap<String, BleAdvertisement>>>,
+  cancellation_token: CancellationToken,
+) {
+  loop {
+    let advertisement = select! {
+      _ = cancellation_token.cancelled() => return,
+      advertisement = receiver.recv() => match advertisement {
+        Ok(advertisement) => advertisement,
+        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+        Err(broadcast::error::RecvError::Closed) => return,
+      }
+    };
+    // Devices can advertise many times a second. Only pass along advertisements that differ from
+    // the last one we reported for the device, so changes in manufacturer data still get a chance
+    // to match.
+    if reported
+      .lock()
+      .expect("Lock is never held across panics")
+      .insert(advertisement.address().clone(), advertisement.clone())
+      .as_ref()
+      == Some(&advertisement)
+    {
+      continue;
+    }
+    trace!("Found new BLE advertisement: {:?}", advertisement);
+    if sender
+      .send(HardwareCommunicationManagerEvent::DeviceFound {
+        name: advertisement.name().clone(),
+        address: advertisement.address().clone(),
+        creator: Box::new(BleAdvertisementHardwareConnector::new(
+          &advertisement,
+          adapter.clone(),
+        )),
+      })
+      .await
+      .is_err()
+    {
+      debug!("Device manager disappeared, exiting.");
+      return;
+    }
+  }
+}
+
+/// Finds devices by listening for advertisements through a [BleAdvertisementAdapter].
+///
+/// Listening starts with the first scan and runs until the manager is dropped, so connected devices
+/// keep hearing advertisements after scanning stops. Scanning only decides whether newly heard
+/// devices are reported.
+pub struct BleAdvertisementCommunicationManager {
+  sender: Sender<HardwareCommunicationManagerEvent>,
+  adapter: Arc<dyn BleAdvertisementAdapter>,
+  broadcast_devices: Vec<String>,
+  // Last advertisement reported for each address during the current scan.
+  reported: Arc<Mutex<HashMap<String, BleAdvertisement>>>,
+  listening: Arc<AtomicBool>,
+  scanning: Arc<AtomicBool>,
+  scan_token: Option<CancellationToken>,
+}
+
+impl BleAdvertisementCommunicationManager {
+  fn new(
+    sender: Sender<HardwareCommunicationManagerEvent>,
+    adapter: Arc<dyn BleAdvertisementAdapter>,
+    broadcast_devices: &[String],
+  ) -> Self {
+    Self {
+      sender,
+      adapter,
+      broadcast_devices: broadcast_devices.to_vec(),
+      reported: Arc::new(Mutex::new(HashMap::new())),
+      listening: Arc::new(AtomicBool::new(false)),
+      scanning: Arc::new(AtomicBool::new(false)),
+      scan_token: None,
+    }
+  }
+}
+
+impl HardwareCommunicationManager for BleAdvertisementCommunicationManager {
+  fn name(&self) -> &'static str {
+    "BleAdvertisementCommunicationManager"
+  }
+
+  fn start_scanning(&mut self) -> ButtplugResultFuture {
+    if let Some(token) = self.scan_token.take() {
+      token.cancel();
+    }
+    self
+      .reported
+      .lock()
+      .expect("Lock is never held across panics")
+      .clear();
+    let token = CancellationToken::new();
+    self.scan_token = Some(token.clone());
+    self.scanning.store(true, Ordering::Relaxed);
+    let sender = self.sender.clone();
+    let adapter = self.adapter.clone();
+    // Broadcast devices can only be commanded, so there's no point in adding them if we can't
+    // advertise.
+    let broadcast_devices = if adapter.can_advertise() {
+      self.broadcast_devices.clone()
+    } else {
+      if !self.broadcast_devices.is_empty() {
+        warn!(
+          "BLE adapter cannot advertise, skipping broadcast devices {:?}",
+          self.broadcast_devices
+        );
+      }
+      vec![]
+    };
+    let reported = self.reported.clone();
+    let listening = self.listening.clone();
+    let scanning = self.scanning.clone();
+    // Subscribe before listening starts, so we can't miss the first advertisements.
+    let receiver = adapter.advertisements();
+    async move {
+      if !listening.swap(true, Ordering::Relaxed)
+        && let Err(err) = adapter.start_listening().await
+      {
+        error!("Cannot start listening for BLE advertisements: {:?}", err);
+        listening.store(false, Ordering::Relaxed);
+        scanning.store(false, Ordering::Relaxed);
+        return Err(err.into());
+      }
+      for name in broadcast_devices {
+        let advertisement = BleAdvertisement::new(
+          &format!("{BROADCAST_DEVICE_ADDRESS_PREFIX}{name}"),
+          &name,
+          &HashMap::new(),
+        );
+        if sender
+          .send(HardwareCommunicationManagerEvent::DeviceFound {
+            name,
+            address: advertisement.address().clone(),
+            creator: Box::new(BleAdvertisementHardwareConnector::new(
+              &advertisement,
+              adapter.clone(),
+            )),
+          })
+          .await
+          .is_err()
+        {
+          debug!("Device manager disappeared, exiting.");
+          return Ok(());
+        }
+      }
+      buttplug_core::spawn!(
+        "BleAdvertisementCommunicationManager scan",
+        report_advertised_devices(sender, adapter, receiver, reported, token)
+      );
+      Ok(())
+    }
+    .boxed()
+  }
+
+  fn stop_scanning(&mut self) -> ButtplugResultFuture {
+    if let Some(token) = self.scan_token.take() {
+      token.cancel();
+    }
+    self
+      .reported
+      .lock()
+      .expect("Lock is never held across panics")
+      .clear();
+    self.scanning.store(false, Ordering::Relaxed);
+    future::ready(Ok(())).boxed()
+  }
+
+  fn scanning_status(&self) -> bool {
+    self.scanning.load(Ordering::Relaxed)
+  }
+
+  fn can_scan(&self) -> bool {
+    self.adapter.is_available()
+  }
+
+  fn status(&self) -> HardwareManagerStatus {
+    if !self.can_scan() {
+      HardwareManagerStatus::Unavailable("No Bluetooth LE adapter found".to_owned())
+    } else if self.scanning_status() {
+      HardwareManagerStatus::Scanning
+    } else {
+      HardwareManagerStatus::Available
+    }
+  }
+}
+
+impl Drop for BleAdvertisementCommunicationManager {
+  fn drop(&mut self) {
+    if let Some(token) = self.scan_token.take() {
+      token.cancel();
+    }
+    if self.listening.load(Ordering::Relaxed) {
+      let adapter = self.adapter.clone();
+      buttplug_core::spawn!(
+        "BleAdvertisementCommunicationManager stop listening",
+        async move {
+          if let Err(err) = adapter.stop_listening().await {
+            error!("Cannot stop listening for BLE advertisements: {:?}", err);
+          }
+        }
+      );
+    }
+  }
+}
@@ -0,0 +1,182 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Reads and subscriptions for hardware whose transport hands us a plain stream of incoming data.
+
+use super::{
+  HardwareEvent,
+  HardwareReadCmd,
+  HardwareReading,
+  HardwareSubscribeCmd,
+  HardwareUnsubscribeCmd,
+};
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server_device_config::Endpoint;
+use futures::future::{self, BoxFuture, FutureExt};
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tokio::{select, sync::broadcast, time::timeout};
+use tokio_util::sync::CancellationToken;
+
+/// Timeout for reads that don't specify one.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Serves [Endpoint::Rx] reads and subscriptions from the data a device sends.
+///
+/// Everything sent through [BufferedRxEndpoint::sender] is buffered from the time this is created,
+/// so reads see data that arrived before they were called. Subscriptions forward the same data as
+/// [HardwareEvent::Notification]s until unsubscribed or the connection token is cancelled.
+pub struct BufferedRxEndpoint {
+  address: String,
+  incoming_sender: broadcast::Sender<Vec<u8>>,
+  read_receiver: Arc<tokio::sync::Mutex<broadcast::Receiver<Vec<u8>>>>,
+  event_sender: broadcast::Sender<HardwareEvent>,
+  subscribe_token: Mutex<Option<CancellationToken>>,
+  connection_token: CancellationToken,
+}
+
+impl BufferedRxEndpoint {
+  pub fn new(
+    address: &str,
+    event_sender: &broadcast::Sender<HardwareEvent>,
+    connection_token: &CancellationToken,
+  ) -> Self {
+    let (incoming_sender, read_receiver) = broadcast::channel(256);
+    Self {
+      address: address.to_owned(),
+      incoming_sender,
+      read_receiver: Arc::new(tokio::sync::Mutex::new(read_receiver)),
+      event_sender: event_sender.clone(),
+      subscribe_token: Mutex::new(None),
+      connection_token: connection_token.clone(),
+    }
+  }
+
+  /// Sender for the transport to pass incoming data to. If nothing is listening, data is dropped.
+  pub fn sender(&self) -> broadcast::Sender<Vec<u8>> {
+    self.incoming_sender.clone()
+  }
+
+  pub fn read_value(
+    &self,
+    msg: &HardwareReadCmd,
+  ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
+    if msg.endpoint() != Endpoint::Rx {
+      return future::ready(Err(ButtplugDeviceError::InvalidEndpoint(
+        msg.endpoint().to_string(),
+      )))
+      .boxed();
+    }
+    let read_timeout = if msg.timeout_ms() == 0 {
+      READ_TIMEOUT
+    } else {
+      Duration::from_millis(msg.timeout_ms() as u64)
+    };
+    let receiver = self.read_receiver.clone();
+    let address = self.address.clone();
+    async move {
+      let mut receiver = receiver.lock().await;
+      let read = async {
+        loop {
+          match receiver.recv().await {
+            Ok(data) => return Ok(data),
+            // We only buffer so much. If nothing has read in a while, skip to what's left.
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => {
+              return Err(ButtplugDeviceError::DeviceNotConnected(address.clone()));
+            }
+          }
+        }
+      };
+      let data = timeout(read_timeout, read).await.map_err(|_| {
+        ButtplugDeviceError::DeviceCommunicationError(format!(
+          "Timed out reading from device {address}"
+        ))
+      })??;
+      Ok(HardwareReading::new(Endpoint::Rx, &data))
+    }
+    .boxed()
+  }
+
+  pub fn subscribe(
+    &self,
+    msg: &HardwareSubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    if msg.endpoint() != Endpoint::Rx {
+      return future::ready(Err(ButtplugDeviceError::InvalidEndpoint(
+        msg.endpoint().to_string(),
+      )))
+      .boxed();
+    }
+    let mut subscribe_token = self
+      .subscribe_token
+      .lock()
+      .expect("Lock is never held across panics");
+    if subscribe_token.is_some() {
+      debug!(
+        "Device {} already subscribed, ignoring and returning Ok.",
+        self.address
+      );
+      return future::ready(Ok(())).boxed();
+    }
+    let token = self.connection_token.child_token();
+    *subscribe_token = Some(token.clone());
+    let mut data_receiver = self.incoming_sender.subscribe();
+    let event_sender = self.event_sender.clone();
+    let address = self.address.clone();
+    buttplug_core::spawn!("BufferedRxEndpoint subscription", async move {
+      loop {
+        select! {
+          result = data_receiver.recv() => match result {
+            Ok(data) => {
+              // We don't really care if there's no one to send the notification to here.
+              let _ = event_sender.send(HardwareEvent::Notification(
+                address.clone(),
+                Endpoint::Rx,
+                data,
+              ));
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+          },
+          _ = token.cancelled() => break,
+        }
+      }
+      debug!("Ending device {} subscription task.", address);
+    });
+    future::ready(Ok(())).boxed()
+  }
+
+  pub fn unsubscribe(
+    &self,
+    msg: &HardwareUnsubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    if msg.endpoint() != Endpoint::Rx {
+      return future::ready(Err(ButtplugDeviceError::InvalidEndpoint(
+        msg.endpoint().to_string(),
+      )))
+      .boxed();
+    }
+    let token = self
+      .subscribe_token
+      .lock()
+      .expect("Lock is never held across panics")
+      .take();
+    match token {
+      Some(token) => {
+        token.cancel();
+        future::ready(Ok(())).boxed()
+      }
+      None => future::ready(Err(ButtplugDeviceError::DeviceCommunicationError(
+        "Device not subscribed.".to_owned(),
+      )))
+      .boxed(),
+    }
+  }
+}
@@ -8,12 +8,16 @@
 use crate::device::hardware::HardwareConnector;
 use async_trait::async_trait;
 use buttplug_core::{
+  message::HardwareManagerStatus,
   util::async_manager,
   {ButtplugResultFuture, errors::ButtplugDeviceError},
 };
 use futures::future::{self, FutureExt};
 use serde::{Deserialize, Serialize};
-use std::{sync::Arc, time::Duration};
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 use thiserror::Error;
 use tokio::sync::mpsc::Sender;
 use tokio_util::sync::CancellationToken;
@@ -28,6 +32,9 @@ pub enum HardwareCommunicationManagerEvent {
     creator: Box<dyn HardwareConnector>,
   },
   ScanningFinished,
+  /// Tells the device manager to check [HardwareCommunicationManager::status] right away, instead
+  /// of waiting for it to be polled.
+  StatusChanged,
 }
 
 pub trait HardwareCommunicationManagerBuilder: Send {
@@ -45,6 +52,17 @@ pub trait HardwareCommunicationManager: Send + Sync {
     false
   }
   fn can_scan(&self) -> bool;
+  /// Current status of the manager, reported to clients whenever it changes. Managers that know
+  /// why they can't scan should override this to say so.
+  fn status(&self) -> HardwareManagerStatus {
+    if !self.can_scan() {
+      HardwareManagerStatus::Unavailable("Hardware not available".to_owned())
+    } else if self.scanning_status() {
+      HardwareManagerStatus::Scanning
+    } else {
+      HardwareManagerStatus::Available
+    }
+  }
   // Events happen via channel senders passed to the comm manager.
 }
 
@@ -67,6 +85,8 @@ pub trait TimedRetryCommunicationManagerImpl: Sync + Send {
 pub struct TimedRetryCommunicationManager<T: TimedRetryCommunicationManagerImpl + 'static> {
   comm_manager: Arc<T>,
   cancellation_token: Option<CancellationToken>,
+  /// Error from the last scan, if it failed. Cleared when scanning starts again.
+  scan_error: Arc<Mutex<Option<String>>>,
 }
 
 impl<T: TimedRetryCommunicationManagerImpl> TimedRetryCommunicationManager<T> {
@@ -74,6 +94,7 @@ impl<T: TimedRetryCommunicationManagerImpl> TimedRetryCommunicationManager<T> {
     Self {
       comm_manager: Arc::new(comm_manager),
       cancellation_token: None,
+      scan_error: Arc::new(Mutex::new(None)),
     }
   }
 }
@@ -94,11 +115,15 @@ impl<T: TimedRetryCommunicationManagerImpl> HardwareCommunicationManager
     let child_token = token.child_token();
     self.cancellation_token = Some(token);
     let duration = self.comm_manager.rescan_wait_duration();
+    let scan_error = self.scan_error.clone();
+    *scan_error.lock().expect("Lock is never held across panics") = None;
     async move {
       buttplug_core::spawn!("TimedDeviceCommunicationManager scanning", async move {
         loop {
           if let Err(err) = comm_manager.scan().await {
             error!("Timed Device Communication Manager Failure: {}", err);
+            *scan_error.lock().expect("Lock is never held across panics") =
+              Some(format!("{err:?}"));
             break;
           }
           tokio::select! {
@@ -126,6 +151,23 @@ impl<T: TimedRetryCommunicationManagerImpl> HardwareCommunicationManager
   fn can_scan(&self) -> bool {
     self.comm_manager.can_scan()
   }
+
+  fn status(&self) -> HardwareManagerStatus {
+    if let Some(err) = self
+      .scan_error
+      .lock()
+      .expect("Lock is never held across panics")
+      .clone()
+    {
+      HardwareManagerStatus::Unavailable(err)
+    } else if !self.can_scan() {
+      HardwareManagerStatus::Unavailable("Hardware not available".to_owned())
+    } else if self.scanning_status() {
+      HardwareManagerStatus::Scanning
+    } else {
+      HardwareManagerStatus::Available
+    }
+  }
 }
 
 impl<T: TimedRetryCommunicationManagerImpl> Drop for TimedRetryCommunicationManager<T> {
@@ -5,7 +5,13 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
+pub mod advertisement;
+pub mod buffered_rx;
 pub mod communication;
+use crate::{
+  metrics,
+  recorder::{RecordedEvent, SessionRecorder},
+};
 use std::{collections::HashSet, fmt::Debug, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
@@ -274,6 +280,8 @@ pub struct Hardware {
   #[getset(get_copy = "pub")]
   requires_keepalive: bool,
   last_write_time: Arc<RwLock<Instant>>,
+  /// Records commands sent to and data received from the hardware, if session recording is on.
+  recorder: Option<SessionRecorder>,
 }
 
 impl Hardware {
@@ -293,6 +301,42 @@ impl Hardware {
       internal_impl,
       requires_keepalive,
       last_write_time: Arc::new(RwLock::new(Instant::now())),
+      recorder: None,
+    }
+  }
+
+  /// Starts recording commands sent to the hardware, as well as data read from it or sent by it.
+  pub fn set_recorder(&mut self, recorder: SessionRecorder) {
+    let mut event_stream = self.event_stream();
+    let recorder_clone = recorder.clone();
+    buttplug_core::spawn!("HardwareRecorder", async move {
+      loop {
+        match event_stream.recv().await {
+          Ok(HardwareEvent::Notification(address, endpoint, data)) => {
+            recorder_clone.record(RecordedEvent::HardwareNotification {
+              address,
+              endpoint,
+              data,
+            });
+          }
+          Ok(_) => {}
+          // Missing a few notifications shouldn't end the recording for this device.
+          Err(broadcast::error::RecvError::Lagged(skipped)) => {
+            warn!(
+              "Session recorder fell behind, {} hardware events were not recorded.",
+              skipped
+            );
+          }
+          Err(broadcast::error::RecvError::Closed) => break,
+        }
+      }
+    });
+    self.recorder = Some(recorder);
+  }
+
+  fn record_command(&self, command: impl FnOnce() -> HardwareCommand) {
+    if let Some(recorder) = &self.recorder {
+      recorder.record_hardware_command(&self.address, command());
     }
   }
 
@@ -329,7 +373,18 @@ impl Hardware {
     &self,
     msg: &HardwareReadCmd,
   ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
-    self.internal_impl.read_value(msg)
+    let read_fut = self.internal_impl.read_value(msg);
+    if let Some(recorder) = self.recorder.clone() {
+      let address = self.address.clone();
+      async move {
+        let reading = read_fut.await?;
+        recorder.record_hardware_read(&address, &reading);
+        Ok(reading)
+      }
+      .boxed()
+    } else {
+      read_fut
+    }
   }
 
   /// Write a value to the device
@@ -337,7 +392,12 @@ impl Hardware {
     &self,
     msg: &HardwareWriteCmd,
   ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
-    let write_fut = self.internal_impl.write_value(msg);
+    self.record_command(|| msg.clone().into());
+    let write_fut = metrics::time_hardware_write(
+      &self.name,
+      &self.address,
+      self.internal_impl.write_value(msg),
+    );
     if self.requires_keepalive {
       let last_write_time = self.last_write_time.clone();
       async move {
@@ -355,6 +415,7 @@ impl Hardware {
     &self,
     msg: &HardwareSubscribeCmd,
   ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.record_command(|| (*msg).into());
     self.internal_impl.subscribe(msg)
   }
 
@@ -363,6 +424,7 @@ impl Hardware {
     &self,
     msg: &HardwareUnsubscribeCmd,
   ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.record_command(|| (*msg).into());
     self.internal_impl.unsubscribe(msg)
   }
 }
@@ -97,13 +97,27 @@
 
 mod device_handle;
 mod device_task;
+mod emergency_stop;
 pub mod hardware;
+mod output_arbiter;
+mod output_mixer;
+mod output_values;
 pub mod protocol;
 pub mod protocol_impl;
+#[cfg(feature = "script-protocols")]
+pub mod protocol_script;
+mod safety_policy;
+mod scan_policy;
 mod server_device_manager;
 mod server_device_manager_event_loop;
+mod server_session;
 
 pub use device_handle::{DeviceCommand, DeviceEvent, DeviceHandle};
+pub use output_arbiter::OutputArbitrationPolicy;
+pub use output_mixer::{DEFAULT_OUTPUT_LAYER, OutputBlendMode, OutputMixerConfig};
+pub use safety_policy::{SafetyViolation, SafetyViolationKind};
+pub use scan_policy::{ScanMode, ScanPolicy};
+pub use server_session::ServerSession;
 
 use crate::message::ButtplugServerDeviceMessage;
 use buttplug_server_device_config::UserDeviceIdentifier;
@@ -0,0 +1,260 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Combines output commands from multiple client sessions that share a device manager.
+
+use crate::message::checked_output_cmd::CheckedOutputCmdV4;
+use buttplug_core::{errors::ButtplugDeviceError, message::OutputType};
+use dashmap::DashMap;
+use std::{
+  collections::HashMap,
+  sync::atomic::{AtomicU64, Ordering},
+  time::{Duration, Instant},
+};
+
+use super::output_values::{OutputCombineMode, combine_outputs, is_position, output_after_removal};
+
+/// How output commands from different client sessions to the same feature output are combined.
+///
+/// Position outputs can't be meaningfully added or compared, so they always use the most recent
+/// command unless the policy is [ExclusiveLock](OutputArbitrationPolicy::ExclusiveLock).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputArbitrationPolicy {
+  /// The most recent command from any session is sent to the device.
+  #[default]
+  LastWriterWins,
+  /// The highest level requested by any session is sent to the device.
+  Max,
+  /// The levels requested by all sessions are added together, clamped to the output's range.
+  SumClamped,
+  /// The first session to command an output holds it until it stops the output, disconnects, or
+  /// sends no commands for the lease duration. Commands from other sessions are rejected.
+  ExclusiveLock { lease: Duration },
+}
+
+impl From<OutputArbitrationPolicy> for OutputCombineMode {
+  fn from(policy: OutputArbitrationPolicy) -> Self {
+    match policy {
+      OutputArbitrationPolicy::LastWriterWins | OutputArbitrationPolicy::ExclusiveLock { .. } => {
+        OutputCombineMode::Winner
+      }
+      OutputArbitrationPolicy::Max => OutputCombineMode::Max,
+      OutputArbitrationPolicy::SumClamped => OutputCombineMode::SumClamped,
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Contribution {
+  command: CheckedOutputCmdV4,
+  sequence: u64,
+}
+
+#[derive(Debug, Default)]
+struct OutputState {
+  /// Latest command from each session, keyed by session id.
+  contributions: HashMap<u32, Contribution>,
+  /// Session holding an exclusive lock, and when the lock expires.
+  lock: Option<(u32, Instant)>,
+  /// Largest value the output accepts, used for clamping sums.
+  max_value: i32,
+}
+
+impl OutputState {
+  fn effective(&self, policy: OutputArbitrationPolicy) -> Option<CheckedOutputCmdV4> {
+    let latest = self.contributions.values().max_by_key(|c| c.sequence)?;
+    Some(combine_outputs(
+      &latest.command,
+      self.contributions.values().map(|c| &c.command),
+      policy.into(),
+      self.max_value,
+    ))
+  }
+}
+
+pub(crate) struct OutputArbiter {
+  policy: OutputArbitrationPolicy,
+  sequence: AtomicU64,
+  /// Keyed by device index, feature index and output type.
+  state: DashMap<(u32, u32, OutputType), OutputState>,
+}
+
+impl OutputArbiter {
+  pub(crate) fn new(policy: OutputArbitrationPolicy) -> Self {
+    Self {
+      policy,
+      sequence: AtomicU64::new(0),
+      state: DashMap::new(),
+    }
+  }
+
+  /// Records a command from a session and returns the command that should actually be sent to the
+  /// device. `max_value` is the largest value the output accepts.
+  pub(crate) fn arbitrate(
+    &self,
+    session: u32,
+    cmd: &CheckedOutputCmdV4,
+    max_value: i32,
+  ) -> Result<CheckedOutputCmdV4, ButtplugDeviceError> {
+    let output_type = cmd.output_command().as_output_type();
+    let mut state = self
+      .state
+      .entry((cmd.device_index(), cmd.feature_index(), output_type))
+      .or_default();
+    state.max_value = max_value;
+    if let OutputArbitrationPolicy::ExclusiveLock { lease } = self.policy {
+      let now = Instant::now();
+      if let Some((holder, expires)) = state.lock
+        && holder != session
+        && expires > now
+      {
+        return Err(ButtplugDeviceError::DeviceFeatureLocked(
+          cmd.device_index(),
+          cmd.feature_index(),
+        ));
+      }
+      state.lock = if cmd.output_command().value() == 0 && !is_position(output_type) {
+        None
+      } else {
+        Some((session, now + lease))
+      };
+    }
+    let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+    state.contributions.insert(
+      session,
+      Contribution {
+        command: cmd.clone(),
+        sequence,
+      },
+    );
+    Ok(state.effective(self.policy).unwrap_or_else(|| cmd.clone()))
+  }
+
+  /// True if a session other than `session` has commanded any output on the device.
+  pub(crate) fn has_other_sessions(&self, session: u32, device_index: u32) -> bool {
+    self.state.iter().any(|entry| {
+      entry.key().0 == device_index && entry.contributions.keys().any(|s| *s != session)
+    })
+  }
+
+  /// Forgets everything a session has commanded, optionally only on a single device. Returns the
+  /// commands needed to bring each affected output back to what the remaining sessions requested,
+  /// or to stop it if no other session was using it.
+  pub(crate) fn clear_session(
+    &self,
+    session: u32,
+    device_index: Option<u32>,
+  ) -> Vec<CheckedOutputCmdV4> {
+    let mut commands = vec![];
+    for mut entry in self.state.iter_mut() {
+      let (entry_device_index, _, _) = *entry.key();
+      if device_index.is_some_and(|index| index != entry_device_index) {
+        continue;
+      }
+      let state = entry.value_mut();
+      if state.lock.is_some_and(|(holder, _)| holder == session) {
+        state.lock = None;
+      }
+      let Some(removed) = state.contributions.remove(&session) else {
+        continue;
+      };
+      commands.extend(output_after_removal(
+        state.effective(self.policy),
+        &removed.command,
+      ));
+    }
+    self
+      .state
+      .retain(|_, state| !state.contributions.is_empty());
+    commands
+  }
+
+  /// Commands each session is running on a device, so they can be restarted if the device drops
+  /// out and comes back.
+  pub(crate) fn device_commands(&self, device_index: u32) -> Vec<(u32, CheckedOutputCmdV4)> {
+    self
+      .state
+      .iter()
+      .filter(|entry| entry.key().0 == device_index)
+      .flat_map(|entry| {
+        entry
+          .contributions
+          .iter()
+          .filter(|(_, contribution)| contribution.command.output_command().value() != 0)
+          .map(|(session, contribution)| (*session, contribution.command.clone()))
+          .collect::<Vec<_>>()
+      })
+      .collect()
+  }
+
+  /// Forgets all commands for a device, used when it disconnects.
+  pub(crate) fn clear_device(&self, device_index: u32) {
+    self.state.retain(|(index, _, _), _| *index != device_index);
+  }
+
+  /// Forgets all commands from every session.
+  pub(crate) fn clear_all(&self) {
+    self.state.clear();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use buttplug_core::message::{OutputCommand, OutputValue};
+  use uuid::Uuid;
+
+  fn vibrate(value: i32) -> CheckedOutputCmdV4 {
+    CheckedOutputCmdV4::new(
+      1,
+      0,
+      0,
+      Uuid::nil(),
+      OutputCommand::Vibrate(OutputValue::new(value)),
+    )
+  }
+
+  fn value(cmd: &CheckedOutputCmdV4) -> i32 {
+    cmd.output_command().value()
+  }
+
+  #[test]
+  fn test_max_and_sum_clamped() {
+    let arbiter = OutputArbiter::new(OutputArbitrationPolicy::Max);
+    assert_eq!(value(&arbiter.arbitrate(1, &vibrate(10), 20).unwrap()), 10);
+    assert_eq!(value(&arbiter.arbitrate(2, &vibrate(5), 20).unwrap()), 10);
+    assert_eq!(value(&arbiter.arbitrate(1, &vibrate(0), 20).unwrap()), 5);
+
+    let arbiter = OutputArbiter::new(OutputArbitrationPolicy::SumClamped);
+    assert_eq!(value(&arbiter.arbitrate(1, &vibrate(10), 20).unwrap()), 10);
+    assert_eq!(value(&arbiter.arbitrate(2, &vibrate(15), 20).unwrap()), 20);
+    // Ending a session brings the output back to what the remaining session asked for.
+    let commands = arbiter.clear_session(2, None);
+    assert_eq!(commands.len(), 1);
+    assert_eq!(value(&commands[0]), 10);
+    let commands = arbiter.clear_session(1, None);
+    assert_eq!(value(&commands[0]), 0);
+  }
+
+  #[test]
+  fn test_exclusive_lock() {
+    let arbiter = OutputArbiter::new(OutputArbitrationPolicy::ExclusiveLock {
+      lease: Duration::from_secs(60),
+    });
+    assert!(arbiter.arbitrate(1, &vibrate(10), 20).is_ok());
+    assert!(matches!(
+      arbiter.arbitrate(2, &vibrate(5), 20),
+      Err(ButtplugDeviceError::DeviceFeatureLocked(0, 0))
+    ));
+    // Stopping the output releases the lock.
+    assert!(arbiter.arbitrate(1, &vibrate(0), 20).is_ok());
+    assert_eq!(value(&arbiter.arbitrate(2, &vibrate(5), 20).unwrap()), 5);
+    // As does ending the session holding it.
+    arbiter.clear_session(2, None);
+    assert!(arbiter.arbitrate(1, &vibrate(7), 20).is_ok());
+  }
+}
@@ -0,0 +1,312 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Mixes output commands from multiple sources driving the same device feature.
+
+use crate::message::checked_output_cmd::CheckedOutputCmdV4;
+use buttplug_core::message::OutputType;
+use dashmap::DashMap;
+use getset::CopyGetters;
+use std::{
+  collections::HashMap,
+  sync::atomic::{AtomicU64, Ordering},
+  time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use super::output_values::{OutputCombineMode, combine_outputs, output_after_removal};
+
+/// Source name for output commands sent through the normal message path. This layer never expires.
+pub const DEFAULT_OUTPUT_LAYER: &str = "default";
+
+/// How to combine layers from different sources driving the same feature output.
+///
+/// Position outputs can't be meaningfully added or compared, so they always use the highest
+/// priority layer, with ties going to the most recently updated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputBlendMode {
+  /// The highest level from any layer is sent to the device.
+  #[default]
+  Max,
+  /// Levels from all layers are added together, clamped to the output's range.
+  AdditiveClamp,
+  /// The highest priority layer is sent to the device, with ties going to the most recently updated.
+  PriorityOverride,
+}
+
+/// Mixer settings, applied to every device connected to a device manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct OutputMixerConfig {
+  blend_mode: OutputBlendMode,
+  /// How long a layer other than [DEFAULT_OUTPUT_LAYER] lives without being updated. If None,
+  /// layers live until they are cleared or the device is stopped.
+  layer_timeout: Option<Duration>,
+}
+
+impl Default for OutputMixerConfig {
+  fn default() -> Self {
+    Self {
+      blend_mode: OutputBlendMode::default(),
+      layer_timeout: Some(Duration::from_secs(5)),
+    }
+  }
+}
+
+impl OutputMixerConfig {
+  pub fn new(blend_mode: OutputBlendMode, layer_timeout: Option<Duration>) -> Self {
+    Self {
+      blend_mode,
+      layer_timeout,
+    }
+  }
+}
+
+impl From<OutputBlendMode> for OutputCombineMode {
+  fn from(mode: OutputBlendMode) -> Self {
+    match mode {
+      OutputBlendMode::Max => OutputCombineMode::Max,
+      OutputBlendMode::AdditiveClamp => OutputCombineMode::SumClamped,
+      OutputBlendMode::PriorityOverride => OutputCombineMode::Winner,
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Layer {
+  command: CheckedOutputCmdV4,
+  priority: u32,
+  sequence: u64,
+  /// Sequence number of the update that created the layer, which identifies its expiry timer.
+  created: u64,
+  /// When the layer expires, if it can.
+  deadline: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct FeatureLayers {
+  layers: HashMap<String, Layer>,
+  /// Largest value the output accepts, used for clamping sums.
+  max_value: i32,
+}
+
+impl FeatureLayers {
+  fn mix(&self, blend_mode: OutputBlendMode) -> Option<CheckedOutputCmdV4> {
+    let top = self
+      .layers
+      .values()
+      .max_by_key(|l| (l.priority, l.sequence))?;
+    Some(combine_outputs(
+      &top.command,
+      self.layers.values().map(|l| &l.command),
+      blend_mode.into(),
+      self.max_value,
+    ))
+  }
+}
+
+/// Result of checking on a layer when its expiry timer fires.
+#[derive(Debug)]
+pub(crate) enum LayerExpiry {
+  /// The layer was updated since the timer was set, and now expires at the given time.
+  Extended(Instant),
+  /// The layer is gone, along with the command to send if that changed the mix.
+  Expired(Option<CheckedOutputCmdV4>),
+}
+
+/// Per-source output layers for every feature output on a device.
+pub(crate) struct OutputMixer {
+  config: OutputMixerConfig,
+  sequence: AtomicU64,
+  /// Keyed by feature id and output type.
+  features: DashMap<(Uuid, OutputType), FeatureLayers>,
+}
+
+impl OutputMixer {
+  pub(crate) fn new(config: OutputMixerConfig) -> Self {
+    Self {
+      config,
+      sequence: AtomicU64::new(0),
+      features: DashMap::new(),
+    }
+  }
+
+  pub(crate) fn layer_timeout(&self) -> Option<Duration> {
+    self.config.layer_timeout
+  }
+
+  /// Updates a source's layer and returns the mixed command to send to the device. If this created
+  /// a layer that can time out, also returns the layer id to hand to [OutputMixer::expire_layer]
+  /// once [OutputMixerConfig::layer_timeout] has passed. Updates to an existing layer push its
+  /// deadline back instead.
+  pub(crate) fn set_layer(
+    &self,
+    source: &str,
+    priority: u32,
+    cmd: &CheckedOutputCmdV4,
+    max_value: i32,
+  ) -> (CheckedOutputCmdV4, Option<u64>) {
+    let output_type = cmd.output_command().as_output_type();
+    let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+    let deadline = self
+      .config
+      .layer_timeout
+      .filter(|_| source != DEFAULT_OUTPUT_LAYER)
+      .map(|timeout| Instant::now() + timeout);
+    let mut feature = self
+      .features
+      .entry((cmd.feature_id(), output_type))
+      .or_default();
+    feature.max_value = max_value;
+    let created = feature
+      .layers
+      .get(source)
+      .map_or(sequence, |layer| layer.created);
+    feature.layers.insert(
+      source.to_owned(),
+      Layer {
+        command: cmd.clone(),
+        priority,
+        sequence,
+        created,
+        deadline,
+      },
+    );
+    let mixed = feature
+      .mix(self.config.blend_mode)
+      .unwrap_or_else(|| cmd.clone());
+    let new_timer = (deadline.is_some() && created == sequence).then_some(created);
+    (mixed, new_timer)
+  }
+
+  /// Checks on a layer when its expiry timer fires, removing it if it hasn't been updated within
+  /// the layer timeout.
+  pub(crate) fn expire_layer(
+    &self,
+    source: &str,
+    cmd: &CheckedOutputCmdV4,
+    created: u64,
+  ) -> LayerExpiry {
+    let output_type = cmd.output_command().as_output_type();
+    let Some(mut feature) = self.features.get_mut(&(cmd.feature_id(), output_type)) else {
+      return LayerExpiry::Expired(None);
+    };
+    // A layer that was cleared and then set again has a timer of its own.
+    let Some(layer) = feature.layers.get(source).filter(|l| l.created == created) else {
+      return LayerExpiry::Expired(None);
+    };
+    if let Some(deadline) = layer.deadline
+      && deadline > Instant::now()
+    {
+      return LayerExpiry::Extended(deadline);
+    }
+    let removed = feature.layers.remove(source).expect("Layer was just found");
+    LayerExpiry::Expired(output_after_removal(
+      feature.mix(self.config.blend_mode),
+      &removed.command,
+    ))
+  }
+
+  /// Removes a source's layers on every feature, returning the new mixed commands.
+  pub(crate) fn clear_layer(&self, source: &str) -> Vec<CheckedOutputCmdV4> {
+    let mut commands = vec![];
+    for mut entry in self.features.iter_mut() {
+      if let Some(removed) = entry.layers.remove(source)
+        && let Some(cmd) = output_after_removal(entry.mix(self.config.blend_mode), &removed.command)
+      {
+        commands.push(cmd);
+      }
+    }
+    commands
+  }
+
+  /// Removes all layers, used when the device is stopped.
+  pub(crate) fn clear(&self) {
+    self.features.clear();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use buttplug_core::message::{OutputCommand, OutputValue};
+
+  fn vibrate(value: i32) -> CheckedOutputCmdV4 {
+    CheckedOutputCmdV4::new(
+      1,
+      0,
+      0,
+      Uuid::nil(),
+      OutputCommand::Vibrate(OutputValue::new(value)),
+    )
+  }
+
+  fn mixed_value(mixer: &OutputMixer, source: &str, priority: u32, value: i32) -> i32 {
+    mixer
+      .set_layer(source, priority, &vibrate(value), 20)
+      .0
+      .output_command()
+      .value()
+  }
+
+  #[test]
+  fn test_blend_modes() {
+    let mixer = OutputMixer::new(OutputMixerConfig::new(OutputBlendMode::Max, None));
+    assert_eq!(mixed_value(&mixer, DEFAULT_OUTPUT_LAYER, 0, 10), 10);
+    assert_eq!(mixed_value(&mixer, "pattern", 0, 5), 10);
+    assert_eq!(mixed_value(&mixer, DEFAULT_OUTPUT_LAYER, 0, 0), 5);
+
+    let mixer = OutputMixer::new(OutputMixerConfig::new(OutputBlendMode::AdditiveClamp, None));
+    assert_eq!(mixed_value(&mixer, DEFAULT_OUTPUT_LAYER, 0, 10), 10);
+    assert_eq!(mixed_value(&mixer, "pattern", 0, 15), 20);
+    let commands = mixer.clear_layer("pattern");
+    assert_eq!(commands[0].output_command().value(), 10);
+
+    let mixer = OutputMixer::new(OutputMixerConfig::new(
+      OutputBlendMode::PriorityOverride,
+      None,
+    ));
+    assert_eq!(mixed_value(&mixer, "game", 10, 15), 15);
+    assert_eq!(mixed_value(&mixer, "pattern", 1, 5), 15);
+    assert_eq!(mixed_value(&mixer, "game", 10, 2), 2);
+  }
+
+  #[test]
+  fn test_layer_expiry() {
+    let mixer = OutputMixer::new(OutputMixerConfig::default());
+    let (_, timer) = mixer.set_layer(DEFAULT_OUTPUT_LAYER, 0, &vibrate(5), 20);
+    assert!(timer.is_none());
+    let (_, timer) = mixer.set_layer("pattern", 0, &vibrate(15), 20);
+    let layer = timer.expect("Non-default layers should expire");
+    // Updating the layer pushes its deadline back, without needing another timer.
+    let (_, timer) = mixer.set_layer("pattern", 0, &vibrate(12), 20);
+    assert!(timer.is_none());
+    assert!(matches!(
+      mixer.expire_layer("pattern", &vibrate(0), layer),
+      LayerExpiry::Extended(_)
+    ));
+
+    let mixer = OutputMixer::new(OutputMixerConfig::new(
+      OutputBlendMode::Max,
+      Some(Duration::ZERO),
+    ));
+    mixer.set_layer(DEFAULT_OUTPUT_LAYER, 0, &vibrate(5), 20);
+    let (_, timer) = mixer.set_layer("pattern", 0, &vibrate(15), 20);
+    let stale = timer.unwrap();
+    // A layer that was cleared and set again belongs to a new timer.
+    mixer.clear_layer("pattern");
+    let (_, timer) = mixer.set_layer("pattern", 0, &vibrate(12), 20);
+    assert!(matches!(
+      mixer.expire_layer("pattern", &vibrate(0), stale),
+      LayerExpiry::Expired(None)
+    ));
+    match mixer.expire_layer("pattern", &vibrate(0), timer.unwrap()) {
+      LayerExpiry::Expired(Some(cmd)) => assert_eq!(cmd.output_command().value(), 5),
+      expiry => panic!("Expiring a layer should remix the remaining layers, got {expiry:?}"),
+    }
+  }
+}
@@ -0,0 +1,99 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Value handling shared by everything that combines or rewrites feature output commands before
+//! they reach a device.
+
+use crate::message::checked_output_cmd::CheckedOutputCmdV4;
+use buttplug_core::message::OutputType;
+use buttplug_server_device_config::ServerDeviceDefinition;
+
+/// Position outputs can't be meaningfully added, compared or ramped.
+pub(crate) fn is_position(output_type: OutputType) -> bool {
+  matches!(
+    output_type,
+    OutputType::Position | OutputType::HwPositionWithDuration
+  )
+}
+
+/// Copy of a command with a different value.
+pub(crate) fn with_value(cmd: &CheckedOutputCmdV4, value: i32) -> CheckedOutputCmdV4 {
+  let mut command = cmd.output_command();
+  command.set_value(value);
+  CheckedOutputCmdV4::new(
+    cmd.id(),
+    cmd.device_index(),
+    cmd.feature_index(),
+    cmd.feature_id(),
+    command,
+  )
+}
+
+/// Largest value the output targeted by the command accepts, taking user ranges into account.
+pub(crate) fn output_max_value(
+  definition: &ServerDeviceDefinition,
+  cmd: &CheckedOutputCmdV4,
+) -> i32 {
+  definition
+    .features()
+    .get(&cmd.feature_index())
+    .and_then(|feature| feature.output().as_ref())
+    .and_then(|output| output.value_range(cmd.output_command().as_output_type()))
+    .map_or(0, |range| {
+      range
+        .user()
+        .as_ref()
+        .map_or(*range.internal_base().end(), |user| *user.end()) as i32
+    })
+}
+
+/// How the values of several commands driving the same feature output are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputCombineMode {
+  /// Only the winning command is used.
+  Winner,
+  /// The highest value is used.
+  Max,
+  /// Values are added together, clamped to the output's range.
+  SumClamped,
+}
+
+/// Combines the commands driving a feature output. `winner` is the command that takes over the
+/// output outright, and the one whose id is kept when values are combined. Position outputs always
+/// use the winner.
+pub(crate) fn combine_outputs<'a>(
+  winner: &CheckedOutputCmdV4,
+  commands: impl Iterator<Item = &'a CheckedOutputCmdV4>,
+  mode: OutputCombineMode,
+  max_value: i32,
+) -> CheckedOutputCmdV4 {
+  if is_position(winner.output_command().as_output_type()) {
+    return winner.clone();
+  }
+  let values = commands.map(|cmd| cmd.output_command().value());
+  match mode {
+    OutputCombineMode::Winner => winner.clone(),
+    OutputCombineMode::Max => values
+      .max()
+      .map_or_else(|| winner.clone(), |value| with_value(winner, value)),
+    OutputCombineMode::SumClamped => {
+      let sum = values.sum::<i32>().clamp(-max_value, max_value);
+      with_value(winner, sum)
+    }
+  }
+}
+
+/// Command to send once `removed` stops driving an output, given whatever still drives it. Outputs
+/// nothing drives anymore are stopped, except for positions, which stay where they are.
+pub(crate) fn output_after_removal(
+  combined: Option<CheckedOutputCmdV4>,
+  removed: &CheckedOutputCmdV4,
+) -> Option<CheckedOutputCmdV4> {
+  combined.or_else(|| {
+    (!is_position(removed.output_command().as_output_type())).then(|| with_value(removed, 0))
+  })
+}
@@ -90,21 +90,28 @@ fn print_type_of<T>(_: &T) -> &'static str {
 }
 
 pub struct ProtocolSpecializer {
+  protocol: String,
   specifiers: Vec<ProtocolCommunicationSpecifier>,
   identifier: Box<dyn ProtocolIdentifier>,
 }
 
 impl ProtocolSpecializer {
   pub fn new(
+    protocol: &str,
     specifiers: Vec<ProtocolCommunicationSpecifier>,
     identifier: Box<dyn ProtocolIdentifier>,
   ) -> Self {
     Self {
+      protocol: protocol.to_owned(),
       specifiers,
       identifier,
     }
   }
 
+  pub fn protocol(&self) -> &str {
+    &self.protocol
+  }
+
   pub fn specifiers(&self) -> &Vec<ProtocolCommunicationSpecifier> {
     &self.specifiers
   }
@@ -555,6 +562,20 @@ impl Default for ProtocolManager {
 }
 
 impl ProtocolManager {
+  /// Registers a protocol implementation alongside the built-in ones. Fails if a protocol with the
+  /// same name already exists.
+  pub fn add_protocol_factory(
+    &mut self,
+    factory: Arc<dyn ProtocolIdentifierFactory>,
+  ) -> Result<(), ButtplugDeviceError> {
+    let name = factory.identifier().to_owned();
+    if self.protocol_map.contains_key(&name) {
+      return Err(ButtplugDeviceError::ProtocolAlreadyAdded(name));
+    }
+    self.protocol_map.insert(name, factory);
+    Ok(())
+  }
+
   pub fn protocol_specializers(
     &self,
     specifier: &ProtocolCommunicationSpecifier,
@@ -575,6 +596,7 @@ impl ProtocolManager {
           );
           if self.protocol_map.contains_key(name) {
             specializers.push(ProtocolSpecializer::new(
+              name,
               specifiers.clone(),
               self
                 .protocol_map
@@ -0,0 +1,232 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Data driven protocol for simple devices that take a fixed packet with values substituted in.
+//!
+//! The packet layout lives in the `byte_template` field of the device configuration, so new devices
+//! of this type can be added without any protocol code. This includes devices controlled through
+//! Bluetooth LE advertisements, where the packet is the manufacturer data to broadcast.
+
+use crate::{
+  device::{
+    hardware::{Hardware, HardwareCommand, HardwareReadCmd, HardwareWriteCmd},
+    protocol::{
+      ProtocolHandler,
+      ProtocolIdentifier,
+      ProtocolInitializer,
+      ProtocolKeepaliveStrategy,
+      generic_protocol_initializer_setup,
+    },
+  },
+  message::checked_output_cmd::CheckedOutputCmdV4,
+};
+use async_trait::async_trait;
+use buttplug_core::{
+  errors::ButtplugDeviceError,
+  message::{InputReadingV4, InputTypeReading, InputValue, OutputCommand},
+};
+use buttplug_server_device_config::{
+  ByteTemplate,
+  ByteTemplateChecksum,
+  ByteTemplateField,
+  ByteTemplateSlot,
+  Endpoint,
+  ProtocolCommunicationSpecifier,
+  ServerDeviceDefinition,
+  UserDeviceIdentifier,
+};
+use futures::{
+  FutureExt,
+  future::{self, BoxFuture},
+};
+use std::{
+  sync::{
+    Arc,
+    atomic::{AtomicU8, Ordering},
+  },
+  time::Duration,
+};
+use uuid::{Uuid, uuid};
+
+const BYTE_TEMPLATE_PROTOCOL_UUID: Uuid = uuid!("3b5e4a1c-8d27-4f0b-9c61-2f7a8e0d5b94");
+const BYTE_TEMPLATE_PROTOCOL_NAME: &str = "byte-template";
+
+generic_protocol_initializer_setup!(ByteTemplate, "byte-template");
+
+#[derive(Default)]
+pub struct ByteTemplateInitializer {}
+
+#[async_trait]
+impl ProtocolInitializer for ByteTemplateInitializer {
+  async fn initialize(
+    &mut self,
+    _hardware: Arc<Hardware>,
+    def: &ServerDeviceDefinition,
+  ) -> Result<Arc<dyn ProtocolHandler>, ButtplugDeviceError> {
+    let template = def.byte_template().as_ref().ok_or_else(|| {
+      ButtplugDeviceError::DeviceConfigurationError(format!(
+        "Device {} uses the byte-template protocol but has no byte_template defined.",
+        def.name()
+      ))
+    })?;
+    Ok(Arc::new(ByteTemplateProtocol::new(
+      template,
+      def.features().len(),
+    )?))
+  }
+}
+
+pub struct ByteTemplateProtocol {
+  template: ByteTemplate,
+  last_values: Vec<AtomicU8>,
+  combined_packet: bool,
+}
+
+impl ByteTemplateProtocol {
+  pub fn new(template: &ByteTemplate, feature_count: usize) -> Result<Self, ButtplugDeviceError> {
+    let mut combined_packet = false;
+    for field in template.packet() {
+      match field {
+        ByteTemplateField::Slot(ByteTemplateSlot::Checksum) if template.checksum().is_none() => {
+          return Err(ButtplugDeviceError::DeviceConfigurationError(
+            "Byte template has a checksum slot but no checksum type.".to_owned(),
+          ));
+        }
+        ByteTemplateField::Slot(ByteTemplateSlot::FeatureValue(index)) => {
+          if *index as usize >= feature_count {
+            return Err(ButtplugDeviceError::DeviceConfigurationError(format!(
+              "Byte template refers to feature {index} but device only has {feature_count} features."
+            )));
+          }
+          combined_packet = true;
+        }
+        _ => {}
+      }
+    }
+    Ok(Self {
+      template: template.clone(),
+      last_values: (0..feature_count).map(|_| AtomicU8::new(0)).collect(),
+      combined_packet,
+    })
+  }
+
+  fn render(&self, feature_index: u32, value: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(self.template.packet().len());
+    for field in self.template.packet() {
+      let byte = match field {
+        ByteTemplateField::Byte(b) => *b,
+        ByteTemplateField::Slot(slot) => match slot {
+          ByteTemplateSlot::Value => value,
+          ByteTemplateSlot::Active => (value != 0) as u8,
+          ByteTemplateSlot::FeatureIndex => feature_index as u8,
+          ByteTemplateSlot::FeatureValue(index) => {
+            self.last_values[*index as usize].load(Ordering::Relaxed)
+          }
+          ByteTemplateSlot::Checksum => match self.template.checksum() {
+            Some(ByteTemplateChecksum::Sum) => data.iter().fold(0u8, |acc, x| acc.wrapping_add(*x)),
+            Some(ByteTemplateChecksum::Xor) => data.iter().fold(0u8, |acc, x| acc ^ x),
+            None => unreachable!("Byte template checksum type checked on creation"),
+          },
+        },
+      };
+      data.push(byte);
+    }
+    data
+  }
+}
+
+impl ProtocolHandler for ByteTemplateProtocol {
+  fn keepalive_strategy(&self) -> ProtocolKeepaliveStrategy {
+    match self.template.keepalive_interval_ms() {
+      Some(interval) => ProtocolKeepaliveStrategy::RepeatLastPacketStrategyWithTiming(
+        Duration::from_millis(interval as u64),
+      ),
+      None => ProtocolKeepaliveStrategy::HardwareRequiredRepeatLastPacketStrategy,
+    }
+  }
+
+  fn handle_output_cmd(
+    &self,
+    cmd: &CheckedOutputCmdV4,
+  ) -> Result<Vec<HardwareCommand>, ButtplugDeviceError> {
+    let output_command = cmd.output_command();
+    if let OutputCommand::HwPositionWithDuration(_) = output_command {
+      return Err(ButtplugDeviceError::OutputNotSupported(
+        output_command.as_output_type(),
+      ));
+    }
+    let value = output_command.value();
+    if value < 0 {
+      return Err(ButtplugDeviceError::DeviceCommandSignError);
+    }
+    let value = u8::try_from(value).map_err(|_| {
+      ButtplugDeviceError::ProtocolSpecificError(
+        BYTE_TEMPLATE_PROTOCOL_NAME.to_owned(),
+        format!("Value {value} does not fit in a byte template slot."),
+      )
+    })?;
+    let feature_index = cmd.feature_index();
+    if let Some(last_value) = self.last_values.get(feature_index as usize) {
+      last_value.store(value, Ordering::Relaxed);
+    }
+    let feature_id = if self.combined_packet {
+      BYTE_TEMPLATE_PROTOCOL_UUID
+    } else {
+      cmd.feature_id()
+    };
+    Ok(vec![
+      HardwareWriteCmd::new(
+        &[feature_id],
+        self.template.endpoint(),
+        self.render(feature_index, value),
+        self.template.write_with_response(),
+      )
+      .into(),
+    ])
+  }
+
+  fn handle_battery_level_cmd(
+    &self,
+    device_index: u32,
+    device: Arc<Hardware>,
+    feature_index: u32,
+    feature_id: Uuid,
+  ) -> BoxFuture<'_, Result<InputReadingV4, ButtplugDeviceError>> {
+    // Without an offset, fall back to the standard BLE battery endpoint, which is a single byte.
+    let (endpoint, offset) = match self.template.battery_offset() {
+      Some(offset) => (Endpoint::Rx, offset as usize),
+      None => (Endpoint::RxBLEBattery, 0),
+    };
+    if !device.endpoints().contains(&endpoint) {
+      return future::ready(Err(ButtplugDeviceError::UnhandledCommand(
+        "Command not implemented for this protocol: SensorReadCmd".to_string(),
+      )))
+      .boxed();
+    }
+    let msg = HardwareReadCmd::new(feature_id, endpoint, offset as u32 + 1, 0);
+    let fut = device.read_value(&msg);
+    async move {
+      let hw_msg = fut.await?;
+      let battery_level = *hw_msg.data().get(offset).ok_or_else(|| {
+        ButtplugDeviceError::ProtocolSpecificError(
+          BYTE_TEMPLATE_PROTOCOL_NAME.to_owned(),
+          format!(
+            "Battery data is {} bytes, too short to read offset {offset}.",
+            hw_msg.data().len()
+          ),
+        )
+      })?;
+      debug!("Got battery reading: {}", battery_level);
+      Ok(InputReadingV4::new(
+        device_index,
+        feature_index,
+        InputTypeReading::Battery(InputValue::new(battery_level)),
+      ))
+    }
+    .boxed()
+  }
+}
@@ -19,6 +19,7 @@ pub mod amorelie_joy;
 pub mod aneros;
 pub mod ankni;
 pub mod bananasome;
+pub mod byte_template;
 pub mod cachito;
 pub mod cowgirl;
 pub mod cowgirl_cone;
@@ -157,6 +158,10 @@ pub fn get_default_protocol_map() -> HashMap<String, Arc<dyn ProtocolIdentifierF
     &mut map,
     bananasome::setup::BananasomeIdentifierFactory::default(),
   );
+  add_to_protocol_map(
+    &mut map,
+    byte_template::setup::ByteTemplateIdentifierFactory::default(),
+  );
   add_to_protocol_map(
     &mut map,
     cachito::setup::CachitoIdentifierFactory::default(),
@@ -358,7 +363,10 @@ pub fn get_default_protocol_map() -> HashMap<String, Arc<dyn ProtocolIdentifierF
     &mut map,
     motorbunny::setup::MotorbunnyIdentifierFactory::default(),
   );
-  add_to_protocol_map(&mut map, mymuselinkplus::setup::MyMuseLinkPlusIdentifierFactory::default());
+  add_to_protocol_map(
+    &mut map,
+    mymuselinkplus::setup::MyMuseLinkPlusIdentifierFactory::default(),
+  );
   add_to_protocol_map(
     &mut map,
     mysteryvibe::setup::MysteryVibeIdentifierFactory::default(),
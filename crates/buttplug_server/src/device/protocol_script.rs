@@ -0,0 +1,767 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Protocol handlers implemented as [Rhai](https://rhai.rs) scripts.
+//!
+//! Scripted protocols allow supporting devices that are too complex for byte templates, but not
+//! worth a library release, by dropping a script in the user configuration directory. Each script is
+//! registered under the protocol name taken from its file name, and devices are matched to it via
+//! the communication specifiers of that protocol in the user device configuration.
+//!
+//! Scripts can define any of the following functions, all of which are called with a per-device
+//! state map bound to `this`:
+//!
+//! - `initialize(hardware, device)`: Called once on connection. `hardware` has `write(endpoint,
+//!   data)`, `write(endpoint, data, write_with_response)`, `read(endpoint, length)` and
+//!   `subscribe(endpoint)` methods. `device` is a map with the `name` and `variant` of the device.
+//! - `keepalive_strategy()`: Returns either `#{ repeat_last_packet_ms: <interval> }` or
+//!   `#{ repeat_packet: <packet> }`. If not defined, the last packet is repeated only when the
+//!   hardware manager requires it.
+//! - `handle_output_<type>_cmd(feature_index, value)` (`handle_output_vibrate_cmd`,
+//!   `handle_output_rotate_cmd`, etc...) and `handle_hw_position_with_duration_cmd(feature_index,
+//!   position, duration)`: Return an array of packets, which are maps with `endpoint`, `data` and an
+//!   optional `write_with_response` field.
+//! - `handle_input_read_cmd(hardware, input_type, feature_index)`: Returns the value read for the
+//!   input.
+//!
+//! Scripts have no file or network access, and are limited in the number of operations they can run
+//! per call. Calls that are given hardware access also have a wall clock time limit.
+
+use crate::{
+  device::{
+    hardware::{
+      Hardware,
+      HardwareCommand,
+      HardwareReadCmd,
+      HardwareReading,
+      HardwareSubscribeCmd,
+      HardwareWriteCmd,
+    },
+    protocol::{
+      ProtocolHandler,
+      ProtocolIdentifier,
+      ProtocolIdentifierFactory,
+      ProtocolInitializer,
+      ProtocolKeepaliveStrategy,
+    },
+  },
+  message::checked_output_cmd::CheckedOutputCmdV4,
+};
+use async_trait::async_trait;
+use buttplug_core::{
+  errors::ButtplugDeviceError,
+  message::{InputReadingV4, InputType, InputTypeReading, InputValue, OutputCommand, OutputType},
+  util::async_manager,
+};
+use buttplug_server_device_config::{
+  Endpoint,
+  ProtocolCommunicationSpecifier,
+  ServerDeviceDefinition,
+  UserDeviceIdentifier,
+};
+use futures::{
+  FutureExt,
+  StreamExt,
+  channel::{mpsc, oneshot},
+  future::BoxFuture,
+  select,
+};
+use rhai::{AST, Array, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope};
+use std::{
+  fmt::Display,
+  path::Path,
+  str::FromStr,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use uuid::{Uuid, uuid};
+
+const SCRIPT_PROTOCOL_UUID: Uuid = uuid!("5d1f0a9e-7b3c-4e62-8a4f-1c9b2e7d6a30");
+const SCRIPT_FILE_EXTENSION: &str = "rhai";
+const SCRIPT_MAX_OPERATIONS: u64 = 100_000;
+const SCRIPT_MAX_CALL_LEVELS: usize = 32;
+const SCRIPT_MAX_COLLECTION_SIZE: usize = 4096;
+const SCRIPT_HARDWARE_TIMEOUT: Duration = Duration::from_secs(10);
+const SCRIPT_READ_TIMEOUT_MS: u32 = 500;
+
+fn script_error(protocol_name: &str, err: impl Display) -> ButtplugDeviceError {
+  ButtplugDeviceError::ProtocolSpecificError(protocol_name.to_owned(), err.to_string())
+}
+
+fn parse_endpoint(endpoint: &str) -> Result<Endpoint, String> {
+  Endpoint::from_str(endpoint).map_err(|_| format!("Unknown endpoint {endpoint}"))
+}
+
+fn parse_bytes(data: &Array) -> Result<Vec<u8>, String> {
+  data
+    .iter()
+    .map(|x| {
+      x.as_int()
+        .ok()
+        .and_then(|x| u8::try_from(x).ok())
+        .ok_or_else(|| format!("Packet data must be bytes, found {x}"))
+    })
+    .collect()
+}
+
+fn parse_packet(packet: Dynamic, feature_id: Uuid) -> Result<HardwareWriteCmd, String> {
+  let packet = packet
+    .try_cast::<Map>()
+    .ok_or("Packets must be maps with endpoint and data fields")?;
+  let endpoint = packet
+    .get("endpoint")
+    .and_then(|x| x.clone().into_immutable_string().ok())
+    .ok_or("Packet endpoint must be a string")?;
+  let data = packet
+    .get("data")
+    .and_then(|x| x.clone().try_cast::<Array>())
+    .ok_or("Packet data must be an array")?;
+  let write_with_response = match packet.get("write_with_response") {
+    Some(x) => x
+      .as_bool()
+      .map_err(|_| "Packet write_with_response must be a bool")?,
+    None => false,
+  };
+  Ok(HardwareWriteCmd::new(
+    &[feature_id],
+    parse_endpoint(&endpoint)?,
+    parse_bytes(&data)?,
+    write_with_response,
+  ))
+}
+
+fn output_fn_name(output_type: OutputType) -> &'static str {
+  match output_type {
+    OutputType::Vibrate => "handle_output_vibrate_cmd",
+    OutputType::Rotate => "handle_output_rotate_cmd",
+    OutputType::Oscillate => "handle_output_oscillate_cmd",
+    OutputType::Constrict => "handle_output_constrict_cmd",
+    OutputType::Temperature => "handle_output_temperature_cmd",
+    OutputType::Led => "handle_output_led_cmd",
+    OutputType::Position => "handle_output_position_cmd",
+    OutputType::HwPositionWithDuration => "handle_hw_position_with_duration_cmd",
+    OutputType::Spray => "handle_output_spray_cmd",
+    OutputType::Unknown => "handle_output_unknown_cmd",
+  }
+}
+
+fn input_type_name(input_type: InputType) -> &'static str {
+  match input_type {
+    InputType::Battery => "battery",
+    InputType::Rssi => "rssi",
+    InputType::Button => "button",
+    InputType::Pressure => "pressure",
+    _ => "unknown",
+  }
+}
+
+fn input_type_reading(input_type: InputType, value: i64) -> Option<InputTypeReading> {
+  match input_type {
+    InputType::Battery => Some(InputTypeReading::Battery(InputValue::new(
+      u8::try_from(value).ok()?,
+    ))),
+    InputType::Rssi => Some(InputTypeReading::Rssi(InputValue::new(
+      i8::try_from(value).ok()?,
+    ))),
+    InputType::Button => Some(InputTypeReading::Button(InputValue::new(
+      u8::try_from(value).ok()?,
+    ))),
+    InputType::Pressure => Some(InputTypeReading::Pressure(InputValue::new(
+      u32::try_from(value).ok()?,
+    ))),
+    _ => None,
+  }
+}
+
+/// Hardware access requested by a script. Scripts with hardware access run synchronously on a
+/// [ScriptWorker] thread, so requests are passed back to the async side and the script blocks until
+/// they're answered.
+enum ScriptHardwareRequest {
+  Write(
+    HardwareWriteCmd,
+    oneshot::Sender<Result<(), ButtplugDeviceError>>,
+  ),
+  Read(
+    HardwareReadCmd,
+    oneshot::Sender<Result<HardwareReading, ButtplugDeviceError>>,
+  ),
+  Subscribe(
+    HardwareSubscribeCmd,
+    oneshot::Sender<Result<(), ButtplugDeviceError>>,
+  ),
+}
+
+#[derive(Clone)]
+struct ScriptHardware {
+  requests: mpsc::UnboundedSender<ScriptHardwareRequest>,
+}
+
+impl ScriptHardware {
+  fn request<T>(
+    &self,
+    request: impl FnOnce(oneshot::Sender<Result<T, ButtplugDeviceError>>) -> ScriptHardwareRequest,
+  ) -> Result<T, Box<EvalAltResult>> {
+    let (sender, receiver) = oneshot::channel();
+    self
+      .requests
+      .unbounded_send(request(sender))
+      .map_err(|_| "Hardware is no longer available to this script")?;
+    futures::executor::block_on(receiver)
+      .map_err(|_| "Hardware is no longer available to this script")?
+      .map_err(|e| e.to_string().into())
+  }
+
+  fn write(
+    &mut self,
+    endpoint: &str,
+    data: Array,
+    write_with_response: bool,
+  ) -> Result<(), Box<EvalAltResult>> {
+    let cmd = HardwareWriteCmd::new(
+      &[SCRIPT_PROTOCOL_UUID],
+      parse_endpoint(endpoint)?,
+      parse_bytes(&data)?,
+      write_with_response,
+    );
+    self.request(|sender| ScriptHardwareRequest::Write(cmd, sender))
+  }
+
+  fn read(&mut self, endpoint: &str, length: i64) -> Result<Array, Box<EvalAltResult>> {
+    let length = u32::try_from(length).map_err(|_| format!("Invalid read length {length}"))?;
+    let cmd = HardwareReadCmd::new(
+      SCRIPT_PROTOCOL_UUID,
+      parse_endpoint(endpoint)?,
+      length,
+      SCRIPT_READ_TIMEOUT_MS,
+    );
+    let reading = self.request(|sender| ScriptHardwareRequest::Read(cmd, sender))?;
+    Ok(
+      reading
+        .data()
+        .iter()
+        .map(|x| Dynamic::from_int(*x as i64))
+        .collect(),
+    )
+  }
+
+  fn subscribe(&mut self, endpoint: &str) -> Result<(), Box<EvalAltResult>> {
+    let cmd = HardwareSubscribeCmd::new(SCRIPT_PROTOCOL_UUID, parse_endpoint(endpoint)?);
+    self.request(|sender| ScriptHardwareRequest::Subscribe(cmd, sender))
+  }
+}
+
+async fn handle_hardware_request(hardware: &Hardware, request: ScriptHardwareRequest) {
+  // If the script has gone away (usually due to a timeout), there's no one to give the result to.
+  match request {
+    ScriptHardwareRequest::Write(cmd, sender) => {
+      let _ = sender.send(hardware.write_value(&cmd).await);
+    }
+    ScriptHardwareRequest::Read(cmd, sender) => {
+      let _ = sender.send(hardware.read_value(&cmd).await);
+    }
+    ScriptHardwareRequest::Subscribe(cmd, sender) => {
+      let _ = sender.send(hardware.subscribe(&cmd).await);
+    }
+  }
+}
+
+type ScriptJob = Box<dyn FnOnce() + Send>;
+
+/// Long lived thread that runs the script calls for a device that need hardware access, since those
+/// block while waiting on the hardware. The thread exits once the worker is dropped.
+struct ScriptWorker {
+  jobs: std::sync::mpsc::Sender<ScriptJob>,
+}
+
+impl ScriptWorker {
+  fn new(protocol_name: &str) -> Result<Self, ButtplugDeviceError> {
+    let (jobs, job_receiver) = std::sync::mpsc::channel::<ScriptJob>();
+    std::thread::Builder::new()
+      .name(format!("{protocol_name} protocol script"))
+      .spawn(move || {
+        while let Ok(job) = job_receiver.recv() {
+          job();
+        }
+      })
+      .map_err(|e| script_error(protocol_name, e))?;
+    Ok(Self { jobs })
+  }
+
+  fn run(&self, job: ScriptJob) -> Result<(), String> {
+    self
+      .jobs
+      .send(job)
+      .map_err(|_| "Script worker is no longer running".to_owned())
+  }
+}
+
+/// Merges the changes a hardware call made to its copy of the script state into the current state.
+/// Output commands can be handled while the call is waiting on hardware, and their changes to other
+/// keys shouldn't be lost.
+fn merge_state(current: &mut Dynamic, before: &Dynamic, after: Dynamic) {
+  // Dynamic values have no equality, but their debug output covers both type and value.
+  fn changed(before: Option<&Dynamic>, after: &Dynamic) -> bool {
+    before.is_none_or(|before| format!("{before:?}") != format!("{after:?}"))
+  }
+  let (Some(mut current_map), Some(before_map), true) = (
+    current.write_lock::<Map>(),
+    before.read_lock::<Map>(),
+    after.is_map(),
+  ) else {
+    // The script replaced the whole state with something other than a map, so take it as is.
+    *current = after;
+    return;
+  };
+  let after = after.cast::<Map>();
+  for key in before_map.keys() {
+    if !after.contains_key(key) {
+      current_map.remove(key);
+    }
+  }
+  for (key, value) in after {
+    if changed(before_map.get(&key), &value) {
+      current_map.insert(key, value);
+    }
+  }
+}
+
+struct ProtocolScript {
+  protocol_name: String,
+  engine: Engine,
+  ast: AST,
+  hardware_timeout: Duration,
+}
+
+impl ProtocolScript {
+  fn new(
+    protocol_name: &str,
+    source: &str,
+    hardware_timeout: Duration,
+  ) -> Result<Self, ButtplugDeviceError> {
+    let mut engine = Engine::new();
+    engine
+      .set_max_operations(SCRIPT_MAX_OPERATIONS)
+      .set_max_call_levels(SCRIPT_MAX_CALL_LEVELS)
+      .set_max_string_size(SCRIPT_MAX_COLLECTION_SIZE)
+      .set_max_array_size(SCRIPT_MAX_COLLECTION_SIZE)
+      .set_max_map_size(SCRIPT_MAX_COLLECTION_SIZE);
+    let print_name = protocol_name.to_owned();
+    engine.on_print(move |s| info!("Protocol script {print_name}: {s}"));
+    let debug_name = protocol_name.to_owned();
+    engine.on_debug(move |s, _, pos| debug!("Protocol script {debug_name} ({pos}): {s}"));
+    engine
+      .register_type_with_name::<ScriptHardware>("Hardware")
+      .register_fn(
+        "write",
+        |hardware: &mut ScriptHardware, endpoint: &str, data: Array| {
+          hardware.write(endpoint, data, false)
+        },
+      )
+      .register_fn("write", ScriptHardware::write)
+      .register_fn("read", ScriptHardware::read)
+      .register_fn("subscribe", ScriptHardware::subscribe);
+    let ast = engine
+      .compile(source)
+      .map_err(|e| script_error(protocol_name, e))?;
+    Ok(Self {
+      protocol_name: protocol_name.to_owned(),
+      engine,
+      ast,
+      hardware_timeout,
+    })
+  }
+
+  fn has_fn(&self, name: &str, param_count: usize) -> bool {
+    self
+      .ast
+      .iter_functions()
+      .any(|f| f.name == name && f.params.len() == param_count)
+  }
+
+  fn call(
+    &self,
+    state: &mut Dynamic,
+    name: &str,
+    args: impl FuncArgs,
+  ) -> Result<Dynamic, ButtplugDeviceError> {
+    let options = CallFnOptions::new().bind_this_ptr(state);
+    self
+      .engine
+      .call_fn_with_options(options, &mut Scope::new(), &self.ast, name, args)
+      .map_err(|e| script_error(&self.protocol_name, e))
+  }
+
+  /// Runs a script function that takes the hardware as its first argument on the worker thread, and
+  /// services hardware requests from the script until it finishes. Returns the updated state along
+  /// with the function result.
+  async fn call_with_hardware(
+    self: Arc<Self>,
+    worker: &ScriptWorker,
+    hardware: Arc<Hardware>,
+    mut state: Dynamic,
+    name: &'static str,
+    mut args: Vec<Dynamic>,
+  ) -> Result<(Dynamic, Dynamic), ButtplugDeviceError> {
+    let (request_sender, mut requests) = mpsc::unbounded();
+    let (result_sender, result_receiver) = oneshot::channel();
+    args.insert(
+      0,
+      Dynamic::from(ScriptHardware {
+        requests: request_sender,
+      }),
+    );
+    let script = self.clone();
+    worker
+      .run(Box::new(move || {
+        let result = script.call(&mut state, name, args);
+        let _ = result_sender.send(result.map(|value| (state, value)));
+      }))
+      .map_err(|e| script_error(&self.protocol_name, e))?;
+
+    let run = async {
+      let mut result_receiver = result_receiver.fuse();
+      loop {
+        select! {
+          request = requests.next() => {
+            if let Some(request) = request {
+              handle_hardware_request(&hardware, request).await;
+            }
+          }
+          result = result_receiver => {
+            return result.map_err(|_| {
+              script_error(&self.protocol_name, format!("Script function {name} did not finish"))
+            })?;
+          }
+        }
+      }
+    };
+    // Race the whole exchange, so hardware that never answers a request can't hold up the caller.
+    select! {
+      result = run.boxed().fuse() => result,
+      _ = async_manager::sleep(self.hardware_timeout).boxed().fuse() => Err(script_error(
+        &self.protocol_name,
+        format!("Script function {name} timed out"),
+      )),
+    }
+  }
+}
+
+/// Factory for a protocol implemented by a script. Register with
+/// [ServerDeviceManagerBuilder::protocol_factory](crate::device::ServerDeviceManagerBuilder::protocol_factory).
+pub struct ScriptProtocolIdentifierFactory {
+  script: Arc<ProtocolScript>,
+}
+
+impl ScriptProtocolIdentifierFactory {
+  /// Compiles a protocol script. Fails if the script has syntax errors.
+  pub fn new(protocol_name: &str, source: &str) -> Result<Self, ButtplugDeviceError> {
+    Self::new_with_hardware_timeout(protocol_name, source, SCRIPT_HARDWARE_TIMEOUT)
+  }
+
+  /// Compiles a protocol script, with a custom wall clock time limit for calls that are given
+  /// hardware access.
+  pub fn new_with_hardware_timeout(
+    protocol_name: &str,
+    source: &str,
+    hardware_timeout: Duration,
+  ) -> Result<Self, ButtplugDeviceError> {
+    Ok(Self {
+      script: Arc::new(ProtocolScript::new(
+        protocol_name,
+        source,
+        hardware_timeout,
+      )?),
+    })
+  }
+}
+
+impl ProtocolIdentifierFactory for ScriptProtocolIdentifierFactory {
+  fn identifier(&self) -> &str {
+    &self.script.protocol_name
+  }
+
+  fn create(&self) -> Box<dyn ProtocolIdentifier> {
+    Box::new(ScriptProtocolIdentifier {
+      script: self.script.clone(),
+    })
+  }
+}
+
+/// Loads all protocol scripts (files with a `.rhai` extension) in a directory, using each file name
+/// as the protocol name.
+pub fn load_protocol_scripts(
+  dir: &Path,
+) -> Result<Vec<ScriptProtocolIdentifierFactory>, ButtplugDeviceError> {
+  let entries = std::fs::read_dir(dir).map_err(|e| {
+    ButtplugDeviceError::DeviceConfigurationError(format!(
+      "Cannot read protocol script directory {}: {e}",
+      dir.display()
+    ))
+  })?;
+  let mut factories = vec![];
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if path.extension().and_then(|x| x.to_str()) != Some(SCRIPT_FILE_EXTENSION) {
+      continue;
+    }
+    let Some(protocol_name) = path.file_stem().and_then(|x| x.to_str()) else {
+      continue;
+    };
+    let source = std::fs::read_to_string(&path).map_err(|e| {
+      ButtplugDeviceError::DeviceConfigurationError(format!(
+        "Cannot read protocol script {}: {e}",
+        path.display()
+      ))
+    })?;
+    info!("Loading protocol script {}", path.display());
+    factories.push(ScriptProtocolIdentifierFactory::new(
+      protocol_name,
+      &source,
+    )?);
+  }
+  Ok(factories)
+}
+
+struct ScriptProtocolIdentifier {
+  script: Arc<ProtocolScript>,
+}
+
+#[async_trait]
+impl ProtocolIdentifier for ScriptProtocolIdentifier {
+  async fn identify(
+    &mut self,
+    hardware: Arc<Hardware>,
+    _: ProtocolCommunicationSpecifier,
+  ) -> Result<(UserDeviceIdentifier, Box<dyn ProtocolInitializer>), ButtplugDeviceError> {
+    Ok((
+      UserDeviceIdentifier::new(
+        hardware.address(),
+        &self.script.protocol_name,
+        &Some(hardware.name().to_owned()),
+      ),
+      Box::new(ScriptProtocolInitializer {
+        script: self.script.clone(),
+      }),
+    ))
+  }
+}
+
+struct ScriptProtocolInitializer {
+  script: Arc<ProtocolScript>,
+}
+
+#[async_trait]
+impl ProtocolInitializer for ScriptProtocolInitializer {
+  async fn initialize(
+    &mut self,
+    hardware: Arc<Hardware>,
+    def: &ServerDeviceDefinition,
+  ) -> Result<Arc<dyn ProtocolHandler>, ButtplugDeviceError> {
+    let worker = ScriptWorker::new(&self.script.protocol_name)?;
+    let mut state = Dynamic::from_map(Map::new());
+    if self.script.has_fn("initialize", 2) {
+      let mut device = Map::new();
+      device.insert("name".into(), def.name().clone().into());
+      device.insert(
+        "variant".into(),
+        def
+          .protocol_variant()
+          .clone()
+          .map_or(Dynamic::UNIT, Dynamic::from),
+      );
+      (state, _) = self
+        .script
+        .clone()
+        .call_with_hardware(
+          &worker,
+          hardware,
+          state,
+          "initialize",
+          vec![Dynamic::from_map(device)],
+        )
+        .await?;
+    }
+    let keepalive_strategy = if self.script.has_fn("keepalive_strategy", 0) {
+      let strategy = self.script.call(&mut state, "keepalive_strategy", ())?;
+      ScriptKeepaliveStrategy::from_script(strategy)
+        .map_err(|e| script_error(&self.script.protocol_name, e))?
+    } else {
+      ScriptKeepaliveStrategy::HardwareRequiredRepeatLastPacket
+    };
+    Ok(Arc::new(ScriptProtocol {
+      script: self.script.clone(),
+      state: Arc::new(Mutex::new(state)),
+      worker,
+      keepalive_strategy,
+    }))
+  }
+}
+
+enum ScriptKeepaliveStrategy {
+  HardwareRequiredRepeatPacket(HardwareWriteCmd),
+  HardwareRequiredRepeatLastPacket,
+  RepeatLastPacketWithTiming(Duration),
+}
+
+impl ScriptKeepaliveStrategy {
+  fn from_script(strategy: Dynamic) -> Result<Self, String> {
+    if strategy.is_unit() {
+      return Ok(Self::HardwareRequiredRepeatLastPacket);
+    }
+    let strategy = strategy
+      .try_cast::<Map>()
+      .ok_or("keepalive_strategy must return a map")?;
+    if let Some(interval) = strategy.get("repeat_last_packet_ms") {
+      let interval = interval
+        .as_int()
+        .ok()
+        .and_then(|x| u64::try_from(x).ok())
+        .ok_or("repeat_last_packet_ms must be a positive integer")?;
+      Ok(Self::RepeatLastPacketWithTiming(Duration::from_millis(
+        interval,
+      )))
+    } else if let Some(packet) = strategy.get("repeat_packet") {
+      Ok(Self::HardwareRequiredRepeatPacket(parse_packet(
+        packet.clone(),
+        SCRIPT_PROTOCOL_UUID,
+      )?))
+    } else {
+      Err("keepalive_strategy must return repeat_last_packet_ms or repeat_packet".to_owned())
+    }
+  }
+}
+
+struct ScriptProtocol {
+  script: Arc<ProtocolScript>,
+  state: Arc<Mutex<Dynamic>>,
+  worker: ScriptWorker,
+  keepalive_strategy: ScriptKeepaliveStrategy,
+}
+
+impl ScriptProtocol {
+  fn unhandled(&self, name: &str) -> ButtplugDeviceError {
+    ButtplugDeviceError::UnhandledCommand(format!(
+      "Protocol script {} does not implement {name}",
+      self.script.protocol_name
+    ))
+  }
+}
+
+impl ProtocolHandler for ScriptProtocol {
+  fn keepalive_strategy(&self) -> ProtocolKeepaliveStrategy {
+    match &self.keepalive_strategy {
+      ScriptKeepaliveStrategy::HardwareRequiredRepeatPacket(cmd) => {
+        ProtocolKeepaliveStrategy::HardwareRequiredRepeatPacketStrategy(cmd.clone())
+      }
+      ScriptKeepaliveStrategy::HardwareRequiredRepeatLastPacket => {
+        ProtocolKeepaliveStrategy::HardwareRequiredRepeatLastPacketStrategy
+      }
+      ScriptKeepaliveStrategy::RepeatLastPacketWithTiming(interval) => {
+        ProtocolKeepaliveStrategy::RepeatLastPacketStrategyWithTiming(*interval)
+      }
+    }
+  }
+
+  fn handle_output_cmd(
+    &self,
+    cmd: &CheckedOutputCmdV4,
+  ) -> Result<Vec<HardwareCommand>, ButtplugDeviceError> {
+    let output_command = cmd.output_command();
+    let feature_index = Dynamic::from_int(cmd.feature_index() as i64);
+    let args = match output_command {
+      OutputCommand::HwPositionWithDuration(x) => vec![
+        feature_index,
+        Dynamic::from_int(x.value() as i64),
+        Dynamic::from_int(x.duration() as i64),
+      ],
+      _ => vec![
+        feature_index,
+        Dynamic::from_int(output_command.value() as i64),
+      ],
+    };
+    let name = output_fn_name(output_command.as_output_type());
+    if !self.script.has_fn(name, args.len()) {
+      return Err(self.unhandled(name));
+    }
+    let packets = {
+      let mut state = self
+        .state
+        .lock()
+        .expect("Script state lock should not be poisoned");
+      self.script.call(&mut state, name, args)?
+    };
+    let packets = packets.try_cast::<Array>().ok_or_else(|| {
+      script_error(
+        &self.script.protocol_name,
+        format!("{name} must return an array of packets"),
+      )
+    })?;
+    packets
+      .into_iter()
+      .map(|packet| {
+        parse_packet(packet, cmd.feature_id())
+          .map(|x| x.into())
+          .map_err(|e| script_error(&self.script.protocol_name, e))
+      })
+      .collect()
+  }
+
+  fn handle_input_read_cmd(
+    &self,
+    device_index: u32,
+    device: Arc<Hardware>,
+    feature_index: u32,
+    _feature_id: Uuid,
+    sensor_type: InputType,
+  ) -> BoxFuture<'_, Result<InputReadingV4, ButtplugDeviceError>> {
+    let name = "handle_input_read_cmd";
+    async move {
+      if !self.script.has_fn(name, 3) {
+        return Err(self.unhandled(name));
+      }
+      let before = self
+        .state
+        .lock()
+        .expect("Script state lock should not be poisoned")
+        .clone();
+      let (after, value) = self
+        .script
+        .clone()
+        .call_with_hardware(
+          &self.worker,
+          device,
+          before.clone(),
+          name,
+          vec![
+            input_type_name(sensor_type).into(),
+            Dynamic::from_int(feature_index as i64),
+          ],
+        )
+        .await?;
+      merge_state(
+        &mut self
+          .state
+          .lock()
+          .expect("Script state lock should not be poisoned"),
+        &before,
+        after,
+      );
+      let reading = value
+        .as_int()
+        .ok()
+        .and_then(|x| input_type_reading(sensor_type, x))
+        .ok_or_else(|| {
+          script_error(
+            &self.script.protocol_name,
+            format!("{name} returned an invalid {sensor_type:?} value"),
+          )
+        })?;
+      Ok(InputReadingV4::new(device_index, feature_index, reading))
+    }
+    .boxed()
+  }
+}
@@ -0,0 +1,492 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Server enforced output safety limits
+//!
+//! Sits between the [DeviceHandle](super::DeviceHandle) and the protocol handler, adjusting or
+//! stopping output commands so they stay within the safety limits set in the user device config,
+//! no matter what clients request.
+
+use std::{
+  collections::HashMap,
+  sync::atomic::{AtomicU64, Ordering},
+  time::{Duration, Instant},
+};
+
+use buttplug_core::message::{OutputCommand, OutputHwPositionWithDuration, OutputType};
+use buttplug_server_device_config::{
+  DeviceSafetyLimits,
+  OutputSafetyLimits,
+  ServerDeviceDefinition,
+  UserDeviceIdentifier,
+};
+use dashmap::DashMap;
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::message::checked_output_cmd::CheckedOutputCmdV4;
+
+use super::output_values::{is_position, with_value};
+
+/// How often slew rate limited outputs are stepped towards the requested level.
+pub(crate) const SAFETY_RAMP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the server did to keep an output command within its safety limits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SafetyViolationKind {
+  /// Requested level was above the level cap, so the cap was sent instead.
+  LevelCapped { requested: i32, sent: i32 },
+  /// Requested level was further away than the slew rate allows. The output is ramped towards it.
+  SlewRateLimited { requested: i32, sent: i32 },
+  /// Timed position move was faster than the slew rate allows, so its duration was lengthened.
+  DurationExtended { requested_ms: u32, sent_ms: u32 },
+  /// Output stayed on longer than the on-time limit, so it was stopped.
+  OnTimeExceeded { limit_ms: u32 },
+}
+
+/// Event emitted whenever the server changes or stops an output command due to safety limits.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, CopyGetters, Serialize, Deserialize)]
+pub struct SafetyViolation {
+  #[getset(get = "pub")]
+  identifier: UserDeviceIdentifier,
+  #[getset(get_copy = "pub")]
+  feature_index: u32,
+  #[getset(get_copy = "pub")]
+  output_type: OutputType,
+  #[getset(get = "pub")]
+  kind: SafetyViolationKind,
+}
+
+/// Work the device handle needs to schedule after sending a command through the policy.
+#[derive(Debug, Default)]
+pub(crate) struct SafetyFollowUp {
+  /// Generation of the ramp to run, if the command was slew rate limited.
+  pub ramp: Option<u64>,
+  /// On-time limit and the generation that turned the output on.
+  pub on_timer: Option<(Duration, u64)>,
+}
+
+#[derive(Debug)]
+struct OutputState {
+  /// Last value sent to the device, if known.
+  sent: Option<i32>,
+  /// Value ramps are moving towards.
+  target: i32,
+  last_update: Instant,
+  /// Bumped on every command, so stale ramps and timers know they have been superseded.
+  generation: u64,
+  /// Generation that turned the output on, if it is on.
+  on_since: Option<u64>,
+}
+
+pub(crate) struct SafetyPolicy {
+  identifier: UserDeviceIdentifier,
+  limits: DeviceSafetyLimits,
+  /// Full scale value of each feature output, used to turn fractional limits into device steps.
+  full_scale: HashMap<(Uuid, OutputType), i32>,
+  state: DashMap<(Uuid, OutputType), OutputState>,
+  generation: AtomicU64,
+  event_sender: broadcast::Sender<SafetyViolation>,
+}
+
+/// Largest change allowed over `elapsed`, never less than a single step so ramps always finish.
+fn max_delta(rate: f64, full_scale: i32, elapsed: Duration) -> i32 {
+  (rate * full_scale as f64 * elapsed.as_secs_f64())
+    .floor()
+    .max(1.0) as i32
+}
+
+impl SafetyPolicy {
+  /// Creates a policy for a device, or None if the device has no safety limits configured.
+  pub(crate) fn new(
+    identifier: &UserDeviceIdentifier,
+    definition: &ServerDeviceDefinition,
+    event_sender: broadcast::Sender<SafetyViolation>,
+  ) -> Option<Self> {
+    let limits = definition.safety_limits().as_ref()?;
+    if limits.is_empty() {
+      return None;
+    }
+    let mut full_scale = HashMap::new();
+    for feature in definition.features().values() {
+      if let Some(output) = feature.output() {
+        for output_type in output.output_types() {
+          if let Some(range) = output.value_range(output_type) {
+            full_scale.insert((feature.id(), output_type), range.base().end().abs());
+          }
+        }
+      }
+    }
+    Some(Self {
+      identifier: identifier.clone(),
+      limits: limits.clone(),
+      full_scale,
+      state: DashMap::new(),
+      generation: AtomicU64::new(0),
+      event_sender,
+    })
+  }
+
+  fn output_limits(&self, cmd: &CheckedOutputCmdV4) -> Option<(&OutputSafetyLimits, i32)> {
+    let output_type = cmd.output_command().as_output_type();
+    let limits = self.limits.limits(output_type)?;
+    let full_scale = *self.full_scale.get(&(cmd.feature_id(), output_type))?;
+    Some((limits, full_scale))
+  }
+
+  fn report(&self, cmd: &CheckedOutputCmdV4, kind: SafetyViolationKind) {
+    let violation = SafetyViolation {
+      identifier: self.identifier.clone(),
+      feature_index: cmd.feature_index(),
+      output_type: cmd.output_command().as_output_type(),
+      kind,
+    };
+    info!("Safety limit applied: {:?}", violation);
+    // No one listening for safety events is fine, the limits are still applied.
+    let _ = self.event_sender.send(violation);
+  }
+
+  /// Applies the safety limits to a command, returning the command to send and any follow up work
+  /// that needs to be scheduled.
+  pub(crate) fn enforce(&self, cmd: &CheckedOutputCmdV4) -> (CheckedOutputCmdV4, SafetyFollowUp) {
+    let mut follow_up = SafetyFollowUp::default();
+    let Some((limits, full_scale)) = self.output_limits(cmd) else {
+      return (cmd.clone(), follow_up);
+    };
+    let output_type = cmd.output_command().as_output_type();
+    let mut command = cmd.output_command();
+    let requested = command.value();
+    let mut value = requested;
+
+    if let Some(max_level) = limits.max_level() {
+      let cap = (max_level * full_scale as f64).floor() as i32;
+      if value > cap {
+        value = cap;
+        self.report(
+          cmd,
+          SafetyViolationKind::LevelCapped {
+            requested,
+            sent: value,
+          },
+        );
+      }
+    }
+    let target = value;
+
+    let now = Instant::now();
+    let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+    let mut state = self
+      .state
+      .entry((cmd.feature_id(), output_type))
+      .or_insert_with(|| OutputState {
+        sent: None,
+        target: 0,
+        last_update: now,
+        generation,
+        on_since: None,
+      });
+    // Outputs start out stopped, but positions are unknown until the first command.
+    let last = state.sent.or((!is_position(output_type)).then_some(0));
+
+    if let (Some(rate), Some(last)) = (limits.max_slew_rate(), last) {
+      if let OutputCommand::HwPositionWithDuration(x) = &mut command {
+        let distance = (value - last).unsigned_abs() as f64 / full_scale as f64;
+        let min_duration = (distance / rate * 1000.0).ceil() as u32;
+        if x.duration() < min_duration {
+          let requested_ms = x.duration();
+          *x = OutputHwPositionWithDuration::new(x.value(), min_duration);
+          self.report(
+            cmd,
+            SafetyViolationKind::DurationExtended {
+              requested_ms,
+              sent_ms: min_duration,
+            },
+          );
+        }
+      } else if value != 0 || is_position(output_type) {
+        // Stop commands always go through right away, everything else moves at most one ramp
+        // interval's worth of distance immediately and the rest is ramped.
+        let elapsed = now
+          .duration_since(state.last_update)
+          .min(SAFETY_RAMP_INTERVAL);
+        let max_delta = max_delta(rate, full_scale, elapsed);
+        if (value - last).abs() > max_delta {
+          value = last + max_delta * (value - last).signum();
+          self.report(
+            cmd,
+            SafetyViolationKind::SlewRateLimited {
+              requested: target,
+              sent: value,
+            },
+          );
+          follow_up.ramp = Some(generation);
+        }
+      }
+    }
+    command.set_value(value);
+
+    state.sent = Some(value);
+    state.target = target;
+    state.last_update = now;
+    state.generation = generation;
+
+    if let Some(limit) = limits.max_on_time_ms()
+      && !is_position(output_type)
+    {
+      if value == 0 {
+        state.on_since = None;
+      } else if state.on_since.is_none() {
+        state.on_since = Some(generation);
+        follow_up.on_timer = Some((Duration::from_millis(limit as u64), generation));
+      }
+    }
+
+    (
+      CheckedOutputCmdV4::new(
+        cmd.id(),
+        cmd.device_index(),
+        cmd.feature_index(),
+        cmd.feature_id(),
+        command,
+      ),
+      follow_up,
+    )
+  }
+
+  /// Moves a slew rate limited output one ramp interval closer to its target, handing the step to
+  /// `send`. Returns false once the target is reached, a newer command has superseded the ramp, or
+  /// `send` fails.
+  ///
+  /// `send` is called while the output's state is still locked, the same lock [Self::cancel_all]
+  /// takes, so a stop can't land between checking the ramp is current and queueing the step.
+  pub(crate) fn ramp_step(
+    &self,
+    cmd: &CheckedOutputCmdV4,
+    generation: u64,
+    send: impl FnOnce(CheckedOutputCmdV4) -> bool,
+  ) -> bool {
+    let Some((limits, full_scale)) = self.output_limits(cmd) else {
+      return false;
+    };
+    let Some(rate) = limits.max_slew_rate() else {
+      return false;
+    };
+    let output_type = cmd.output_command().as_output_type();
+    let Some(mut state) = self.state.get_mut(&(cmd.feature_id(), output_type)) else {
+      return false;
+    };
+    let Some(last) = state.sent else {
+      return false;
+    };
+    if state.generation != generation || last == state.target {
+      return false;
+    }
+    let max_delta = max_delta(rate, full_scale, SAFETY_RAMP_INTERVAL);
+    let next = if (state.target - last).abs() <= max_delta {
+      state.target
+    } else {
+      last + max_delta * (state.target - last).signum()
+    };
+    state.sent = Some(next);
+    state.last_update = Instant::now();
+    send(with_value(cmd, next))
+  }
+
+  /// Called when an on-time timer runs out. Hands the stop command to `send` if the output has
+  /// stayed on since the timer was started, with the output's state locked like [Self::ramp_step].
+  pub(crate) fn on_time_expired(
+    &self,
+    cmd: &CheckedOutputCmdV4,
+    generation: u64,
+    send: impl FnOnce(CheckedOutputCmdV4) -> bool,
+  ) {
+    let Some((limits, _)) = self.output_limits(cmd) else {
+      return;
+    };
+    let Some(limit_ms) = limits.max_on_time_ms() else {
+      return;
+    };
+    let output_type = cmd.output_command().as_output_type();
+    {
+      let Some(mut state) = self.state.get_mut(&(cmd.feature_id(), output_type)) else {
+        return;
+      };
+      if state.on_since != Some(generation) {
+        return;
+      }
+      state.on_since = None;
+      state.sent = Some(0);
+      state.target = 0;
+      state.last_update = Instant::now();
+      // Cancel any ramp still moving the output.
+      state.generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+      send(with_value(cmd, 0));
+    }
+    self.report(cmd, SafetyViolationKind::OnTimeExceeded { limit_ms });
+  }
+
+  /// Supersedes every pending ramp and on-time timer, so nothing keeps moving outputs after the
+  /// device has been stopped.
+  pub(crate) fn cancel_all(&self) {
+    for mut state in self.state.iter_mut() {
+      state.generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+      state.on_since = None;
+      if let Some(sent) = state.sent {
+        state.target = sent;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use buttplug_core::message::OutputValue;
+  use buttplug_server_device_config::{
+    RangeWithLimit,
+    ServerDeviceDefinitionBuilder,
+    ServerDeviceFeature,
+    ServerDeviceFeatureOutput,
+    ServerDeviceFeatureOutputHwPositionWithDurationProperties,
+    ServerDeviceFeatureOutputValueProperties,
+  };
+
+  fn test_policy(
+    limits: DeviceSafetyLimits,
+  ) -> (SafetyPolicy, Uuid, broadcast::Receiver<SafetyViolation>) {
+    let feature_id = Uuid::new_v4();
+    let mut output = ServerDeviceFeatureOutput::default();
+    output.set_temperature(Some(ServerDeviceFeatureOutputValueProperties::new(
+      &RangeWithLimit::from(0..=100),
+      false,
+    )));
+    output.set_hw_position_with_duration(Some(
+      ServerDeviceFeatureOutputHwPositionWithDurationProperties::new(
+        &RangeWithLimit::from(0..=100),
+        &RangeWithLimit::from(0..=10000),
+        false,
+        false,
+      ),
+    ));
+    let mut builder = ServerDeviceDefinitionBuilder::new("Test Device", &Uuid::new_v4());
+    builder
+      .add_feature(&ServerDeviceFeature::new(
+        0,
+        "",
+        feature_id,
+        None,
+        None,
+        &Some(output),
+        &None,
+      ))
+      .safety_limits(&Some(limits));
+    let (sender, receiver) = broadcast::channel(16);
+    let policy = SafetyPolicy::new(
+      &UserDeviceIdentifier::new("test", "test", &None),
+      &builder.finish(),
+      sender,
+    )
+    .expect("Limits are set");
+    (policy, feature_id, receiver)
+  }
+
+  #[test]
+  fn test_level_cap() {
+    let mut limits = DeviceSafetyLimits::default();
+    limits.set_limits(
+      OutputType::Temperature,
+      Some(OutputSafetyLimits::new(None, None, Some(0.4))),
+    );
+    let (policy, feature_id, mut receiver) = test_policy(limits);
+    let cmd = |value| {
+      CheckedOutputCmdV4::new(
+        1,
+        0,
+        0,
+        feature_id,
+        OutputCommand::Temperature(OutputValue::new(value)),
+      )
+    };
+    let (sent, follow_up) = policy.enforce(&cmd(90));
+    assert_eq!(sent.output_command().value(), 40);
+    assert!(follow_up.ramp.is_none() && follow_up.on_timer.is_none());
+    assert_eq!(
+      receiver.try_recv().unwrap().kind(),
+      &SafetyViolationKind::LevelCapped {
+        requested: 90,
+        sent: 40
+      }
+    );
+    // Values under the cap go through untouched.
+    let (sent, _) = policy.enforce(&cmd(30));
+    assert_eq!(sent.output_command().value(), 30);
+    assert!(receiver.try_recv().is_err());
+  }
+
+  #[test]
+  fn test_ramp_superseded_by_cancel() {
+    let mut limits = DeviceSafetyLimits::default();
+    limits.set_limits(
+      OutputType::Temperature,
+      Some(OutputSafetyLimits::new(Some(0.1), None, None)),
+    );
+    let (policy, feature_id, _receiver) = test_policy(limits);
+    let cmd = CheckedOutputCmdV4::new(
+      1,
+      0,
+      0,
+      feature_id,
+      OutputCommand::Temperature(OutputValue::new(100)),
+    );
+    let (_, follow_up) = policy.enforce(&cmd);
+    let generation = follow_up.ramp.expect("Command should be slew rate limited");
+    let mut steps = vec![];
+    assert!(policy.ramp_step(&cmd, generation, |step| {
+      steps.push(step.output_command().value());
+      true
+    }));
+    assert_eq!(steps.len(), 1);
+    // Once the device is stopped, the ramp must not send anything else.
+    policy.cancel_all();
+    assert!(!policy.ramp_step(&cmd, generation, |_| panic!("Ramp should be cancelled")));
+  }
+
+  #[test]
+  fn test_position_duration_extended() {
+    let mut limits = DeviceSafetyLimits::default();
+    limits.set_limits(
+      OutputType::HwPositionWithDuration,
+      Some(OutputSafetyLimits::new(Some(2.0), None, None)),
+    );
+    let (policy, feature_id, mut receiver) = test_policy(limits);
+    let cmd = |value, duration| {
+      CheckedOutputCmdV4::new(
+        1,
+        0,
+        0,
+        feature_id,
+        OutputCommand::HwPositionWithDuration(OutputHwPositionWithDuration::new(value, duration)),
+      )
+    };
+    // The starting position is unknown, so the first move is not limited.
+    let (sent, _) = policy.enforce(&cmd(0, 10));
+    assert_eq!(sent.output_command(), cmd(0, 10).output_command());
+    // Moving the full range at 2x full range per second takes at least 500ms.
+    let (sent, follow_up) = policy.enforce(&cmd(100, 100));
+    assert_eq!(sent.output_command(), cmd(100, 500).output_command());
+    assert!(follow_up.ramp.is_none());
+    assert_eq!(
+      receiver.try_recv().unwrap().kind(),
+      &SafetyViolationKind::DurationExtended {
+        requested_ms: 100,
+        sent_ms: 500
+      }
+    );
+  }
+}
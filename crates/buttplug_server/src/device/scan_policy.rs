@@ -0,0 +1,48 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Per comm manager scanning behavior.
+
+use getset::CopyGetters;
+use std::time::Duration;
+
+/// When a comm manager looks for devices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanMode {
+  /// Scans when a client starts scanning, and stops when the client stops scanning.
+  #[default]
+  OnDemand,
+  /// Scans from the time the manager is added until it is removed or the device manager shuts
+  /// down. Clients starting and stopping scans don't affect it, and it is left out of deciding
+  /// when to send ScanningFinished.
+  Continuous,
+}
+
+/// Scanning settings for a single comm manager, set by manager name on the
+/// [ServerDeviceManagerBuilder](super::ServerDeviceManagerBuilder).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct ScanPolicy {
+  mode: ScanMode,
+  /// Longest an on demand scan can run before the manager is stopped, whether or not a client
+  /// stopped scanning. If None, the manager scans until it finishes or is stopped. Ignored for
+  /// continuous managers.
+  duration_limit: Option<Duration>,
+}
+
+impl ScanPolicy {
+  pub fn new(mode: ScanMode, duration_limit: Option<Duration>) -> Self {
+    Self {
+      mode,
+      duration_limit,
+    }
+  }
+
+  pub fn is_continuous(&self) -> bool {
+    self.mode == ScanMode::Continuous
+  }
+}
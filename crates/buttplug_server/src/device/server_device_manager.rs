@@ -13,10 +13,25 @@ use crate::{
   ButtplugServerResultFuture,
   device::{
     DeviceHandle,
-    hardware::communication::{HardwareCommunicationManager, HardwareCommunicationManagerBuilder},
+    OutputArbitrationPolicy,
+    SafetyViolation,
+    ServerSession,
+    emergency_stop::EmergencyStop,
+    hardware::communication::{
+      HardwareCommunicationManager,
+      HardwareCommunicationManagerBuilder,
+      HardwareCommunicationManagerEvent,
+    },
+    output_arbiter::OutputArbiter,
+    output_mixer::OutputMixerConfig,
+    output_values::output_max_value,
+    protocol::{ProtocolIdentifierFactory, ProtocolManager},
+    scan_policy::ScanPolicy,
     server_device_manager_event_loop::ServerDeviceManagerEventLoop,
   },
   message::{
+    checked_output_cmd::CheckedOutputCmdV4,
+    checked_output_vec_cmd::CheckedOutputVecCmdV4,
     server_device_attributes::ServerDeviceAttributes,
     spec_enums::{
       ButtplugCheckedClientMessageV4,
@@ -24,41 +39,71 @@ use crate::{
       ButtplugDeviceManagerMessageUnion,
     },
   },
+  recorder::SessionRecorder,
 };
 use buttplug_core::{
-  errors::{ButtplugDeviceError, ButtplugMessageError, ButtplugUnknownError},
+  errors::{ButtplugDeviceError, ButtplugError, ButtplugMessageError, ButtplugUnknownError},
   message::{
     self,
     ButtplugDeviceMessage,
     ButtplugMessage,
     ButtplugServerMessageV4,
     DeviceListV4,
+    HardwareManagerStatus,
     StopCmdV4,
   },
   util::stream::convert_broadcast_receiver_to_stream,
 };
-use buttplug_server_device_config::{DeviceConfigurationManager, UserDeviceIdentifier};
-use dashmap::DashMap;
+use buttplug_server_device_config::{
+  DeviceConfigurationManager,
+  DevicePreset,
+  PresetOutput,
+  ServerDeviceDefinitionBuilder,
+  UserDeviceIdentifier,
+};
+use dashmap::{DashMap, DashSet};
 use futures::{
   Stream,
   future::{self, FutureExt},
 };
 use getset::Getters;
 use std::{
-  collections::BTreeMap,
+  collections::{BTreeMap, HashMap, HashSet},
   convert::TryFrom,
+  fmt::{self, Debug},
   sync::{
     Arc,
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU32, Ordering},
   },
 };
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio_util::sync::CancellationToken;
 
-#[derive(Debug)]
 pub(super) enum DeviceManagerCommand {
   StartScanning,
   StopScanning,
+  AddCommManager(
+    Box<dyn HardwareCommunicationManager>,
+    oneshot::Sender<Result<(), ButtplugServerError>>,
+  ),
+  RemoveCommManager(String, oneshot::Sender<Result<(), ButtplugServerError>>),
+  /// Forgets the outputs a session was running on devices waiting to reconnect, or every session's
+  /// if None.
+  ClearPendingOutputs(Option<u32>),
+}
+
+impl Debug for DeviceManagerCommand {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::StartScanning => write!(f, "StartScanning"),
+      Self::StopScanning => write!(f, "StopScanning"),
+      Self::AddCommManager(mgr, _) => f.debug_tuple("AddCommManager").field(&mgr.name()).finish(),
+      Self::RemoveCommManager(name, _) => f.debug_tuple("RemoveCommManager").field(name).finish(),
+      Self::ClearPendingOutputs(session) => {
+        f.debug_tuple("ClearPendingOutputs").field(session).finish()
+      }
+    }
+  }
 }
 
 #[derive(Debug, Getters)]
@@ -72,6 +117,11 @@ pub struct ServerDeviceInfo {
 pub struct ServerDeviceManagerBuilder {
   device_configuration_manager: Arc<DeviceConfigurationManager>,
   comm_managers: Vec<Box<dyn HardwareCommunicationManagerBuilder>>,
+  protocol_factories: Vec<Arc<dyn ProtocolIdentifierFactory>>,
+  arbitration_policy: OutputArbitrationPolicy,
+  mixer_config: OutputMixerConfig,
+  recorder: Option<SessionRecorder>,
+  scan_policies: HashMap<String, ScanPolicy>,
 }
 
 impl ServerDeviceManagerBuilder {
@@ -79,6 +129,11 @@ impl ServerDeviceManagerBuilder {
     Self {
       device_configuration_manager: Arc::new(device_configuration_manager),
       comm_managers: vec![],
+      protocol_factories: vec![],
+      arbitration_policy: OutputArbitrationPolicy::default(),
+      mixer_config: OutputMixerConfig::default(),
+      recorder: None,
+      scan_policies: HashMap::new(),
     }
   }
 
@@ -88,6 +143,11 @@ impl ServerDeviceManagerBuilder {
     Self {
       device_configuration_manager,
       comm_managers: vec![],
+      protocol_factories: vec![],
+      arbitration_policy: OutputArbitrationPolicy::default(),
+      mixer_config: OutputMixerConfig::default(),
+      recorder: None,
+      scan_policies: HashMap::new(),
     }
   }
 
@@ -99,7 +159,49 @@ impl ServerDeviceManagerBuilder {
     self
   }
 
+  /// Adds a protocol implementation that isn't built into the library, such as a scripted protocol.
+  pub fn protocol_factory<T>(&mut self, factory: T) -> &mut Self
+  where
+    T: ProtocolIdentifierFactory + 'static,
+  {
+    self.protocol_factories.push(Arc::new(factory));
+    self
+  }
+
+  /// Sets how output commands from multiple client sessions to the same output are combined.
+  pub fn arbitration_policy(&mut self, policy: OutputArbitrationPolicy) -> &mut Self {
+    self.arbitration_policy = policy;
+    self
+  }
+
+  /// Records client messages and hardware traffic for every server using this device manager.
+  pub fn recorder(&mut self, recorder: SessionRecorder) -> &mut Self {
+    self.recorder = Some(recorder);
+    self
+  }
+
+  /// Sets how output layers from different sources driving the same feature are mixed.
+  pub fn output_mixer(&mut self, config: OutputMixerConfig) -> &mut Self {
+    self.mixer_config = config;
+    self
+  }
+
+  /// Sets how the comm manager with the given name scans for devices. This also applies to comm
+  /// managers added with [ServerDeviceManager::add_comm_manager]. Managers without a policy scan on
+  /// demand with no duration limit.
+  pub fn scan_policy(&mut self, manager_name: &str, policy: ScanPolicy) -> &mut Self {
+    self.scan_policies.insert(manager_name.to_owned(), policy);
+    self
+  }
+
   pub fn finish(&mut self) -> Result<ServerDeviceManager, ButtplugServerError> {
+    let mut protocol_manager = ProtocolManager::default();
+    for factory in &self.protocol_factories {
+      protocol_manager
+        .add_protocol_factory(factory.clone())
+        .map_err(|_| ButtplugServerError::ProtocolAlreadyAdded(factory.identifier().to_owned()))?;
+    }
+
     let (device_command_sender, device_command_receiver) = mpsc::channel(256);
     let (device_event_sender, device_event_receiver) = mpsc::channel(256);
     let mut comm_managers: Vec<Box<dyn HardwareCommunicationManager>> = Vec::new();
@@ -149,15 +251,38 @@ impl ServerDeviceManagerBuilder {
     let loop_cancellation_token = CancellationToken::new();
 
     let output_sender = broadcast::channel(255).0;
+    let safety_sender = broadcast::channel(255).0;
+    let arbiter = Arc::new(OutputArbiter::new(self.arbitration_policy));
+    let emergency_stop = Arc::new(EmergencyStop::new(
+      devices.clone(),
+      output_sender.clone(),
+      arbiter.clone(),
+    ));
 
+    let running = Arc::new(AtomicBool::new(true));
+    let manager_statuses = Arc::new(DashMap::new());
+    // Messages sent to the device manager directly come from the default session, which never
+    // ends.
+    let active_sessions = Arc::new(DashSet::new());
+    active_sessions.insert(ServerSession::default().id());
     let mut event_loop = ServerDeviceManagerEventLoop::new(
       comm_managers,
       self.device_configuration_manager.clone(),
       devices.clone(),
       loop_cancellation_token.child_token(),
       output_sender.clone(),
+      safety_sender.clone(),
+      emergency_stop.clone(),
+      arbiter.clone(),
+      self.mixer_config,
+      self.recorder.clone(),
       device_event_receiver,
       device_command_receiver,
+      protocol_manager,
+      running.clone(),
+      manager_statuses.clone(),
+      active_sessions.clone(),
+      self.scan_policies.clone(),
     );
     buttplug_core::spawn!("ServerDeviceManager event loop", async move {
       event_loop.run().await;
@@ -166,9 +291,18 @@ impl ServerDeviceManagerBuilder {
       device_configuration_manager: self.device_configuration_manager.clone(),
       devices,
       device_command_sender,
+      hardware_event_sender: device_event_sender,
+      manager_statuses,
       loop_cancellation_token,
-      running: Arc::new(AtomicBool::new(true)),
+      running,
       output_sender,
+      safety_sender,
+      user_config_sender: broadcast::channel(255).0,
+      emergency_stop,
+      arbiter,
+      next_session_id: AtomicU32::new(1),
+      active_sessions,
+      recorder: self.recorder.clone(),
     })
   }
 }
@@ -180,9 +314,20 @@ pub struct ServerDeviceManager {
   #[getset(get = "pub(crate)")]
   devices: Arc<DashMap<u32, DeviceHandle>>,
   device_command_sender: mpsc::Sender<DeviceManagerCommand>,
+  hardware_event_sender: mpsc::Sender<HardwareCommunicationManagerEvent>,
+  manager_statuses: Arc<DashMap<String, HardwareManagerStatus>>,
   loop_cancellation_token: CancellationToken,
   running: Arc<AtomicBool>,
   output_sender: broadcast::Sender<ButtplugServerMessageV4>,
+  safety_sender: broadcast::Sender<SafetyViolation>,
+  user_config_sender: broadcast::Sender<UserDeviceIdentifier>,
+  emergency_stop: Arc<EmergencyStop>,
+  arbiter: Arc<OutputArbiter>,
+  next_session_id: AtomicU32,
+  /// Ids of sessions that haven't ended yet.
+  active_sessions: Arc<DashSet<u32>>,
+  #[getset(get = "pub")]
+  recorder: Option<SessionRecorder>,
 }
 
 impl ServerDeviceManager {
@@ -192,6 +337,194 @@ impl ServerDeviceManager {
     convert_broadcast_receiver_to_stream(self.output_sender.subscribe())
   }
 
+  /// Stream of events emitted whenever a device's safety limits change or stop an output command.
+  pub fn safety_event_stream(&self) -> impl Stream<Item = SafetyViolation> + use<> {
+    convert_broadcast_receiver_to_stream(self.safety_sender.subscribe())
+  }
+
+  /// Stream of devices whose user configuration changed while the server was running, such as
+  /// when a client stores a preset. Owners that keep the user configuration in a file should save
+  /// it again when this fires.
+  pub fn user_config_event_stream(&self) -> impl Stream<Item = UserDeviceIdentifier> + use<> {
+    convert_broadcast_receiver_to_stream(self.user_config_sender.subscribe())
+  }
+
+  /// Stops all outputs on every device and rejects output commands until
+  /// [reset_emergency_stop](Self::reset_emergency_stop) is called. All clients are sent an
+  /// EmergencyStopState message when the stop is first engaged.
+  pub fn emergency_stop(&self) -> ButtplugServerResultFuture {
+    let emergency_stop = self.emergency_stop.clone();
+    let command_sender = self.device_command_sender.clone();
+    async move {
+      emergency_stop.engage().await;
+      // Devices waiting to reconnect shouldn't start back up once the stop is reset.
+      let _ = command_sender
+        .send(DeviceManagerCommand::ClearPendingOutputs(None))
+        .await;
+      Ok(message::OkV0::default().into())
+    }
+    .boxed()
+  }
+
+  /// Releases the emergency stop. Devices stay stopped until a client sends new commands.
+  pub fn reset_emergency_stop(&self) {
+    self.emergency_stop.reset();
+  }
+
+  pub fn emergency_stop_engaged(&self) -> bool {
+    self.emergency_stop.is_engaged()
+  }
+
+  /// Sets an output layer for a source other than the connected clients, such as a background
+  /// pattern. The layer is mixed with client commands for the same feature output, see
+  /// [DeviceHandle::parse_layered_output].
+  pub fn set_output_layer(
+    &self,
+    source: &str,
+    priority: u32,
+    cmd: &CheckedOutputCmdV4,
+  ) -> ButtplugServerResultFuture {
+    if self.emergency_stop.is_engaged() {
+      return ButtplugDeviceError::EmergencyStopEngaged.into();
+    }
+    match self.devices.get(&cmd.device_index()) {
+      Some(device) => device.parse_layered_output(source, priority, cmd),
+      None => ButtplugDeviceError::DeviceNotAvailable(cmd.device_index()).into(),
+    }
+  }
+
+  /// Removes a source's output layers from a device.
+  pub fn clear_output_layer(&self, device_index: u32, source: &str) -> ButtplugServerResultFuture {
+    match self.devices.get(&device_index) {
+      Some(device) => device.clear_output_layer(source),
+      None => ButtplugDeviceError::DeviceNotAvailable(device_index).into(),
+    }
+  }
+
+  /// Applies one of the named presets from a device's user configuration, including any stored
+  /// with [store_preset](Self::store_preset) since the device connected.
+  pub fn apply_preset(&self, device_index: u32, name: &str) -> ButtplugServerResultFuture {
+    self.apply_session_preset(&ServerSession::default(), device_index, name)
+  }
+
+  fn apply_session_preset(
+    &self,
+    session: &ServerSession,
+    device_index: u32,
+    name: &str,
+  ) -> ButtplugServerResultFuture {
+    if self.emergency_stop.is_engaged() {
+      return ButtplugDeviceError::EmergencyStopEngaged.into();
+    }
+    let device = match self.devices.get(&device_index) {
+      Some(device) if session.can_see(device.identifier()) => device,
+      _ => return ButtplugDeviceError::DeviceNotAvailable(device_index).into(),
+    };
+    let preset = self
+      .device_configuration_manager
+      .device_definition(device.identifier())
+      .and_then(|def| def.presets().get(name).cloned());
+    match preset {
+      Some(preset) => device.apply_preset(&self.arbiter, session.id(), &preset),
+      None => ButtplugDeviceError::DeviceConfigurationError(format!(
+        "Device {device_index} has no preset named {name}"
+      ))
+      .into(),
+    }
+  }
+
+  /// Adds or replaces a named preset in a device's user configuration. The preset is checked
+  /// against the device's outputs before it is stored, and
+  /// [user_config_event_stream](Self::user_config_event_stream) fires once it is.
+  pub fn store_preset(
+    &self,
+    device_index: u32,
+    name: &str,
+    preset: &DevicePreset,
+  ) -> Result<(), ButtplugDeviceError> {
+    self.store_session_preset(&ServerSession::default(), device_index, name, preset)
+  }
+
+  fn store_session_preset(
+    &self,
+    session: &ServerSession,
+    device_index: u32,
+    name: &str,
+    preset: &DevicePreset,
+  ) -> Result<(), ButtplugDeviceError> {
+    let device = self
+      .devices
+      .get(&device_index)
+      .filter(|device| session.can_see(device.identifier()))
+      .ok_or(ButtplugDeviceError::DeviceNotAvailable(device_index))?;
+    let definition = self
+      .device_configuration_manager
+      .device_definition(device.identifier())
+      .unwrap_or_else(|| device.definition().clone());
+    let definition = ServerDeviceDefinitionBuilder::from_user(&definition)
+      .add_preset(name, preset)
+      .finish();
+    self
+      .device_configuration_manager
+      .add_user_device_definition(device.identifier(), &definition)
+      .map_err(|e| ButtplugDeviceError::DeviceConfigurationError(e.to_string()))?;
+    if self
+      .user_config_sender
+      .send(device.identifier().clone())
+      .is_err()
+    {
+      debug!("No user config listeners, dropping user config change event.");
+    }
+    Ok(())
+  }
+
+  /// Adds a communication manager to a running device manager. If the server is scanning, the new
+  /// manager starts scanning too.
+  pub async fn add_comm_manager<T>(&self, mut builder: T) -> Result<(), ButtplugServerError>
+  where
+    T: HardwareCommunicationManagerBuilder + 'static,
+  {
+    let comm_manager = builder.finish(self.hardware_event_sender.clone());
+    let (result_sender, result_receiver) = oneshot::channel();
+    self
+      .device_command_sender
+      .send(DeviceManagerCommand::AddCommManager(
+        comm_manager,
+        result_sender,
+      ))
+      .await
+      .map_err(|_| ButtplugServerError::DeviceManagerNotRunning)?;
+    result_receiver
+      .await
+      .map_err(|_| ButtplugServerError::DeviceManagerNotRunning)?
+  }
+
+  /// Stops and removes a communication manager from a running device manager, by name. Devices it
+  /// already connected stay connected.
+  pub async fn remove_comm_manager(&self, name: &str) -> Result<(), ButtplugServerError> {
+    let (result_sender, result_receiver) = oneshot::channel();
+    self
+      .device_command_sender
+      .send(DeviceManagerCommand::RemoveCommManager(
+        name.to_owned(),
+        result_sender,
+      ))
+      .await
+      .map_err(|_| ButtplugServerError::DeviceManagerNotRunning)?;
+    result_receiver
+      .await
+      .map_err(|_| ButtplugServerError::DeviceManagerNotRunning)?
+  }
+
+  /// Last known status of every communication manager, keyed by manager name.
+  pub fn comm_manager_statuses(&self) -> HashMap<String, HardwareManagerStatus> {
+    self
+      .manager_statuses
+      .iter()
+      .map(|entry| (entry.key().clone(), entry.value().clone()))
+      .collect()
+  }
+
   fn start_scanning(&self) -> ButtplugServerResultFuture {
     let command_sender = self.device_command_sender.clone();
     async move {
@@ -222,88 +555,227 @@ impl ServerDeviceManager {
     .boxed()
   }
 
-  pub(crate) fn stop_devices(&self, msg: &StopCmdV4) -> ButtplugServerResultFuture {
-    let device_map = self.devices.clone();
+  /// Registers a new client session. The session can only see devices with addresses in
+  /// `visible_addresses`, or all devices if it is None.
+  pub fn create_session(&self, visible_addresses: Option<HashSet<String>>) -> ServerSession {
+    let id = self.next_session_id.fetch_add(1, Ordering::Relaxed);
+    self.active_sessions.insert(id);
+    ServerSession::new(id, visible_addresses)
+  }
+
+  /// Ends a client session, stopping the outputs it was running. Outputs that other sessions are
+  /// also using go back to what those sessions requested.
+  pub fn end_session(&self, session: &ServerSession) -> ButtplugServerResultFuture {
+    self.active_sessions.remove(&session.id());
+    self.stop_devices(session, &StopCmdV4::default())
+  }
+
+  pub(crate) fn stop_devices(
+    &self,
+    session: &ServerSession,
+    msg: &StopCmdV4,
+  ) -> ButtplugServerResultFuture {
     // TODO This could use some error reporting.
-    let msg = msg.clone();
+    let mut fut_vec = vec![];
+    for dev in self
+      .devices
+      .iter()
+      .filter(|dev| session.can_see(dev.value().identifier()))
+    {
+      let device = dev.value();
+      let device_index = *dev.key();
+      if msg.outputs() && self.arbiter.has_other_sessions(session.id(), device_index) {
+        // Other sessions are still using this device, so only undo what this session asked for.
+        for cmd in self.arbiter.clear_session(session.id(), Some(device_index)) {
+          fut_vec.push(device.parse_message(cmd.into()));
+        }
+        continue;
+      }
+      if msg.outputs() {
+        self.arbiter.clear_session(session.id(), Some(device_index));
+      }
+      fut_vec.push(device.stop(&message::StopCmdV4::new(
+        None,
+        None,
+        msg.inputs(),
+        msg.outputs(),
+      )));
+    }
+    let command_sender = self.device_command_sender.clone();
+    let session_id = session.id();
+    let clear_pending = msg.outputs();
     async move {
-      let fut_vec: Vec<_> = device_map
-        .iter()
-        .map(|dev| {
-          let device = dev.value();
-          device.stop(&message::StopCmdV4::new(
-            None,
-            None,
-            msg.inputs(),
-            msg.outputs(),
-          ))
-        })
-        .collect();
+      // Devices waiting to reconnect are stopped too, so they don't restart what this session was
+      // running when they come back.
+      if clear_pending {
+        let _ = command_sender
+          .send(DeviceManagerCommand::ClearPendingOutputs(Some(session_id)))
+          .await;
+      }
       future::join_all(fut_vec).await;
       Ok(message::OkV0::default().into())
     }
     .boxed()
   }
 
+  fn arbitrate_output(
+    &self,
+    session: &ServerSession,
+    device: &DeviceHandle,
+    cmd: &CheckedOutputCmdV4,
+  ) -> Result<CheckedOutputCmdV4, ButtplugDeviceError> {
+    self.arbiter.arbitrate(
+      session.id(),
+      cmd,
+      output_max_value(device.definition(), cmd),
+    )
+  }
+
   fn parse_device_message(
     &self,
+    session: &ServerSession,
     device_msg: ButtplugDeviceCommandMessageUnionV4,
   ) -> ButtplugServerResultFuture {
-    match self.devices.get(&device_msg.device_index()) {
-      Some(device) => {
-        //let fut = device.parse_message(device_msg);
-        device.parse_message(device_msg)
-        // Create a future to run the message through the device, then handle adding the id to the result.
-        //fut.boxed()
-      }
-      None => ButtplugDeviceError::DeviceNotAvailable(device_msg.device_index()).into(),
+    if self.emergency_stop.is_engaged()
+      && matches!(
+        device_msg,
+        ButtplugDeviceCommandMessageUnionV4::OutputCmd(_)
+          | ButtplugDeviceCommandMessageUnionV4::OutputVecCmd(_)
+      )
+    {
+      return ButtplugDeviceError::EmergencyStopEngaged.into();
     }
+    let device = match self.devices.get(&device_msg.device_index()) {
+      Some(device) if session.can_see(device.identifier()) => device,
+      _ => return ButtplugDeviceError::DeviceNotAvailable(device_msg.device_index()).into(),
+    };
+    let device_msg = match device_msg {
+      ButtplugDeviceCommandMessageUnionV4::OutputCmd(cmd) => {
+        match self.arbitrate_output(session, &device, &cmd) {
+          Ok(cmd) => cmd.into(),
+          Err(e) => return e.into(),
+        }
+      }
+      ButtplugDeviceCommandMessageUnionV4::OutputVecCmd(cmd) => {
+        let value_vec: Result<Vec<_>, _> = cmd
+          .value_vec()
+          .iter()
+          .map(|x| self.arbitrate_output(session, &device, x))
+          .collect();
+        match value_vec {
+          Ok(value_vec) => {
+            CheckedOutputVecCmdV4::new(cmd.id(), cmd.device_index(), value_vec).into()
+          }
+          Err(e) => return e.into(),
+        }
+      }
+      msg => msg,
+    };
+    device.parse_message(device_msg)
   }
 
-  fn generate_device_list(&self) -> DeviceListV4 {
+  fn generate_device_list(&self, session: &ServerSession) -> DeviceListV4 {
     let devices = self
       .devices
       .iter()
+      .filter(|device| session.can_see(device.value().identifier()))
       .map(|device| device.value().as_device_message_info(*device.key()))
       .collect();
     DeviceListV4::new(devices)
   }
 
+  /// Removes devices the session can't see from a device list.
+  pub(crate) fn filter_device_list(
+    &self,
+    session: &ServerSession,
+    device_list: &DeviceListV4,
+  ) -> DeviceListV4 {
+    let mut filtered = DeviceListV4::new(
+      device_list
+        .devices()
+        .values()
+        .filter(|info| self.session_can_see_device(session, info.device_index()))
+        .cloned()
+        .collect(),
+    );
+    filtered.set_id(device_list.id());
+    filtered
+  }
+
+  pub(crate) fn session_can_see_device(&self, session: &ServerSession, device_index: u32) -> bool {
+    self
+      .devices
+      .get(&device_index)
+      .is_some_and(|device| session.can_see(device.identifier()))
+  }
+
   fn parse_device_manager_message(
     &self,
+    session: &ServerSession,
     manager_msg: ButtplugDeviceManagerMessageUnion,
   ) -> ButtplugServerResultFuture {
     match manager_msg {
       ButtplugDeviceManagerMessageUnion::RequestDeviceList(msg) => {
-        let mut device_list = self.generate_device_list();
+        let mut device_list = self.generate_device_list(session);
         device_list.set_id(msg.id());
         future::ready(Ok(device_list.into())).boxed()
       }
-      ButtplugDeviceManagerMessageUnion::StopCmd(m) => self.stop_devices(&m),
+      ButtplugDeviceManagerMessageUnion::StopCmd(m) => self.stop_devices(session, &m),
       ButtplugDeviceManagerMessageUnion::StartScanning(_) => self.start_scanning(),
       ButtplugDeviceManagerMessageUnion::StopScanning(_) => self.stop_scanning(),
+      ButtplugDeviceManagerMessageUnion::ApplyPresetCmd(m) => {
+        self.apply_session_preset(session, m.device_index(), m.preset_name())
+      }
+      ButtplugDeviceManagerMessageUnion::StorePresetCmd(m) => {
+        let preset = DevicePreset::new(
+          &m.outputs()
+            .iter()
+            .map(|output| {
+              PresetOutput::new(output.feature_index(), output.output_type(), output.value())
+            })
+            .collect::<Vec<_>>(),
+        );
+        let result = self
+          .store_session_preset(session, m.device_index(), m.preset_name(), &preset)
+          .map(|_| message::OkV0::default().into())
+          .map_err(ButtplugError::from);
+        future::ready(result).boxed()
+      }
     }
   }
 
+  /// Parses a message as if it came from a session that can see every device.
   pub fn parse_message(&self, msg: ButtplugCheckedClientMessageV4) -> ButtplugServerResultFuture {
+    self.parse_session_message(&ServerSession::default(), msg)
+  }
+
+  pub fn parse_session_message(
+    &self,
+    session: &ServerSession,
+    msg: ButtplugCheckedClientMessageV4,
+  ) -> ButtplugServerResultFuture {
     if !self.running.load(Ordering::Relaxed) {
       return future::ready(Err(ButtplugUnknownError::DeviceManagerNotRunning.into())).boxed();
     }
     // If this is a device command message, just route it directly to the
     // device.
     if let Ok(device_msg) = ButtplugDeviceCommandMessageUnionV4::try_from(msg.clone()) {
-      self.parse_device_message(device_msg)
+      self.parse_device_message(session, device_msg)
     } else if let Ok(manager_msg) = ButtplugDeviceManagerMessageUnion::try_from(msg.clone()) {
-      self.parse_device_manager_message(manager_msg)
+      self.parse_device_manager_message(session, manager_msg)
     } else {
       ButtplugMessageError::UnexpectedMessageType(format!("{msg:?}")).into()
     }
   }
 
-  pub(crate) fn feature_map(&self) -> BTreeMap<u32, ServerDeviceAttributes> {
+  pub(crate) fn feature_map(
+    &self,
+    session: &ServerSession,
+  ) -> BTreeMap<u32, ServerDeviceAttributes> {
     self
       .devices()
       .iter()
+      .filter(|x| session.can_see(x.value().identifier()))
       .map(|x| (*x.key(), x.legacy_attributes().clone()))
       .collect()
   }
@@ -327,7 +799,8 @@ impl ServerDeviceManager {
     // again. Otherwise we can have all sorts of ownership weirdness.
     self.running.store(false, Ordering::Relaxed);
     let stop_scanning = self.stop_scanning();
-    let stop_devices = self.stop_devices(&StopCmdV4::default());
+    self.arbiter.clear_all();
+    let stop_devices = self.stop_devices(&ServerSession::default(), &StopCmdV4::default());
     let token = self.loop_cancellation_token.clone();
     async move {
       // Force stop scanning, otherwise we can disconnect and instantly try to reconnect while
@@ -5,22 +5,56 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
-use buttplug_core::message::{ButtplugMessage, ButtplugServerMessageV4, DeviceListV4, ScanningFinishedV0};
+use buttplug_core::message::{
+  ButtplugMessage,
+  ButtplugServerMessageV4,
+  DeviceConnectionFailedV4,
+  DeviceListV4,
+  DeviceReconnectedV4,
+  HardwareManagerStatus,
+  HardwareManagerStatusV4,
+  InputTypeReading,
+  ScanningFinishedV0,
+};
 use buttplug_server_device_config::DeviceConfigurationManager;
 use tracing::info_span;
 
 use super::server_device_manager::DeviceManagerCommand;
-use crate::device::{
-  DeviceHandle,
-  InternalDeviceEvent,
-  device_handle::build_device_handle,
-  hardware::communication::{HardwareCommunicationManager, HardwareCommunicationManagerEvent},
-  protocol::ProtocolManager,
+use crate::{
+  ButtplugServerError,
+  device::{
+    DeviceHandle,
+    InternalDeviceEvent,
+    SafetyViolation,
+    ServerSession,
+    device_handle::build_device_handle,
+    emergency_stop::EmergencyStop,
+    hardware::communication::{HardwareCommunicationManager, HardwareCommunicationManagerEvent},
+    output_arbiter::OutputArbiter,
+    output_mixer::OutputMixerConfig,
+    output_values::output_max_value,
+    protocol::ProtocolManager,
+    scan_policy::ScanPolicy,
+  },
+  message::{ButtplugServerDeviceMessage, checked_output_cmd::CheckedOutputCmdV4},
+  metrics,
+  recorder::SessionRecorder,
 };
+use buttplug_server_device_config::UserDeviceIdentifier;
 use dashmap::{DashMap, DashSet};
 use futures::{FutureExt, future};
-use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc};
+use std::{
+  collections::HashMap,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::Duration,
+};
+use tokio::{
+  sync::{broadcast, mpsc},
+  time::{Instant, MissedTickBehavior},
+};
 use tokio_util::sync::CancellationToken;
 
 /// Scanning state machine for the device manager event loop.
@@ -40,6 +74,22 @@ enum ScanningState {
   ActiveStopRequested,
 }
 
+/// How long to wait before scanning again when a background scan finished without finding every
+/// device waiting to reconnect.
+const BACKGROUND_RESCAN_DELAY: Duration = Duration::from_secs(1);
+
+/// How often to check comm manager statuses, for managers that don't tell us when they change.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A device with an auto-reconnect policy that dropped out and hasn't come back yet.
+struct PendingReconnect {
+  /// Outputs each session was running when the device dropped out, keyed by session id. Empty if
+  /// they shouldn't be restored.
+  outputs: Vec<(u32, CheckedOutputCmdV4)>,
+  /// When to give up on the device, if ever.
+  deadline: Option<Instant>,
+}
+
 pub(super) struct ServerDeviceManagerEventLoop {
   comm_managers: Vec<Box<dyn HardwareCommunicationManager>>,
   device_config_manager: Arc<DeviceConfigurationManager>,
@@ -49,6 +99,16 @@ pub(super) struct ServerDeviceManagerEventLoop {
   /// Broadcaster that relays device events in the form of Buttplug Messages to
   /// whoever owns the Buttplug Server.
   server_sender: broadcast::Sender<ButtplugServerMessageV4>,
+  /// Sender for safety limit events, handed to each device on connection
+  safety_sender: broadcast::Sender<SafetyViolation>,
+  /// Emergency stop latch, engaged from here when a device's emergency stop button is pressed
+  emergency_stop: Arc<EmergencyStop>,
+  /// Output arbitration state, cleared for devices that disconnect
+  arbiter: Arc<OutputArbiter>,
+  /// Output mixer settings, handed to each device on connection
+  mixer_config: OutputMixerConfig,
+  /// Session recorder, handed to each device's hardware on connection
+  recorder: Option<SessionRecorder>,
   /// As the device manager owns the Device Communication Managers, it will have
   /// a receiver that the comm managers all send thru.
   device_comm_receiver: mpsc::Receiver<HardwareCommunicationManagerEvent>,
@@ -64,23 +124,57 @@ pub(super) struct ServerDeviceManagerEventLoop {
   loop_cancellation_token: CancellationToken,
   /// Protocol map, for mapping user definitions to protocols
   protocol_manager: ProtocolManager,
+  /// Cleared when the device manager shuts down, so devices disconnected on the way out aren't
+  /// waited on.
+  running: Arc<AtomicBool>,
+  /// Ids of sessions that haven't ended yet. Outputs are only restored for these.
+  active_sessions: Arc<DashSet<u32>>,
+  /// Devices waiting to be reconnected, keyed by address.
+  pending_reconnects: HashMap<String, PendingReconnect>,
+  /// True while the hardware is scanning on behalf of pending_reconnects rather than a client.
+  /// Background scans never send ScanningFinished.
+  background_scanning: bool,
+  /// When to start the next background scan, if the last one finished with devices still missing.
+  background_rescan: Option<Instant>,
+  /// Last status reported for each comm manager, keyed by manager name.
+  manager_statuses: Arc<DashMap<String, HardwareManagerStatus>>,
+  /// Scan policies, keyed by manager name. Managers without one scan on demand.
+  scan_policies: HashMap<String, ScanPolicy>,
+  /// When to stop on demand managers that have a scan duration limit, keyed by manager name.
+  scan_deadlines: HashMap<String, Instant>,
 }
 
 impl ServerDeviceManagerEventLoop {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     comm_managers: Vec<Box<dyn HardwareCommunicationManager>>,
     device_config_manager: Arc<DeviceConfigurationManager>,
     device_map: Arc<DashMap<u32, DeviceHandle>>,
     loop_cancellation_token: CancellationToken,
     server_sender: broadcast::Sender<ButtplugServerMessageV4>,
+    safety_sender: broadcast::Sender<SafetyViolation>,
+    emergency_stop: Arc<EmergencyStop>,
+    arbiter: Arc<OutputArbiter>,
+    mixer_config: OutputMixerConfig,
+    recorder: Option<SessionRecorder>,
     device_comm_receiver: mpsc::Receiver<HardwareCommunicationManagerEvent>,
     device_command_receiver: mpsc::Receiver<DeviceManagerCommand>,
+    protocol_manager: ProtocolManager,
+    running: Arc<AtomicBool>,
+    manager_statuses: Arc<DashMap<String, HardwareManagerStatus>>,
+    active_sessions: Arc<DashSet<u32>>,
+    scan_policies: HashMap<String, ScanPolicy>,
   ) -> Self {
     let (device_event_sender, device_event_receiver) = mpsc::channel(256);
     Self {
       comm_managers,
       device_config_manager,
       server_sender,
+      safety_sender,
+      emergency_stop,
+      arbiter,
+      mixer_config,
+      recorder,
       device_map,
       device_comm_receiver,
       device_event_sender,
@@ -89,12 +183,225 @@ impl ServerDeviceManagerEventLoop {
       scanning_state: ScanningState::Idle,
       connecting_devices: Arc::new(DashSet::new()),
       loop_cancellation_token,
-      protocol_manager: ProtocolManager::default(),
+      protocol_manager,
+      running,
+      active_sessions,
+      pending_reconnects: HashMap::new(),
+      background_scanning: false,
+      background_rescan: None,
+      manager_statuses,
+      scan_policies,
+      scan_deadlines: HashMap::new(),
+    }
+  }
+
+  fn scan_policy(&self, manager_name: &str) -> ScanPolicy {
+    self
+      .scan_policies
+      .get(manager_name)
+      .copied()
+      .unwrap_or_default()
+  }
+
+  /// Starts every on demand manager, setting deadlines for those with a scan duration limit.
+  async fn start_on_demand_scans(&mut self) {
+    let now = Instant::now();
+    let mut fut_vec = vec![];
+    for mgr in self.comm_managers.iter_mut() {
+      let policy = self
+        .scan_policies
+        .get(mgr.name())
+        .copied()
+        .unwrap_or_default();
+      if policy.is_continuous() {
+        continue;
+      }
+      if let Some(limit) = policy.duration_limit() {
+        self
+          .scan_deadlines
+          .insert(mgr.name().to_owned(), now + limit);
+      }
+      fut_vec.push(mgr.start_scanning());
+    }
+    // TODO If start_scanning fails anywhere, this will ignore it. We should maybe at least log?
+    future::join_all(fut_vec).await;
+  }
+
+  /// Stops every on demand manager. Continuous managers keep scanning.
+  async fn stop_on_demand_scans(&mut self) {
+    self.scan_deadlines.clear();
+    let fut_vec: Vec<_> = self
+      .comm_managers
+      .iter_mut()
+      .filter(|mgr| {
+        !self
+          .scan_policies
+          .get(mgr.name())
+          .is_some_and(|policy| policy.is_continuous())
+      })
+      .map(|mgr| mgr.stop_scanning())
+      .collect();
+    // TODO If stop_scanning fails anywhere, this will ignore it. We should maybe at least log?
+    future::join_all(fut_vec).await;
+  }
+
+  /// Starts continuous managers, which scan for as long as the event loop runs.
+  async fn start_continuous_scans(&mut self) {
+    for mgr in self.comm_managers.iter_mut() {
+      if !self
+        .scan_policies
+        .get(mgr.name())
+        .is_some_and(|policy| policy.is_continuous())
+      {
+        continue;
+      }
+      info!("Starting continuous scan for {}", mgr.name());
+      if let Err(err) = mgr.start_scanning().await {
+        error!("{} failed to start scanning: {:?}", mgr.name(), err);
+      }
+    }
+  }
+
+  /// Stops on demand managers that have scanned for as long as their policy allows.
+  async fn expire_scan_deadlines(&mut self) {
+    let now = Instant::now();
+    let mut expired = vec![];
+    self.scan_deadlines.retain(|name, deadline| {
+      if *deadline <= now {
+        expired.push(name.clone());
+        false
+      } else {
+        true
+      }
+    });
+    for mgr in self
+      .comm_managers
+      .iter_mut()
+      .filter(|mgr| expired.iter().any(|name| name == mgr.name()))
+    {
+      info!("{} reached its scan duration limit, stopping.", mgr.name());
+      if let Err(err) = mgr.stop_scanning().await {
+        error!("{} failed to stop scanning: {:?}", mgr.name(), err);
+      }
+    }
+    // Managers like btleplug never send ScanningFinished, so check whether that was the last one
+    // scanning ourselves.
+    self
+      .handle_device_communication(HardwareCommunicationManagerEvent::ScanningFinished)
+      .await;
+  }
+
+  /// Lets clients know about comm manager status changes. If `report_all` is true, every
+  /// manager's status is sent, whether it changed or not.
+  fn update_manager_statuses(&self, report_all: bool) {
+    for mgr in &self.comm_managers {
+      let status = mgr.status();
+      let changed = self
+        .manager_statuses
+        .insert(mgr.name().to_owned(), status.clone())
+        .is_none_or(|old_status| old_status != status);
+      if !changed && !report_all {
+        continue;
+      }
+      debug!("{} status: {:?}", mgr.name(), status);
+      if self
+        .server_sender
+        .send(HardwareManagerStatusV4::new(mgr.name(), &status).into())
+        .is_err()
+      {
+        debug!("Server not currently available, dropping Hardware Manager Status event.");
+      }
+    }
+  }
+
+  async fn handle_add_comm_manager(
+    &mut self,
+    mut comm_manager: Box<dyn HardwareCommunicationManager>,
+  ) -> Result<(), ButtplugServerError> {
+    if self
+      .comm_managers
+      .iter()
+      .any(|mgr| mgr.name() == comm_manager.name())
+    {
+      return Err(
+        ButtplugServerError::DeviceCommunicationManagerTypeAlreadyAdded(
+          comm_manager.name().to_owned(),
+        ),
+      );
+    }
+    info!(
+      "Adding {}: {}",
+      comm_manager.name(),
+      comm_manager.can_scan()
+    );
+    // Continuous managers always scan, others join in on whatever scan is already running.
+    let policy = self.scan_policy(comm_manager.name());
+    if policy.is_continuous()
+      || self.scanning_state != ScanningState::Idle
+      || self.background_scanning
+    {
+      if let Some(limit) = policy.duration_limit()
+        && !policy.is_continuous()
+      {
+        self
+          .scan_deadlines
+          .insert(comm_manager.name().to_owned(), Instant::now() + limit);
+      }
+      if let Err(err) = comm_manager.start_scanning().await {
+        error!(
+          "{} failed to start scanning: {:?}",
+          comm_manager.name(),
+          err
+        );
+      }
+    }
+    self.comm_managers.push(comm_manager);
+    self.update_manager_statuses(false);
+    Ok(())
+  }
+
+  async fn handle_remove_comm_manager(&mut self, name: &str) -> Result<(), ButtplugServerError> {
+    let Some(index) = self.comm_managers.iter().position(|mgr| mgr.name() == name) else {
+      return Err(ButtplugServerError::DeviceCommunicationManagerDoesNotExist(
+        name.to_owned(),
+      ));
+    };
+    info!("Removing {}", name);
+    // Devices the manager already connected stay connected, we just won't find any new ones.
+    let mut comm_manager = self.comm_managers.remove(index);
+    if let Err(err) = comm_manager.stop_scanning().await {
+      error!("{} failed to stop scanning: {:?}", name, err);
     }
+    self.manager_statuses.remove(name);
+    self.scan_deadlines.remove(name);
+    if self
+      .server_sender
+      .send(
+        HardwareManagerStatusV4::new(
+          name,
+          &HardwareManagerStatus::Unavailable("Removed from device manager".to_owned()),
+        )
+        .into(),
+      )
+      .is_err()
+    {
+      debug!("Server not currently available, dropping Hardware Manager Status event.");
+    }
+    // The manager we removed may have been the last one scanning.
+    self
+      .handle_device_communication(HardwareCommunicationManagerEvent::ScanningFinished)
+      .await;
+    Ok(())
   }
 
+  /// Whether any on demand manager is still scanning. Continuous managers never finish, so they
+  /// don't count.
   fn scanning_status(&self) -> bool {
-    if self.comm_managers.iter().any(|x| x.scanning_status()) {
+    if self
+      .comm_managers
+      .iter()
+      .any(|x| !self.scan_policy(x.name()).is_continuous() && x.scanning_status())
+    {
       debug!("At least one manager still scanning, continuing event loop.");
       return true;
     }
@@ -111,6 +418,14 @@ impl ServerDeviceManagerEventLoop {
       return;
     }
 
+    // If we're already scanning for devices to reconnect, hand the scan over to the client.
+    self.background_rescan = None;
+    if std::mem::take(&mut self.background_scanning) && self.scanning_status() {
+      debug!("Background scan in progress, reporting it to clients from now on");
+      self.scanning_state = ScanningState::Active;
+      return;
+    }
+
     // Also check if hardware is still scanning (edge case: state is Idle but hardware lagging)
     if self.scanning_status() {
       debug!("Hardware still scanning, ignoring new scanning request");
@@ -120,13 +435,7 @@ impl ServerDeviceManagerEventLoop {
     info!("No scan currently in progress, starting new scan.");
     self.scanning_state = ScanningState::BringupInProgress;
 
-    let fut_vec: Vec<_> = self
-      .comm_managers
-      .iter_mut()
-      .map(|guard| guard.start_scanning())
-      .collect();
-    // TODO If start_scanning fails anywhere, this will ignore it. We should maybe at least log?
-    future::join_all(fut_vec).await;
+    self.start_on_demand_scans().await;
 
     debug!("Scanning started for all hardware comm managers.");
     // Check if stop was requested during bringup
@@ -138,6 +447,11 @@ impl ServerDeviceManagerEventLoop {
   }
 
   async fn handle_stop_scanning(&mut self) {
+    // Clients didn't start background scans, so they don't get to stop them.
+    if self.scanning_state == ScanningState::Idle && self.background_scanning {
+      debug!("Stop scanning called during background scan, ignoring");
+      return;
+    }
     // Transition to stop-requested state (only meaningful if currently scanning)
     match self.scanning_state {
       ScanningState::Active => {
@@ -156,13 +470,7 @@ impl ServerDeviceManagerEventLoop {
       }
     }
 
-    let fut_vec: Vec<_> = self
-      .comm_managers
-      .iter_mut()
-      .map(|guard| guard.stop_scanning())
-      .collect();
-    // TODO If stop_scanning fails anywhere, this will ignore it. We should maybe at least log?
-    future::join_all(fut_vec).await;
+    self.stop_on_demand_scans().await;
 
     // Transition to Idle if all managers have stopped. This handles comm managers
     // (like btleplug) that never emit ScanningFinished because they use long-running
@@ -170,9 +478,64 @@ impl ServerDeviceManagerEventLoop {
     if self.scanning_state == ScanningState::ActiveStopRequested && !self.scanning_status() {
       debug!("All managers stopped after explicit stop request, transitioning to Idle");
       self.scanning_state = ScanningState::Idle;
+      self.start_background_scan().await;
     }
   }
 
+  /// Starts scanning for devices waiting to reconnect, unless something is already scanning.
+  async fn start_background_scan(&mut self) {
+    if self.pending_reconnects.is_empty()
+      || self.background_scanning
+      || self.scanning_state != ScanningState::Idle
+      || self.scanning_status()
+    {
+      return;
+    }
+    debug!(
+      "Scanning in background for {} device(s) waiting to reconnect",
+      self.pending_reconnects.len()
+    );
+    self.background_scanning = true;
+    self.background_rescan = None;
+    self.start_on_demand_scans().await;
+  }
+
+  /// Stops a background scan once no devices are waiting to reconnect.
+  async fn stop_background_scan(&mut self) {
+    if !self.pending_reconnects.is_empty() {
+      return;
+    }
+    self.background_rescan = None;
+    if !std::mem::take(&mut self.background_scanning) {
+      return;
+    }
+    debug!("No devices waiting to reconnect, stopping background scan");
+    self.stop_on_demand_scans().await;
+  }
+
+  /// Drops devices that didn't come back before their reconnect timeout.
+  async fn expire_pending_reconnects(&mut self) {
+    let now = Instant::now();
+    self.pending_reconnects.retain(|address, pending| {
+      let expired = pending.deadline.is_some_and(|deadline| deadline <= now);
+      if expired {
+        info!("Device {} did not reconnect in time, giving up.", address);
+      }
+      !expired
+    });
+    self.stop_background_scan().await;
+  }
+
+  /// Next time the event loop needs to wake up for reconnect timeouts or a background rescan.
+  fn next_reconnect_wakeup(&self) -> Option<Instant> {
+    self
+      .pending_reconnects
+      .values()
+      .filter_map(|pending| pending.deadline)
+      .chain(self.background_rescan)
+      .min()
+  }
+
   async fn handle_device_communication(&mut self, event: HardwareCommunicationManagerEvent) {
     match event {
       HardwareCommunicationManagerEvent::ScanningFinished => {
@@ -182,6 +545,15 @@ impl ServerDeviceManagerEventLoop {
         );
 
         match self.scanning_state {
+          ScanningState::Idle if self.background_scanning => {
+            if !self.scanning_status() {
+              debug!("Background scan finished, scheduling another for missing devices");
+              self.background_scanning = false;
+              if !self.pending_reconnects.is_empty() {
+                self.background_rescan = Some(Instant::now() + BACKGROUND_RESCAN_DELAY);
+              }
+            }
+          }
           ScanningState::Idle => {
             // Spurious event, ignore
             debug!("Received ScanningFinished in Idle state, ignoring");
@@ -202,6 +574,7 @@ impl ServerDeviceManagerEventLoop {
               {
                 info!("Server disappeared, exiting loop.");
               }
+              self.start_background_scan().await;
             }
           }
           ScanningState::ActiveStopRequested => {
@@ -209,10 +582,13 @@ impl ServerDeviceManagerEventLoop {
             if !self.scanning_status() {
               debug!("All managers finished after stop request, not emitting ScanningFinished");
               self.scanning_state = ScanningState::Idle;
+              self.start_background_scan().await;
             }
           }
         }
       }
+      // Statuses are checked after every comm manager event, so there's nothing else to do here.
+      HardwareCommunicationManagerEvent::StatusChanged => {}
       HardwareCommunicationManagerEvent::DeviceFound {
         name,
         address,
@@ -228,6 +604,19 @@ impl ServerDeviceManagerEventLoop {
           address
         );
 
+        // Background scans are only looking for devices waiting to reconnect. This also drops
+        // devices from continuous managers, which will report them again on their next pass.
+        if self.background_scanning
+          && self.scanning_state == ScanningState::Idle
+          && !self.pending_reconnects.contains_key(&address)
+        {
+          debug!(
+            "Device {} found during background scan but not waiting to reconnect, ignoring.",
+            address
+          );
+          return;
+        }
+
         // Check to make sure the device isn't already connected. If it is, drop what we've been
         // sent and return.
         if self
@@ -285,12 +674,20 @@ impl ServerDeviceManagerEventLoop {
         let connecting_devices = self.connecting_devices.clone();
         let span = info_span!(
           "device creation",
-          name = tracing::field::display(name),
+          name = tracing::field::display(&name),
           address = tracing::field::display(address.clone())
         );
 
         // Clone sender again for the forwarding task that build_device_handle will spawn
         let device_event_sender_for_forwarding = self.device_event_sender.clone();
+        let safety_sender = self.safety_sender.clone();
+        let mixer_config = self.mixer_config;
+        let recorder = self.recorder.clone();
+        let protocols: Vec<String> = protocol_specializers
+          .iter()
+          .map(|specializer| specializer.protocol().to_owned())
+          .collect();
+        let server_sender = self.server_sender.clone();
 
         buttplug_core::util::async_manager::spawn(
           async move {
@@ -299,6 +696,9 @@ impl ServerDeviceManagerEventLoop {
               creator,
               protocol_specializers,
               device_event_sender_for_forwarding,
+              safety_sender,
+              mixer_config,
+              recorder,
             )
             .await
             {
@@ -315,6 +715,13 @@ impl ServerDeviceManagerEventLoop {
               }
               Err(e) => {
                 error!("Device errored while trying to connect: {:?}", e);
+                metrics::device_connect_failed(&protocols);
+                let failure = DeviceConnectionFailedV4::new(&name, &address, &protocols, &e);
+                if server_sender.send(failure.into()).is_err() {
+                  debug!(
+                    "Server not currently available, dropping Device Connection Failed event."
+                  );
+                }
               }
             }
             connecting_devices.remove(&address);
@@ -338,6 +745,18 @@ impl ServerDeviceManagerEventLoop {
     device_list
   }
 
+  fn is_emergency_stop_button(
+    &self,
+    identifier: &UserDeviceIdentifier,
+    message: &ButtplugServerDeviceMessage,
+  ) -> bool {
+    let ButtplugServerDeviceMessage::SensorReading(reading) = message;
+    self.device_map.iter().any(|device| {
+      device.value().identifier() == identifier
+        && device.value().definition().emergency_stop_button() == Some(reading.feature_index())
+    })
+  }
+
   async fn handle_device_event(&mut self, device_event: InternalDeviceEvent) {
     trace!("Got device event: {:?}", device_event);
     match device_event {
@@ -383,6 +802,21 @@ impl ServerDeviceManagerEventLoop {
           device_handle.name()
         );
         self.device_map.insert(device_index, device_handle.clone());
+        metrics::devices_connected(self.device_map.len());
+
+        let reconnect = self
+          .pending_reconnects
+          .remove(device_handle.identifier().address());
+        if reconnect.is_some() {
+          info!("Device {} reconnected.", device_handle.name());
+          if self
+            .server_sender
+            .send(DeviceReconnectedV4::new(device_index).into())
+            .is_err()
+          {
+            debug!("Server not currently available, dropping Device Reconnected event.");
+          }
+        }
 
         let device_update_message: ButtplugServerMessageV4 = self.generate_device_list().into();
 
@@ -391,6 +825,26 @@ impl ServerDeviceManagerEventLoop {
         if self.server_sender.send(device_update_message).is_err() {
           debug!("Server not currently available, dropping Device Added event.");
         }
+
+        let reconnected = reconnect.is_some();
+        // Outputs that were running before a dropout take precedence over the connect preset.
+        // Sessions that ended while the device was gone don't get theirs back.
+        match reconnect
+          .map(|reconnect| {
+            reconnect
+              .outputs
+              .into_iter()
+              .filter(|(session, _)| self.active_sessions.contains(session))
+              .collect::<Vec<_>>()
+          })
+          .filter(|outputs| !outputs.is_empty())
+        {
+          Some(outputs) => self.restore_outputs(device_index, &device_handle, outputs),
+          None => self.apply_connect_preset(&device_handle),
+        }
+        if reconnected {
+          self.stop_background_scan().await;
+        }
       }
       InternalDeviceEvent::Disconnected(identifier) => {
         let mut device_index = None;
@@ -401,17 +855,32 @@ impl ServerDeviceManagerEventLoop {
           }
         }
         if let Some(device_index) = device_index {
-          self
+          let (_, device) = self
             .device_map
             .remove(&device_index)
             .expect("Remove will always work.");
+          metrics::devices_connected(self.device_map.len());
+          self.queue_reconnect(device_index, &device).await;
+          self.arbiter.clear_device(device_index);
           let device_update_message: ButtplugServerMessageV4 = self.generate_device_list().into();
           if self.server_sender.send(device_update_message).is_err() {
             debug!("Server not currently available, dropping Device Removed event.");
           }
         }
       }
-      InternalDeviceEvent::Notification(_, message) => {
+      InternalDeviceEvent::Notification(identifier, message) => {
+        if self.is_emergency_stop_button(&identifier, &message) {
+          // Emergency stop button readings are handled by the server and never reach clients.
+          let ButtplugServerDeviceMessage::SensorReading(reading) = &message;
+          if let InputTypeReading::Button(value) = reading.reading()
+            && value.data() > 0
+          {
+            info!("Emergency stop button pressed on {:?}", identifier);
+            self.emergency_stop.engage().await;
+            self.clear_pending_outputs(None);
+          }
+          return;
+        }
         if self.server_sender.send(message.into()).is_err() {
           debug!("Server not currently available, dropping Device Added event.");
         }
@@ -419,14 +888,146 @@ impl ServerDeviceManagerEventLoop {
     }
   }
 
+  /// Starts watching for a device that dropped out, if its configuration asks for it.
+  async fn queue_reconnect(&mut self, device_index: u32, device: &DeviceHandle) {
+    let Some(policy) = device.definition().auto_reconnect() else {
+      return;
+    };
+    if !self.running.load(Ordering::Relaxed) {
+      return;
+    }
+    info!(
+      "Device {} disconnected, waiting for it to reconnect.",
+      device.name()
+    );
+    let outputs = if policy.restore_outputs() {
+      self.arbiter.device_commands(device_index)
+    } else {
+      vec![]
+    };
+    let deadline = policy
+      .timeout_ms()
+      .map(|timeout| Instant::now() + Duration::from_millis(timeout as u64));
+    self.pending_reconnects.insert(
+      device.identifier().address().clone(),
+      PendingReconnect { outputs, deadline },
+    );
+    self.start_background_scan().await;
+  }
+
+  /// Forgets the outputs a session was running on devices waiting to reconnect, or every session's
+  /// if `session` is None.
+  fn clear_pending_outputs(&mut self, session: Option<u32>) {
+    for pending in self.pending_reconnects.values_mut() {
+      pending
+        .outputs
+        .retain(|(id, _)| session.is_some_and(|session| session != *id));
+    }
+  }
+
+  /// Restarts the outputs sessions were running on a device before it dropped out. Commands go back
+  /// through the arbiter, so they're combined with whatever other sessions have sent since.
+  fn restore_outputs(
+    &self,
+    device_index: u32,
+    device: &DeviceHandle,
+    outputs: Vec<(u32, CheckedOutputCmdV4)>,
+  ) {
+    if self.emergency_stop.is_engaged() {
+      info!(
+        "Emergency stop engaged, not restoring outputs for {}",
+        device.name()
+      );
+      return;
+    }
+    // Only the last arbitrated command for each output needs to go out.
+    let mut commands = HashMap::new();
+    for (session, cmd) in outputs {
+      let cmd = CheckedOutputCmdV4::new(
+        cmd.id(),
+        device_index,
+        cmd.feature_index(),
+        cmd.feature_id(),
+        cmd.output_command(),
+      );
+      match self
+        .arbiter
+        .arbitrate(session, &cmd, output_max_value(device.definition(), &cmd))
+      {
+        Ok(cmd) => {
+          commands.insert(
+            (cmd.feature_index(), cmd.output_command().as_output_type()),
+            cmd,
+          );
+        }
+        Err(e) => error!("Error restoring output after reconnect: {:?}", e),
+      }
+    }
+    let fut_vec: Vec<_> = commands
+      .into_values()
+      .map(|cmd| device.parse_message(cmd.into()))
+      .collect();
+    buttplug_core::spawn!("DeviceReconnectRestoreOutputs", async move {
+      for result in future::join_all(fut_vec).await {
+        if let Err(e) = result {
+          error!("Error restoring output after reconnect: {:?}", e);
+        }
+      }
+    });
+  }
+
+  /// Applies the preset a device's configuration asks for on connect, if any.
+  fn apply_connect_preset(&self, device: &DeviceHandle) {
+    let Some(preset) = device
+      .definition()
+      .connect_preset()
+      .as_ref()
+      .and_then(|name| device.definition().presets().get(name))
+    else {
+      return;
+    };
+    if self.emergency_stop.is_engaged() {
+      info!(
+        "Emergency stop engaged, not applying connect preset for {}",
+        device.name()
+      );
+      return;
+    }
+    let fut = device.apply_preset(&self.arbiter, ServerSession::default().id(), preset);
+    buttplug_core::spawn!("DeviceConnectPreset", async move {
+      if let Err(e) = fut.await {
+        error!("Error applying connect preset: {:?}", e);
+      }
+    });
+  }
+
   pub async fn run(&mut self) {
     debug!("Starting Device Manager Loop");
+    self.start_continuous_scans().await;
+    self.update_manager_statuses(false);
+    let mut status_poll = tokio::time::interval(STATUS_POLL_INTERVAL);
+    status_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
     loop {
+      let reconnect_wakeup = self.next_reconnect_wakeup();
+      let reconnect_fut = async {
+        match reconnect_wakeup {
+          Some(deadline) => tokio::time::sleep_until(deadline).await,
+          None => future::pending::<()>().await,
+        }
+      };
+      let scan_deadline = self.scan_deadlines.values().min().copied();
+      let scan_deadline_fut = async {
+        match scan_deadline {
+          Some(deadline) => tokio::time::sleep_until(deadline).await,
+          None => future::pending::<()>().await,
+        }
+      };
       tokio::select! {
         device_comm_msg = self.device_comm_receiver.recv() => {
           if let Some(msg) = device_comm_msg {
             trace!("Got device communication message {:?}", msg);
             self.handle_device_communication(msg).await;
+            self.update_manager_statuses(false);
           } else {
             break;
           }
@@ -444,14 +1045,48 @@ impl ServerDeviceManagerEventLoop {
           if let Some(msg) = device_command_msg {
             trace!("Got device command message {:?}", msg);
             match msg {
-              DeviceManagerCommand::StartScanning => self.handle_start_scanning().await,
-              DeviceManagerCommand::StopScanning => self.handle_stop_scanning().await,
+              DeviceManagerCommand::StartScanning => {
+                self.handle_start_scanning().await;
+                // Clients that just started scanning will want to know what can and can't find
+                // devices.
+                self.update_manager_statuses(true);
+              }
+              DeviceManagerCommand::StopScanning => {
+                self.handle_stop_scanning().await;
+                self.update_manager_statuses(false);
+              }
+              DeviceManagerCommand::AddCommManager(comm_manager, result_sender) => {
+                let result = self.handle_add_comm_manager(comm_manager).await;
+                let _ = result_sender.send(result);
+              }
+              DeviceManagerCommand::RemoveCommManager(name, result_sender) => {
+                let result = self.handle_remove_comm_manager(&name).await;
+                let _ = result_sender.send(result);
+              }
+              DeviceManagerCommand::ClearPendingOutputs(session) => {
+                self.clear_pending_outputs(session);
+              }
             }
           } else {
             debug!("Channel to Device Manager frontend dropped, exiting event loop.");
             break;
           }
         }
+        _ = reconnect_fut => {
+          self.expire_pending_reconnects().await;
+          if self
+            .background_rescan
+            .is_some_and(|rescan| rescan <= Instant::now())
+          {
+            self.background_rescan = None;
+            self.start_background_scan().await;
+          }
+        }
+        _ = scan_deadline_fut => {
+          self.expire_scan_deadlines().await;
+          self.update_manager_statuses(false);
+        }
+        _ = status_poll.tick() => self.update_manager_statuses(false),
         _ = self.loop_cancellation_token.cancelled().fuse() => {
           debug!("Device event loop cancelled, exiting.");
           break;
@@ -0,0 +1,45 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use buttplug_server_device_config::UserDeviceIdentifier;
+use getset::{CopyGetters, Getters};
+use std::collections::HashSet;
+
+/// A client session on a [ServerDeviceManager](super::ServerDeviceManager).
+///
+/// Every [ButtplugServer](crate::ButtplugServer) sharing a device manager gets its own session, so
+/// device visibility, stopping, and output arbitration are tracked per client.
+#[derive(Debug, Clone, Default, Getters, CopyGetters)]
+pub struct ServerSession {
+  /// Session id, unique for the lifetime of the device manager. Id 0 is used for messages sent to
+  /// the device manager directly.
+  #[getset(get_copy = "pub")]
+  id: u32,
+  /// Addresses of the devices this session can see. If None, all devices are visible.
+  #[getset(get = "pub")]
+  visible_addresses: Option<HashSet<String>>,
+}
+
+impl ServerSession {
+  pub(crate) fn new(id: u32, visible_addresses: Option<HashSet<String>>) -> Self {
+    Self {
+      id,
+      visible_addresses,
+    }
+  }
+
+  pub fn can_see(&self, identifier: &UserDeviceIdentifier) -> bool {
+    self.can_see_address(identifier.address())
+  }
+
+  pub fn can_see_address(&self, address: &str) -> bool {
+    self
+      .visible_addresses
+      .as_ref()
+      .is_none_or(|addresses| addresses.contains(address))
+  }
+}
@@ -54,7 +54,9 @@ extern crate strum_macros;
 pub mod connector;
 pub mod device;
 pub mod message;
+pub mod metrics;
 mod ping_timer;
+pub mod recorder;
 mod server;
 mod server_builder;
 mod server_message_conversion;
@@ -88,10 +90,21 @@ pub enum ButtplugServerError {
   /// DeviceCommunicationManager type has already been added to the system.
   #[error("DeviceCommunicationManager of type {0} has already been added.")]
   DeviceCommunicationManagerTypeAlreadyAdded(String),
+  /// Requested DeviceCommunicationManager is not running in the system.
+  #[error(
+    "DeviceCommunicationManager of type {0} does not exist in the system and cannot be removed."
+  )]
+  DeviceCommunicationManagerDoesNotExist(String),
+  /// The device manager event loop has shut down.
+  #[error("The device manager is no longer running.")]
+  DeviceManagerNotRunning,
   /// Protocol has already been added to the system.
   #[error("Buttplug Protocol of type {0} has already been added to the system.")]
   ProtocolAlreadyAdded(String),
   /// Requested protocol has not been registered with the system.
   #[error("Buttplug Protocol of type {0} does not exist in the system and cannot be removed.")]
   ProtocolDoesNotExist(String),
+  /// Session recording could not be read or written.
+  #[error("Session recording error: {0}")]
+  RecordingError(String),
 }
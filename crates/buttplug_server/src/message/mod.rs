@@ -18,6 +18,7 @@ use buttplug_core::{
     InputReadingV4,
   },
 };
+use serde::{Deserialize, Serialize};
 use server_device_attributes::ServerDeviceAttributes;
 
 /// Macro for implementing ButtplugMessage and ButtplugMessageValidator on message enums
@@ -73,7 +74,7 @@ pub use v2::*;
 pub use v3::*;
 pub use v4::*;
 
-#[derive(Debug, Clone, PartialEq, derive_more::From)]
+#[derive(Debug, Clone, PartialEq, derive_more::From, Serialize, Deserialize)]
 pub enum ButtplugClientMessageVariant {
   V0(ButtplugClientMessageV0),
   V1(ButtplugClientMessageV1),
@@ -136,7 +137,11 @@ impl ButtplugClientMessageVariant {
           SensorReadCmd
         ]
       ),
-      Self::V4(msg) => extract_device_index!(msg, ButtplugClientMessageV4, [OutputCmd, InputCmd]),
+      Self::V4(msg) => extract_device_index!(
+        msg,
+        ButtplugClientMessageV4,
+        [OutputCmd, InputCmd, ApplyPresetCmd, StorePresetCmd]
+      ),
     }
   }
 }
@@ -12,7 +12,7 @@ use crate::message::{
   v2::{ButtplugClientMessageV2, ButtplugServerMessageV2, ServerInfoV2},
 };
 use buttplug_core::{
-  errors::{ButtplugError, ButtplugMessageError},
+  errors::{ButtplugDeviceError, ButtplugError, ButtplugMessageError},
   message::{
     ButtplugMessageFinalizer,
     ButtplugServerMessageV4,
@@ -192,6 +192,13 @@ impl TryFrom<ButtplugServerMessageV4> for ButtplugServerMessageV3 {
         Ok(ButtplugServerMessageV3::ScanningFinished(m))
       }
       ButtplugServerMessageV4::DeviceList(m) => Ok(ButtplugServerMessageV3::DeviceList(m.into())),
+      // Older specs have no emergency stop message, so let clients know via a system Error. There's
+      // no error that describes a reset, so resets are dropped by the server instead.
+      ButtplugServerMessageV4::EmergencyStopState(m) if m.engaged() => {
+        Ok(ButtplugServerMessageV3::Error(ErrorV0::from(
+          ButtplugError::from(ButtplugDeviceError::EmergencyStopEngaged),
+        )))
+      }
       // All other messages (SensorReading) requires device manager context.
       _ => Err(ButtplugMessageError::MessageConversionError(format!(
         "Cannot convert message {value:?} to current message spec while lacking state."
@@ -23,6 +23,7 @@ use crate::message::{
 use buttplug_core::{
   errors::{ButtplugDeviceError, ButtplugError, ButtplugMessageError},
   message::{
+    ApplyPresetCmdV4,
     ButtplugClientMessageV4,
     ButtplugDeviceMessage,
     ButtplugMessage,
@@ -32,6 +33,7 @@ use buttplug_core::{
     StartScanningV0,
     StopCmdV4,
     StopScanningV0,
+    StorePresetCmdV4,
   },
 };
 
@@ -63,6 +65,9 @@ pub enum ButtplugCheckedClientMessageV4 {
   OutputCmd(CheckedOutputCmdV4),
   // Sensor commands
   InputCmd(CheckedInputCmdV4),
+  // Preset commands
+  ApplyPresetCmd(ApplyPresetCmdV4),
+  StorePresetCmd(StorePresetCmdV4),
   // Internal conversions for v1-v3 messages with subcommands
   OutputVecCmd(CheckedOutputVecCmdV4),
 }
@@ -76,6 +81,8 @@ impl_message_enum_traits!(ButtplugCheckedClientMessageV4 {
   StopCmd,
   OutputCmd,
   InputCmd,
+  ApplyPresetCmd,
+  StorePresetCmd,
   OutputVecCmd,
 });
 
@@ -112,6 +119,25 @@ impl TryFromClientMessage<ButtplugClientMessageV4> for ButtplugCheckedClientMess
           ))
         }
       }
+      ButtplugClientMessageV4::ApplyPresetCmd(m) => {
+        if feature_map.contains_key(&m.device_index()) {
+          Ok(ButtplugCheckedClientMessageV4::ApplyPresetCmd(m))
+        } else {
+          Err(ButtplugError::from(
+            ButtplugDeviceError::DeviceNotAvailable(m.device_index()),
+          ))
+        }
+      }
+      // Preset outputs are checked against the device configuration when the preset is stored.
+      ButtplugClientMessageV4::StorePresetCmd(m) => {
+        if feature_map.contains_key(&m.device_index()) {
+          Ok(ButtplugCheckedClientMessageV4::StorePresetCmd(m))
+        } else {
+          Err(ButtplugError::from(
+            ButtplugDeviceError::DeviceNotAvailable(m.device_index()),
+          ))
+        }
+      }
 
       // Message that need device index and feature checking
       ButtplugClientMessageV4::OutputCmd(m) => {
@@ -330,6 +356,8 @@ pub(crate) enum ButtplugDeviceManagerMessageUnion {
   StopCmd(StopCmdV4),
   StartScanning(StartScanningV0),
   StopScanning(StopScanningV0),
+  ApplyPresetCmd(ApplyPresetCmdV4),
+  StorePresetCmd(StorePresetCmdV4),
 }
 
 impl_message_enum_traits!(ButtplugDeviceManagerMessageUnion {
@@ -337,6 +365,8 @@ impl_message_enum_traits!(ButtplugDeviceManagerMessageUnion {
   StopCmd,
   StartScanning,
   StopScanning,
+  ApplyPresetCmd,
+  StorePresetCmd,
 });
 
 impl TryFrom<ButtplugCheckedClientMessageV4> for ButtplugDeviceManagerMessageUnion {
@@ -356,6 +386,12 @@ impl TryFrom<ButtplugCheckedClientMessageV4> for ButtplugDeviceManagerMessageUni
       ButtplugCheckedClientMessageV4::StopScanning(m) => {
         Ok(ButtplugDeviceManagerMessageUnion::StopScanning(m))
       }
+      ButtplugCheckedClientMessageV4::ApplyPresetCmd(m) => {
+        Ok(ButtplugDeviceManagerMessageUnion::ApplyPresetCmd(m))
+      }
+      ButtplugCheckedClientMessageV4::StorePresetCmd(m) => {
+        Ok(ButtplugDeviceManagerMessageUnion::StorePresetCmd(m))
+      }
       _ => Err(()),
     }
   }
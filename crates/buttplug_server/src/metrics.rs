@@ -0,0 +1,209 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Server metrics, reported through the [metrics](https://docs.rs/metrics) facade.
+//!
+//! Metrics are only emitted when the `metrics` feature is enabled, and only go somewhere once the
+//! application installs a recorder (for instance, a Prometheus exporter). Without the feature, all
+//! of the recording functions compile to nothing.
+
+use futures::future::BoxFuture;
+
+/// Counter of commands sent to hardware, labeled by `device` name and `address`.
+pub const DEVICE_COMMANDS_SENT: &str = "buttplug_device_commands_sent_total";
+/// Counter of queued commands dropped because a newer command for the same endpoint replaced them
+/// before the batch was flushed, labeled by `device` name and `address`.
+pub const DEVICE_COMMANDS_COALESCED: &str = "buttplug_device_commands_coalesced_total";
+/// Counter of keepalive packets written, labeled by `device` name and `address`.
+pub const DEVICE_KEEPALIVE_WRITES: &str = "buttplug_device_keepalive_writes_total";
+/// Histogram of hardware write latency in seconds, labeled by `device` name and `address`.
+pub const HARDWARE_WRITE_SECONDS: &str = "buttplug_hardware_write_seconds";
+/// Counter of failed device connections, labeled by `protocol`. A device that could have been
+/// handled by several protocols counts once against each of them.
+pub const DEVICE_CONNECT_FAILURES: &str = "buttplug_device_connect_failures_total";
+/// Gauge of devices currently connected to the device manager.
+pub const DEVICES_CONNECTED: &str = "buttplug_devices_connected";
+/// Counter of client connections dropped because no ping was received in time.
+pub const PING_TIMEOUTS: &str = "buttplug_ping_timeouts_total";
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn device_commands_sent(device: &str, address: &str, count: usize) {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(
+    DEVICE_COMMANDS_SENT,
+    "device" => device.to_owned(),
+    "address" => address.to_owned()
+  )
+  .increment(count as u64);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn device_commands_coalesced(device: &str, address: &str, count: usize) {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(
+    DEVICE_COMMANDS_COALESCED,
+    "device" => device.to_owned(),
+    "address" => address.to_owned()
+  )
+  .increment(count as u64);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn device_keepalive_write(device: &str, address: &str) {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(
+    DEVICE_KEEPALIVE_WRITES,
+    "device" => device.to_owned(),
+    "address" => address.to_owned()
+  )
+  .increment(1);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn device_connect_failed(protocols: &[String]) {
+  #[cfg(feature = "metrics")]
+  for protocol in protocols {
+    metrics::counter!(DEVICE_CONNECT_FAILURES, "protocol" => protocol.clone()).increment(1);
+  }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn devices_connected(count: usize) {
+  #[cfg(feature = "metrics")]
+  metrics::gauge!(DEVICES_CONNECTED).set(count as f64);
+}
+
+pub(crate) fn ping_timeout() {
+  #[cfg(feature = "metrics")]
+  metrics::counter!(PING_TIMEOUTS).increment(1);
+}
+
+/// Wraps a hardware write so its latency is recorded once it resolves.
+#[cfg(feature = "metrics")]
+pub(crate) fn time_hardware_write<T: Send + 'static>(
+  device: &str,
+  address: &str,
+  fut: BoxFuture<'static, T>,
+) -> BoxFuture<'static, T> {
+  use futures::FutureExt;
+  let histogram = metrics::histogram!(
+    HARDWARE_WRITE_SECONDS,
+    "device" => device.to_owned(),
+    "address" => address.to_owned()
+  );
+  async move {
+    let start = instant::Instant::now();
+    let result = fut.await;
+    histogram.record(start.elapsed().as_secs_f64());
+    result
+  }
+  .boxed()
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn time_hardware_write<T>(
+  _device: &str,
+  _address: &str,
+  fut: BoxFuture<'static, T>,
+) -> BoxFuture<'static, T> {
+  fut
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod test {
+  use super::*;
+  use metrics::{Key, Label};
+  use metrics_util::{
+    CompositeKey,
+    MetricKind,
+    debugging::{DebugValue, DebuggingRecorder},
+  };
+  use std::collections::HashMap;
+
+  fn key(kind: MetricKind, name: &'static str, labels: &[(&'static str, &str)]) -> CompositeKey {
+    let labels: Vec<_> = labels
+      .iter()
+      .map(|(key, value)| Label::new(*key, value.to_string()))
+      .collect();
+    CompositeKey::new(kind, Key::from_parts(name, labels))
+  }
+
+  fn counter(
+    snapshot: &HashMap<
+      CompositeKey,
+      (
+        Option<metrics::Unit>,
+        Option<metrics::SharedString>,
+        DebugValue,
+      ),
+    >,
+    name: &'static str,
+    labels: &[(&'static str, &str)],
+  ) -> Option<u64> {
+    match snapshot.get(&key(MetricKind::Counter, name, labels)) {
+      Some((_, _, DebugValue::Counter(value))) => Some(*value),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn test_metrics_recorded() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+      device_commands_sent("Test Device", "test-address", 3);
+      device_commands_coalesced("Test Device", "test-address", 2);
+      device_keepalive_write("Test Device", "test-address");
+      device_connect_failed(&["lovense".to_owned(), "kiiroo-v2".to_owned()]);
+      device_connect_failed(&["lovense".to_owned()]);
+      devices_connected(2);
+      ping_timeout();
+      futures::executor::block_on(time_hardware_write(
+        "Test Device",
+        "test-address",
+        Box::pin(async {}),
+      ));
+    });
+    let snapshot = snapshotter.snapshot().into_hashmap();
+    let device = [("device", "Test Device"), ("address", "test-address")];
+    assert_eq!(counter(&snapshot, DEVICE_COMMANDS_SENT, &device), Some(3));
+    assert_eq!(
+      counter(&snapshot, DEVICE_COMMANDS_COALESCED, &device),
+      Some(2)
+    );
+    assert_eq!(
+      counter(&snapshot, DEVICE_KEEPALIVE_WRITES, &device),
+      Some(1)
+    );
+    // Each candidate protocol gets its own count, rather than a label for the combination.
+    assert_eq!(
+      counter(
+        &snapshot,
+        DEVICE_CONNECT_FAILURES,
+        &[("protocol", "lovense")]
+      ),
+      Some(2)
+    );
+    assert_eq!(
+      counter(
+        &snapshot,
+        DEVICE_CONNECT_FAILURES,
+        &[("protocol", "kiiroo-v2")]
+      ),
+      Some(1)
+    );
+    assert_eq!(counter(&snapshot, PING_TIMEOUTS, &[]), Some(1));
+    assert!(matches!(
+      snapshot.get(&key(MetricKind::Gauge, DEVICES_CONNECTED, &[])),
+      Some((_, _, DebugValue::Gauge(value))) if value.into_inner() == 2.0
+    ));
+    assert!(matches!(
+      snapshot.get(&key(MetricKind::Histogram, HARDWARE_WRITE_SECONDS, &device)),
+      Some((_, _, DebugValue::Histogram(values))) if values.len() == 1
+    ));
+  }
+}
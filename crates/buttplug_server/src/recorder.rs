@@ -0,0 +1,189 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Session recording, for capturing what a client sent and what the hardware received.
+//!
+//! Recordings are written as one JSON [RecordedEntry] per line, in the order events happened. They
+//! can be loaded with [read_recording] and replayed against simulated hardware to check that the
+//! same client messages still produce the same hardware commands.
+
+use crate::{
+  ButtplugServerError,
+  device::hardware::{HardwareCommand, HardwareReading},
+  message::ButtplugClientMessageVariant,
+};
+use buttplug_server_device_config::Endpoint;
+use getset::{CopyGetters, Getters};
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+use std::{
+  fs::File,
+  io::{BufRead, BufReader, LineWriter, Write},
+  path::Path,
+  sync::mpsc,
+  thread,
+};
+
+/// Something that happened during a recorded session.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+  /// Message received from a client session, before any conversion to the current spec version.
+  ClientMessage {
+    session: u32,
+    message: ButtplugClientMessageVariant,
+  },
+  /// Hardware connected, before protocol initialization ran.
+  DeviceConnected { name: String, address: String },
+  /// Command sent to the hardware, including ones sent during protocol initialization.
+  HardwareCommand {
+    address: String,
+    command: HardwareCommand,
+  },
+  /// Data returned from reading a hardware endpoint.
+  HardwareRead {
+    address: String,
+    endpoint: Endpoint,
+    data: Vec<u8>,
+  },
+  /// Data the hardware sent on a subscribed endpoint.
+  HardwareNotification {
+    address: String,
+    endpoint: Endpoint,
+    data: Vec<u8>,
+  },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct RecordedEntry {
+  /// Milliseconds since the recorder was created.
+  #[getset(get_copy = "pub")]
+  timestamp_ms: u64,
+  #[getset(get = "pub")]
+  event: RecordedEvent,
+}
+
+enum RecorderMessage {
+  Entry(RecordedEntry),
+  /// Acknowledged once everything sent before it has been written.
+  Flush(mpsc::Sender<()>),
+}
+
+/// Writes a timestamped log of a session. Cheap to clone, all clones write to the same log.
+///
+/// Entries are serialized and written on a dedicated thread, so recording never blocks the device
+/// or message handling code on file I/O. The thread exits once every clone has been dropped.
+#[derive(Clone)]
+pub struct SessionRecorder {
+  start: Instant,
+  sender: mpsc::Sender<RecorderMessage>,
+}
+
+impl std::fmt::Debug for SessionRecorder {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SessionRecorder")
+      .field("start", &self.start)
+      .finish()
+  }
+}
+
+fn run_writer(mut writer: impl Write, receiver: mpsc::Receiver<RecorderMessage>) {
+  for message in receiver {
+    match message {
+      RecorderMessage::Entry(entry) => {
+        let line = match serde_json::to_string(&entry) {
+          Ok(line) => line,
+          Err(e) => {
+            error!("Cannot serialize recorded event {:?}: {:?}", entry, e);
+            continue;
+          }
+        };
+        if let Err(e) = writeln!(writer, "{line}") {
+          error!("Cannot write to session recording: {:?}", e);
+        }
+      }
+      RecorderMessage::Flush(ack) => {
+        if let Err(e) = writer.flush() {
+          error!("Cannot flush session recording: {:?}", e);
+        }
+        let _ = ack.send(());
+      }
+    }
+  }
+  if let Err(e) = writer.flush() {
+    error!("Cannot flush session recording: {:?}", e);
+  }
+}
+
+impl SessionRecorder {
+  pub fn new(writer: impl Write + Send + 'static) -> Self {
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+      .name("SessionRecorder".to_owned())
+      .spawn(move || run_writer(writer, receiver))
+      .expect("Should be able to spawn the session recorder thread");
+    Self {
+      start: Instant::now(),
+      sender,
+    }
+  }
+
+  /// Creates a recorder writing to a file, replacing the file if it already exists.
+  pub fn to_file(path: &Path) -> Result<Self, ButtplugServerError> {
+    let file = File::create(path).map_err(|e| {
+      ButtplugServerError::RecordingError(format!("Cannot create {}: {e}", path.display()))
+    })?;
+    Ok(Self::new(LineWriter::new(file)))
+  }
+
+  pub fn record(&self, event: RecordedEvent) {
+    let entry = RecordedEntry {
+      timestamp_ms: Instant::now().duration_since(self.start).as_millis() as u64,
+      event,
+    };
+    if self.sender.send(RecorderMessage::Entry(entry)).is_err() {
+      error!("Session recording writer stopped, dropping recorded event.");
+    }
+  }
+
+  /// Blocks until everything recorded so far has been written out.
+  pub fn flush(&self) {
+    let (ack_sender, ack_receiver) = mpsc::channel();
+    if self.sender.send(RecorderMessage::Flush(ack_sender)).is_ok() {
+      let _ = ack_receiver.recv();
+    }
+  }
+
+  pub(crate) fn record_hardware_command(&self, address: &str, command: HardwareCommand) {
+    self.record(RecordedEvent::HardwareCommand {
+      address: address.to_owned(),
+      command,
+    });
+  }
+
+  pub(crate) fn record_hardware_read(&self, address: &str, reading: &HardwareReading) {
+    self.record(RecordedEvent::HardwareRead {
+      address: address.to_owned(),
+      endpoint: *reading.endpoint(),
+      data: reading.data().clone(),
+    });
+  }
+}
+
+/// Loads a recording written by a [SessionRecorder].
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedEntry>, ButtplugServerError> {
+  let file = File::open(path).map_err(|e| {
+    ButtplugServerError::RecordingError(format!("Cannot open {}: {e}", path.display()))
+  })?;
+  BufReader::new(file)
+    .lines()
+    .filter(|line| !line.as_ref().is_ok_and(|l| l.trim().is_empty()))
+    .map(|line| {
+      let line = line.map_err(|e| ButtplugServerError::RecordingError(e.to_string()))?;
+      serde_json::from_str(&line).map_err(|e| ButtplugServerError::RecordingError(e.to_string()))
+    })
+    .collect()
+}
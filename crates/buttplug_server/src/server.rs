@@ -8,8 +8,10 @@
 use crate::server_message_conversion::ButtplugServerDeviceEventMessageConverter;
 
 use super::{
+  ButtplugServerBuilder,
+  ButtplugServerError,
   ButtplugServerResultFuture,
-  device::ServerDeviceManager,
+  device::{ServerDeviceManager, ServerSession},
   message::{
     ButtplugClientMessageVariant,
     ButtplugServerMessageVariant,
@@ -21,6 +23,7 @@ use super::{
     },
   },
   ping_timer::PingTimer,
+  recorder::RecordedEvent,
   server_message_conversion::ButtplugServerMessageConverter,
 };
 use buttplug_core::{
@@ -28,11 +31,11 @@ use buttplug_core::{
   message::{
     self,
     BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+    ButtplugDeviceMessage,
     ButtplugMessage,
     ButtplugMessageSpecVersion,
     ButtplugServerMessageV4,
     ErrorV0,
-    StopCmdV4,
     StopScanningV0,
   },
   util::stream::convert_broadcast_receiver_to_stream,
@@ -90,6 +93,8 @@ pub struct ButtplugServer {
   ping_timer: Arc<PingTimer>,
   /// Manages device discovery and communication.
   device_manager: Arc<ServerDeviceManager>,
+  /// This server's session on the device manager, which may be shared with other servers.
+  session: ServerSession,
   /// Connection state - tracks handshake, client info, and disconnection reason.
   state: Arc<RwLock<ConnectionState>>,
   /// Broadcaster for server events. Receivers for this are handed out through the
@@ -113,6 +118,7 @@ impl ButtplugServer {
     max_ping_time: u32,
     ping_timer: Arc<PingTimer>,
     device_manager: Arc<ServerDeviceManager>,
+    session: ServerSession,
     state: Arc<RwLock<ConnectionState>>,
     output_sender: broadcast::Sender<ButtplugServerMessageV4>,
   ) -> Self {
@@ -121,6 +127,7 @@ impl ButtplugServer {
       max_ping_time,
       ping_timer,
       device_manager,
+      session,
       state,
       output_sender,
     }
@@ -157,20 +164,44 @@ impl ButtplugServer {
       .device_manager
       .devices()
       .iter()
+      .filter(|x| self.session.can_see(x.value().identifier()))
       .map(|x| *x.key())
       .collect();
     let device_event_converter = ButtplugServerDeviceEventMessageConverter::new(device_indexes);
     self.server_version_event_stream().filter_map(move |m| {
       // Get spec_version from Connected state, default to Version4 if not connected
-      let spec_version = {
+      let (spec_version, connected) = {
         let state_guard = state.read().expect("State lock poisoned");
         match &*state_guard {
-          ConnectionState::Connected { spec_version, .. } => *spec_version,
-          _ => ButtplugMessageSpecVersion::Version4,
+          ConnectionState::Connected { spec_version, .. } => (*spec_version, true),
+          _ => (ButtplugMessageSpecVersion::Version4, false),
         }
       };
       if let ButtplugServerMessageV4::DeviceList(list) = m {
         device_event_converter.convert_device_list(&spec_version, &list)
+      } else if matches!(m, ButtplugServerMessageV4::DeviceReconnected(_))
+        && spec_version < ButtplugMessageSpecVersion::Version4
+      {
+        // Older specs only see the device being added again.
+        None
+      } else if matches!(m, ButtplugServerMessageV4::DeviceConnectionFailed(_))
+        && spec_version < ButtplugMessageSpecVersion::Version4
+      {
+        // Older specs never heard about devices that failed to connect, so keep it that way.
+        None
+      } else if let ButtplugServerMessageV4::EmergencyStopState(state) = &m
+        && !state.engaged()
+        && spec_version < ButtplugMessageSpecVersion::Version4
+      {
+        // Older specs learn about an engaged emergency stop via an error, but have nothing that
+        // can tell them it was reset.
+        None
+      } else if matches!(m, ButtplugServerMessageV4::HardwareManagerStatus(_))
+        && (!connected || spec_version < ButtplugMessageSpecVersion::Version4)
+      {
+        // Statuses are broadcast while the manager runs, whether or not anyone has connected yet,
+        // and older specs have no way to describe them.
+        None
       } else {
         // If we get an event and don't have a spec version yet, just throw out the latest.
         Some(converter.convert_outgoing(&m, &spec_version).unwrap())
@@ -185,7 +216,32 @@ impl ButtplugServer {
     // Unlike the client API, we can expect anyone using the server to pin this
     // themselves.
     let server_receiver = convert_broadcast_receiver_to_stream(self.output_sender.subscribe());
-    let device_receiver = self.device_manager.event_stream();
+    let device_manager = self.device_manager.clone();
+    let session = self.session.clone();
+    let device_receiver = self
+      .device_manager
+      .event_stream()
+      .filter_map(move |m| match m {
+        ButtplugServerMessageV4::DeviceList(list) => {
+          Some(device_manager.filter_device_list(&session, &list).into())
+        }
+        ButtplugServerMessageV4::InputReading(ref reading)
+          if !device_manager.session_can_see_device(&session, reading.device_index()) =>
+        {
+          None
+        }
+        ButtplugServerMessageV4::DeviceConnectionFailed(ref failure)
+          if !session.can_see_address(failure.device_address()) =>
+        {
+          None
+        }
+        ButtplugServerMessageV4::DeviceReconnected(ref reconnected)
+          if !device_manager.session_can_see_device(&session, reconnected.device_index()) =>
+        {
+          None
+        }
+        m => Some(m),
+      });
     device_receiver.merge(server_receiver)
   }
 
@@ -194,6 +250,25 @@ impl ButtplugServer {
     self.device_manager.clone()
   }
 
+  /// This server's session on the device manager.
+  pub fn session(&self) -> &ServerSession {
+    &self.session
+  }
+
+  /// Creates a server with the same name, ping time, and device visibility as this one, as a new
+  /// session on the same device manager. Used to serve several clients at once.
+  pub fn new_session(&self) -> Result<ButtplugServer, ButtplugServerError> {
+    let mut builder =
+      ButtplugServerBuilder::with_shared_device_manager(self.device_manager.clone());
+    builder
+      .name(&self.server_name)
+      .max_ping_time(self.max_ping_time);
+    if let Some(addresses) = self.session.visible_addresses() {
+      builder.visible_devices(addresses.clone());
+    }
+    builder.finish()
+  }
+
   /// If true, client is currently connected to the server.
   pub fn connected(&self) -> bool {
     matches!(
@@ -206,13 +281,13 @@ impl ButtplugServer {
   pub fn disconnect(&self) -> BoxFuture<'_, Result<(), message::ErrorV0>> {
     debug!("Buttplug Server {} disconnect requested", self.server_name);
     let ping_timer = self.ping_timer.clone();
-    // As long as StopScanning/StopAllDevices aren't changed across message specs, we can inject
-    // them using parse_checked_message and bypass version checking.
+    // As long as StopScanning isn't changed across message specs, we can inject it using
+    // parse_checked_message and bypass version checking.
     let stop_scanning_fut = self.parse_checked_message(
       ButtplugCheckedClientMessageV4::StopScanning(StopScanningV0::default()),
     );
-    let stop_fut =
-      self.parse_checked_message(ButtplugCheckedClientMessageV4::StopCmd(StopCmdV4::default()));
+    // Only stop what this session was running, other sessions may share the device manager.
+    let stop_fut = self.device_manager.end_session(&self.session);
     let state = self.state.clone();
     async move {
       {
@@ -242,7 +317,13 @@ impl ButtplugServer {
     &self,
     msg: ButtplugClientMessageVariant,
   ) -> BoxFuture<'static, Result<ButtplugServerMessageVariant, ButtplugServerMessageVariant>> {
-    let features = self.device_manager().feature_map();
+    if let Some(recorder) = self.device_manager.recorder() {
+      recorder.record(RecordedEvent::ClientMessage {
+        session: self.session.id(),
+        message: msg.clone(),
+      });
+    }
+    let features = self.device_manager().feature_map(&self.session);
     let msg_id = msg.id();
     trace!("Server received: {:?}", msg);
     let v = msg.version();
@@ -374,7 +455,9 @@ impl ButtplugServer {
     let out_fut = if ButtplugDeviceManagerMessageUnion::try_from(msg.clone()).is_ok()
       || ButtplugDeviceCommandMessageUnionV4::try_from(msg.clone()).is_ok()
     {
-      self.device_manager.parse_message(msg.clone())
+      self
+        .device_manager
+        .parse_session_message(&self.session, msg.clone())
     } else {
       match msg {
         ButtplugCheckedClientMessageV4::RequestServerInfo(rsi_msg) => {
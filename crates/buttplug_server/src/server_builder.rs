@@ -7,16 +7,20 @@
 
 use super::{
   ButtplugServerError,
-  device::{ServerDeviceManager, ServerDeviceManagerBuilder},
+  device::{ServerDeviceManager, ServerDeviceManagerBuilder, ServerSession},
+  metrics,
   ping_timer::PingTimer,
   server::{ButtplugServer, ConnectionState},
 };
 use buttplug_core::{
   errors::*,
-  message::{self, ButtplugServerMessageV4, StopCmdV4},
+  message::{self, ButtplugServerMessageV4},
 };
 use buttplug_server_device_config::DeviceConfigurationManagerBuilder;
-use std::sync::{Arc, RwLock};
+use std::{
+  collections::HashSet,
+  sync::{Arc, RwLock},
+};
 use tokio::sync::broadcast;
 
 /// Configures and creates [ButtplugServer] instances.
@@ -29,6 +33,8 @@ pub struct ButtplugServerBuilder {
   max_ping_time: Option<u32>,
   /// Device manager builder for the server
   device_manager: Arc<ServerDeviceManager>,
+  /// Addresses of the devices the client can see. If None, all devices are visible.
+  visible_devices: Option<HashSet<String>>,
 }
 
 impl Default for ButtplugServerBuilder {
@@ -45,6 +51,7 @@ impl Default for ButtplugServerBuilder {
         .finish()
         .unwrap(),
       ),
+      visible_devices: None,
     }
   }
 }
@@ -55,6 +62,7 @@ impl ButtplugServerBuilder {
       name: "Buttplug Server".to_owned(),
       max_ping_time: None,
       device_manager: Arc::new(device_manager),
+      visible_devices: None,
     }
   }
 
@@ -63,6 +71,7 @@ impl ButtplugServerBuilder {
       name: "Buttplug Server".to_owned(),
       max_ping_time: None,
       device_manager,
+      visible_devices: None,
     }
   }
 
@@ -85,6 +94,14 @@ impl ButtplugServerBuilder {
     self
   }
 
+  /// Only let the client see devices with the given addresses. Useful when several servers share a
+  /// device manager via [ButtplugServerBuilder::with_shared_device_manager], and each client should
+  /// only get access to some of the devices.
+  pub fn visible_devices(&mut self, addresses: HashSet<String>) -> &mut Self {
+    self.visible_devices = Some(addresses);
+    self
+  }
+
   /// Try to build a [ButtplugServer] using the parameters given.
   pub fn finish(&self) -> Result<ButtplugServer, ButtplugServerError> {
     // Create the server
@@ -98,26 +115,32 @@ impl ButtplugServerBuilder {
 
     let ping_time = self.max_ping_time.unwrap_or(0);
 
+    // Each server is a separate session on the device manager, which may be shared with other
+    // servers.
+    let session: ServerSession = self
+      .device_manager
+      .create_session(self.visible_devices.clone());
+
     // Create the ping timeout callback if ping time is configured.
     // The callback handles: updating state, stopping devices, and sending error.
     let ping_timeout_callback = if ping_time > 0 {
       let state_clone = state.clone();
       let device_manager_clone = self.device_manager.clone();
+      let session_clone = session.clone();
       let output_sender_clone = output_sender.clone();
 
       Some(move || {
         error!("Ping out signal received, stopping server");
+        metrics::ping_timeout();
         // Update connection state to PingedOut
         {
           let mut state_guard = state_clone.write().expect("State lock poisoned");
           *state_guard = ConnectionState::PingedOut;
         }
-        // Stop all devices (spawn async task since callback is sync)
+        // Stop all devices the session was using (spawn async task since callback is sync)
+        let stop_fut = device_manager_clone.end_session(&session_clone);
         buttplug_core::spawn!("PingTimeoutStopDevices", async move {
-          if let Err(e) = device_manager_clone
-            .stop_devices(&StopCmdV4::default())
-            .await
-          {
+          if let Err(e) = stop_fut.await {
             error!("Could not stop devices on ping timeout: {:?}", e);
           }
         });
@@ -143,6 +166,7 @@ impl ButtplugServerBuilder {
       ping_time,
       ping_timer,
       self.device_manager.clone(),
+      session,
       state,
       output_sender,
     ))
@@ -0,0 +1,50 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use getset::CopyGetters;
+use serde::{Deserialize, Serialize};
+
+fn default_restore_outputs() -> bool {
+  true
+}
+
+/// How the server should handle a device dropping out unexpectedly, set in the user device
+/// configuration.
+///
+/// While a device is waiting to reconnect, the device manager keeps scanning in the background.
+/// When the device comes back it keeps its index, and clients are told it reconnected instead of
+/// seeing it as a new device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters, Serialize, Deserialize)]
+#[getset(get_copy = "pub")]
+pub struct AutoReconnectPolicy {
+  /// How long to keep looking for the device after it disconnects. If None, the server looks for
+  /// it until it comes back or the server shuts down.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  timeout_ms: Option<u32>,
+  /// If true, outputs that were running when the device dropped out are restarted when it
+  /// reconnects, unless the emergency stop is engaged at that point.
+  #[serde(default = "default_restore_outputs")]
+  restore_outputs: bool,
+}
+
+impl Default for AutoReconnectPolicy {
+  fn default() -> Self {
+    Self {
+      timeout_ms: None,
+      restore_outputs: default_restore_outputs(),
+    }
+  }
+}
+
+impl AutoReconnectPolicy {
+  pub fn new(timeout_ms: Option<u32>, restore_outputs: bool) -> Self {
+    Self {
+      timeout_ms,
+      restore_outputs,
+    }
+  }
+}
@@ -0,0 +1,100 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+use super::Endpoint;
+
+/// Checksum algorithms available for [ByteTemplate] packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteTemplateChecksum {
+  /// Wrapping 8-bit sum of all bytes preceding the checksum slot
+  Sum,
+  /// 8-bit XOR of all bytes preceding the checksum slot
+  Xor,
+}
+
+/// Placeholders in a [ByteTemplate] packet that are filled in when a command is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteTemplateSlot {
+  /// Value of the feature being commanded
+  Value,
+  /// 1 if the value of the feature being commanded is non-zero, 0 otherwise
+  Active,
+  /// Index of the feature being commanded
+  FeatureIndex,
+  /// Last value sent to the feature at the given index, for devices that take the values of all of
+  /// their features in a single packet
+  FeatureValue(u32),
+  /// Checksum of all bytes preceding this slot, using the template's checksum type
+  Checksum,
+}
+
+/// A single byte in a [ByteTemplate] packet, either a literal or a slot to fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ByteTemplateField {
+  Byte(u8),
+  Slot(ByteTemplateSlot),
+}
+
+fn default_endpoint() -> Endpoint {
+  Endpoint::Tx
+}
+
+/// Declarative description of a simple device protocol.
+///
+/// Used by the `byte-template` protocol for devices that take a fixed packet with values substituted
+/// in, so they can be supported purely through device configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, CopyGetters, Serialize, Deserialize)]
+pub struct ByteTemplate {
+  /// Endpoint packets are written to
+  #[getset(get_copy = "pub")]
+  #[serde(default = "default_endpoint")]
+  endpoint: Endpoint,
+  #[getset(get_copy = "pub")]
+  #[serde(default)]
+  write_with_response: bool,
+  /// Packet layout, with literal header/footer bytes and value slots
+  #[getset(get = "pub")]
+  packet: Vec<ByteTemplateField>,
+  #[getset(get_copy = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  checksum: Option<ByteTemplateChecksum>,
+  /// If set, the last packet is resent at this interval to keep the device running
+  #[getset(get_copy = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  keepalive_interval_ms: Option<u32>,
+  /// If set, battery level (0-100) is read from the byte at this offset of the data read from the
+  /// `rx` endpoint
+  #[getset(get_copy = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  battery_offset: Option<u32>,
+}
+
+impl ByteTemplate {
+  pub fn new(
+    endpoint: Endpoint,
+    packet: &[ByteTemplateField],
+    checksum: Option<ByteTemplateChecksum>,
+    keepalive_interval_ms: Option<u32>,
+    write_with_response: bool,
+    battery_offset: Option<u32>,
+  ) -> Self {
+    Self {
+      endpoint,
+      write_with_response,
+      packet: packet.to_vec(),
+      checksum,
+      keepalive_interval_ms,
+      battery_offset,
+    }
+  }
+}
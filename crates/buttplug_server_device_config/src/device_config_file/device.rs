@@ -5,17 +5,28 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
+use std::collections::BTreeMap;
+
 use getset::{CopyGetters, Getters, MutGetters};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{ButtplugDeviceConfigError, ServerDeviceDefinition, ServerDeviceDefinitionBuilder};
+use crate::{
+  AutoReconnectPolicy,
+  ButtplugDeviceConfigError,
+  ByteTemplate,
+  DevicePreset,
+  DeviceSafetyLimits,
+  ServerDeviceDefinition,
+  ServerDeviceDefinitionBuilder,
+};
 
 use super::feature::{ConfigBaseDeviceFeature, ConfigUserDeviceFeature};
 
 #[derive(Debug, Clone, Getters, CopyGetters, Serialize, Deserialize)]
 pub struct ConfigBaseDeviceDefinition {
   #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   identifier: Option<Vec<String>>,
   #[getset(get = "pub")]
   /// Given name of the device this instance represents.
@@ -23,10 +34,16 @@ pub struct ConfigBaseDeviceDefinition {
   #[getset(get_copy = "pub")]
   id: Uuid,
   #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   protocol_variant: Option<String>,
   #[getset(get_copy = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   message_gap_ms: Option<u32>,
   #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  byte_template: Option<ByteTemplate>,
+  #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   features: Option<Vec<ConfigBaseDeviceFeature>>,
 }
 
@@ -38,11 +55,35 @@ impl ConfigBaseDeviceDefinition {
       id: config.id(),
       protocol_variant: config.protocol_variant.or(self.protocol_variant.clone()),
       message_gap_ms: config.message_gap_ms.or(self.message_gap_ms),
+      byte_template: config.byte_template.or(self.byte_template.clone()),
       features: config.features.or(self.features.clone()),
     }
   }
 }
 
+impl ConfigBaseDeviceDefinition {
+  pub fn add_identifier(&mut self, identifier: &str) {
+    self
+      .identifier
+      .get_or_insert_default()
+      .push(identifier.to_owned());
+  }
+}
+
+impl From<&ServerDeviceDefinition> for ConfigBaseDeviceDefinition {
+  fn from(value: &ServerDeviceDefinition) -> Self {
+    Self {
+      identifier: None,
+      name: value.name().clone(),
+      id: value.id(),
+      protocol_variant: value.protocol_variant().clone(),
+      message_gap_ms: value.message_gap_ms(),
+      byte_template: value.byte_template().clone(),
+      features: Some(value.features().values().map(|x| x.into()).collect()),
+    }
+  }
+}
+
 impl From<ConfigBaseDeviceDefinition> for ServerDeviceDefinition {
   fn from(val: ConfigBaseDeviceDefinition) -> Self {
     let mut builder = ServerDeviceDefinitionBuilder::new(&val.name, &val.id);
@@ -52,6 +93,9 @@ impl From<ConfigBaseDeviceDefinition> for ServerDeviceDefinition {
     if let Some(gap) = val.message_gap_ms {
       builder.message_gap_ms(Some(gap));
     }
+    if let Some(template) = val.byte_template {
+      builder.byte_template(&template);
+    }
     if let Some(features) = val.features {
       for feature in features {
         builder.add_feature(&feature.into());
@@ -77,6 +121,21 @@ pub struct ConfigUserDeviceCustomization {
   #[getset(get_copy = "pub")]
   #[serde(default, skip_serializing_if = "Option::is_none")]
   message_gap_ms: Option<u32>,
+  #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  safety: Option<DeviceSafetyLimits>,
+  #[getset(get_copy = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  emergency_stop_button: Option<u32>,
+  #[getset(get_copy = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  auto_reconnect: Option<AutoReconnectPolicy>,
+  #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  presets: BTreeMap<String, DevicePreset>,
+  #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  connect_preset: Option<String>,
 }
 
 impl From<&ServerDeviceDefinition> for ConfigUserDeviceCustomization {
@@ -87,6 +146,11 @@ impl From<&ServerDeviceDefinition> for ConfigUserDeviceCustomization {
       deny: value.deny(),
       index: value.index(),
       message_gap_ms: value.message_gap_ms(),
+      safety: value.safety_limits().clone(),
+      emergency_stop_button: value.emergency_stop_button(),
+      auto_reconnect: value.auto_reconnect(),
+      presets: value.presets().clone(),
+      connect_preset: value.connect_preset().clone(),
     }
   }
 }
@@ -107,6 +171,10 @@ pub struct ConfigUserDeviceDefinition {
 }
 
 impl ConfigUserDeviceDefinition {
+  pub(crate) fn set_base_id(&mut self, base_id: Uuid) {
+    self.base_id = base_id;
+  }
+
   pub fn build_from_base_definition(
     &self,
     base: &ServerDeviceDefinition,
@@ -117,6 +185,14 @@ impl ConfigUserDeviceDefinition {
     self.user_config.allow.then(|| builder.allow(true));
     self.user_config.deny.then(|| builder.deny(true));
     builder.index(self.user_config.index);
+    builder.emergency_stop_button(self.user_config.emergency_stop_button);
+    builder.auto_reconnect(self.user_config.auto_reconnect);
+    builder.presets(&self.user_config.presets);
+    builder.connect_preset(&self.user_config.connect_preset);
+    if let Some(safety) = &self.user_config.safety {
+      safety.validate()?;
+      builder.safety_limits(&self.user_config.safety);
+    }
     if self.features().len() != base.features().len() {
       return Err(ButtplugDeviceConfigError::UserFeatureMismatch);
     }
@@ -131,7 +207,10 @@ impl ConfigUserDeviceDefinition {
         return Err(ButtplugDeviceConfigError::UserFeatureMismatch);
       }
     }
-    Ok(builder.finish())
+    let definition = builder.finish();
+    definition.validate_emergency_stop_button()?;
+    definition.validate_presets()?;
+    Ok(definition)
   }
 }
 
@@ -10,6 +10,7 @@ use std::ops::RangeInclusive;
 use crate::{
   ButtplugDeviceConfigError,
   RangeWithLimit,
+  ResponseCurve,
   ServerDeviceFeature,
   ServerDeviceFeatureInput,
   ServerDeviceFeatureOutput,
@@ -17,7 +18,7 @@ use crate::{
   ServerDeviceFeatureOutputPositionProperties,
   ServerDeviceFeatureOutputValueProperties,
 };
-use buttplug_core::util::range_serialize::option_range_serialize;
+use buttplug_core::{message::OutputType, util::range_serialize::option_range_serialize};
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -220,6 +221,28 @@ impl UserDeviceFeatureOutput {
   }
 }
 
+impl UserDeviceFeatureOutput {
+  fn output_types(&self) -> Vec<OutputType> {
+    [
+      (self.vibrate.is_some(), OutputType::Vibrate),
+      (self.rotate.is_some(), OutputType::Rotate),
+      (self.oscillate.is_some(), OutputType::Oscillate),
+      (self.constrict.is_some(), OutputType::Constrict),
+      (self.temperature.is_some(), OutputType::Temperature),
+      (self.led.is_some(), OutputType::Led),
+      (self.position.is_some(), OutputType::Position),
+      (
+        self.hw_position_with_duration.is_some(),
+        OutputType::HwPositionWithDuration,
+      ),
+      (self.spray.is_some(), OutputType::Spray),
+    ]
+    .into_iter()
+    .filter_map(|(present, output_type)| present.then_some(output_type))
+    .collect()
+  }
+}
+
 impl From<&ServerDeviceFeatureOutput> for UserDeviceFeatureOutput {
   fn from(value: &ServerDeviceFeatureOutput) -> Self {
     Self {
@@ -270,6 +293,21 @@ impl From<ConfigBaseDeviceFeature> for ServerDeviceFeature {
   }
 }
 
+impl From<&ServerDeviceFeature> for ConfigBaseDeviceFeature {
+  fn from(value: &ServerDeviceFeature) -> Self {
+    Self {
+      index: value.index(),
+      description: value.description().clone(),
+      output: value.output().clone(),
+      input: value.input().clone(),
+      id: value.id(),
+      feature_settings: BaseFeatureSettings {
+        alt_protocol_index: value.alt_protocol_index(),
+      },
+    }
+  }
+}
+
 #[derive(Clone, Debug, Default, Getters, Serialize, Deserialize, CopyGetters)]
 pub struct ConfigUserDeviceFeature {
   #[getset(get_copy = "pub")]
@@ -279,14 +317,49 @@ pub struct ConfigUserDeviceFeature {
   #[getset(get = "pub")]
   #[serde(rename = "output", skip_serializing_if = "Option::is_none")]
   output: Option<UserDeviceFeatureOutput>,
+  /// Curve applied to all outputs of the feature
+  #[getset(get = "pub")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  response_curve: Option<ResponseCurve>,
 }
 
 impl ConfigUserDeviceFeature {
+  /// Creates a user feature with no customizations for the base feature with the given id.
+  pub(crate) fn new_for_base_feature(base_id: Uuid) -> Self {
+    Self {
+      id: Uuid::new_v4(),
+      base_id,
+      output: None,
+      response_curve: None,
+    }
+  }
+
+  pub(crate) fn set_base_id(&mut self, base_id: Uuid) {
+    self.base_id = base_id;
+  }
+
+  /// Removes all output customizations, so the feature inherits the base feature's output.
+  pub(crate) fn clear_output(&mut self) {
+    self.output = None;
+  }
+
+  /// Output types that have user customizations.
+  pub(crate) fn output_types(&self) -> Vec<OutputType> {
+    self
+      .output
+      .as_ref()
+      .map(|x| x.output_types())
+      .unwrap_or_default()
+  }
+
   pub fn with_base_feature(
     &self,
     base_feature: &ServerDeviceFeature,
   ) -> Result<ServerDeviceFeature, ButtplugDeviceConfigError> {
-    let output = if let Some(o) = &self.output {
+    if let Some(curve) = &self.response_curve {
+      curve.validate()?;
+    }
+    let mut output = if let Some(o) = &self.output {
       if let Some(base) = base_feature.output() {
         Some(o.with_base_output(base)?)
       } else {
@@ -296,6 +369,9 @@ impl ConfigUserDeviceFeature {
       // No user output override: inherit the base feature's output unchanged.
       base_feature.output().clone()
     };
+    if let Some(output) = &mut output {
+      output.set_response_curve(&self.response_curve);
+    }
     Ok(ServerDeviceFeature::new(
       base_feature.index(),
       base_feature.description(),
@@ -318,6 +394,10 @@ impl TryFrom<&ServerDeviceFeature> for ConfigUserDeviceFeature {
         .base_id()
         .ok_or(ButtplugDeviceConfigError::MissingBaseId)?,
       output: value.output().as_ref().map(|x| x.into()),
+      response_curve: value
+        .output()
+        .as_ref()
+        .and_then(|x| x.response_curve().cloned()),
     })
   }
 }
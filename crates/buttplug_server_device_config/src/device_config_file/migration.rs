@@ -0,0 +1,407 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Migration of user device configurations between versions of the base device configuration.
+//!
+//! User device customizations refer to base devices and features by id. When a base definition is
+//! replaced or its features change, those ids stop resolving and the customizations are skipped on
+//! load. Migration maps them onto the new base definitions by feature index and type, and reports
+//! what happened to each entry so tools can preview the result before saving it.
+
+use std::collections::HashSet;
+
+use buttplug_core::{
+  errors::ButtplugDeviceError,
+  message::{InputType, OutputType},
+};
+use getset::{CopyGetters, Getters};
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+use crate::{
+  BaseDeviceIdentifier,
+  DeviceConfigurationManager,
+  ServerDeviceDefinition,
+  ServerDeviceFeature,
+  UserDeviceIdentifier,
+};
+
+use super::{
+  ConfigVersionGetter,
+  add_user_protocol_definitions,
+  base::BaseConfigFile,
+  device::ConfigUserDeviceDefinition,
+  feature::ConfigUserDeviceFeature,
+  get_internal_config_version,
+  load_main_config,
+  load_protocol_config_from_json,
+  user::{UserConfigDefinition, UserConfigFile, UserDeviceConfigPair},
+};
+
+/// What happened to a single user feature customization during migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureMigrationResult {
+  /// Base feature still exists, customization kept as is
+  Unchanged,
+  /// Customization moved to a different base feature with the same index and type
+  Migrated {
+    old_base_id: Uuid,
+    new_base_id: Uuid,
+  },
+  /// Customization could not be carried over safely, and was reset or removed
+  Conflicted(String),
+  /// No matching base feature exists anymore, customization removed
+  Dropped(String),
+  /// Base feature is new, an empty customization was added for it
+  Added,
+}
+
+#[derive(Debug, Clone, Getters, CopyGetters)]
+pub struct FeatureMigration {
+  /// Id of the user feature
+  #[getset(get_copy = "pub")]
+  id: Uuid,
+  /// Index of the feature in the new base definition, if it still maps to one
+  #[getset(get_copy = "pub")]
+  index: Option<u32>,
+  #[getset(get = "pub")]
+  result: FeatureMigrationResult,
+}
+
+/// What happened to a user device entry during migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceMigrationResult {
+  /// Base device id still exists
+  Unchanged,
+  /// Base device was replaced, entry now refers to the definition for the same protocol identifier
+  Migrated {
+    old_base_id: Uuid,
+    new_base_id: Uuid,
+  },
+  /// No base definition exists for the device anymore, entry removed
+  Dropped(String),
+}
+
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct DeviceMigration {
+  identifier: UserDeviceIdentifier,
+  result: DeviceMigrationResult,
+  features: Vec<FeatureMigration>,
+}
+
+impl DeviceMigration {
+  /// True if the device entry or any of its features differ from the original user config.
+  pub fn has_changes(&self) -> bool {
+    self.result != DeviceMigrationResult::Unchanged
+      || self
+        .features
+        .iter()
+        .any(|x| x.result != FeatureMigrationResult::Unchanged)
+  }
+}
+
+/// Result of [migrate_user_config]: a per device report, and the migrated user config file.
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct UserConfigMigration {
+  devices: Vec<DeviceMigration>,
+  /// Migrated user config, serialized as JSON
+  user_config: String,
+}
+
+impl UserConfigMigration {
+  pub fn has_changes(&self) -> bool {
+    self.devices.iter().any(|x| x.has_changes())
+  }
+}
+
+/// Outputs and inputs of a feature, used to decide whether two features are the same kind of
+/// feature when ids no longer match.
+#[derive(Debug, PartialEq, Eq)]
+struct FeatureSignature {
+  outputs: Vec<OutputType>,
+  inputs: Vec<InputType>,
+}
+
+impl From<&ServerDeviceFeature> for FeatureSignature {
+  fn from(feature: &ServerDeviceFeature) -> Self {
+    Self {
+      outputs: feature
+        .output()
+        .as_ref()
+        .map(|x| x.output_types())
+        .unwrap_or_default(),
+      inputs: feature
+        .input()
+        .as_ref()
+        .map(|input| InputType::iter().filter(|x| input.contains(*x)).collect())
+        .unwrap_or_default(),
+    }
+  }
+}
+
+fn load_base_dcm(
+  base_config_str: &Option<String>,
+  user_config: &UserConfigDefinition,
+) -> Result<DeviceConfigurationManager, ButtplugDeviceError> {
+  let mut dcm_builder = load_main_config(base_config_str, true)?;
+  add_user_protocol_definitions(user_config, &mut dcm_builder);
+  dcm_builder.finish()
+}
+
+fn pick_candidate(
+  candidates: Vec<&ServerDeviceFeature>,
+  description: String,
+) -> Result<&ServerDeviceFeature, FeatureMigrationResult> {
+  match candidates.len() {
+    0 => Err(FeatureMigrationResult::Dropped(format!(
+      "No unclaimed feature with {description} in new base definition"
+    ))),
+    1 => Ok(candidates[0]),
+    _ => Err(FeatureMigrationResult::Conflicted(format!(
+      "Features {:?} all have {description}, cannot choose between them",
+      candidates.iter().map(|x| x.index()).collect::<Vec<_>>()
+    ))),
+  }
+}
+
+/// Finds the feature in the new base definition that a user feature should be attached to.
+///
+/// Features whose base id still exists keep it. Otherwise, if the old base definition is known,
+/// the old base feature's outputs and inputs are matched against the new base features, preferring
+/// one at the same index. If it is not known, only the output types the user customized can be
+/// matched on.
+fn find_new_base_feature<'a>(
+  user_feature: &ConfigUserDeviceFeature,
+  old_base: Option<&ServerDeviceDefinition>,
+  new_base: &'a ServerDeviceDefinition,
+  claimed: &HashSet<Uuid>,
+) -> Result<&'a ServerDeviceFeature, FeatureMigrationResult> {
+  let base_id = user_feature.base_id();
+  let unclaimed = new_base
+    .features()
+    .values()
+    .filter(|x| !claimed.contains(&x.id()));
+  if let Some(feature) = unclaimed.clone().find(|x| x.id() == base_id) {
+    return Ok(feature);
+  }
+
+  if let Some(old_feature) =
+    old_base.and_then(|x| x.features().values().find(|x| x.id() == base_id))
+  {
+    let signature = FeatureSignature::from(old_feature);
+    let candidates: Vec<_> = unclaimed
+      .filter(|x| FeatureSignature::from(*x) == signature)
+      .collect();
+    if let Some(feature) = candidates.iter().find(|x| x.index() == old_feature.index()) {
+      return Ok(feature);
+    }
+    return pick_candidate(
+      candidates,
+      format!(
+        "outputs {:?} and inputs {:?}",
+        signature.outputs, signature.inputs
+      ),
+    );
+  }
+
+  let output_types = user_feature.output_types();
+  if output_types.is_empty() {
+    return Err(FeatureMigrationResult::Dropped(format!(
+      "Base feature {base_id} not found, and feature has no customizations to match by type"
+    )));
+  }
+  let candidates: Vec<_> = unclaimed
+    .filter(|x| {
+      x.output()
+        .as_ref()
+        .is_some_and(|output| output_types.iter().all(|t| output.contains(*t)))
+    })
+    .collect();
+  pick_candidate(candidates, format!("outputs {output_types:?}"))
+}
+
+fn migrate_features(
+  config: &ConfigUserDeviceDefinition,
+  old_base: Option<&ServerDeviceDefinition>,
+  new_base: &ServerDeviceDefinition,
+) -> (Vec<ConfigUserDeviceFeature>, Vec<FeatureMigration>) {
+  let mut claimed = HashSet::new();
+  let mut features = vec![];
+  let mut report = vec![];
+
+  for user_feature in config.features() {
+    let new_base_feature = match find_new_base_feature(user_feature, old_base, new_base, &claimed) {
+      Ok(feature) => feature,
+      Err(result) => {
+        report.push(FeatureMigration {
+          id: user_feature.id(),
+          index: None,
+          result,
+        });
+        continue;
+      }
+    };
+    claimed.insert(new_base_feature.id());
+
+    let mut feature = user_feature.clone();
+    feature.set_base_id(new_base_feature.id());
+    let result = if let Err(e) = feature.with_base_feature(new_base_feature) {
+      feature.clear_output();
+      FeatureMigrationResult::Conflicted(format!(
+        "Customization does not fit new base feature and was reset: {e}"
+      ))
+    } else if new_base_feature.id() == user_feature.base_id() {
+      FeatureMigrationResult::Unchanged
+    } else {
+      FeatureMigrationResult::Migrated {
+        old_base_id: user_feature.base_id(),
+        new_base_id: new_base_feature.id(),
+      }
+    };
+    report.push(FeatureMigration {
+      id: feature.id(),
+      index: Some(new_base_feature.index()),
+      result,
+    });
+    features.push((new_base_feature.index(), feature));
+  }
+
+  // Every base feature needs a user feature, so fill in any that nothing was migrated to.
+  for base_feature in new_base
+    .features()
+    .values()
+    .filter(|x| !claimed.contains(&x.id()))
+  {
+    let feature = ConfigUserDeviceFeature::new_for_base_feature(base_feature.id());
+    report.push(FeatureMigration {
+      id: feature.id(),
+      index: Some(base_feature.index()),
+      result: FeatureMigrationResult::Added,
+    });
+    features.push((base_feature.index(), feature));
+  }
+
+  features.sort_by_key(|(index, _)| *index);
+  (features.into_iter().map(|(_, x)| x).collect(), report)
+}
+
+fn migrate_device(
+  pair: &UserDeviceConfigPair,
+  old_dcm: Option<&DeviceConfigurationManager>,
+  new_dcm: &DeviceConfigurationManager,
+) -> (Option<UserDeviceConfigPair>, DeviceMigration) {
+  let identifier = pair.identifier();
+  let base_id = pair.config().base_id();
+  let new_definitions = new_dcm.base_device_definitions();
+
+  let (new_base, result) = if let Some(base) = new_definitions.values().find(|x| x.id() == base_id)
+  {
+    (base, DeviceMigrationResult::Unchanged)
+  } else if let Some(base) = new_definitions
+    .get(&BaseDeviceIdentifier::from(identifier))
+    .or_else(|| new_definitions.get(&BaseDeviceIdentifier::new_default(identifier.protocol())))
+  {
+    (
+      base,
+      DeviceMigrationResult::Migrated {
+        old_base_id: base_id,
+        new_base_id: base.id(),
+      },
+    )
+  } else {
+    return (
+      None,
+      DeviceMigration {
+        identifier: identifier.clone(),
+        result: DeviceMigrationResult::Dropped(format!(
+          "No base definition for protocol {} with identifier {:?}",
+          identifier.protocol(),
+          identifier.identifier()
+        )),
+        features: vec![],
+      },
+    );
+  };
+
+  let old_base = old_dcm.and_then(|dcm| {
+    dcm
+      .base_device_definitions()
+      .values()
+      .find(|x| x.id() == base_id)
+  });
+  let (features, feature_report) = migrate_features(pair.config(), old_base, new_base);
+
+  let mut config = pair.config().clone();
+  config.set_base_id(new_base.id());
+  *config.features_mut() = features;
+  (
+    Some(UserDeviceConfigPair {
+      identifier: identifier.clone(),
+      config,
+    }),
+    DeviceMigration {
+      identifier: identifier.clone(),
+      result,
+      features: feature_report,
+    },
+  )
+}
+
+/// Migrates the device customizations in a user config to a new base device configuration.
+///
+/// `old_base_config_str` is the base configuration the user config was created against. It is
+/// optional, but without it features whose base ids no longer exist can only be matched by the
+/// output types the user customized. `new_base_config_str` is the base configuration to migrate
+/// to, or None to use the internal configuration. Protocol definitions in the user config are
+/// layered over both, the same way they are when the configs are loaded.
+///
+/// Version checks are skipped, as migrating between config versions is the point. The migrated
+/// user config takes the version of the base configuration it was migrated to.
+pub fn migrate_user_config(
+  user_config_str: &str,
+  old_base_config_str: &Option<String>,
+  new_base_config_str: &Option<String>,
+) -> Result<UserConfigMigration, ButtplugDeviceError> {
+  let mut user_config_file =
+    load_protocol_config_from_json::<UserConfigFile>(user_config_str, true)?;
+  let new_version = match new_base_config_str {
+    Some(config) => load_protocol_config_from_json::<BaseConfigFile>(config, true)?.version(),
+    None => get_internal_config_version(),
+  };
+  let mut devices = vec![];
+
+  if let Some(user_config) = user_config_file.user_configs_mut() {
+    let old_dcm = old_base_config_str
+      .as_ref()
+      .map(|x| load_base_dcm(&Some(x.clone()), user_config))
+      .transpose()?;
+    let new_dcm = load_base_dcm(new_base_config_str, user_config)?;
+
+    let mut migrated_pairs = vec![];
+    for pair in user_config.user_device_configs().iter().flatten() {
+      let (migrated_pair, report) = migrate_device(pair, old_dcm.as_ref(), &new_dcm);
+      migrated_pairs.extend(migrated_pair);
+      devices.push(report);
+    }
+    if user_config.user_device_configs().is_some() {
+      user_config.set_user_device_configs(Some(migrated_pairs));
+    }
+  }
+
+  user_config_file.set_version(new_version);
+  let user_config = serde_json::to_string_pretty(&user_config_file).map_err(|e| {
+    ButtplugDeviceError::DeviceConfigurationError(format!(
+      "Cannot serialize migrated user configuration: {e:?}"
+    ))
+  })?;
+  Ok(UserConfigMigration {
+    devices,
+    user_config,
+  })
+}
@@ -8,17 +8,33 @@
 mod base;
 mod device;
 mod feature;
+mod migration;
 mod protocol;
 mod user;
+mod validation;
 
 use base::BaseConfigFile;
+pub use migration::{
+  DeviceMigration,
+  DeviceMigrationResult,
+  FeatureMigration,
+  FeatureMigrationResult,
+  UserConfigMigration,
+  migrate_user_config,
+};
+pub use validation::{UserConfigValidationError, validate_user_config};
 
 use crate::device_config_file::{
+  device::ConfigBaseDeviceDefinition,
   protocol::ProtocolDefinition,
   user::{UserConfigDefinition, UserConfigFile, UserDeviceConfigPair},
 };
 
-use super::{BaseDeviceIdentifier, DeviceConfigurationManager, DeviceConfigurationManagerBuilder};
+use super::{
+  ButtplugDeviceConfigError,
+  DeviceConfigurationManager,
+  DeviceConfigurationManagerBuilder,
+};
 use buttplug_core::{
   errors::{ButtplugDeviceError, ButtplugError},
   util::json::JSONValidator,
@@ -114,27 +130,8 @@ fn load_main_config(
       dcm_builder.communication_specifier(&protocol_name, specifiers);
     }
 
-    let mut default = None;
-    if let Some(features) = protocol_def.defaults() {
-      default = Some(features.clone());
-      dcm_builder.base_device_definition(
-        &BaseDeviceIdentifier::new_default(&protocol_name),
-        &features.clone().into(),
-      );
-    }
-
-    for config in protocol_def.configurations() {
-      if let Some(idents) = config.identifier() {
-        for config_ident in idents {
-          let ident = BaseDeviceIdentifier::new_with_identifier(&protocol_name, config_ident);
-          if let Some(d) = &default {
-            dcm_builder
-              .base_device_definition(&ident, &d.update_with_configuration(config.clone()).into());
-          } else {
-            dcm_builder.base_device_definition(&ident, &config.clone().into());
-          }
-        }
-      }
+    for (ident, definition) in protocol_def.device_definitions(&protocol_name) {
+      dcm_builder.base_device_definition(&ident, &definition);
     }
   }
 
@@ -150,69 +147,84 @@ fn load_user_config(
   let user_config_file =
     load_protocol_config_from_json::<UserConfigFile>(user_config_str, skip_version_check)?;
 
-  if user_config_file.user_configs().is_none() {
+  let Some(user_config) = user_config_file.user_configs() else {
     info!("No user configurations provided in user config.");
     return Ok(());
+  };
+
+  for err in add_user_config_definitions(user_config, dcm_builder) {
+    error!("{err}, skipping.");
   }
 
-  let user_config = user_config_file
-    .user_configs()
-    .clone()
-    .expect("Just checked validity");
+  Ok(())
+}
 
+/// Adds protocol communication specifiers and definitions from a user config to the builder.
+fn add_user_protocol_definitions(
+  user_config: &UserConfigDefinition,
+  dcm_builder: &mut DeviceConfigurationManagerBuilder,
+) {
   for (protocol_name, protocol_def) in user_config.protocols().clone().unwrap_or_default() {
     if let Some(specifiers) = protocol_def.communication() {
       dcm_builder.user_communication_specifier(&protocol_name, specifiers);
     }
 
-    // Defaults aren't valid in user config files. All we can do is create new configurations with
-    // valid identifiers.
-
-    for config in protocol_def.configurations() {
-      if let Some(idents) = config.identifier() {
-        for config_ident in idents {
-          let ident = BaseDeviceIdentifier::new_with_identifier(&protocol_name, config_ident);
-          dcm_builder.base_device_definition(&ident, &config.clone().into());
-        }
-      }
+    // Protocol defaults and configurations in user config files are treated as base definitions,
+    // so devices that speak an already supported protocol can be added without a library update.
+    for (ident, definition) in protocol_def.device_definitions(&protocol_name) {
+      dcm_builder.user_base_device_definition(&ident, &definition);
     }
   }
+}
+
+/// Adds protocol and device definitions from a user config to the builder. Entries that cannot be
+/// resolved against the base configuration are skipped, and returned as errors so the caller can
+/// decide whether to log or report them.
+fn add_user_config_definitions(
+  user_config: &UserConfigDefinition,
+  dcm_builder: &mut DeviceConfigurationManagerBuilder,
+) -> Vec<UserConfigValidationError> {
+  add_user_protocol_definitions(user_config, dcm_builder);
 
   // Snapshot taken after user-defined configurations are added so that user device config pairs
   // whose base_id refers to a configuration defined in the same user config file can be resolved.
   let base_dcm = dcm_builder.clone().finish().unwrap();
 
-  for user_device_config_pair in user_config
+  let mut errors = vec![];
+  for (index, user_device_config_pair) in user_config
     .user_device_configs()
     .clone()
     .unwrap_or_default()
+    .into_iter()
+    .enumerate()
   {
+    let identifier = user_device_config_pair.identifier();
+    let base_id = user_device_config_pair.config().base_id();
     // Use device UUID instead of identifier to match here, otherwise we have to do really weird stuff with identifier hashes.
-    if let Some(base_config) = base_dcm
+    let result = base_dcm
       .base_device_definitions()
-      .iter()
-      .find(|x| x.1.id() == user_device_config_pair.config().base_id())
-    {
-      if let Ok(loaded_user_config) = user_device_config_pair
-        .config()
-        .build_from_base_definition(base_config.1)
-        && let Err(e) = dcm_builder
-          .user_device_definition(user_device_config_pair.identifier(), &loaded_user_config)
-      {
-        error!(
-          "Device definition not valid, skipping:\n{:?}\n{:?}",
-          e, user_config
-        )
-      }
-    } else {
-      error!(
-        "Device identifier {:?} does not have a match base identifier that matches anything in the base config, removing from database.",
-        user_device_config_pair.identifier()
-      );
+      .values()
+      .find(|x| x.id() == base_id)
+      .ok_or(ButtplugDeviceConfigError::BaseIdNotFound(base_id))
+      .and_then(|base_config| {
+        user_device_config_pair
+          .config()
+          .build_from_base_definition(base_config)
+      })
+      .and_then(|loaded_user_config| {
+        dcm_builder
+          .user_device_definition(identifier, &loaded_user_config)
+          .map(|_| ())
+      });
+    if let Err(error) = result {
+      errors.push(UserConfigValidationError::Device {
+        index,
+        identifier: identifier.clone(),
+        error,
+      });
     }
   }
-
-  Ok(())
+  errors
 }
 
 pub fn load_protocol_configs(
@@ -247,7 +259,7 @@ pub fn save_user_config(dcm: &DeviceConfigurationManager) -> Result<String, Butt
       })
     })
     .collect::<Result<_, ButtplugError>>()?;
-  let user_protos = DashMap::new();
+  let user_protos: DashMap<String, ProtocolDefinition> = DashMap::new();
   for spec in user_specifiers {
     user_protos.insert(
       spec.key().clone(),
@@ -257,6 +269,25 @@ pub fn save_user_config(dcm: &DeviceConfigurationManager) -> Result<String, Butt
       },
     );
   }
+  for (ident, definition) in dcm.user_base_device_definitions() {
+    let mut protocol_def = user_protos.entry(ident.protocol().clone()).or_default();
+    if let Some(config_ident) = ident.identifier() {
+      // Identifiers that share a definition are stored as a single configuration.
+      if let Some(config) = protocol_def
+        .configurations
+        .iter_mut()
+        .find(|x| x.id() == definition.id())
+      {
+        config.add_identifier(config_ident);
+      } else {
+        let mut config = ConfigBaseDeviceDefinition::from(definition);
+        config.add_identifier(config_ident);
+        protocol_def.configurations.push(config);
+      }
+    } else {
+      protocol_def.defaults = Some(definition.into());
+    }
+  }
   let user_config_definition = UserConfigDefinition {
     protocols: Some(user_protos.clone()),
     user_device_configs: Some(user_definitions_vec),
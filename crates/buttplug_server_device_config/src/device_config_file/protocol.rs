@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use super::device::ConfigBaseDeviceDefinition;
 
-use crate::ProtocolCommunicationSpecifier;
+use crate::{BaseDeviceIdentifier, ProtocolCommunicationSpecifier, ServerDeviceDefinition};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default, Getters, Setters, MutGetters)]
 #[getset(get = "pub", set = "pub", get_mut = "pub(crate)")]
@@ -19,6 +19,40 @@ pub(super) struct ProtocolDefinition {
   pub communication: Option<Vec<ProtocolCommunicationSpecifier>>,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub defaults: Option<ConfigBaseDeviceDefinition>,
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub configurations: Vec<ConfigBaseDeviceDefinition>,
 }
+
+impl ProtocolDefinition {
+  /// Resolves the defaults and configurations of this protocol into device definitions, keyed by
+  /// the identifier they should be looked up with. Configurations are merged on top of the protocol
+  /// defaults, if there are any.
+  pub fn device_definitions(
+    &self,
+    protocol_name: &str,
+  ) -> Vec<(BaseDeviceIdentifier, ServerDeviceDefinition)> {
+    let mut definitions = vec![];
+    if let Some(defaults) = &self.defaults {
+      definitions.push((
+        BaseDeviceIdentifier::new_default(protocol_name),
+        defaults.clone().into(),
+      ));
+    }
+    for config in &self.configurations {
+      if let Some(idents) = config.identifier() {
+        for config_ident in idents {
+          let ident = BaseDeviceIdentifier::new_with_identifier(protocol_name, config_ident);
+          if let Some(defaults) = &self.defaults {
+            definitions.push((
+              ident,
+              defaults.update_with_configuration(config.clone()).into(),
+            ));
+          } else {
+            definitions.push((ident, config.clone().into()));
+          }
+        }
+      }
+    }
+    definitions
+  }
+}
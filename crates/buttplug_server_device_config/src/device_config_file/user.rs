@@ -35,7 +35,7 @@ pub struct UserConfigDefinition {
   pub user_device_configs: Option<Vec<UserDeviceConfigPair>>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Getters, Setters)]
+#[derive(Deserialize, Serialize, Debug, Getters, Setters, MutGetters)]
 #[getset(get = "pub", get_mut = "pub", set = "pub")]
 pub struct UserConfigFile {
   version: ConfigVersion,
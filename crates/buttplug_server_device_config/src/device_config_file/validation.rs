@@ -0,0 +1,85 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Validation of user device configuration files, so that tools can check a configuration before
+//! saving it instead of finding out about skipped entries in the logs later.
+
+use jsonschema::Validator;
+use thiserror::Error;
+
+use crate::{ButtplugDeviceConfigError, UserDeviceIdentifier};
+
+use super::{
+  DEVICE_CONFIGURATION_JSON_SCHEMA,
+  add_user_config_definitions,
+  load_main_config,
+  load_protocol_config_from_json,
+  user::UserConfigFile,
+};
+
+/// A single problem found while validating a user device configuration.
+#[derive(Error, Debug, Clone)]
+pub enum UserConfigValidationError {
+  /// Configuration does not conform to the device configuration JSON schema
+  #[error("Schema validation failed at {path}: {message}")]
+  Schema { path: String, message: String },
+  /// Configuration could not be parsed or loaded (invalid JSON, mismatched version, etc...)
+  #[error("User configuration could not be loaded: {0}")]
+  Load(String),
+  /// A user device entry does not match the base definition it refers to
+  #[error("User device entry {index} ({identifier:?}) is not valid: {error}")]
+  Device {
+    index: usize,
+    identifier: UserDeviceIdentifier,
+    error: ButtplugDeviceConfigError,
+  },
+}
+
+fn validate_schema(config_str: &str) -> Result<(), Vec<UserConfigValidationError>> {
+  let config_json: serde_json::Value = serde_json::from_str(config_str)
+    .map_err(|e| vec![UserConfigValidationError::Load(e.to_string())])?;
+  let schema_json: serde_json::Value = serde_json::from_str(DEVICE_CONFIGURATION_JSON_SCHEMA)
+    .expect("Schema is embedded at compile time and validated by build.rs");
+  let validator = Validator::new(&schema_json)
+    .expect("Schema is embedded at compile time and validated by build.rs");
+  let errors: Vec<_> = validator
+    .iter_errors(&config_json)
+    .map(|e| UserConfigValidationError::Schema {
+      path: e.instance_path().to_string(),
+      message: e.to_string(),
+    })
+    .collect();
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+/// Validates a user device configuration against the device config schema and the internal base
+/// device configuration.
+///
+/// Runs the same resolution that happens when the configuration is loaded by a server, but instead
+/// of logging and skipping invalid entries, returns every problem found.
+pub fn validate_user_config(user_config_str: &str) -> Result<(), Vec<UserConfigValidationError>> {
+  validate_schema(user_config_str)?;
+
+  let user_config_file = load_protocol_config_from_json::<UserConfigFile>(user_config_str, false)
+    .map_err(|e| vec![UserConfigValidationError::Load(e.to_string())])?;
+  let Some(user_config) = user_config_file.user_configs() else {
+    return Ok(());
+  };
+
+  let mut dcm_builder = load_main_config(&None, false)
+    .map_err(|e| vec![UserConfigValidationError::Load(e.to_string())])?;
+  let errors = add_user_config_definitions(user_config, &mut dcm_builder);
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
@@ -28,6 +28,7 @@ pub struct DeviceConfigurationManagerBuilder {
   base_communication_specifiers: HashMap<String, Vec<ProtocolCommunicationSpecifier>>,
   user_communication_specifiers: DashMap<String, Vec<ProtocolCommunicationSpecifier>>,
   base_device_definitions: HashMap<BaseDeviceIdentifier, ServerDeviceDefinition>,
+  user_base_device_definitions: HashMap<BaseDeviceIdentifier, ServerDeviceDefinition>,
   user_device_definitions: DashMap<UserDeviceIdentifier, ServerDeviceDefinition>,
 }
 
@@ -56,6 +57,21 @@ impl DeviceConfigurationManagerBuilder {
     self
   }
 
+  /// Adds a protocol device definition that comes from the user config instead of the base device
+  /// config. These are used the same way as base definitions, but are tracked separately so they
+  /// can be saved back to the user config. If an identifier is defined in both, the user
+  /// definition wins.
+  pub fn user_base_device_definition(
+    &mut self,
+    identifier: &BaseDeviceIdentifier,
+    features: &ServerDeviceDefinition,
+  ) -> &mut Self {
+    self
+      .user_base_device_definitions
+      .insert(identifier.clone(), features.clone());
+    self
+  }
+
   pub fn user_communication_specifier(
     &mut self,
     protocol_name: &str,
@@ -74,25 +90,25 @@ impl DeviceConfigurationManagerBuilder {
     identifier: &UserDeviceIdentifier,
     device_definition: &ServerDeviceDefinition,
   ) -> Result<&mut Self, ButtplugDeviceConfigError> {
-    if self
-      .base_device_definitions
-      .iter()
-      .any(|(_, x)| x.id() == device_definition.base_id().unwrap_or_default())
-    {
+    if let Err(e) = validate_user_device_definition(
       self
-        .user_device_definitions
-        .insert(identifier.clone(), device_definition.clone());
-      Ok(self)
-    } else {
+        .base_device_definitions
+        .values()
+        .chain(self.user_base_device_definitions.values()),
+      device_definition,
+    ) {
       error!(
-        "Cannot find protocol with base id {:?} for user id {}",
+        "User device definition {} with base id {:?} is not valid: {}",
+        device_definition.id(),
         device_definition.base_id(),
-        device_definition.id()
+        e
       );
-      Err(ButtplugDeviceConfigError::BaseIdNotFound(
-        device_definition.id(),
-      ))
+      return Err(e);
     }
+    self
+      .user_device_definitions
+      .insert(identifier.clone(), device_definition.clone());
+    Ok(self)
   }
 
   pub fn finish(&mut self) -> Result<DeviceConfigurationManager, ButtplugDeviceError> {
@@ -104,6 +120,19 @@ impl DeviceConfigurationManagerBuilder {
       attribute_tree_map.insert(ident.clone(), attr.clone());
     }
 
+    // User protocol definitions are layered over the base config.
+    for (ident, attr) in &self.user_base_device_definitions {
+      if attribute_tree_map
+        .insert(ident.clone(), attr.clone())
+        .is_some()
+      {
+        info!(
+          "User protocol definition for {:?} replaces base definition.",
+          ident
+        );
+      }
+    }
+
     let user_attribute_tree_map = DashMap::new();
     // Finally, add in user configurations, which will have an address.
     for kv in &self.user_device_definitions {
@@ -114,12 +143,28 @@ impl DeviceConfigurationManagerBuilder {
       base_communication_specifiers: self.base_communication_specifiers.clone(),
       user_communication_specifiers: self.user_communication_specifiers.clone(),
       base_device_definitions: attribute_tree_map,
+      user_base_device_definitions: self.user_base_device_definitions.clone(),
       user_device_definitions: user_attribute_tree_map,
       //protocol_map,
     })
   }
 }
 
+/// Finds the base definition a user device definition was derived from and checks the user
+/// definition against it.
+fn validate_user_device_definition<'a>(
+  mut base_device_definitions: impl Iterator<Item = &'a ServerDeviceDefinition>,
+  definition: &ServerDeviceDefinition,
+) -> Result<(), ButtplugDeviceConfigError> {
+  let base_id = definition
+    .base_id()
+    .ok_or(ButtplugDeviceConfigError::MissingBaseId)?;
+  let base_definition = base_device_definitions
+    .find(|x| x.id() == base_id)
+    .ok_or(ButtplugDeviceConfigError::BaseIdNotFound(base_id))?;
+  definition.validate_against_base(base_definition)
+}
+
 /// Correlates information about protocols and which devices they support.
 ///
 /// The [DeviceConfigurationManager] handles stores information about which device protocols the
@@ -138,8 +183,12 @@ pub struct DeviceConfigurationManager {
   /// specifiers. Should not change/update during a session.
   #[getset(get = "pub")]
   base_communication_specifiers: HashMap<String, Vec<ProtocolCommunicationSpecifier>>,
-  /// Device definitions from the base device config. Should not change/update during a session.
+  /// Device definitions from the base device config, with protocol definitions from the user config
+  /// layered on top. Should not change/update during a session.
   base_device_definitions: HashMap<BaseDeviceIdentifier, ServerDeviceDefinition>,
+  /// Protocol device definitions provided by the user config. These are already merged into
+  /// base_device_definitions, and only kept separately so they can be saved back out.
+  user_base_device_definitions: HashMap<BaseDeviceIdentifier, ServerDeviceDefinition>,
   /// Communication specifiers provided by the user, mapped from protocol name to vector of
   /// specifiers. Loaded at session start, may change over life of session.
   #[getset(get = "pub")]
@@ -196,16 +245,25 @@ impl DeviceConfigurationManager {
     }
   }
 
+  /// Checks a user device definition against the base definition it refers to, without adding it
+  /// to the manager.
+  pub fn validate_user_device_definition(
+    &self,
+    definition: &ServerDeviceDefinition,
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    validate_user_device_definition(self.base_device_definitions.values(), definition)
+  }
+
   pub fn add_user_device_definition(
     &self,
     identifier: &UserDeviceIdentifier,
     definition: &ServerDeviceDefinition,
-  ) {
-    // TODO we should actually check validity of the definition we're adding here, i.e. does it have
-    // a base id, is that ID in our base selections, etc...
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    self.validate_user_device_definition(definition)?;
     self
       .user_device_definitions
       .insert(identifier.clone(), definition.clone());
+    Ok(())
   }
 
   pub fn remove_user_device_definition(&self, identifier: &UserDeviceIdentifier) {
@@ -7,9 +7,18 @@
 
 use std::collections::BTreeMap;
 
+use buttplug_core::message::InputCommandType;
 use getset::{CopyGetters, Getters};
 use uuid::Uuid;
 
+use crate::{
+  AutoReconnectPolicy,
+  ButtplugDeviceConfigError,
+  ByteTemplate,
+  DevicePreset,
+  DeviceSafetyLimits,
+};
+
 use super::server_device_feature::ServerDeviceFeature;
 #[derive(Debug, Clone, Getters, CopyGetters)]
 pub struct ServerDeviceDefinition {
@@ -25,6 +34,8 @@ pub struct ServerDeviceDefinition {
   #[getset(get_copy = "pub")]
   message_gap_ms: Option<u32>,
   #[getset(get = "pub")]
+  byte_template: Option<ByteTemplate>,
+  #[getset(get = "pub")]
   display_name: Option<String>,
   #[getset(get_copy = "pub")]
   allow: bool,
@@ -32,6 +43,20 @@ pub struct ServerDeviceDefinition {
   deny: bool,
   #[getset(get_copy = "pub")]
   index: u32,
+  #[getset(get = "pub")]
+  safety_limits: Option<DeviceSafetyLimits>,
+  /// Index of a button input feature that engages the server emergency stop when pressed.
+  #[getset(get_copy = "pub")]
+  emergency_stop_button: Option<u32>,
+  /// If set, the device manager tries to reconnect the device if it drops out.
+  #[getset(get_copy = "pub")]
+  auto_reconnect: Option<AutoReconnectPolicy>,
+  /// Named output presets, keyed by name.
+  #[getset(get = "pub")]
+  presets: BTreeMap<String, DevicePreset>,
+  /// Name of a preset to apply whenever the device connects.
+  #[getset(get = "pub")]
+  connect_preset: Option<String>,
   // FEATURES MUST BE A BTREEMAP
   //
   // Older versions of the protocol expect specific ordering, so we need to make sure storage
@@ -40,6 +65,74 @@ pub struct ServerDeviceDefinition {
   features: BTreeMap<u32, ServerDeviceFeature>,
 }
 
+impl ServerDeviceDefinition {
+  /// Checks that a user device definition is consistent with the base definition it was derived
+  /// from: the base id must match, every feature must map to a base feature, user ranges must
+  /// stay within base ranges, safety limits must be in range, any emergency stop button must
+  /// refer to a subscribable button input, and presets must fit the device's outputs.
+  pub fn validate_against_base(
+    &self,
+    base: &ServerDeviceDefinition,
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    let base_id = self
+      .base_id
+      .ok_or(ButtplugDeviceConfigError::MissingBaseId)?;
+    if base_id != base.id {
+      return Err(ButtplugDeviceConfigError::BaseIdNotFound(base_id));
+    }
+    if let Some(limits) = &self.safety_limits {
+      limits.validate()?;
+    }
+    if self.features.len() != base.features.len() {
+      return Err(ButtplugDeviceConfigError::UserFeatureMismatch);
+    }
+    for feature in self.features.values() {
+      let feature_base_id = feature
+        .base_id()
+        .ok_or(ButtplugDeviceConfigError::MissingBaseId)?;
+      let base_feature = base
+        .features
+        .values()
+        .find(|x| x.id() == feature_base_id)
+        .ok_or(ButtplugDeviceConfigError::BaseFeatureIdNotFound(
+          feature_base_id,
+        ))?;
+      feature.validate_against_base(base_feature)?;
+    }
+    self.validate_emergency_stop_button()?;
+    self.validate_presets()
+  }
+
+  pub(crate) fn validate_presets(&self) -> Result<(), ButtplugDeviceConfigError> {
+    for (name, preset) in &self.presets {
+      preset.validate(name, &self.features)?;
+    }
+    match &self.connect_preset {
+      Some(name) if !self.presets.contains_key(name) => Err(
+        ButtplugDeviceConfigError::InvalidPreset(format!("connect preset {name} does not exist")),
+      ),
+      _ => Ok(()),
+    }
+  }
+
+  pub(crate) fn validate_emergency_stop_button(&self) -> Result<(), ButtplugDeviceConfigError> {
+    let Some(index) = self.emergency_stop_button else {
+      return Ok(());
+    };
+    let is_button = self
+      .features
+      .get(&index)
+      .and_then(|feature| feature.input().as_ref())
+      .and_then(|input| input.button().as_ref())
+      .is_some_and(|button| button.command().contains(&InputCommandType::Subscribe));
+    if is_button {
+      Ok(())
+    } else {
+      Err(ButtplugDeviceConfigError::InvalidEmergencyStopButton(index))
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct ServerDeviceDefinitionBuilder {
   def: ServerDeviceDefinition,
@@ -54,10 +147,16 @@ impl ServerDeviceDefinitionBuilder {
         base_id: None,
         protocol_variant: None,
         message_gap_ms: None,
+        byte_template: None,
         display_name: None,
         allow: false,
         deny: false,
         index: 0,
+        safety_limits: None,
+        emergency_stop_button: None,
+        auto_reconnect: None,
+        presets: BTreeMap::new(),
+        connect_preset: None,
         features: BTreeMap::new(),
       },
     }
@@ -112,6 +211,11 @@ impl ServerDeviceDefinitionBuilder {
     self
   }
 
+  pub fn byte_template(&mut self, template: &ByteTemplate) -> &mut Self {
+    self.def.byte_template = Some(template.clone());
+    self
+  }
+
   pub fn allow(&mut self, allow: bool) -> &mut Self {
     self.def.allow = allow;
     self
@@ -127,6 +231,36 @@ impl ServerDeviceDefinitionBuilder {
     self
   }
 
+  pub fn safety_limits(&mut self, limits: &Option<DeviceSafetyLimits>) -> &mut Self {
+    self.def.safety_limits = limits.clone();
+    self
+  }
+
+  pub fn emergency_stop_button(&mut self, index: Option<u32>) -> &mut Self {
+    self.def.emergency_stop_button = index;
+    self
+  }
+
+  pub fn auto_reconnect(&mut self, policy: Option<AutoReconnectPolicy>) -> &mut Self {
+    self.def.auto_reconnect = policy;
+    self
+  }
+
+  pub fn presets(&mut self, presets: &BTreeMap<String, DevicePreset>) -> &mut Self {
+    self.def.presets = presets.clone();
+    self
+  }
+
+  pub fn add_preset(&mut self, name: &str, preset: &DevicePreset) -> &mut Self {
+    self.def.presets.insert(name.to_owned(), preset.clone());
+    self
+  }
+
+  pub fn connect_preset(&mut self, name: &Option<String>) -> &mut Self {
+    self.def.connect_preset = name.clone();
+    self
+  }
+
   pub fn add_feature(&mut self, feature: &ServerDeviceFeature) -> &mut Self {
     self.def.features.insert(feature.index(), feature.clone());
     self
@@ -0,0 +1,100 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use std::collections::BTreeMap;
+
+use buttplug_core::message::OutputType;
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+use crate::{ButtplugDeviceConfigError, ServerDeviceFeature};
+
+/// Level for one feature output in a [DevicePreset].
+///
+/// Values use the same step range clients see for the output, so user ranges and response curves
+/// are applied to presets the same way they are applied to client commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters, Serialize, Deserialize)]
+#[getset(get_copy = "pub")]
+pub struct PresetOutput {
+  feature_index: u32,
+  output_type: OutputType,
+  value: i32,
+}
+
+impl PresetOutput {
+  pub fn new(feature_index: u32, output_type: OutputType, value: i32) -> Self {
+    Self {
+      feature_index,
+      output_type,
+      value,
+    }
+  }
+
+  fn validate(
+    &self,
+    preset_name: &str,
+    features: &BTreeMap<u32, ServerDeviceFeature>,
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    let invalid = |reason: &str| {
+      Err(ButtplugDeviceConfigError::InvalidPreset(format!(
+        "{preset_name}, feature {} {}: {reason}",
+        self.feature_index, self.output_type
+      )))
+    };
+    let Some(output) = features
+      .get(&self.feature_index)
+      .and_then(|feature| feature.output().as_ref())
+    else {
+      return invalid("feature has no outputs");
+    };
+    // Timed moves need a duration, which presets don't have.
+    if matches!(
+      self.output_type,
+      OutputType::Unknown | OutputType::HwPositionWithDuration
+    ) || !output.contains(self.output_type)
+    {
+      return invalid("output type not available");
+    }
+    if output.is_disabled(self.output_type) {
+      return invalid("output is disabled");
+    }
+    if output
+      .calculate_from_value(self.output_type, self.value)
+      .is_err()
+    {
+      return invalid(&format!("value {} is out of range", self.value));
+    }
+    Ok(())
+  }
+}
+
+/// Named set of output levels for a device, set in the user device configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[serde(transparent)]
+#[getset(get = "pub")]
+pub struct DevicePreset {
+  outputs: Vec<PresetOutput>,
+}
+
+impl DevicePreset {
+  pub fn new(outputs: &[PresetOutput]) -> Self {
+    Self {
+      outputs: outputs.to_vec(),
+    }
+  }
+
+  pub(crate) fn validate(
+    &self,
+    name: &str,
+    features: &BTreeMap<u32, ServerDeviceFeature>,
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    self
+      .outputs
+      .iter()
+      .try_for_each(|output| output.validate(name, features))
+  }
+}
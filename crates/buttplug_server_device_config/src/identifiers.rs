@@ -24,6 +24,7 @@ pub struct UserDeviceIdentifier {
   /// Name of the protocol used
   protocol: String,
   /// Internal identifier for the protocol used
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   identifier: Option<String>,
   /// Address, as possibly serialized by whatever the managing library for the Device Communication Manager is.
   address: String,
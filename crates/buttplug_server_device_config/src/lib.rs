@@ -62,7 +62,8 @@
 //!   to the main Device Configuration file, or else a user creates their own DIY device that uses
 //!   another protocol (hence it will never be in the main Device Configuration file as there may
 //!   only be one of the device, period), a user can add an extension to an established protocol to
-//!   provide new identifier information.
+//!   provide new identifier information. User configs can also carry full protocol definitions
+//!   (defaults and configurations, including features), which are merged in as base definitions.
 //! - User configured message attributes: limits that can be set for certain messages a device
 //!   takes. For instance, setting an upper limit on the vibration speed of a vibrator so it will
 //!   only go to 80% instead of 100%.
@@ -143,7 +144,18 @@ extern crate log;
 mod device_config_file;
 
 use buttplug_core::message::OutputType;
-pub use device_config_file::{load_protocol_configs, save_user_config};
+pub use device_config_file::{
+  DeviceMigration,
+  DeviceMigrationResult,
+  FeatureMigration,
+  FeatureMigrationResult,
+  UserConfigMigration,
+  UserConfigValidationError,
+  load_protocol_configs,
+  migrate_user_config,
+  save_user_config,
+  validate_user_config,
+};
 mod device_config_manager;
 pub use device_config_manager::*;
 mod specifier;
@@ -156,11 +168,21 @@ mod server_device_feature;
 pub use server_device_feature::*;
 mod endpoint;
 pub use endpoint::*;
+mod byte_template;
+pub use byte_template::*;
+mod response_curve;
+pub use response_curve::*;
+mod safety_limits;
+pub use safety_limits::*;
+mod auto_reconnect;
+pub use auto_reconnect::*;
+mod device_preset;
+pub use device_preset::*;
 use uuid::Uuid;
 
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ButtplugDeviceConfigError {
   /// Conversion to client type not possible with requested property type
   #[error("Conversion of {0} to client type not possible with requested property type")]
@@ -176,12 +198,27 @@ pub enum ButtplugDeviceConfigError {
   BaseIdNotFound(Uuid),
   #[error("Feature vectors between base and user device definitions do not match")]
   UserFeatureMismatch,
+  /// User feature refers to a base feature id that does not exist on the base device
+  #[error("Feature with base id {0} not found in base device definition")]
+  BaseFeatureIdNotFound(Uuid),
   #[error("Output value {0} not in range {1}")]
   InvalidOutputValue(i32, String),
   #[error("Output type {0} not available on device")]
   InvalidOutput(OutputType),
   #[error("Float value {0} is not 0 < x < 1")]
   InvalidFloatConversion(f64),
+  /// Response curve parameters are out of range
+  #[error("Response curve {0} is not valid")]
+  InvalidResponseCurve(String),
+  /// Device safety limits are out of range
+  #[error("Safety limits are not valid: {0}")]
+  InvalidSafetyLimits(String),
+  /// Emergency stop button does not refer to a subscribable button input
+  #[error("Feature {0} is not a button input that can be used as an emergency stop")]
+  InvalidEmergencyStopButton(u32),
+  /// Preset refers to outputs the device doesn't have, or values out of range
+  #[error("Preset is not valid: {0}")]
+  InvalidPreset(String),
   /// Feature or device is missing required base_id for user config conversion
   #[error("Feature or device is missing required base_id for user config conversion")]
   MissingBaseId,
@@ -0,0 +1,153 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ButtplugDeviceConfigError;
+
+/// User defined mapping from requested output levels to the levels sent to the device.
+///
+/// Curves work on levels normalized to 0.0-1.0, before they are fit into the step range of the
+/// output, so they stack with user ranges. A level of 0 always maps to 0, so stop commands are never
+/// changed by a curve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseCurve {
+  /// Level is raised to this power. Values below 1 boost low levels, values above 1 soften them.
+  Gamma(f64),
+  /// Piecewise linear curve through `[input, output]` points, with inputs increasing from 0 to 1.
+  Points(Vec<[f64; 2]>),
+  /// Lowest level sent for any non-zero level, with the rest of the range scaled above it. Useful
+  /// for motors that do not start until they get a certain amount of power.
+  Deadzone(f64),
+}
+
+impl ResponseCurve {
+  pub fn validate(&self) -> Result<(), ButtplugDeviceConfigError> {
+    let valid = match self {
+      ResponseCurve::Gamma(gamma) => gamma.is_finite() && *gamma > 0.0,
+      ResponseCurve::Points(points) => {
+        points.len() >= 2
+          && points.iter().flatten().all(|x| (0.0..=1.0).contains(x))
+          && points.windows(2).all(|x| x[0][0] < x[1][0])
+      }
+      ResponseCurve::Deadzone(offset) => (0.0..1.0).contains(offset),
+    };
+    if valid {
+      Ok(())
+    } else {
+      Err(ButtplugDeviceConfigError::InvalidResponseCurve(format!(
+        "{self:?}"
+      )))
+    }
+  }
+
+  /// Applies the curve to a level between 0.0 and 1.0.
+  pub fn apply(&self, level: f64) -> f64 {
+    if level <= 0.0 {
+      return 0.0;
+    }
+    let level = level.min(1.0);
+    let mapped = match self {
+      ResponseCurve::Gamma(gamma) => level.powf(*gamma),
+      ResponseCurve::Points(points) => {
+        // Levels outside of the defined points hold the output of the nearest point.
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if level <= first[0] {
+          first[1]
+        } else if level >= last[0] {
+          last[1]
+        } else {
+          let segment = points
+            .windows(2)
+            .find(|x| level <= x[1][0])
+            .expect("Level is between first and last point");
+          let [x0, y0] = segment[0];
+          let [x1, y1] = segment[1];
+          y0 + (level - x0) * (y1 - y0) / (x1 - x0)
+        }
+      }
+      ResponseCurve::Deadzone(offset) => offset + (1.0 - offset) * level,
+    };
+    mapped.clamp(0.0, 1.0)
+  }
+
+  /// Applies the curve to a step value out of `step_count` steps. Non-zero steps stay non-zero,
+  /// negative steps (i.e. rotation direction) are curved by magnitude, and steps outside of the
+  /// step count are passed through untouched so range checking still catches them.
+  pub fn apply_step(&self, step: i32, step_count: u32) -> i32 {
+    let magnitude = step.unsigned_abs();
+    if step == 0 || step_count == 0 || magnitude > step_count {
+      return step;
+    }
+    let curved = (self.apply(magnitude as f64 / step_count as f64) * step_count as f64).ceil();
+    let curved = (curved as u32).clamp(1, step_count) as i32;
+    if step < 0 { -curved } else { curved }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::ResponseCurve;
+
+  #[test]
+  fn test_response_curve_zero_is_zero() {
+    for curve in [
+      ResponseCurve::Gamma(0.5),
+      ResponseCurve::Points(vec![[0.0, 0.3], [1.0, 1.0]]),
+      ResponseCurve::Deadzone(0.2),
+    ] {
+      assert_eq!(curve.apply(0.0), 0.0);
+      assert_eq!(curve.apply_step(0, 20), 0);
+      assert_eq!(curve.apply(1.0), 1.0);
+    }
+  }
+
+  fn assert_level(actual: f64, expected: f64) {
+    assert!(
+      (actual - expected).abs() < 1e-9,
+      "Expected {expected}, got {actual}"
+    );
+  }
+
+  #[test]
+  fn test_response_curve_values() {
+    assert_level(ResponseCurve::Gamma(2.0).apply(0.5), 0.25);
+    assert_level(ResponseCurve::Deadzone(0.2).apply(0.5), 0.6);
+    let points = ResponseCurve::Points(vec![[0.0, 0.0], [0.5, 0.8], [1.0, 1.0]]);
+    assert_level(points.apply(0.25), 0.4);
+    assert_level(points.apply(0.75), 0.9);
+    // With a 20% deadzone over 20 steps, the lowest non-zero step is sent as step 5.
+    assert_eq!(ResponseCurve::Deadzone(0.2).apply_step(1, 20), 5);
+    assert_eq!(ResponseCurve::Deadzone(0.2).apply_step(-1, 20), -5);
+    assert_eq!(ResponseCurve::Deadzone(0.2).apply_step(25, 20), 25);
+    // Curves that flatten low levels still keep non-zero steps moving.
+    assert_eq!(ResponseCurve::Gamma(3.0).apply_step(1, 20), 1);
+  }
+
+  #[test]
+  fn test_response_curve_validation() {
+    assert!(ResponseCurve::Gamma(0.0).validate().is_err());
+    assert!(ResponseCurve::Deadzone(1.0).validate().is_err());
+    assert!(
+      ResponseCurve::Points(vec![[0.5, 0.5], [0.2, 1.0]])
+        .validate()
+        .is_err()
+    );
+    assert!(ResponseCurve::Points(vec![[0.0, 0.0]]).validate().is_err());
+    assert!(
+      ResponseCurve::Points(vec![[0.0, 0.0], [1.0, 1.5]])
+        .validate()
+        .is_err()
+    );
+    assert!(
+      ResponseCurve::Points(vec![[0.0, 0.2], [1.0, 1.0]])
+        .validate()
+        .is_ok()
+    );
+  }
+}
@@ -0,0 +1,171 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use buttplug_core::message::OutputType;
+use getset::CopyGetters;
+use serde::{Deserialize, Serialize};
+
+use crate::ButtplugDeviceConfigError;
+
+/// Limits the server enforces on a single output type, regardless of what clients request.
+///
+/// Levels and rates are expressed as fractions of the full range of the output, so the same limits
+/// can be applied to devices with different step counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, CopyGetters, Serialize, Deserialize)]
+#[getset(get_copy = "pub")]
+pub struct OutputSafetyLimits {
+  /// Largest change in level allowed per second. Stop commands are never slowed down, and timed
+  /// position moves are lengthened instead.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  max_slew_rate: Option<f64>,
+  /// Longest time an output may stay on before the server stops it. Not available for position
+  /// outputs, which have no off state.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  max_on_time_ms: Option<u32>,
+  /// Highest level that will ever be sent. Only available for temperature and constrict outputs.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  max_level: Option<f64>,
+}
+
+impl OutputSafetyLimits {
+  pub fn new(
+    max_slew_rate: Option<f64>,
+    max_on_time_ms: Option<u32>,
+    max_level: Option<f64>,
+  ) -> Self {
+    Self {
+      max_slew_rate,
+      max_on_time_ms,
+      max_level,
+    }
+  }
+
+  fn validate(&self, output_type: OutputType) -> Result<(), ButtplugDeviceConfigError> {
+    let invalid = |reason: &str| {
+      Err(ButtplugDeviceConfigError::InvalidSafetyLimits(format!(
+        "{output_type}: {reason}"
+      )))
+    };
+    if let Some(rate) = self.max_slew_rate
+      && !(rate.is_finite() && rate > 0.0)
+    {
+      return invalid("max_slew_rate must be greater than 0");
+    }
+    if let Some(on_time) = self.max_on_time_ms {
+      if matches!(
+        output_type,
+        OutputType::Position | OutputType::HwPositionWithDuration
+      ) {
+        return invalid("max_on_time_ms is not available for position outputs");
+      }
+      if on_time == 0 {
+        return invalid("max_on_time_ms must be greater than 0");
+      }
+    }
+    if let Some(level) = self.max_level {
+      if !matches!(output_type, OutputType::Temperature | OutputType::Constrict) {
+        return invalid("max_level is only available for temperature and constrict outputs");
+      }
+      if !(0.0..=1.0).contains(&level) {
+        return invalid("max_level must be between 0 and 1");
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Per output type safety limits for a device, set in the user device configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSafetyLimits {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  vibrate: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  rotate: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  oscillate: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  constrict: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  temperature: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  led: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  position: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  hw_position_with_duration: Option<OutputSafetyLimits>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  spray: Option<OutputSafetyLimits>,
+}
+
+impl DeviceSafetyLimits {
+  pub fn limits(&self, output_type: OutputType) -> Option<&OutputSafetyLimits> {
+    self.slot(output_type).as_ref()
+  }
+
+  pub fn set_limits(&mut self, output_type: OutputType, limits: Option<OutputSafetyLimits>) {
+    if output_type != OutputType::Unknown {
+      *self.slot_mut(output_type) = limits;
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.iter().next().is_none()
+  }
+
+  /// Iterates over the output types that have limits set.
+  pub fn iter(&self) -> impl Iterator<Item = (OutputType, &OutputSafetyLimits)> {
+    [
+      OutputType::Vibrate,
+      OutputType::Rotate,
+      OutputType::Oscillate,
+      OutputType::Constrict,
+      OutputType::Temperature,
+      OutputType::Led,
+      OutputType::Position,
+      OutputType::HwPositionWithDuration,
+      OutputType::Spray,
+    ]
+    .into_iter()
+    .filter_map(|output_type| self.limits(output_type).map(|x| (output_type, x)))
+  }
+
+  pub fn validate(&self) -> Result<(), ButtplugDeviceConfigError> {
+    self
+      .iter()
+      .try_for_each(|(output_type, limits)| limits.validate(output_type))
+  }
+
+  fn slot(&self, output_type: OutputType) -> &Option<OutputSafetyLimits> {
+    match output_type {
+      OutputType::Vibrate => &self.vibrate,
+      OutputType::Rotate => &self.rotate,
+      OutputType::Oscillate => &self.oscillate,
+      OutputType::Constrict => &self.constrict,
+      OutputType::Temperature => &self.temperature,
+      OutputType::Led => &self.led,
+      OutputType::Position => &self.position,
+      OutputType::HwPositionWithDuration => &self.hw_position_with_duration,
+      OutputType::Spray => &self.spray,
+      OutputType::Unknown => &None,
+    }
+  }
+
+  fn slot_mut(&mut self, output_type: OutputType) -> &mut Option<OutputSafetyLimits> {
+    match output_type {
+      OutputType::Vibrate => &mut self.vibrate,
+      OutputType::Rotate => &mut self.rotate,
+      OutputType::Oscillate => &mut self.oscillate,
+      OutputType::Constrict => &mut self.constrict,
+      OutputType::Temperature => &mut self.temperature,
+      OutputType::Led => &mut self.led,
+      OutputType::Position => &mut self.position,
+      OutputType::HwPositionWithDuration => &mut self.hw_position_with_duration,
+      OutputType::Spray => &mut self.spray,
+      OutputType::Unknown => unreachable!("Unknown output types are filtered before lookup"),
+    }
+  }
+}
@@ -5,7 +5,7 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
-use crate::ButtplugDeviceConfigError;
+use crate::{ButtplugDeviceConfigError, ResponseCurve};
 
 use buttplug_core::message::{
   DeviceFeature,
@@ -200,13 +200,17 @@ impl<'de> Deserialize<'de> for RangeWithLimit {
   }
 }
 
-#[derive(Debug, Clone, Getters, CopyGetters, Serialize, Deserialize)]
+#[derive(Debug, Clone, Getters, CopyGetters, Setters, Serialize, Deserialize)]
 pub struct ServerDeviceFeatureOutputValueProperties {
   #[getset(get = "pub")]
   value: RangeWithLimit,
   #[getset(get_copy = "pub")]
   #[serde(default, skip_serializing_if = "std::ops::Not::not")]
   disabled: bool,
+  /// User response curve, applied before values are fit into the user range
+  #[getset(get = "pub", set = "pub")]
+  #[serde(skip)]
+  response_curve: Option<ResponseCurve>,
 }
 
 impl ServerDeviceFeatureOutputValueProperties {
@@ -214,6 +218,7 @@ impl ServerDeviceFeatureOutputValueProperties {
     Self {
       value: value.clone(),
       disabled,
+      response_curve: None,
     }
   }
 
@@ -222,13 +227,25 @@ impl ServerDeviceFeatureOutputValueProperties {
       Err(ButtplugDeviceConfigError::InvalidFloatConversion(value))
     } else {
       let value = if value < 0.000001 { 0f64 } else { value };
-      self.calculate_scaled_value((self.value.step_count() as f64 * value).ceil() as i32)
+      let value = self
+        .response_curve
+        .as_ref()
+        .map_or(value, |x| x.apply(value));
+      self.scale_value((self.value.step_count() as f64 * value).ceil() as i32)
     }
   }
 
+  pub fn calculate_scaled_value(&self, value: i32) -> Result<i32, ButtplugDeviceConfigError> {
+    let value = self
+      .response_curve
+      .as_ref()
+      .map_or(value, |x| x.apply_step(value, self.value.step_count()));
+    self.scale_value(value)
+  }
+
   // We'll get a number from 0-x here. We'll need to calculate it with in the range we have. We'll
   // consider negative ranges symmetric.
-  pub fn calculate_scaled_value(&self, value: i32) -> Result<i32, ButtplugDeviceConfigError> {
+  fn scale_value(&self, value: i32) -> Result<i32, ButtplugDeviceConfigError> {
     let range = if let Some(user_range) = self.value.user() {
       user_range
     } else {
@@ -255,7 +272,7 @@ impl From<&ServerDeviceFeatureOutputValueProperties> for DeviceFeatureOutputValu
   }
 }
 
-#[derive(Debug, Clone, Getters, CopyGetters, Serialize, Deserialize)]
+#[derive(Debug, Clone, Getters, CopyGetters, Setters, Serialize, Deserialize)]
 pub struct ServerDeviceFeatureOutputPositionProperties {
   #[getset(get = "pub")]
   value: RangeWithLimit,
@@ -265,6 +282,10 @@ pub struct ServerDeviceFeatureOutputPositionProperties {
   #[getset(get_copy = "pub")]
   #[serde(default, skip_serializing_if = "std::ops::Not::not")]
   reverse_position: bool,
+  /// User response curve, applied before values are fit into the user range
+  #[getset(get = "pub", set = "pub")]
+  #[serde(skip)]
+  response_curve: Option<ResponseCurve>,
 }
 
 impl ServerDeviceFeatureOutputPositionProperties {
@@ -273,6 +294,7 @@ impl ServerDeviceFeatureOutputPositionProperties {
       value: value.clone(),
       disabled,
       reverse_position,
+      response_curve: None,
     }
   }
 
@@ -280,14 +302,25 @@ impl ServerDeviceFeatureOutputPositionProperties {
     if !(0.0..=1.0).contains(&input) {
       Err(ButtplugDeviceConfigError::InvalidFloatConversion(input))
     } else {
+      let input = self
+        .response_curve
+        .as_ref()
+        .map_or(input, |x| x.apply(input));
       self
-        .calculate_scaled_value((self.value.step_count() as f64 * input).ceil() as u32)
+        .scale_value((self.value.step_count() as f64 * input).ceil() as u32)
         .map(|x| x as i32)
     }
   }
 
-  // We'll get a number from 0-x here. We'll need to calculate it with in the range we have.
   pub fn calculate_scaled_value(&self, input: u32) -> Result<u32, ButtplugDeviceConfigError> {
+    let input = self.response_curve.as_ref().map_or(input, |x| {
+      x.apply_step(input as i32, self.value.step_count()) as u32
+    });
+    self.scale_value(input)
+  }
+
+  // We'll get a number from 0-x here. We'll need to calculate it with in the range we have.
+  fn scale_value(&self, input: u32) -> Result<u32, ButtplugDeviceConfigError> {
     let range = if let Some(user_range) = self.value.user() {
       user_range
     } else {
@@ -314,7 +347,7 @@ impl From<&ServerDeviceFeatureOutputPositionProperties> for DeviceFeatureOutputV
   }
 }
 
-#[derive(Debug, Clone, Getters, CopyGetters, Serialize, Deserialize)]
+#[derive(Debug, Clone, Getters, CopyGetters, Setters, Serialize, Deserialize)]
 pub struct ServerDeviceFeatureOutputHwPositionWithDurationProperties {
   #[getset(get = "pub")]
   value: RangeWithLimit,
@@ -326,6 +359,10 @@ pub struct ServerDeviceFeatureOutputHwPositionWithDurationProperties {
   #[getset(get_copy = "pub")]
   #[serde(default, skip_serializing_if = "std::ops::Not::not")]
   reverse_position: bool,
+  /// User response curve, applied to position values before they are fit into the user range
+  #[getset(get = "pub", set = "pub")]
+  #[serde(skip)]
+  response_curve: Option<ResponseCurve>,
 }
 
 impl ServerDeviceFeatureOutputHwPositionWithDurationProperties {
@@ -340,15 +377,27 @@ impl ServerDeviceFeatureOutputHwPositionWithDurationProperties {
       duration: duration.clone(),
       disabled,
       reverse_position,
+      response_curve: None,
     }
   }
 
   pub fn calculate_scaled_float(&self, input: f64) -> Result<u32, ButtplugDeviceConfigError> {
-    self.calculate_scaled_value((self.value.step_count() as f64 * input) as u32)
+    let input = self
+      .response_curve
+      .as_ref()
+      .map_or(input, |x| x.apply(input));
+    self.scale_value((self.value.step_count() as f64 * input) as u32)
   }
 
-  // We'll get a number from 0-x here. We'll need to calculate it with in the range we have.
   pub fn calculate_scaled_value(&self, input: u32) -> Result<u32, ButtplugDeviceConfigError> {
+    let input = self.response_curve.as_ref().map_or(input, |x| {
+      x.apply_step(input as i32, self.value.step_count()) as u32
+    });
+    self.scale_value(input)
+  }
+
+  // We'll get a number from 0-x here. We'll need to calculate it with in the range we have.
+  fn scale_value(&self, input: u32) -> Result<u32, ButtplugDeviceConfigError> {
     let range = if let Some(user_range) = self.value.user() {
       user_range
     } else {
@@ -442,6 +491,103 @@ impl ServerDeviceFeatureOutput {
     .collect()
   }
 
+  /// Returns all ranges associated with an output type, or an empty vector if the output type is
+  /// not present.
+  fn ranges(&self, output_type: OutputType) -> Vec<&RangeWithLimit> {
+    match output_type {
+      OutputType::Constrict => self.constrict.iter().map(|x| x.value()).collect(),
+      OutputType::Temperature => self.temperature.iter().map(|x| x.value()).collect(),
+      OutputType::Led => self.led.iter().map(|x| x.value()).collect(),
+      OutputType::Oscillate => self.oscillate.iter().map(|x| x.value()).collect(),
+      OutputType::Position => self.position.iter().map(|x| x.value()).collect(),
+      OutputType::HwPositionWithDuration => self
+        .hw_position_with_duration
+        .iter()
+        .flat_map(|x| [x.value(), x.duration()])
+        .collect(),
+      OutputType::Rotate => self.rotate.iter().map(|x| x.value()).collect(),
+      OutputType::Spray => self.spray.iter().map(|x| x.value()).collect(),
+      OutputType::Unknown => vec![],
+      OutputType::Vibrate => self.vibrate.iter().map(|x| x.value()).collect(),
+    }
+  }
+
+  /// Checks that this output only uses output types available on the base output, and that every
+  /// user range sits inside the matching base range.
+  pub fn validate_against_base(
+    &self,
+    base: &ServerDeviceFeatureOutput,
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    for output_type in self.output_types() {
+      if !base.contains(output_type) {
+        return Err(ButtplugDeviceConfigError::InvalidOutput(output_type));
+      }
+      for (range, base_range) in self
+        .ranges(output_type)
+        .into_iter()
+        .zip(base.ranges(output_type))
+      {
+        if range.base() != base_range.base() {
+          return Err(ButtplugDeviceConfigError::InvalidUserRange);
+        }
+        RangeWithLimit::try_new(base_range.base(), range.user())?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Response curve set on the outputs of this feature, if any.
+  pub fn response_curve(&self) -> Option<&ResponseCurve> {
+    [
+      &self.vibrate,
+      &self.rotate,
+      &self.oscillate,
+      &self.constrict,
+      &self.temperature,
+      &self.led,
+      &self.spray,
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|x| x.response_curve().as_ref())
+    .or_else(|| {
+      self
+        .position
+        .as_ref()
+        .and_then(|x| x.response_curve().as_ref())
+    })
+    .or_else(|| {
+      self
+        .hw_position_with_duration
+        .as_ref()
+        .and_then(|x| x.response_curve().as_ref())
+    })
+  }
+
+  /// Sets the response curve used by all outputs of this feature.
+  pub fn set_response_curve(&mut self, curve: &Option<ResponseCurve>) {
+    for output in [
+      &mut self.vibrate,
+      &mut self.rotate,
+      &mut self.oscillate,
+      &mut self.constrict,
+      &mut self.temperature,
+      &mut self.led,
+      &mut self.spray,
+    ]
+    .into_iter()
+    .flatten()
+    {
+      output.set_response_curve(curve.clone());
+    }
+    if let Some(position) = &mut self.position {
+      position.set_response_curve(curve.clone());
+    }
+    if let Some(hw_position) = &mut self.hw_position_with_duration {
+      hw_position.set_response_curve(curve.clone());
+    }
+  }
+
   pub fn calculate_from_value(
     &self,
     output_type: OutputType,
@@ -507,6 +653,24 @@ impl ServerDeviceFeatureOutput {
     }
   }
 
+  /// Range of values the device accepts for an output type, before user limits are applied.
+  pub fn value_range(&self, output_type: OutputType) -> Option<&RangeWithLimit> {
+    match output_type {
+      OutputType::Vibrate => self.vibrate.as_ref().map(|x| x.value()),
+      OutputType::Rotate => self.rotate.as_ref().map(|x| x.value()),
+      OutputType::Oscillate => self.oscillate.as_ref().map(|x| x.value()),
+      OutputType::Constrict => self.constrict.as_ref().map(|x| x.value()),
+      OutputType::Temperature => self.temperature.as_ref().map(|x| x.value()),
+      OutputType::Led => self.led.as_ref().map(|x| x.value()),
+      OutputType::Position => self.position.as_ref().map(|x| x.value()),
+      OutputType::HwPositionWithDuration => {
+        self.hw_position_with_duration.as_ref().map(|x| x.value())
+      }
+      OutputType::Spray => self.spray.as_ref().map(|x| x.value()),
+      OutputType::Unknown => None,
+    }
+  }
+
   pub fn calculate_from_float(
     &self,
     output_type: OutputType,
@@ -771,6 +935,26 @@ impl ServerDeviceFeature {
     new_feature
   }
 
+  /// Checks that a user feature is consistent with the base feature it was derived from.
+  pub fn validate_against_base(
+    &self,
+    base: &ServerDeviceFeature,
+  ) -> Result<(), ButtplugDeviceConfigError> {
+    if self.index != base.index {
+      return Err(ButtplugDeviceConfigError::UserFeatureMismatch);
+    }
+    if let Some(curve) = self.output.as_ref().and_then(|x| x.response_curve()) {
+      curve.validate()?;
+    }
+    match (&self.output, &base.output) {
+      (Some(output), Some(base_output)) => output.validate_against_base(base_output),
+      (Some(output), None) => output.output_types().first().map_or(Ok(()), |x| {
+        Err(ButtplugDeviceConfigError::InvalidOutput(*x))
+      }),
+      (None, _) => Ok(()),
+    }
+  }
+
   pub fn as_device_feature(&self) -> Result<DeviceFeature, ButtplugDeviceConfigError> {
     Ok(DeviceFeature::new(
       self.index,
@@ -6,9 +6,13 @@
 // for full license information.
 
 use super::Endpoint;
-use getset::{Getters, MutGetters, Setters};
+use getset::{CopyGetters, Getters, MutGetters, Setters};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  str::FromStr,
+};
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Getters, MutGetters, Setters, Eq)]
@@ -98,40 +102,52 @@ pub struct BluetoothLESpecifier {
   services: HashMap<Uuid, HashMap<Endpoint, Uuid>>,
 }
 
+/// Checks whether two sets of advertised names share a name, allowing either side to end with a '*'
+/// wildcard.
+fn bluetooth_le_names_match(names: &HashSet<String>, other_names: &HashSet<String>) -> bool {
+  if names.intersection(other_names).count() > 0 {
+    return true;
+  }
+  // Otherwise, try wildcarded names.
+  for name in names {
+    for other_name in other_names {
+      let compare_name: &String;
+      let mut wildcard: String;
+      if name.ends_with('*') {
+        wildcard = name.clone();
+        compare_name = other_name;
+      } else if other_name.ends_with('*') {
+        wildcard = other_name.clone();
+        compare_name = name;
+      } else {
+        continue;
+      }
+      // Remove asterisk from the end of the wildcard
+      wildcard.pop();
+      if compare_name.starts_with(&wildcard) {
+        return true;
+      }
+    }
+  }
+  false
+}
+
+fn bluetooth_le_manufacturer_data_match(
+  data: &[BluetoothLEManufacturerData],
+  other_data: &[BluetoothLEManufacturerData],
+) -> bool {
+  data.iter().any(|entry| other_data.contains(entry))
+}
+
 impl PartialEq for BluetoothLESpecifier {
   fn eq(&self, other: &Self) -> bool {
     // If names or manufacturer data are found, use those automatically.
-    if self.names.intersection(&other.names).count() > 0 {
+    if bluetooth_le_names_match(&self.names, &other.names) {
       return true;
     }
-    // Otherwise, try wildcarded names.
-    for name in &self.names {
-      for other_name in &other.names {
-        let compare_name: &String;
-        let mut wildcard: String;
-        if name.ends_with('*') {
-          wildcard = name.clone();
-          compare_name = other_name;
-        } else if other_name.ends_with('*') {
-          wildcard = other_name.clone();
-          compare_name = name;
-        } else {
-          continue;
-        }
-        // Remove asterisk from the end of the wildcard
-        wildcard.pop();
-        if compare_name.starts_with(&wildcard) {
-          return true;
-        }
-      }
-    }
 
-    if !self.manufacturer_data.is_empty() && !other.manufacturer_data.is_empty() {
-      for data in &self.manufacturer_data {
-        if other.manufacturer_data.contains(data) {
-          return true;
-        }
-      }
+    if bluetooth_le_manufacturer_data_match(&self.manufacturer_data, &other.manufacturer_data) {
+      return true;
     }
 
     if self
@@ -201,6 +217,52 @@ impl BluetoothLESpecifier {
   }
 }
 
+/// Specifier for Bluetooth LE devices that are only ever talked to through advertisements
+///
+/// These devices never take a connection, so there are no services to match. They're matched on the
+/// name they advertise under (or, for devices that only listen, the name they were configured with)
+/// and their manufacturer data.
+#[derive(Serialize, Deserialize, Debug, Clone, Getters, Eq)]
+#[getset(get = "pub")]
+pub struct BluetoothLEAdvertisementSpecifier {
+  /// Set of expected advertised names for this device.
+  names: HashSet<String>,
+  /// Array of possible manufacturer data values.
+  #[serde(default)]
+  manufacturer_data: Vec<BluetoothLEManufacturerData>,
+}
+
+impl PartialEq for BluetoothLEAdvertisementSpecifier {
+  fn eq(&self, other: &Self) -> bool {
+    bluetooth_le_names_match(&self.names, &other.names)
+      || bluetooth_le_manufacturer_data_match(&self.manufacturer_data, &other.manufacturer_data)
+  }
+}
+
+impl BluetoothLEAdvertisementSpecifier {
+  pub fn new(names: HashSet<String>, manufacturer_data: Vec<BluetoothLEManufacturerData>) -> Self {
+    Self {
+      names,
+      manufacturer_data,
+    }
+  }
+
+  /// Creates a specifier from a BLE device advertisement.
+  pub fn new_from_device(
+    name: &str,
+    manufacturer_data: &HashMap<u16, Vec<u8>>,
+  ) -> BluetoothLEAdvertisementSpecifier {
+    let data_vec = manufacturer_data
+      .iter()
+      .map(|(company, data)| BluetoothLEManufacturerData::new(*company, &Some(data.clone())))
+      .collect();
+    BluetoothLEAdvertisementSpecifier {
+      names: HashSet::from([name.to_owned()]),
+      manufacturer_data: data_vec,
+    }
+  }
+}
+
 /// Specifier for [Lovense Connect
 /// Service](crate::server::device::communication_manager::lovense_connect_service) devices
 ///
@@ -250,6 +312,29 @@ impl PartialEq for XInputSpecifier {
   }
 }
 
+/// Specifier for Linux evdev gamepads with rumble support
+///
+/// Like [XInputSpecifier], this has no attributes, as the evdev device communication manager only
+/// picks up devices that can rumble, and all of them are driven the same way.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct EvdevSpecifier {
+  // Needed for deserialziation but unused.
+  #[allow(dead_code)]
+  exists: bool,
+}
+
+impl Default for EvdevSpecifier {
+  fn default() -> Self {
+    Self { exists: true }
+  }
+}
+
+impl PartialEq for EvdevSpecifier {
+  fn eq(&self, _other: &Self) -> bool {
+    true
+  }
+}
+
 #[derive(
   Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Getters, Setters, MutGetters,
 )]
@@ -258,13 +343,36 @@ pub struct VIDPIDPair {
   product_id: u16,
 }
 
-/// Specifier for HID (USB, Bluetooth) devices
+/// Location of a USB endpoint, used to map [Endpoint]s to bulk or interrupt endpoints on raw USB
+/// devices.
+#[derive(
+  Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, CopyGetters, Setters, MutGetters,
+)]
+#[getset(get_copy = "pub", set = "pub", get_mut = "pub(crate)")]
+pub struct USBEndpointAddress {
+  /// Interface number the endpoint belongs to, which will be claimed on connection.
+  interface: u8,
+  /// Endpoint address, including the direction bit (0x80 for IN endpoints).
+  address: u8,
+}
+
+impl USBEndpointAddress {
+  pub fn new(interface: u8, address: u8) -> Self {
+    Self { interface, address }
+  }
+}
+
+/// Specifier for HID (USB, Bluetooth) and raw USB devices
 ///
-/// Handles devices managed by the operating system's HID manager.
+/// Handles devices managed by the operating system's HID manager, as well as USB devices we talk to
+/// directly via bulk/interrupt transfers. Endpoint addresses are only used by the latter, as the HID
+/// manager handles endpoints itself.
 #[derive(Serialize, Deserialize, Debug, Eq, Clone, Getters, Setters, MutGetters)]
 #[getset(get = "pub", set = "pub", get_mut = "pub(crate)")]
 pub struct VIDPIDSpecifier {
   pairs: Vec<VIDPIDPair>,
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  endpoints: HashMap<Endpoint, USBEndpointAddress>,
 }
 
 impl VIDPIDSpecifier {
@@ -274,6 +382,7 @@ impl VIDPIDSpecifier {
         vendor_id,
         product_id,
       }],
+      endpoints: HashMap::new(),
     }
   }
 }
@@ -291,9 +400,23 @@ impl PartialEq for VIDPIDSpecifier {
   }
 }
 
+/// Flow control used on a serial port.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SerialFlowControl {
+  #[default]
+  None,
+  /// XON/XOFF
+  Software,
+  /// RTS/CTS
+  Hardware,
+}
+
 /// Specifier for Serial devices
 ///
-/// Handles serial port device identification (via port names) and configuration.
+/// Handles serial port device identification and configuration. Ports can be identified either by
+/// name, or by the USB vendor/product id and serial number of the adapter they live on, so that
+/// devices can be found no matter which port name the OS hands out to them.
 #[derive(Serialize, Deserialize, Debug, Clone, Default, Getters, Setters, MutGetters)]
 #[getset(get = "pub", set = "pub", get_mut = "pub(crate)")]
 pub struct SerialSpecifier {
@@ -301,7 +424,26 @@ pub struct SerialSpecifier {
   data_bits: u8,
   stop_bits: u8,
   parity: char,
+  #[serde(default)]
   port: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  vendor_id: Option<u16>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  product_id: Option<u16>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  serial_number: Option<String>,
+  #[serde(default)]
+  flow_control: SerialFlowControl,
+  /// Level to set DTR to when opening the port. Left at the OS default if unset.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  dtr: Option<bool>,
+  /// Level to set RTS to when opening the port. Left at the OS default if unset.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  rts: Option<bool>,
+  /// Time to wait after opening the port before talking to the device, for boards that reset when
+  /// the port is opened.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  open_delay_ms: Option<u32>,
 }
 
 impl SerialSpecifier {
@@ -312,25 +454,61 @@ impl SerialSpecifier {
       data_bits,
       stop_bits,
       parity,
+      ..Default::default()
     }
   }
 
-  /// Given a serial port name (the only identifier we have for this type of device), create a
-  /// specifier instance.
+  /// Given a serial port name, create a specifier instance.
   pub fn new_from_name(port: &str) -> Self {
     Self {
       port: port.to_owned(),
       ..Default::default()
     }
   }
+
+  /// Given a serial port name and the info of the USB adapter the port lives on, create a specifier
+  /// instance.
+  pub fn new_from_usb_port(
+    port: &str,
+    vendor_id: u16,
+    product_id: u16,
+    serial_number: &Option<String>,
+  ) -> Self {
+    Self {
+      port: port.to_owned(),
+      vendor_id: Some(vendor_id),
+      product_id: Some(product_id),
+      serial_number: serial_number.clone(),
+      ..Default::default()
+    }
+  }
+
+  /// USB info matches if every field set on both specifiers is equal, and at least one field was
+  /// set on both.
+  fn usb_info_matches(&self, other: &Self) -> bool {
+    fn field_matches<T: PartialEq>(a: &Option<T>, b: &Option<T>, compared: &mut bool) -> bool {
+      match (a, b) {
+        (Some(a), Some(b)) => {
+          *compared = true;
+          a == b
+        }
+        _ => true,
+      }
+    }
+    let mut compared = false;
+    field_matches(&self.vendor_id, &other.vendor_id, &mut compared)
+      && field_matches(&self.product_id, &other.product_id, &mut compared)
+      && field_matches(&self.serial_number, &other.serial_number, &mut compared)
+      && compared
+  }
 }
 
 impl PartialEq for SerialSpecifier {
   fn eq(&self, other: &Self) -> bool {
-    if *self.port == *other.port {
+    if !self.port.is_empty() && *self.port == *other.port {
       return true;
     }
-    false
+    self.usb_info_matches(other)
   }
 }
 
@@ -361,6 +539,95 @@ impl WebsocketSpecifier {
   }
 }
 
+/// Socket type used to talk to a network device.
+#[derive(
+  Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Display, EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum NetworkTransport {
+  #[default]
+  Tcp,
+  Udp,
+}
+
+/// Specifier for devices we talk to over raw TCP or UDP sockets
+///
+/// There's no discovery for these, so devices are identified purely by where they live on the
+/// network. Specifiers display (and parse) as `transport://host:port`, e.g. `tcp://10.0.0.5:8000`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Getters, CopyGetters)]
+pub struct NetworkSpecifier {
+  #[getset(get = "pub")]
+  host: String,
+  #[getset(get_copy = "pub")]
+  port: u16,
+  #[serde(default)]
+  #[getset(get_copy = "pub")]
+  transport: NetworkTransport,
+}
+
+impl NetworkSpecifier {
+  pub fn new(host: &str, port: u16, transport: NetworkTransport) -> Self {
+    Self {
+      host: host.to_owned(),
+      port,
+      transport,
+    }
+  }
+}
+
+impl fmt::Display for NetworkSpecifier {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    // IPv6 literals need brackets to be told apart from the port.
+    if self.host.contains(':') {
+      write!(f, "{}://[{}]:{}", self.transport, self.host, self.port)
+    } else {
+      write!(f, "{}://{}:{}", self.transport, self.host, self.port)
+    }
+  }
+}
+
+impl FromStr for NetworkSpecifier {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (transport, address) = s
+      .split_once("://")
+      .ok_or_else(|| format!("{s} is not in transport://host:port form"))?;
+    let transport = NetworkTransport::from_str(transport)
+      .map_err(|_| format!("{transport} is not a valid network transport"))?;
+    let (host, port) = address
+      .rsplit_once(':')
+      .ok_or_else(|| format!("{s} does not include a port"))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+      return Err(format!("{s} does not include a host"));
+    }
+    let port = port
+      .parse()
+      .map_err(|_| format!("{port} is not a valid port"))?;
+    Ok(Self::new(host, port, transport))
+  }
+}
+
+/// Specifier for devices we talk to through an MQTT broker
+///
+/// Devices announce themselves on the broker with the identifier of the protocol config they
+/// expect, which is all we can match on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct MqttSpecifier {
+  identifier: String,
+}
+
+impl MqttSpecifier {
+  pub fn new(identifier: &str) -> Self {
+    Self {
+      identifier: identifier.to_owned(),
+    }
+  }
+}
+
 /// Enum that covers all types of communication specifiers.
 ///
 /// Allows generalization of specifiers to handle checking for equality. Used for testing newly discovered
@@ -369,6 +636,8 @@ impl WebsocketSpecifier {
 pub enum ProtocolCommunicationSpecifier {
   #[serde(rename = "btle")]
   BluetoothLE(BluetoothLESpecifier),
+  #[serde(rename = "btle_advertisement")]
+  BluetoothLEAdvertisement(BluetoothLEAdvertisementSpecifier),
   #[serde(rename = "hid")]
   HID(VIDPIDSpecifier),
   #[serde(rename = "usb")]
@@ -377,10 +646,16 @@ pub enum ProtocolCommunicationSpecifier {
   Serial(SerialSpecifier),
   #[serde(rename = "xinput")]
   XInput(XInputSpecifier),
+  #[serde(rename = "evdev")]
+  Evdev(EvdevSpecifier),
   #[serde(rename = "lovense_connect_service")]
   LovenseConnectService(LovenseConnectServiceSpecifier),
   #[serde(rename = "websocket")]
   Websocket(WebsocketSpecifier),
+  #[serde(rename = "network")]
+  Network(NetworkSpecifier),
+  #[serde(rename = "mqtt")]
+  Mqtt(MqttSpecifier),
 }
 
 impl PartialEq for ProtocolCommunicationSpecifier {
@@ -390,9 +665,15 @@ impl PartialEq for ProtocolCommunicationSpecifier {
       (USB(self_spec), USB(other_spec)) => self_spec == other_spec,
       (Serial(self_spec), Serial(other_spec)) => self_spec == other_spec,
       (BluetoothLE(self_spec), BluetoothLE(other_spec)) => self_spec == other_spec,
+      (BluetoothLEAdvertisement(self_spec), BluetoothLEAdvertisement(other_spec)) => {
+        self_spec == other_spec
+      }
       (HID(self_spec), HID(other_spec)) => self_spec == other_spec,
       (XInput(self_spec), XInput(other_spec)) => self_spec == other_spec,
+      (Evdev(self_spec), Evdev(other_spec)) => self_spec == other_spec,
       (Websocket(self_spec), Websocket(other_spec)) => self_spec == other_spec,
+      (Network(self_spec), Network(other_spec)) => self_spec == other_spec,
+      (Mqtt(self_spec), Mqtt(other_spec)) => self_spec == other_spec,
       (LovenseConnectService(self_spec), LovenseConnectService(other_spec)) => {
         self_spec == other_spec
       }
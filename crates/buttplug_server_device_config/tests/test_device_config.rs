@@ -5,8 +5,44 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
-use buttplug_server_device_config::{UserDeviceIdentifier, load_protocol_configs};
+use buttplug_core::message::OutputType;
+use buttplug_server_device_config::{
+  AutoReconnectPolicy,
+  BluetoothLEAdvertisementSpecifier,
+  BluetoothLESpecifier,
+  ButtplugDeviceConfigError,
+  DeviceMigrationResult,
+  DevicePreset,
+  DeviceSafetyLimits,
+  Endpoint,
+  FeatureMigrationResult,
+  NetworkSpecifier,
+  NetworkTransport,
+  OutputSafetyLimits,
+  PresetOutput,
+  ProtocolCommunicationSpecifier,
+  ResponseCurve,
+  SerialFlowControl,
+  SerialSpecifier,
+  ServerDeviceDefinitionBuilder,
+  USBEndpointAddress,
+  UserConfigValidationError,
+  UserDeviceIdentifier,
+  VIDPIDSpecifier,
+  load_protocol_configs,
+  migrate_user_config,
+  save_user_config,
+  validate_user_config,
+};
+use std::collections::HashMap;
 use test_case::test_case;
+use uuid::Uuid;
+
+fn read_test_config(test_file: &str) -> String {
+  str::from_utf8(&std::fs::read(format!("tests/test_configs/{}", test_file)).unwrap())
+    .unwrap()
+    .to_owned()
+}
 
 #[test_case("version_only.json" ; "Version Only")]
 #[test_case("base_aneros_protocol.json" ; "Aneros Protocol")]
@@ -69,3 +105,602 @@ fn test_tcode_device_creation() {
     .unwrap();
   assert_eq!(device.name(), "TCode v0.3 (Single Linear Axis)");
 }
+
+#[test]
+fn test_validate_valid_user_config() {
+  validate_user_config(&read_test_config("user_aneros_device.json")).unwrap();
+}
+
+#[test_case("user_aneros_device_invalid_range.json", |e| matches!(e, ButtplugDeviceConfigError::InvalidUserRange) ; "Range outside base range")]
+#[test_case("user_aneros_device_invalid_base_id.json", |e| matches!(e, ButtplugDeviceConfigError::BaseIdNotFound(_)) ; "Unknown base id")]
+#[test_case("user_aneros_device_feature_mismatch.json", |e| matches!(e, ButtplugDeviceConfigError::UserFeatureMismatch) ; "Feature count mismatch")]
+#[test_case("user_aneros_device_invalid_response_curve.json", |e| matches!(e, ButtplugDeviceConfigError::InvalidResponseCurve(_)) ; "Response curve points out of order")]
+#[test_case("user_aneros_device_invalid_emergency_stop_button.json", |e| matches!(e, ButtplugDeviceConfigError::InvalidEmergencyStopButton(0)) ; "Emergency stop button is not a button input")]
+#[test_case("user_aneros_device_invalid_preset.json", |e| matches!(e, ButtplugDeviceConfigError::InvalidPreset(_)) ; "Preset value outside user range")]
+fn test_validate_invalid_user_device(
+  test_file: &str,
+  expected_error: fn(&ButtplugDeviceConfigError) -> bool,
+) {
+  let errors = validate_user_config(&read_test_config(test_file)).unwrap_err();
+  assert_eq!(errors.len(), 1);
+  match &errors[0] {
+    UserConfigValidationError::Device {
+      index,
+      identifier,
+      error,
+    } => {
+      assert_eq!(*index, 0);
+      assert_eq!(identifier.address(), "aneros-test");
+      assert!(expected_error(error), "Unexpected error {error:?}");
+    }
+    e => panic!("Unexpected validation error {e:?}"),
+  }
+}
+
+#[test]
+fn test_validate_user_config_schema_error() {
+  let errors =
+    validate_user_config(&read_test_config("user_aneros_device_invalid_schema.json")).unwrap_err();
+  assert!(errors.iter().all(|e| matches!(
+    e,
+    UserConfigValidationError::Schema { path, .. } if path.ends_with("/user_config/index")
+  )));
+}
+
+#[test]
+fn test_invalid_user_device_skipped_on_load() {
+  let dcm = load_protocol_configs(
+    &None,
+    &Some(read_test_config("user_aneros_device_invalid_range.json")),
+    false,
+  )
+  .unwrap()
+  .finish()
+  .unwrap();
+  assert!(dcm.user_device_definitions().is_empty());
+}
+
+#[test]
+fn test_add_user_device_definition_validation() {
+  let dcm = load_protocol_configs(&None, &None, false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let identifier = UserDeviceIdentifier::new("aneros-test", "aneros", &None);
+  let definition = ServerDeviceDefinitionBuilder::new("Aneros Vivi", &Uuid::new_v4()).finish();
+  assert!(matches!(
+    dcm.add_user_device_definition(&identifier, &definition),
+    Err(ButtplugDeviceConfigError::MissingBaseId)
+  ));
+
+  let valid_definition = dcm.device_definition(&identifier).unwrap();
+  dcm.remove_user_device_definition(&identifier);
+  dcm
+    .add_user_device_definition(&identifier, &valid_definition)
+    .unwrap();
+}
+
+#[test]
+fn test_user_protocol_definition() {
+  let user_config = read_test_config("user_protocol_definition.json");
+  validate_user_config(&user_config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+
+  // User defaults replace the base defaults for the protocol.
+  let device = dcm
+    .device_definition(&UserDeviceIdentifier::new("vivi-test", "aneros", &None))
+    .unwrap();
+  assert_eq!(device.name(), "Rebadged Vivi");
+  assert_eq!(device.features().len(), 1);
+
+  // User configurations are merged on top of user defaults.
+  let device = dcm
+    .device_definition(&UserDeviceIdentifier::new(
+      "rebadge-test",
+      "aneros",
+      &Some("Rebadged Vivi Mini".to_owned()),
+    ))
+    .unwrap();
+  assert_eq!(device.name(), "Rebadged Vivi Mini");
+  assert_eq!(device.features().len(), 1);
+  assert_eq!(
+    device.features()[&0]
+      .output()
+      .as_ref()
+      .unwrap()
+      .vibrate()
+      .as_ref()
+      .unwrap()
+      .value()
+      .user(),
+    &Some(0..=50)
+  );
+}
+
+#[test]
+fn test_user_usb_endpoints() {
+  let user_config = read_test_config("user_usb_protocol_definition.json");
+  validate_user_config(&user_config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let specifiers = dcm.user_communication_specifiers().get("aneros").unwrap();
+  let usb = specifiers
+    .iter()
+    .find_map(|specifier| match specifier {
+      ProtocolCommunicationSpecifier::USB(usb) => Some(usb.clone()),
+      _ => None,
+    })
+    .unwrap();
+  // Endpoint mappings don't take part in matching, only vendor and product IDs do.
+  assert_eq!(usb, VIDPIDSpecifier::new(0x1234, 0x5678));
+  assert_eq!(
+    usb.endpoints().get(&Endpoint::Tx),
+    Some(&USBEndpointAddress::new(0, 0x01))
+  );
+  assert_eq!(
+    usb.endpoints().get(&Endpoint::Rx),
+    Some(&USBEndpointAddress::new(0, 0x81))
+  );
+}
+
+#[test]
+fn test_user_network_specifiers() {
+  let user_config = read_test_config("user_network_protocol_definition.json");
+  validate_user_config(&user_config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let specifiers = dcm
+    .user_communication_specifiers()
+    .get("tcode-v03")
+    .unwrap();
+  let network: Vec<_> = specifiers
+    .iter()
+    .filter_map(|specifier| match specifier {
+      ProtocolCommunicationSpecifier::Network(network) => Some(network.clone()),
+      _ => None,
+    })
+    .collect();
+  // Transport defaults to TCP if it's left out.
+  assert_eq!(
+    network,
+    vec![
+      NetworkSpecifier::new("192.168.1.50", 8000, NetworkTransport::Tcp),
+      NetworkSpecifier::new("osr.local", 8000, NetworkTransport::Tcp),
+    ]
+  );
+}
+
+#[test_case("ADV-Vibe", HashMap::new(), true ; "Wildcard Name")]
+#[test_case("", HashMap::from([(0xfff0, vec![1, 2, 3])]), true ; "Manufacturer Data")]
+#[test_case("Other", HashMap::from([(0x0001, vec![1, 2, 3])]), false ; "No Match")]
+fn test_user_btle_advertisement_specifiers(
+  name: &str,
+  manufacturer_data: HashMap<u16, Vec<u8>>,
+  matches: bool,
+) {
+  let user_config = read_test_config("user_btle_advertisement_protocol_definition.json");
+  validate_user_config(&user_config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let specifiers = dcm
+    .user_communication_specifiers()
+    .get("byte-template")
+    .unwrap();
+  let device = ProtocolCommunicationSpecifier::BluetoothLEAdvertisement(
+    BluetoothLEAdvertisementSpecifier::new_from_device(name, &manufacturer_data),
+  );
+  assert_eq!(specifiers.contains(&device), matches);
+  // Devices found through connections never match advertisement-only protocols.
+  let connected_device = ProtocolCommunicationSpecifier::BluetoothLE(
+    BluetoothLESpecifier::new_from_device(name, &manufacturer_data, &[]),
+  );
+  assert!(!specifiers.contains(&connected_device));
+}
+
+#[test_case("tcp://192.168.1.50:8000", NetworkSpecifier::new("192.168.1.50", 8000, NetworkTransport::Tcp) ; "TCP")]
+#[test_case("udp://osr.local:8000", NetworkSpecifier::new("osr.local", 8000, NetworkTransport::Udp) ; "UDP")]
+#[test_case("udp://[fe80::1]:8000", NetworkSpecifier::new("fe80::1", 8000, NetworkTransport::Udp) ; "IPv6")]
+fn test_network_specifier_address_round_trip(address: &str, specifier: NetworkSpecifier) {
+  assert_eq!(address.parse::<NetworkSpecifier>(), Ok(specifier.clone()));
+  assert_eq!(specifier.to_string(), address);
+}
+
+#[test_case("192.168.1.50:8000" ; "No Transport")]
+#[test_case("sctp://192.168.1.50:8000" ; "Unknown Transport")]
+#[test_case("tcp://192.168.1.50" ; "No Port")]
+#[test_case("tcp://:8000" ; "No Host")]
+fn test_network_specifier_invalid_address(address: &str) {
+  assert!(address.parse::<NetworkSpecifier>().is_err());
+}
+
+#[test]
+fn test_user_serial_specifiers() {
+  let user_config = read_test_config("user_serial_protocol_definition.json");
+  validate_user_config(&user_config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let specifiers = dcm
+    .user_communication_specifiers()
+    .get("tcode-v03")
+    .unwrap();
+  let Some(ProtocolCommunicationSpecifier::Serial(serial)) = specifiers.first() else {
+    panic!("Should have a serial specifier");
+  };
+  assert_eq!(*serial.baud_rate(), 115200);
+  assert_eq!(*serial.data_bits(), 7);
+  assert_eq!(*serial.parity(), 'E');
+  assert_eq!(*serial.stop_bits(), 2);
+  assert_eq!(*serial.flow_control(), SerialFlowControl::Hardware);
+  assert_eq!(*serial.dtr(), Some(false));
+  assert_eq!(*serial.rts(), Some(true));
+  assert_eq!(*serial.open_delay_ms(), Some(2000));
+  // No port name was given, so this should match the adapter wherever it shows up.
+  assert!(serial.port().is_empty());
+  assert_eq!(
+    *serial,
+    SerialSpecifier::new_from_usb_port(
+      "/dev/ttyUSB3",
+      0x1a86,
+      0x7523,
+      &Some("OSR2-0001".to_owned())
+    )
+  );
+
+  let saved_config = save_user_config(&dcm).unwrap();
+  validate_user_config(&saved_config).unwrap();
+}
+
+#[test_case(SerialSpecifier::new_from_name("COM3"), true ; "Port Name")]
+#[test_case(SerialSpecifier::new_from_usb_port("", 0x1a86, 0x7523, &None), true ; "VID/PID")]
+#[test_case(SerialSpecifier::new_from_usb_port("", 0x1a86, 0x7524, &None), false ; "Wrong PID")]
+#[test_case(SerialSpecifier::new_from_usb_port("", 0x1a86, 0x7523, &Some("A".to_owned())), true ; "Serial Number")]
+#[test_case(SerialSpecifier::new_from_usb_port("", 0x1a86, 0x7523, &Some("B".to_owned())), false ; "Wrong Serial Number")]
+#[test_case(SerialSpecifier::new_from_name(""), false ; "Empty")]
+fn test_serial_specifier_matching(config_specifier: SerialSpecifier, matches: bool) {
+  let found_specifier =
+    SerialSpecifier::new_from_usb_port("COM3", 0x1a86, 0x7523, &Some("A".to_owned()));
+  assert_eq!(config_specifier == found_specifier, matches);
+  assert_eq!(found_specifier == config_specifier, matches);
+}
+
+#[test]
+fn test_user_protocol_definition_save_round_trip() {
+  let dcm = load_protocol_configs(
+    &None,
+    &Some(read_test_config("user_protocol_definition.json")),
+    false,
+  )
+  .unwrap()
+  .finish()
+  .unwrap();
+  let saved_config = save_user_config(&dcm).unwrap();
+  validate_user_config(&saved_config).unwrap();
+
+  let reloaded_dcm = load_protocol_configs(&None, &Some(saved_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let identifier = UserDeviceIdentifier::new(
+    "rebadge-test",
+    "aneros",
+    &Some("Rebadged Vivi Mini".to_owned()),
+  );
+  assert_eq!(
+    reloaded_dcm.user_device_definitions().len(),
+    dcm.user_device_definitions().len()
+  );
+  assert_eq!(
+    reloaded_dcm.device_definition(&identifier).unwrap().name(),
+    "Rebadged Vivi Mini"
+  );
+  assert_eq!(
+    reloaded_dcm
+      .device_definition(&UserDeviceIdentifier::new("vivi-test", "aneros", &None))
+      .unwrap()
+      .name(),
+    "Rebadged Vivi"
+  );
+}
+
+#[test]
+fn test_migrate_user_config_no_changes() {
+  let migration =
+    migrate_user_config(&read_test_config("user_aneros_device.json"), &None, &None).unwrap();
+  assert!(!migration.has_changes());
+  assert_eq!(migration.devices().len(), 1);
+  validate_user_config(migration.user_config()).unwrap();
+}
+
+#[test]
+fn test_migrate_user_config_from_older_major_version() {
+  let user_config =
+    read_test_config("user_aneros_device.json").replace("\"major\": 4", "\"major\": 3");
+  assert!(load_protocol_configs(&None, &Some(user_config.clone()), false).is_err());
+  let migration = migrate_user_config(&user_config, &None, &None).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(migration.user_config().clone()), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  assert_eq!(dcm.user_device_definitions().len(), 1);
+}
+
+#[test]
+fn test_migrate_user_config_with_old_base() {
+  let new_base = read_test_config("base_aneros_protocol_updated.json");
+  let migration = migrate_user_config(
+    &read_test_config("user_aneros_device.json"),
+    &Some(read_test_config("base_aneros_protocol.json")),
+    &Some(new_base.clone()),
+  )
+  .unwrap();
+  assert!(migration.has_changes());
+  let device = &migration.devices()[0];
+  assert!(matches!(
+    device.result(),
+    DeviceMigrationResult::Migrated { .. }
+  ));
+  let results: Vec<_> = device
+    .features()
+    .iter()
+    .map(|x| (x.index(), x.result().clone()))
+    .collect();
+  // User range of 0-64 no longer fits in the new 0-50 base range, so it is reset.
+  assert!(matches!(
+    results[0],
+    (Some(0), FeatureMigrationResult::Conflicted(_))
+  ));
+  assert!(matches!(
+    results[1],
+    (Some(1), FeatureMigrationResult::Migrated { .. })
+  ));
+  assert_eq!(results[2], (Some(2), FeatureMigrationResult::Added));
+
+  let dcm = load_protocol_configs(
+    &Some(new_base),
+    &Some(migration.user_config().clone()),
+    false,
+  )
+  .unwrap()
+  .finish()
+  .unwrap();
+  let device = dcm
+    .device_definition(&UserDeviceIdentifier::new("aneros-test", "aneros", &None))
+    .unwrap();
+  assert_eq!(device.features().len(), 3);
+  assert_eq!(dcm.user_device_definitions().len(), 1);
+}
+
+#[test]
+fn test_migrate_user_config_without_old_base() {
+  let migration = migrate_user_config(
+    &read_test_config("user_aneros_device.json"),
+    &None,
+    &Some(read_test_config("base_aneros_protocol_updated.json")),
+  )
+  .unwrap();
+  let device = &migration.devices()[0];
+  let results: Vec<_> = device.features().iter().map(|x| x.result()).collect();
+  // Without the old base, a customized vibrator could map to either new vibrator.
+  assert!(matches!(results[0], FeatureMigrationResult::Conflicted(_)));
+  assert!(matches!(results[1], FeatureMigrationResult::Dropped(_)));
+  assert_eq!(results[2..], [&FeatureMigrationResult::Added; 3]);
+}
+
+#[test]
+fn test_user_feature_response_curve() {
+  let dcm = load_protocol_configs(
+    &None,
+    &Some(read_test_config("user_aneros_device_response_curve.json")),
+    false,
+  )
+  .unwrap()
+  .finish()
+  .unwrap();
+  let identifier = UserDeviceIdentifier::new("aneros-test", "aneros", &None);
+  let device = dcm.device_definition(&identifier).unwrap();
+  let output = device.features()[&0].output().as_ref().unwrap();
+  assert_eq!(
+    output.response_curve(),
+    Some(&ResponseCurve::Deadzone(0.25))
+  );
+  // User range is 0-64, so 0.5 is curved to 0.625, or step 40.
+  assert_eq!(
+    output
+      .calculate_from_float(OutputType::Vibrate, 0.5)
+      .unwrap(),
+    40
+  );
+  // Steps are curved the same way, with the lowest step landing just above the deadzone.
+  assert_eq!(
+    output.calculate_from_value(OutputType::Vibrate, 1).unwrap(),
+    17
+  );
+  assert_eq!(
+    output.calculate_from_value(OutputType::Vibrate, 0).unwrap(),
+    0
+  );
+  // Features without a curve are untouched.
+  let output = device.features()[&1].output().as_ref().unwrap();
+  assert_eq!(output.response_curve(), None);
+  assert_eq!(
+    output
+      .calculate_from_float(OutputType::Vibrate, 0.5)
+      .unwrap(),
+    64
+  );
+
+  let saved_config = save_user_config(&dcm).unwrap();
+  validate_user_config(&saved_config).unwrap();
+  let reloaded_dcm = load_protocol_configs(&None, &Some(saved_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  assert_eq!(
+    reloaded_dcm
+      .device_definition(&identifier)
+      .unwrap()
+      .features()[&0]
+      .output()
+      .as_ref()
+      .unwrap()
+      .response_curve(),
+    Some(&ResponseCurve::Deadzone(0.25))
+  );
+}
+
+#[test]
+fn test_user_device_safety_limits() {
+  let config = read_test_config("user_aneros_device_safety_limits.json");
+  validate_user_config(&config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let identifier = UserDeviceIdentifier::new("aneros-test", "aneros", &None);
+  let limits = dcm
+    .device_definition(&identifier)
+    .unwrap()
+    .safety_limits()
+    .clone()
+    .unwrap();
+  assert_eq!(
+    limits.limits(OutputType::Vibrate),
+    Some(&OutputSafetyLimits::new(Some(2.0), Some(30000), None))
+  );
+  assert_eq!(limits.limits(OutputType::Rotate), None);
+
+  let saved_config = save_user_config(&dcm).unwrap();
+  validate_user_config(&saved_config).unwrap();
+  let reloaded_dcm = load_protocol_configs(&None, &Some(saved_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  assert_eq!(
+    reloaded_dcm
+      .device_definition(&identifier)
+      .unwrap()
+      .safety_limits(),
+    &Some(limits)
+  );
+}
+
+#[test]
+fn test_user_device_auto_reconnect() {
+  let config = read_test_config("user_aneros_device_auto_reconnect.json");
+  validate_user_config(&config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let identifier = UserDeviceIdentifier::new("aneros-test", "aneros", &None);
+  let policy = dcm.device_definition(&identifier).unwrap().auto_reconnect();
+  // Outputs are restored unless the config says otherwise.
+  assert_eq!(policy, Some(AutoReconnectPolicy::new(Some(60000), true)));
+
+  let saved_config = save_user_config(&dcm).unwrap();
+  validate_user_config(&saved_config).unwrap();
+  let reloaded_dcm = load_protocol_configs(&None, &Some(saved_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  assert_eq!(
+    reloaded_dcm
+      .device_definition(&identifier)
+      .unwrap()
+      .auto_reconnect(),
+    policy
+  );
+}
+
+#[test]
+fn test_user_device_presets() {
+  let config = read_test_config("user_aneros_device_presets.json");
+  validate_user_config(&config).unwrap();
+  let dcm = load_protocol_configs(&None, &Some(config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  let identifier = UserDeviceIdentifier::new("aneros-test", "aneros", &None);
+  let definition = dcm.device_definition(&identifier).unwrap();
+  assert_eq!(definition.connect_preset(), &Some("gentle".to_owned()));
+  assert_eq!(
+    definition.presets().get("gentle"),
+    Some(&DevicePreset::new(&[PresetOutput::new(
+      0,
+      OutputType::Vibrate,
+      16
+    )]))
+  );
+  assert_eq!(definition.presets()["full"].outputs().len(), 2);
+
+  // Presets must name outputs the device has, and connect presets must exist.
+  let mut builder = ServerDeviceDefinitionBuilder::from_user(&definition);
+  builder.add_preset(
+    "rotate",
+    &DevicePreset::new(&[PresetOutput::new(0, OutputType::Rotate, 1)]),
+  );
+  assert!(matches!(
+    dcm.add_user_device_definition(&identifier, &builder.finish()),
+    Err(ButtplugDeviceConfigError::InvalidPreset(_))
+  ));
+  let mut builder = ServerDeviceDefinitionBuilder::from_user(&definition);
+  builder.connect_preset(&Some("missing".to_owned()));
+  assert!(matches!(
+    dcm.add_user_device_definition(&identifier, &builder.finish()),
+    Err(ButtplugDeviceConfigError::InvalidPreset(_))
+  ));
+
+  let saved_config = save_user_config(&dcm).unwrap();
+  validate_user_config(&saved_config).unwrap();
+  let reloaded_dcm = load_protocol_configs(&None, &Some(saved_config), false)
+    .unwrap()
+    .finish()
+    .unwrap();
+  assert_eq!(
+    reloaded_dcm
+      .device_definition(&identifier)
+      .unwrap()
+      .presets(),
+    definition.presets()
+  );
+}
+
+#[test]
+fn test_safety_limits_validation() {
+  let mut limits = DeviceSafetyLimits::default();
+  limits.set_limits(
+    OutputType::Temperature,
+    Some(OutputSafetyLimits::new(None, None, Some(0.5))),
+  );
+  assert!(limits.validate().is_ok());
+  // Level caps are only allowed on temperature and constrict outputs.
+  limits.set_limits(
+    OutputType::Vibrate,
+    Some(OutputSafetyLimits::new(None, None, Some(0.5))),
+  );
+  assert!(matches!(
+    limits.validate(),
+    Err(ButtplugDeviceConfigError::InvalidSafetyLimits(_))
+  ));
+  limits.set_limits(
+    OutputType::Vibrate,
+    Some(OutputSafetyLimits::new(Some(0.0), None, None)),
+  );
+  assert!(limits.validate().is_err());
+  limits.set_limits(
+    OutputType::Vibrate,
+    Some(OutputSafetyLimits::new(None, Some(0), None)),
+  );
+  assert!(limits.validate().is_err());
+}
@@ -143,6 +143,17 @@ impl BtleplugAdapterTask {
     }
   }
 
+  /// Sets whether we have an adapter, letting the device manager know if that changed.
+  async fn set_adapter_connected(&self, connected: bool) {
+    if self.adapter_connected.swap(connected, Ordering::Relaxed) != connected {
+      // If the device manager is gone, we'll find out soon enough elsewhere.
+      let _ = self
+        .event_sender
+        .send(HardwareCommunicationManagerEvent::StatusChanged)
+        .await;
+    }
+  }
+
   pub async fn run(&mut self) {
     let manager = match Manager::new().await {
       Ok(mgr) => mgr,
@@ -167,6 +178,7 @@ impl BtleplugAdapterTask {
         Ok(adapters) => {
           if let Some(adapter) = adapters.into_iter().next() {
             info!("Bluetooth LE adapter found.");
+            self.set_adapter_connected(true).await;
             // Bluetooth dongle identification for Windows
             #[cfg(target_os = "windows")]
             {
@@ -205,7 +217,7 @@ impl BtleplugAdapterTask {
             adapter
           } else {
             if adapter_found {
-              self.adapter_connected.store(false, Ordering::Relaxed);
+              self.set_adapter_connected(false).await;
               warn!(
                 "Bluetooth LE adapter not found, will not be using bluetooth scanning until found. Buttplug will continue polling for the adapter, but no more warning messages will be posted."
               );
@@ -215,7 +227,7 @@ impl BtleplugAdapterTask {
         }
         Err(e) => {
           if adapter_found {
-            self.adapter_connected.store(false, Ordering::Relaxed);
+            self.set_adapter_connected(false).await;
             error!("Error retreiving BTLE adapters: {:?}", e);
           }
           continue;
@@ -0,0 +1,197 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! [BleAdvertisementAdapter] on top of btleplug.
+//!
+//! btleplug only supports the central role, so this adapter can hear advertisements (enough to
+//! find devices and read state they advertise, like battery level), but can't send them. Devices
+//! that are commanded through advertisements will be found, but output commands to them fail, and
+//! broadcast-only devices aren't added at all.
+
+use async_trait::async_trait;
+use btleplug::{
+  api::{Central, CentralEvent, Manager as _, Peripheral, ScanFilter},
+  platform::{Adapter, Manager},
+};
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{
+  advertisement::{BleAdvertisement, BleAdvertisementAdapter},
+  communication::HardwareSpecificError,
+};
+use futures::{Stream, StreamExt};
+use std::{
+  pin::Pin,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+};
+use tokio::{
+  select,
+  sync::{Mutex, broadcast},
+};
+use tokio_util::sync::CancellationToken;
+
+fn btleplug_error(err: btleplug::Error) -> ButtplugDeviceError {
+  ButtplugDeviceError::DeviceSpecificError(
+    HardwareSpecificError::HardwareSpecificError("btleplug".to_owned(), format!("{err:?}"))
+      .to_string(),
+  )
+}
+
+async fn forward_advertisements(
+  adapter: Adapter,
+  mut events: Pin<Box<dyn Stream<Item = CentralEvent> + Send>>,
+  sender: broadcast::Sender<BleAdvertisement>,
+  cancellation_token: CancellationToken,
+) {
+  loop {
+    let event = select! {
+      _ = cancellation_token.cancelled() => return,
+      event = events.next() => match event {
+        Some(event) => event,
+        None => {
+          error!("btleplug event stream closed, no longer listening for advertisements.");
+          return;
+        }
+      }
+    };
+    let CentralEvent::ManufacturerDataAdvertisement {
+      id,
+      manufacturer_data,
+    } = event
+    else {
+      continue;
+    };
+    // The name isn't part of the event, but btleplug keeps the last one it saw for the device.
+    let name = match adapter.peripheral(&id).await {
+      Ok(peripheral) => peripheral
+        .properties()
+        .await
+        .ok()
+        .flatten()
+        .and_then(|properties| properties.local_name)
+        .unwrap_or_default(),
+      Err(_) => String::new(),
+    };
+    // If there's no one listening right now, there's nothing to do with the advertisement.
+    let _ = sender.send(BleAdvertisement::new(
+      &format!("{id:?}"),
+      &name,
+      &manufacturer_data,
+    ));
+  }
+}
+
+pub struct BtleplugAdvertisementAdapter {
+  adapter: Mutex<Option<Adapter>>,
+  available: Arc<AtomicBool>,
+  sender: broadcast::Sender<BleAdvertisement>,
+  cancellation_token: CancellationToken,
+}
+
+impl Default for BtleplugAdvertisementAdapter {
+  fn default() -> Self {
+    let (sender, _) = broadcast::channel(256);
+    Self {
+      adapter: Mutex::new(None),
+      // Assume we'll find an adapter until we've looked for one.
+      available: Arc::new(AtomicBool::new(true)),
+      sender,
+      cancellation_token: CancellationToken::new(),
+    }
+  }
+}
+
+impl BtleplugAdvertisementAdapter {
+  /// Returns the adapter, finding it and starting to forward its events the first time through.
+  async fn adapter(&self) -> Result<Adapter, ButtplugDeviceError> {
+    let mut adapter = self.adapter.lock().await;
+    if let Some(adapter) = adapter.as_ref() {
+      return Ok(adapter.clone());
+    }
+    let manager = Manager::new().await.map_err(btleplug_error)?;
+    let Some(found) = manager
+      .adapters()
+      .await
+      .map_err(btleplug_error)?
+      .into_iter()
+      .next()
+    else {
+      self.available.store(false, Ordering::Relaxed);
+      return Err(ButtplugDeviceError::DeviceConnectionError(
+        "No Bluetooth LE adapter found".to_owned(),
+      ));
+    };
+    self.available.store(true, Ordering::Relaxed);
+    let events = found.events().await.map_err(btleplug_error)?;
+    buttplug_core::spawn!(
+      "BtleplugAdvertisementAdapter event loop",
+      forward_advertisements(
+        found.clone(),
+        events,
+        self.sender.clone(),
+        self.cancellation_token.child_token()
+      )
+    );
+    *adapter = Some(found.clone());
+    Ok(found)
+  }
+}
+
+#[async_trait]
+impl BleAdvertisementAdapter for BtleplugAdvertisementAdapter {
+  fn is_available(&self) -> bool {
+    self.available.load(Ordering::Relaxed)
+  }
+
+  fn can_advertise(&self) -> bool {
+    false
+  }
+
+  fn advertisements(&self) -> broadcast::Receiver<BleAdvertisement> {
+    self.sender.subscribe()
+  }
+
+  async fn start_listening(&self) -> Result<(), ButtplugDeviceError> {
+    self
+      .adapter()
+      .await?
+      .start_scan(ScanFilter::default())
+      .await
+      .map_err(btleplug_error)
+  }
+
+  async fn stop_listening(&self) -> Result<(), ButtplugDeviceError> {
+    if let Some(adapter) = self.adapter.lock().await.as_ref() {
+      adapter.stop_scan().await.map_err(btleplug_error)?;
+    }
+    Ok(())
+  }
+
+  async fn advertise(
+    &self,
+    _handle: &str,
+    _company: u16,
+    _data: &[u8],
+  ) -> Result<(), ButtplugDeviceError> {
+    Err(ButtplugDeviceError::UnhandledCommand(
+      "btleplug cannot broadcast advertisements".to_owned(),
+    ))
+  }
+
+  async fn stop_advertising(&self, _handle: &str) -> Result<(), ButtplugDeviceError> {
+    // Nothing can have been broadcast, so there's nothing to stop.
+    Ok(())
+  }
+}
+
+impl Drop for BtleplugAdvertisementAdapter {
+  fn drop(&mut self) {
+    self.cancellation_token.cancel();
+  }
+}
@@ -6,7 +6,11 @@
 // for full license information.
 
 use super::btleplug_adapter_task::{BtleplugAdapterCommand, BtleplugAdapterTask};
-use buttplug_core::{ButtplugResultFuture, errors::ButtplugDeviceError};
+use buttplug_core::{
+  ButtplugResultFuture,
+  errors::ButtplugDeviceError,
+  message::HardwareManagerStatus,
+};
 use buttplug_server::device::hardware::communication::{
   HardwareCommunicationManager,
   HardwareCommunicationManagerBuilder,
@@ -136,6 +140,16 @@ impl HardwareCommunicationManager for BtlePlugCommunicationManager {
   fn can_scan(&self) -> bool {
     self.adapter_connected.load(Ordering::Relaxed)
   }
+
+  fn status(&self) -> HardwareManagerStatus {
+    if !self.can_scan() {
+      HardwareManagerStatus::Unavailable("No Bluetooth LE adapter found".to_owned())
+    } else if self.scanning_status() {
+      HardwareManagerStatus::Scanning
+    } else {
+      HardwareManagerStatus::Available
+    }
+  }
 }
 /*
 impl Drop for BtlePlugCommunicationManager {
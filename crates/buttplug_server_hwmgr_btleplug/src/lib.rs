@@ -11,4 +11,6 @@ extern crate log;
 pub mod btleplug_comm_manager;
 pub use btleplug_comm_manager::BtlePlugCommunicationManagerBuilder;
 mod btleplug_adapter_task;
+pub mod btleplug_advertisement_adapter;
+pub use btleplug_advertisement_adapter::BtleplugAdvertisementAdapter;
 pub mod btleplug_hardware;
@@ -0,0 +1,89 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use super::evdev_hardware::EvdevHardwareConnector;
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::communication::{
+  HardwareCommunicationManager,
+  HardwareCommunicationManagerBuilder,
+  HardwareCommunicationManagerEvent,
+  TimedRetryCommunicationManager,
+  TimedRetryCommunicationManagerImpl,
+};
+use evdev::FFEffectCode;
+use tokio::sync::mpsc;
+
+#[derive(Default, Clone)]
+pub struct EvdevCommunicationManagerBuilder {}
+
+impl HardwareCommunicationManagerBuilder for EvdevCommunicationManagerBuilder {
+  fn finish(
+    &mut self,
+    sender: mpsc::Sender<HardwareCommunicationManagerEvent>,
+  ) -> Box<dyn HardwareCommunicationManager> {
+    Box::new(TimedRetryCommunicationManager::new(
+      EvdevCommunicationManager::new(sender),
+    ))
+  }
+}
+
+pub struct EvdevCommunicationManager {
+  sender: mpsc::Sender<HardwareCommunicationManagerEvent>,
+}
+
+impl EvdevCommunicationManager {
+  fn new(sender: mpsc::Sender<HardwareCommunicationManagerEvent>) -> Self {
+    trace!("Evdev manager created.");
+    Self { sender }
+  }
+}
+
+#[async_trait]
+impl TimedRetryCommunicationManagerImpl for EvdevCommunicationManager {
+  fn name(&self) -> &'static str {
+    "EvdevCommunicationManager"
+  }
+
+  async fn scan(&self) -> Result<(), ButtplugDeviceError> {
+    trace!("Evdev manager scanning for devices");
+    // Only devices we were allowed to open show up here, which is everything we'd be able to
+    // rumble anyways.
+    let connectors: Vec<EvdevHardwareConnector> = evdev::enumerate()
+      .filter(|(_, device)| {
+        device
+          .supported_ff()
+          .is_some_and(|ff| ff.contains(FFEffectCode::FF_RUMBLE))
+      })
+      .map(|(path, device)| {
+        EvdevHardwareConnector::new(&path, device.name().unwrap_or("Evdev Gamepad"))
+      })
+      .collect();
+    for connector in connectors {
+      debug!("Evdev manager found device {:?}", connector);
+      if self
+        .sender
+        .send(HardwareCommunicationManagerEvent::DeviceFound {
+          name: connector.name().to_owned(),
+          address: connector.address(),
+          creator: Box::new(connector),
+        })
+        .await
+        .is_err()
+      {
+        error!("Error sending device found message from Evdev.");
+        break;
+      }
+    }
+    Ok(())
+  }
+
+  // Nothing to turn on or off, if we can see the devices we can use them.
+  fn can_scan(&self) -> bool {
+    true
+  }
+}
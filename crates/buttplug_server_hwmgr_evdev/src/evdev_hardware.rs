@@ -0,0 +1,263 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{
+  GenericHardwareSpecializer,
+  Hardware,
+  HardwareConnector,
+  HardwareEvent,
+  HardwareInternal,
+  HardwareReadCmd,
+  HardwareReading,
+  HardwareSpecializer,
+  HardwareSubscribeCmd,
+  HardwareUnsubscribeCmd,
+  HardwareWriteCmd,
+  communication::HardwareSpecificError,
+};
+use buttplug_server_device_config::{Endpoint, EvdevSpecifier, ProtocolCommunicationSpecifier};
+use byteorder::{LittleEndian, ReadBytesExt};
+use evdev::{Device, FFEffect, FFEffectCode, FFEffectData, FFEffectKind, FFReplay, FFTrigger};
+use futures::future::{self, BoxFuture, FutureExt};
+use std::{
+  fmt::{self, Debug},
+  io::{self, Cursor},
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+fn evdev_error(err: io::Error) -> ButtplugDeviceError {
+  ButtplugDeviceError::DeviceSpecificError(
+    HardwareSpecificError::HardwareSpecificError("Evdev".to_owned(), format!("{err:?}"))
+      .to_string(),
+  )
+}
+
+fn rumble_effect(strong_magnitude: u16, weak_magnitude: u16) -> FFEffectData {
+  FFEffectData {
+    direction: 0,
+    trigger: FFTrigger::default(),
+    // A length of 0 keeps the effect playing until we stop it or change it.
+    replay: FFReplay {
+      length: 0,
+      delay: 0,
+    },
+    kind: FFEffectKind::Rumble {
+      strong_magnitude,
+      weak_magnitude,
+    },
+  }
+}
+
+async fn check_gamepad_connectivity(
+  path: PathBuf,
+  sender: broadcast::Sender<HardwareEvent>,
+  cancellation_token: CancellationToken,
+) {
+  loop {
+    // Device nodes are removed as soon as the device is unplugged.
+    if !path.exists() {
+      info!("Evdev gamepad {} has disconnected.", path.display());
+      // If this fails, we don't care because we're exiting anyways.
+      let _ = sender.send(HardwareEvent::Disconnected(
+        path.to_string_lossy().to_string(),
+      ));
+      return;
+    }
+    tokio::select! {
+      _ = cancellation_token.cancelled() => return,
+      _ = tokio::time::sleep(Duration::from_millis(500)) => continue
+    }
+  }
+}
+
+pub struct EvdevHardwareConnector {
+  path: PathBuf,
+  name: String,
+}
+
+impl EvdevHardwareConnector {
+  pub fn new(path: &Path, name: &str) -> Self {
+    Self {
+      path: path.to_owned(),
+      name: name.to_owned(),
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn address(&self) -> String {
+    self.path.to_string_lossy().to_string()
+  }
+}
+
+impl Debug for EvdevHardwareConnector {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EvdevHardwareConnector")
+      .field("path", &self.path)
+      .field("name", &self.name)
+      .finish()
+  }
+}
+
+#[async_trait]
+impl HardwareConnector for EvdevHardwareConnector {
+  fn specifier(&self) -> ProtocolCommunicationSpecifier {
+    ProtocolCommunicationSpecifier::Evdev(EvdevSpecifier::default())
+  }
+
+  async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
+    debug!("Emitting a new evdev gamepad impl.");
+    let hardware_internal = EvdevHardware::try_create(&self.path)?;
+    let hardware = Hardware::new(
+      &self.name,
+      &self.address(),
+      &[Endpoint::Tx],
+      &None,
+      false,
+      Box::new(hardware_internal),
+    );
+    Ok(Box::new(GenericHardwareSpecializer::new(hardware)))
+  }
+}
+
+pub struct EvdevHardware {
+  address: String,
+  // The effect holds its own handle to the device, so we don't need to keep the device around.
+  effect: Arc<Mutex<FFEffect>>,
+  event_sender: broadcast::Sender<HardwareEvent>,
+  cancellation_token: CancellationToken,
+}
+
+impl EvdevHardware {
+  fn try_create(path: &Path) -> Result<Self, ButtplugDeviceError> {
+    let address = path.to_string_lossy().to_string();
+    let mut device = Device::open(path).map_err(|err| {
+      ButtplugDeviceError::DeviceConnectionError(format!(
+        "Cannot open evdev device {address}: {err}"
+      ))
+    })?;
+    if !device
+      .supported_ff()
+      .is_some_and(|ff| ff.contains(FFEffectCode::FF_RUMBLE))
+    {
+      return Err(ButtplugDeviceError::DeviceConnectionError(format!(
+        "Evdev device {address} does not support rumble"
+      )));
+    }
+    // Every write updates this one effect, so we never run out of effect slots on the device.
+    let effect = device
+      .upload_ff_effect(rumble_effect(0, 0))
+      .map_err(|err| {
+        ButtplugDeviceError::DeviceConnectionError(format!(
+          "Cannot upload rumble effect to evdev device {address}: {err}"
+        ))
+      })?;
+
+    let (device_event_sender, _) = broadcast::channel(256);
+    let token = CancellationToken::new();
+    let child = token.child_token();
+    let sender = device_event_sender.clone();
+    let watch_path = path.to_owned();
+    buttplug_core::spawn!("EvdevHardware connectivity check", async move {
+      check_gamepad_connectivity(watch_path, sender, child).await;
+    });
+    Ok(Self {
+      address,
+      effect: Arc::new(Mutex::new(effect)),
+      event_sender: device_event_sender,
+      cancellation_token: token,
+    })
+  }
+}
+
+impl HardwareInternal for EvdevHardware {
+  fn event_stream(&self) -> broadcast::Receiver<HardwareEvent> {
+    self.event_sender.subscribe()
+  }
+
+  fn disconnect(&self) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.cancellation_token.cancel();
+    future::ready(Ok(())).boxed()
+  }
+
+  fn read_value(
+    &self,
+    _msg: &HardwareReadCmd,
+  ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
+    future::ready(Err(ButtplugDeviceError::UnhandledCommand(
+      "Evdev hardware does not support read".to_owned(),
+    )))
+    .boxed()
+  }
+
+  fn write_value(
+    &self,
+    msg: &HardwareWriteCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    let effect = self.effect.clone();
+    let address = self.address.clone();
+    let data = msg.data().clone();
+    async move {
+      // Packed the same way as XInput, left (strong) motor first, then right (weak) motor.
+      let mut cursor = Cursor::new(data);
+      let strong_magnitude = cursor
+        .read_u16::<LittleEndian>()
+        .expect("Packed in protocol, infallible");
+      let weak_magnitude = cursor
+        .read_u16::<LittleEndian>()
+        .expect("Packed in protocol, infallible");
+      trace!(
+        "Setting evdev gamepad {} rumble to {}/{}",
+        address, strong_magnitude, weak_magnitude
+      );
+      let mut effect = effect.lock().expect("Lock is never held across panics");
+      effect
+        .update(rumble_effect(strong_magnitude, weak_magnitude))
+        .map_err(evdev_error)?;
+      if strong_magnitude == 0 && weak_magnitude == 0 {
+        effect.stop().map_err(evdev_error)
+      } else {
+        effect.play(1).map_err(evdev_error)
+      }
+    }
+    .boxed()
+  }
+
+  fn subscribe(
+    &self,
+    _msg: &HardwareSubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    future::ready(Err(ButtplugDeviceError::UnhandledCommand(
+      "Evdev hardware does not support subscribe".to_owned(),
+    )))
+    .boxed()
+  }
+
+  fn unsubscribe(
+    &self,
+    _msg: &HardwareUnsubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    future::ready(Err(ButtplugDeviceError::UnhandledCommand(
+      "Evdev hardware does not support unsubscribe".to_owned(),
+    )))
+    .boxed()
+  }
+}
+
+impl Drop for EvdevHardware {
+  fn drop(&mut self) {
+    self.cancellation_token.cancel();
+  }
+}
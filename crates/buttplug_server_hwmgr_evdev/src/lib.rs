@@ -0,0 +1,20 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+#[cfg(target_os = "linux")]
+#[macro_use]
+extern crate log;
+
+#[cfg(target_os = "linux")]
+mod evdev_comm_manager;
+#[cfg(target_os = "linux")]
+mod evdev_hardware;
+
+#[cfg(target_os = "linux")]
+pub use evdev_comm_manager::{EvdevCommunicationManager, EvdevCommunicationManagerBuilder};
+#[cfg(target_os = "linux")]
+pub use evdev_hardware::{EvdevHardware, EvdevHardwareConnector};
@@ -0,0 +1,190 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+// These stand up a virtual force feedback device through uinput, which needs /dev/uinput to exist
+// and be writable. Plenty of CI and container environments don't have it, so the tests are ignored
+// by default. Run them with `cargo test -p buttplug_server_hwmgr_evdev -- --ignored`.
+#![cfg(target_os = "linux")]
+
+use buttplug_server::device::hardware::{
+  HardwareConnector,
+  HardwareWriteCmd,
+  communication::{HardwareCommunicationManagerBuilder, HardwareCommunicationManagerEvent},
+};
+use buttplug_server_device_config::{Endpoint, EvdevSpecifier, ProtocolCommunicationSpecifier};
+use buttplug_server_hwmgr_evdev::{EvdevCommunicationManagerBuilder, EvdevHardwareConnector};
+use evdev::{
+  AttributeSet,
+  EventSummary,
+  FFEffectCode,
+  FFEffectKind,
+  UInputCode,
+  uinput::VirtualDevice,
+};
+use std::{path::PathBuf, sync::mpsc, thread, time::Duration};
+
+const VIRTUAL_DEVICE_NAME: &str = "Buttplug Test Rumble Gamepad";
+
+/// Things the virtual device sees the hardware do.
+#[derive(Debug, PartialEq)]
+enum RumbleEvent {
+  Upload { strong: u16, weak: u16 },
+  Play,
+  Stop,
+}
+
+/// Creates a virtual rumble gamepad, returning its event node and a channel of everything done to
+/// it. The device is serviced on its own thread, which lives as long as the test process.
+fn virtual_gamepad() -> (PathBuf, mpsc::Receiver<RumbleEvent>) {
+  let mut device = VirtualDevice::builder()
+    .expect("Cannot open /dev/uinput")
+    .name(VIRTUAL_DEVICE_NAME)
+    .with_ff(&AttributeSet::from_iter([FFEffectCode::FF_RUMBLE]))
+    .expect("Test, assuming infallible")
+    .with_ff_effects_max(4)
+    .build()
+    .expect("Test, assuming infallible");
+  let path = device
+    .enumerate_dev_nodes_blocking()
+    .expect("Test, assuming infallible")
+    .next()
+    .expect("Test, assuming infallible")
+    .expect("Test, assuming infallible");
+  let (sender, receiver) = mpsc::channel();
+  thread::spawn(move || {
+    loop {
+      let events: Vec<_> = match device.fetch_events() {
+        Ok(events) => events.collect(),
+        Err(_) => return,
+      };
+      for event in events {
+        let rumble_event = match event.destructure() {
+          EventSummary::UInput(event, UInputCode::UI_FF_UPLOAD, ..) => {
+            // The kernel has already picked an id for the effect, all we need to do is accept it.
+            let mut upload = device
+              .process_ff_upload(event)
+              .expect("Test, assuming infallible");
+            upload.set_retval(0);
+            match upload.effect().kind {
+              FFEffectKind::Rumble {
+                strong_magnitude,
+                weak_magnitude,
+              } => RumbleEvent::Upload {
+                strong: strong_magnitude,
+                weak: weak_magnitude,
+              },
+              _ => continue,
+            }
+          }
+          EventSummary::UInput(event, UInputCode::UI_FF_ERASE, ..) => {
+            device
+              .process_ff_erase(event)
+              .expect("Test, assuming infallible");
+            continue;
+          }
+          EventSummary::ForceFeedback(_, _, 0) => RumbleEvent::Stop,
+          EventSummary::ForceFeedback(..) => RumbleEvent::Play,
+          _ => continue,
+        };
+        if sender.send(rumble_event).is_err() {
+          return;
+        }
+      }
+    }
+  });
+  (path, receiver)
+}
+
+fn next_event(receiver: &mpsc::Receiver<RumbleEvent>) -> RumbleEvent {
+  receiver
+    .recv_timeout(Duration::from_secs(1))
+    .expect("Test, assuming infallible")
+}
+
+#[tokio::test]
+#[ignore = "needs a writable /dev/uinput"]
+async fn test_evdev_hardware_rumble() {
+  let (path, receiver) = virtual_gamepad();
+  let mut connector = EvdevHardwareConnector::new(&path, VIRTUAL_DEVICE_NAME);
+  let hardware = connector
+    .connect()
+    .await
+    .unwrap()
+    .specialize(&[ProtocolCommunicationSpecifier::Evdev(
+      EvdevSpecifier::default(),
+    )])
+    .await
+    .unwrap();
+  // Connecting sets up a stopped effect for writes to update.
+  assert_eq!(
+    next_event(&receiver),
+    RumbleEvent::Upload { strong: 0, weak: 0 }
+  );
+
+  // Same packing as XInput, strong motor first.
+  hardware
+    .write_value(&HardwareWriteCmd::new(
+      &[],
+      Endpoint::Tx,
+      vec![0x00, 0x80, 0xff, 0xff],
+      false,
+    ))
+    .await
+    .unwrap();
+  assert_eq!(
+    next_event(&receiver),
+    RumbleEvent::Upload {
+      strong: 0x8000,
+      weak: 0xffff
+    }
+  );
+  assert_eq!(next_event(&receiver), RumbleEvent::Play);
+
+  hardware
+    .write_value(&HardwareWriteCmd::new(
+      &[],
+      Endpoint::Tx,
+      vec![0, 0, 0, 0],
+      false,
+    ))
+    .await
+    .unwrap();
+  assert_eq!(
+    next_event(&receiver),
+    RumbleEvent::Upload { strong: 0, weak: 0 }
+  );
+  assert_eq!(next_event(&receiver), RumbleEvent::Stop);
+}
+
+#[tokio::test]
+#[ignore = "needs a writable /dev/uinput"]
+async fn test_evdev_comm_manager_finds_rumble_devices() {
+  let (path, _receiver) = virtual_gamepad();
+  let (sender, mut receiver) = tokio::sync::mpsc::channel(256);
+  let mut manager = EvdevCommunicationManagerBuilder::default().finish(sender);
+  manager.start_scanning().await.unwrap();
+  let address = path.to_string_lossy().to_string();
+  let found = tokio::time::timeout(Duration::from_secs(5), async {
+    // Any real rumble devices on the machine will show up here too.
+    while let Some(event) = receiver.recv().await {
+      if let HardwareCommunicationManagerEvent::DeviceFound {
+        name,
+        address: found_address,
+        ..
+      } = event
+        && found_address == address
+      {
+        return name;
+      }
+    }
+    panic!("Manager went away before finding the virtual device");
+  })
+  .await
+  .expect("Test, assuming infallible");
+  assert_eq!(found, VIRTUAL_DEVICE_NAME);
+  manager.stop_scanning().await.unwrap();
+}
@@ -0,0 +1,20 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+#[macro_use]
+extern crate log;
+
+mod mqtt_comm_manager;
+mod mqtt_connection;
+mod mqtt_hardware;
+
+pub use mqtt_comm_manager::{
+  DEFAULT_MQTT_DISCOVERY_TOPIC,
+  MqttCommunicationManager,
+  MqttCommunicationManagerBuilder,
+};
+pub use mqtt_hardware::{MqttHardware, MqttHardwareConnector};
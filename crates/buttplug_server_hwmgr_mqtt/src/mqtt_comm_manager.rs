@@ -0,0 +1,176 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use super::{
+  mqtt_connection::{MqttConnection, run_event_loop},
+  mqtt_hardware::MqttHardwareConnector,
+};
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::communication::{
+  HardwareCommunicationManager,
+  HardwareCommunicationManagerBuilder,
+  HardwareCommunicationManagerEvent,
+  TimedRetryCommunicationManager,
+  TimedRetryCommunicationManagerImpl,
+};
+use rumqttc::{AsyncClient, MqttOptions};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Topic prefix devices announce themselves under if the builder isn't given a different one.
+pub const DEFAULT_MQTT_DISCOVERY_TOPIC: &str = "buttplug/devices";
+
+#[derive(Clone)]
+pub struct MqttCommunicationManagerBuilder {
+  broker_host: String,
+  broker_port: u16,
+  client_id: Option<String>,
+  credentials: Option<(String, String)>,
+  discovery_topic: String,
+}
+
+impl Default for MqttCommunicationManagerBuilder {
+  fn default() -> Self {
+    Self {
+      broker_host: "localhost".to_owned(),
+      broker_port: 1883,
+      client_id: None,
+      credentials: None,
+      discovery_topic: DEFAULT_MQTT_DISCOVERY_TOPIC.to_owned(),
+    }
+  }
+}
+
+impl MqttCommunicationManagerBuilder {
+  /// Broker to connect to. Defaults to `localhost:1883`.
+  pub fn broker(mut self, host: &str, port: u16) -> Self {
+    self.broker_host = host.to_owned();
+    self.broker_port = port;
+    self
+  }
+
+  /// Client id to connect to the broker with. A random one is used if this isn't set.
+  pub fn client_id(mut self, client_id: &str) -> Self {
+    self.client_id = Some(client_id.to_owned());
+    self
+  }
+
+  pub fn credentials(mut self, username: &str, password: &str) -> Self {
+    self.credentials = Some((username.to_owned(), password.to_owned()));
+    self
+  }
+
+  /// Topic prefix devices announce themselves under, as `<discovery topic>/<device id>`. Defaults
+  /// to [DEFAULT_MQTT_DISCOVERY_TOPIC].
+  pub fn discovery_topic(mut self, topic: &str) -> Self {
+    self.discovery_topic = topic.to_owned();
+    self
+  }
+}
+
+impl HardwareCommunicationManagerBuilder for MqttCommunicationManagerBuilder {
+  fn finish(
+    &mut self,
+    sender: Sender<HardwareCommunicationManagerEvent>,
+  ) -> Box<dyn HardwareCommunicationManager> {
+    Box::new(TimedRetryCommunicationManager::new(
+      MqttCommunicationManager::new(sender, self),
+    ))
+  }
+}
+
+pub struct MqttCommunicationManager {
+  sender: Sender<HardwareCommunicationManagerEvent>,
+  connection: Arc<MqttConnection>,
+  cancellation_token: CancellationToken,
+}
+
+impl MqttCommunicationManager {
+  fn new(
+    sender: Sender<HardwareCommunicationManagerEvent>,
+    builder: &MqttCommunicationManagerBuilder,
+  ) -> Self {
+    trace!("MQTT manager created.");
+    let client_id = builder
+      .client_id
+      .clone()
+      .unwrap_or_else(|| format!("buttplug-{}", &Uuid::new_v4().simple().to_string()[..8]));
+    let mut options = MqttOptions::new(client_id, &builder.broker_host, builder.broker_port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some((username, password)) = &builder.credentials {
+      options.set_credentials(username, password);
+    }
+    let (client, event_loop) = AsyncClient::new(options, 64);
+    let connection = Arc::new(MqttConnection::new(client, &builder.discovery_topic));
+    let cancellation_token = CancellationToken::new();
+    // The broker connection stays up for the life of the manager, so announcements are already
+    // waiting for us whenever scanning starts.
+    buttplug_core::spawn!(
+      "MqttCommunicationManager event loop",
+      run_event_loop(
+        connection.clone(),
+        event_loop,
+        cancellation_token.child_token()
+      )
+    );
+    Self {
+      sender,
+      connection,
+      cancellation_token,
+    }
+  }
+}
+
+#[async_trait]
+impl TimedRetryCommunicationManagerImpl for MqttCommunicationManager {
+  fn name(&self) -> &'static str {
+    "MqttCommunicationManager"
+  }
+
+  async fn scan(&self) -> Result<(), ButtplugDeviceError> {
+    trace!("MQTT manager scanning for devices.");
+    if !self.connection.is_connected() {
+      debug!("Not connected to MQTT broker, skipping scan.");
+      return Ok(());
+    }
+    for (device_id, info, claim) in self.connection.claim_unused_devices() {
+      let connector = MqttHardwareConnector::new(&device_id, &info, self.connection.clone(), claim);
+      trace!(
+        "Sending MQTT device {:?} for possible device connection.",
+        connector
+      );
+      if self
+        .sender
+        .send(HardwareCommunicationManagerEvent::DeviceFound {
+          name: connector.name(),
+          address: device_id,
+          creator: Box::new(connector),
+        })
+        .await
+        .is_err()
+      {
+        debug!("Device manager disappeared, exiting.");
+        break;
+      }
+    }
+    Ok(())
+  }
+
+  // No restrictions since this is network not hardware.
+  fn can_scan(&self) -> bool {
+    true
+  }
+}
+
+impl Drop for MqttCommunicationManager {
+  fn drop(&mut self) {
+    self.cancellation_token.cancel();
+  }
+}
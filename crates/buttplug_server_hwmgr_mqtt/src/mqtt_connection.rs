@@ -0,0 +1,274 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Broker connection shared by the MQTT manager and all of its devices. Keeps track of what's been
+//! announced, and routes messages on device rx topics to whichever device is using them.
+
+use dashmap::{DashMap, DashSet};
+use rumqttc::{AsyncClient, Event, EventLoop, Packet, QoS};
+use serde::Deserialize;
+use std::{
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::Duration,
+};
+use tokio::{select, sync::broadcast, time::sleep};
+use tokio_util::sync::CancellationToken;
+
+/// How long to wait before trying to reach the broker again after the connection fails or drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Retained JSON message a device publishes to `<discovery topic>/<device id>` to announce itself.
+#[derive(Deserialize, Debug)]
+struct MqttDeviceAnnouncement {
+  identifier: String,
+  #[serde(default)]
+  tx_topic: Option<String>,
+  #[serde(default)]
+  rx_topic: Option<String>,
+}
+
+/// Everything we need to know to talk to an announced device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MqttDeviceInfo {
+  pub(crate) identifier: String,
+  pub(crate) tx_topic: String,
+  pub(crate) rx_topic: String,
+}
+
+/// Where to send what arrives on a connected device's rx topic.
+pub(crate) struct MqttDeviceRoute {
+  pub(crate) rx_topic: String,
+  pub(crate) incoming: broadcast::Sender<Vec<u8>>,
+  /// Cancelled when the device goes away on the broker side.
+  pub(crate) token: CancellationToken,
+}
+
+/// Marks a device as in use from the time it's handed to the device manager until its hardware
+/// goes away, so scans don't hand the same device over twice.
+pub(crate) struct DeviceClaim {
+  claimed: Arc<DashSet<String>>,
+  device_id: String,
+}
+
+impl DeviceClaim {
+  pub(crate) fn try_new(claimed: &Arc<DashSet<String>>, device_id: &str) -> Option<Self> {
+    claimed.insert(device_id.to_owned()).then(|| Self {
+      claimed: claimed.clone(),
+      device_id: device_id.to_owned(),
+    })
+  }
+}
+
+impl Drop for DeviceClaim {
+  fn drop(&mut self) {
+    self.claimed.remove(&self.device_id);
+  }
+}
+
+pub(crate) struct MqttConnection {
+  client: AsyncClient,
+  discovery_topic: String,
+  connected: AtomicBool,
+  devices: DashMap<String, MqttDeviceInfo>,
+  claimed: Arc<DashSet<String>>,
+  routes: DashMap<String, MqttDeviceRoute>,
+}
+
+impl MqttConnection {
+  pub(crate) fn new(client: AsyncClient, discovery_topic: &str) -> Self {
+    Self {
+      client,
+      discovery_topic: discovery_topic.trim_end_matches('/').to_owned(),
+      connected: AtomicBool::new(false),
+      devices: DashMap::new(),
+      claimed: Arc::new(DashSet::new()),
+      routes: DashMap::new(),
+    }
+  }
+
+  pub(crate) fn client(&self) -> &AsyncClient {
+    &self.client
+  }
+
+  pub(crate) fn is_connected(&self) -> bool {
+    self.connected.load(Ordering::Relaxed)
+  }
+
+  /// Announced devices that aren't already in use, along with a claim on each of them.
+  pub(crate) fn claim_unused_devices(&self) -> Vec<(String, MqttDeviceInfo, DeviceClaim)> {
+    self
+      .devices
+      .iter()
+      .filter_map(|device| {
+        let claim = DeviceClaim::try_new(&self.claimed, device.key())?;
+        Some((device.key().clone(), device.value().clone(), claim))
+      })
+      .collect()
+  }
+
+  /// Starts routing messages on the route's rx topic to it.
+  pub(crate) async fn add_route(
+    &self,
+    device_id: &str,
+    route: MqttDeviceRoute,
+  ) -> Result<(), rumqttc::ClientError> {
+    let rx_topic = route.rx_topic.clone();
+    self.routes.insert(device_id.to_owned(), route);
+    self.client.subscribe(rx_topic, QoS::AtMostOnce).await
+  }
+
+  /// Stops routing messages to a device, as long as the route is still the one that belongs to
+  /// `incoming` (the device may have reconnected with a new route in the meantime).
+  pub(crate) fn remove_route(&self, device_id: &str, incoming: &broadcast::Sender<Vec<u8>>) {
+    let Some((_, route)) = self
+      .routes
+      .remove_if(device_id, |_, route| route.incoming.same_channel(incoming))
+    else {
+      return;
+    };
+    if !self
+      .routes
+      .iter()
+      .any(|other| other.rx_topic == route.rx_topic)
+    {
+      // This can only fail if the event loop is gone, in which case there's no subscription left.
+      let _ = self.client.try_unsubscribe(route.rx_topic);
+    }
+  }
+
+  /// The device's hardware removes its own route once it sees the cancellation.
+  fn disconnect_device(&self, device_id: &str) {
+    if let Some(route) = self.routes.get(device_id) {
+      route.token.cancel();
+    }
+  }
+
+  fn handle_connected(&self) {
+    info!("Connected to MQTT broker.");
+    self.connected.store(true, Ordering::Relaxed);
+    // We connect with a clean session, so all of our subscriptions need to be set up again.
+    // Announcements are retained, so the broker will send them all to us again.
+    let mut topics = vec![format!("{}/+", self.discovery_topic)];
+    topics.extend(self.routes.iter().map(|route| route.rx_topic.clone()));
+    for topic in topics {
+      if let Err(err) = self.client.try_subscribe(&topic, QoS::AtMostOnce) {
+        error!("Cannot subscribe to MQTT topic {}: {}", topic, err);
+      }
+    }
+  }
+
+  fn handle_disconnected(&self) {
+    self.connected.store(false, Ordering::Relaxed);
+    // We can't reach any devices without the broker, and we'll get all of the announcements again
+    // once we're back.
+    self.devices.clear();
+    let device_ids: Vec<String> = self
+      .routes
+      .iter()
+      .map(|route| route.key().clone())
+      .collect();
+    for device_id in device_ids {
+      self.disconnect_device(&device_id);
+    }
+  }
+
+  fn handle_announcement(&self, device_id: &str, payload: &[u8]) {
+    // An empty retained message clears the announcement, meaning the device is gone.
+    if payload.is_empty() {
+      if self.devices.remove(device_id).is_some() {
+        info!("MQTT device {} removed its announcement.", device_id);
+      }
+      self.disconnect_device(device_id);
+      return;
+    }
+    let announcement: MqttDeviceAnnouncement = match serde_json::from_slice(payload) {
+      Ok(announcement) => announcement,
+      Err(err) => {
+        warn!(
+          "Ignoring invalid announcement for MQTT device {}: {}",
+          device_id, err
+        );
+        return;
+      }
+    };
+    let device_topic = format!("{}/{}", self.discovery_topic, device_id);
+    let info = MqttDeviceInfo {
+      identifier: announcement.identifier,
+      tx_topic: announcement
+        .tx_topic
+        .unwrap_or_else(|| format!("{device_topic}/tx")),
+      rx_topic: announcement
+        .rx_topic
+        .unwrap_or_else(|| format!("{device_topic}/rx")),
+    };
+    debug!("Got announcement for MQTT device {}: {:?}", device_id, info);
+    if let Some(old_info) = self.devices.insert(device_id.to_owned(), info.clone())
+      && old_info != info
+    {
+      // If the device changed how it wants to be talked to, drop it so it gets picked up again
+      // with the new info.
+      self.disconnect_device(device_id);
+    }
+  }
+
+  fn handle_publish(&self, topic: &str, payload: &[u8]) {
+    if let Some(device_id) = topic
+      .strip_prefix(&self.discovery_topic)
+      .and_then(|rest| rest.strip_prefix('/'))
+      && !device_id.is_empty()
+      && !device_id.contains('/')
+    {
+      self.handle_announcement(device_id, payload);
+      return;
+    }
+    for route in self.routes.iter() {
+      if route.rx_topic == topic {
+        // If no one is listening, ignore input.
+        let _ = route.incoming.send(payload.to_vec());
+      }
+    }
+  }
+}
+
+/// Drives the broker connection until the token is cancelled. rumqttc reconnects on its own the
+/// next time the event loop is polled after an error, so all we have to do is keep polling.
+pub(crate) async fn run_event_loop(
+  connection: Arc<MqttConnection>,
+  mut event_loop: EventLoop,
+  token: CancellationToken,
+) {
+  loop {
+    let event = select! {
+      event = event_loop.poll() => event,
+      _ = token.cancelled() => break,
+    };
+    match event {
+      Ok(Event::Incoming(Packet::ConnAck(_))) => connection.handle_connected(),
+      Ok(Event::Incoming(Packet::Publish(publish))) => {
+        connection.handle_publish(&publish.topic, &publish.payload)
+      }
+      Ok(_) => {}
+      Err(err) => {
+        if connection.is_connected() {
+          warn!("Lost connection to MQTT broker: {}", err);
+          connection.handle_disconnected();
+        } else {
+          debug!("Cannot connect to MQTT broker: {}", err);
+        }
+        select! {
+          _ = sleep(RECONNECT_DELAY) => {}
+          _ = token.cancelled() => break,
+        }
+      }
+    }
+  }
+  connection.handle_disconnected();
+  debug!("Exiting MQTT event loop.");
+}
@@ -0,0 +1,245 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use super::mqtt_connection::{DeviceClaim, MqttConnection, MqttDeviceInfo, MqttDeviceRoute};
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{
+  GenericHardwareSpecializer,
+  Hardware,
+  HardwareConnector,
+  HardwareEvent,
+  HardwareInternal,
+  HardwareReadCmd,
+  HardwareReading,
+  HardwareSpecializer,
+  HardwareSubscribeCmd,
+  HardwareUnsubscribeCmd,
+  HardwareWriteCmd,
+  buffered_rx::BufferedRxEndpoint,
+};
+use buttplug_server_device_config::{Endpoint, MqttSpecifier, ProtocolCommunicationSpecifier};
+use futures::{
+  FutureExt,
+  future::{self, BoxFuture},
+};
+use rumqttc::QoS;
+use std::{
+  fmt::{self, Debug},
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+pub struct MqttHardwareConnector {
+  device_id: String,
+  info: MqttDeviceInfo,
+  connection: Arc<MqttConnection>,
+  claim: Option<DeviceClaim>,
+}
+
+impl MqttHardwareConnector {
+  pub(crate) fn new(
+    device_id: &str,
+    info: &MqttDeviceInfo,
+    connection: Arc<MqttConnection>,
+    claim: DeviceClaim,
+  ) -> Self {
+    Self {
+      device_id: device_id.to_owned(),
+      info: info.clone(),
+      connection,
+      claim: Some(claim),
+    }
+  }
+
+  pub fn name(&self) -> String {
+    format!("MQTT Device {}", self.device_id)
+  }
+}
+
+impl Debug for MqttHardwareConnector {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MqttHardwareConnector")
+      .field("device_id", &self.device_id)
+      .field("info", &self.info)
+      .finish()
+  }
+}
+
+#[async_trait]
+impl HardwareConnector for MqttHardwareConnector {
+  fn specifier(&self) -> ProtocolCommunicationSpecifier {
+    ProtocolCommunicationSpecifier::Mqtt(MqttSpecifier::new(&self.info.identifier))
+  }
+
+  async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
+    let Some(claim) = self.claim.take() else {
+      return Err(ButtplugDeviceError::DeviceConnectionError(format!(
+        "MQTT device {} already handed off",
+        self.device_id
+      )));
+    };
+    let hardware_internal =
+      MqttHardware::try_create(&self.device_id, &self.info, self.connection.clone(), claim).await?;
+    let hardware = Hardware::new(
+      &self.name(),
+      &self.device_id,
+      &[Endpoint::Rx, Endpoint::Tx],
+      &None,
+      false,
+      Box::new(hardware_internal),
+    );
+    Ok(Box::new(GenericHardwareSpecializer::new(hardware)))
+  }
+}
+
+pub struct MqttHardware {
+  address: String,
+  tx_topic: String,
+  connection: Arc<MqttConnection>,
+  connected: Arc<AtomicBool>,
+  rx: BufferedRxEndpoint,
+  device_event_sender: broadcast::Sender<HardwareEvent>,
+  connection_token: CancellationToken,
+}
+
+impl MqttHardware {
+  async fn try_create(
+    device_id: &str,
+    info: &MqttDeviceInfo,
+    connection: Arc<MqttConnection>,
+    claim: DeviceClaim,
+  ) -> Result<Self, ButtplugDeviceError> {
+    let (device_event_sender, _) = broadcast::channel(256);
+    let connected = Arc::new(AtomicBool::new(true));
+    let connection_token = CancellationToken::new();
+    let rx = BufferedRxEndpoint::new(device_id, &device_event_sender, &connection_token);
+    let incoming_broadcaster = rx.sender();
+
+    connection
+      .add_route(
+        device_id,
+        MqttDeviceRoute {
+          rx_topic: info.rx_topic.clone(),
+          incoming: incoming_broadcaster.clone(),
+          token: connection_token.clone(),
+        },
+      )
+      .await
+      .map_err(|err| {
+        connection.remove_route(device_id, &incoming_broadcaster);
+        ButtplugDeviceError::DeviceConnectionError(format!(
+          "Cannot subscribe to {} for MQTT device {}: {}",
+          info.rx_topic, device_id, err
+        ))
+      })?;
+
+    let task_address = device_id.to_owned();
+    let task_connection = connection.clone();
+    let task_incoming = incoming_broadcaster.clone();
+    let task_event_sender = device_event_sender.clone();
+    let task_connected = connected.clone();
+    let task_token = connection_token.clone();
+    buttplug_core::spawn!("MqttHardware connection watcher", async move {
+      task_token.cancelled().await;
+      task_connection.remove_route(&task_address, &task_incoming);
+      if task_connected.swap(false, Ordering::Relaxed) && task_event_sender.receiver_count() != 0 {
+        let _ = task_event_sender.send(HardwareEvent::Disconnected(task_address.clone()));
+      }
+      // Now that we're done with the device, let the next scan pick it up again.
+      drop(claim);
+      debug!("MQTT device {} disconnected.", task_address);
+    });
+
+    Ok(Self {
+      address: device_id.to_owned(),
+      tx_topic: info.tx_topic.clone(),
+      connection,
+      connected,
+      rx,
+      device_event_sender,
+      connection_token,
+    })
+  }
+}
+
+impl HardwareInternal for MqttHardware {
+  fn event_stream(&self) -> broadcast::Receiver<HardwareEvent> {
+    self.device_event_sender.subscribe()
+  }
+
+  fn disconnect(&self) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.connected.store(false, Ordering::Relaxed);
+    self.connection_token.cancel();
+    future::ready(Ok(())).boxed()
+  }
+
+  fn read_value(
+    &self,
+    msg: &HardwareReadCmd,
+  ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
+    self.rx.read_value(msg)
+  }
+
+  fn write_value(
+    &self,
+    msg: &HardwareWriteCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    if msg.endpoint() != Endpoint::Tx {
+      return future::ready(Err(ButtplugDeviceError::InvalidEndpoint(
+        msg.endpoint().to_string(),
+      )))
+      .boxed();
+    }
+    if !self.connected.load(Ordering::Relaxed) {
+      return future::ready(Err(ButtplugDeviceError::DeviceNotConnected(
+        self.address.clone(),
+      )))
+      .boxed();
+    }
+    let connection = self.connection.clone();
+    let topic = self.tx_topic.clone();
+    let data = msg.data().clone();
+    let address = self.address.clone();
+    async move {
+      connection
+        .client()
+        .publish(topic, QoS::AtLeastOnce, false, data)
+        .await
+        .map_err(|err| {
+          ButtplugDeviceError::DeviceCommunicationError(format!(
+            "Cannot publish to MQTT device {address}: {err}"
+          ))
+        })
+    }
+    .boxed()
+  }
+
+  fn subscribe(
+    &self,
+    msg: &HardwareSubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.rx.subscribe(msg)
+  }
+
+  fn unsubscribe(
+    &self,
+    msg: &HardwareUnsubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.rx.unsubscribe(msg)
+  }
+}
+
+impl Drop for MqttHardware {
+  fn drop(&mut self) {
+    self.connection_token.cancel();
+  }
+}
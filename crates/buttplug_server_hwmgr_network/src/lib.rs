@@ -0,0 +1,15 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+#[macro_use]
+extern crate log;
+
+mod network_comm_manager;
+mod network_hardware;
+
+pub use network_comm_manager::{NetworkCommunicationManager, NetworkCommunicationManagerBuilder};
+pub use network_hardware::{NetworkHardware, NetworkHardwareConnector};
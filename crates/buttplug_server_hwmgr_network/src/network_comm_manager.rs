@@ -0,0 +1,118 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use super::network_hardware::{AddressClaim, NetworkHardwareConnector};
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::communication::{
+  HardwareCommunicationManager,
+  HardwareCommunicationManagerBuilder,
+  HardwareCommunicationManagerEvent,
+  TimedRetryCommunicationManager,
+  TimedRetryCommunicationManagerImpl,
+};
+use buttplug_server_device_config::NetworkSpecifier;
+use dashmap::DashSet;
+use futures::future::join_all;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+
+#[derive(Default, Clone)]
+pub struct NetworkCommunicationManagerBuilder {
+  devices: Vec<NetworkSpecifier>,
+}
+
+impl NetworkCommunicationManagerBuilder {
+  /// Adds a device address to connect to while scanning. Protocols still need a matching `network`
+  /// communication specifier in the device config to be used with the device.
+  pub fn add_device(mut self, device: &NetworkSpecifier) -> Self {
+    self.devices.push(device.clone());
+    self
+  }
+
+  pub fn devices(mut self, devices: &[NetworkSpecifier]) -> Self {
+    self.devices = devices.to_vec();
+    self
+  }
+}
+
+impl HardwareCommunicationManagerBuilder for NetworkCommunicationManagerBuilder {
+  fn finish(
+    &mut self,
+    sender: Sender<HardwareCommunicationManagerEvent>,
+  ) -> Box<dyn HardwareCommunicationManager> {
+    Box::new(TimedRetryCommunicationManager::new(
+      NetworkCommunicationManager::new(sender, &self.devices),
+    ))
+  }
+}
+
+pub struct NetworkCommunicationManager {
+  sender: Sender<HardwareCommunicationManagerEvent>,
+  devices: Vec<NetworkSpecifier>,
+  active_addresses: Arc<DashSet<String>>,
+}
+
+impl NetworkCommunicationManager {
+  fn new(sender: Sender<HardwareCommunicationManagerEvent>, devices: &[NetworkSpecifier]) -> Self {
+    trace!("Network manager created.");
+    Self {
+      sender,
+      devices: devices.to_vec(),
+      active_addresses: Arc::new(DashSet::new()),
+    }
+  }
+}
+
+#[async_trait]
+impl TimedRetryCommunicationManagerImpl for NetworkCommunicationManager {
+  fn name(&self) -> &'static str {
+    "NetworkCommunicationManager"
+  }
+
+  async fn scan(&self) -> Result<(), ButtplugDeviceError> {
+    trace!("Network manager scanning for devices.");
+    // There's no way to find these devices other than trying to connect to them, so that's what
+    // scanning is. Devices we're already connected to are skipped.
+    let attempts = self.devices.iter().filter_map(|device| {
+      let claim = AddressClaim::try_new(&self.active_addresses, &device.to_string())?;
+      Some(async move {
+        NetworkHardwareConnector::try_connect(device, claim)
+          .await
+          .map_err(|err| {
+            debug!("Cannot connect to network device {}: {}", device, err);
+          })
+          .ok()
+      })
+    });
+    for connector in join_all(attempts).await.into_iter().flatten() {
+      trace!(
+        "Sending network device {:?} for possible device connection.",
+        connector
+      );
+      if self
+        .sender
+        .send(HardwareCommunicationManagerEvent::DeviceFound {
+          name: connector.name(),
+          address: connector.address(),
+          creator: Box::new(connector),
+        })
+        .await
+        .is_err()
+      {
+        debug!("Device manager disappeared, exiting.");
+        break;
+      }
+    }
+    Ok(())
+  }
+
+  // No restrictions since this is network not hardware.
+  fn can_scan(&self) -> bool {
+    true
+  }
+}
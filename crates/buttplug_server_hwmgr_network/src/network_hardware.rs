@@ -0,0 +1,388 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{
+  GenericHardwareSpecializer,
+  Hardware,
+  HardwareConnector,
+  HardwareEvent,
+  HardwareInternal,
+  HardwareReadCmd,
+  HardwareReading,
+  HardwareSpecializer,
+  HardwareSubscribeCmd,
+  HardwareUnsubscribeCmd,
+  HardwareWriteCmd,
+  buffered_rx::BufferedRxEndpoint,
+};
+use buttplug_server_device_config::{
+  Endpoint,
+  NetworkSpecifier,
+  NetworkTransport,
+  ProtocolCommunicationSpecifier,
+};
+use dashmap::DashSet;
+use futures::{
+  FutureExt,
+  future::{self, BoxFuture},
+};
+use std::{
+  fmt::{self, Debug},
+  io,
+  net::SocketAddr,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::Duration,
+};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::{TcpStream, UdpSocket, lookup_host},
+  select,
+  sync::{broadcast, mpsc},
+  time::timeout,
+};
+use tokio_util::sync::CancellationToken;
+
+/// How long to wait for a TCP connection before giving up until the next scan.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Marks an address as in use from the time we start connecting to it until the connection closes,
+/// so scans don't open a second socket to a device we're already talking to.
+pub(crate) struct AddressClaim {
+  addresses: Arc<DashSet<String>>,
+  address: String,
+}
+
+impl AddressClaim {
+  pub(crate) fn try_new(addresses: &Arc<DashSet<String>>, address: &str) -> Option<Self> {
+    addresses.insert(address.to_owned()).then(|| Self {
+      addresses: addresses.clone(),
+      address: address.to_owned(),
+    })
+  }
+}
+
+impl Drop for AddressClaim {
+  fn drop(&mut self) {
+    self.addresses.remove(&self.address);
+  }
+}
+
+enum NetworkSocket {
+  Tcp(TcpStream),
+  Udp(UdpSocket),
+}
+
+impl NetworkSocket {
+  async fn connect(specifier: &NetworkSpecifier) -> io::Result<Self> {
+    let address = (specifier.host().as_str(), specifier.port());
+    match specifier.transport() {
+      NetworkTransport::Tcp => timeout(CONNECT_TIMEOUT, TcpStream::connect(address))
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?
+        .map(Self::Tcp),
+      // UDP has no handshake, so "connecting" just fixes the remote address for sends and filters
+      // what we receive to packets from the device.
+      NetworkTransport::Udp => {
+        let remote = lookup_host(address)
+          .await?
+          .next()
+          .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let local = if remote.is_ipv4() {
+          SocketAddr::from(([0, 0, 0, 0], 0))
+        } else {
+          SocketAddr::from(([0u16; 8], 0))
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(remote).await?;
+        Ok(Self::Udp(socket))
+      }
+    }
+  }
+}
+
+pub struct NetworkHardwareConnector {
+  specifier: NetworkSpecifier,
+  socket: Option<NetworkSocket>,
+  claim: Option<AddressClaim>,
+}
+
+impl NetworkHardwareConnector {
+  pub(crate) async fn try_connect(
+    specifier: &NetworkSpecifier,
+    claim: AddressClaim,
+  ) -> io::Result<Self> {
+    let socket = NetworkSocket::connect(specifier).await?;
+    Ok(Self {
+      specifier: specifier.clone(),
+      socket: Some(socket),
+      claim: Some(claim),
+    })
+  }
+
+  pub fn name(&self) -> String {
+    format!("Network Device {}", self.specifier)
+  }
+
+  pub fn address(&self) -> String {
+    self.specifier.to_string()
+  }
+}
+
+impl Debug for NetworkHardwareConnector {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("NetworkHardwareConnector")
+      .field("specifier", &self.specifier)
+      .finish()
+  }
+}
+
+#[async_trait]
+impl HardwareConnector for NetworkHardwareConnector {
+  fn specifier(&self) -> ProtocolCommunicationSpecifier {
+    ProtocolCommunicationSpecifier::Network(self.specifier.clone())
+  }
+
+  async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
+    let (Some(socket), Some(claim)) = (self.socket.take(), self.claim.take()) else {
+      return Err(ButtplugDeviceError::DeviceConnectionError(format!(
+        "Connection to {} already handed off",
+        self.specifier
+      )));
+    };
+    let hardware_internal = NetworkHardware::new(&self.address(), socket, claim);
+    let hardware = Hardware::new(
+      &self.name(),
+      &self.address(),
+      &[Endpoint::Rx, Endpoint::Tx],
+      &None,
+      false,
+      Box::new(hardware_internal),
+    );
+    Ok(Box::new(GenericHardwareSpecializer::new(hardware)))
+  }
+}
+
+/// Marks the device as disconnected and lets the device know, if that hasn't already happened.
+fn notify_disconnect(
+  connected: &AtomicBool,
+  event_sender: &broadcast::Sender<HardwareEvent>,
+  address: &str,
+) {
+  if connected.swap(false, Ordering::Relaxed) && event_sender.receiver_count() != 0 {
+    let _ = event_sender.send(HardwareEvent::Disconnected(address.to_owned()));
+  }
+}
+
+async fn run_tcp_loop(
+  address: &str,
+  stream: TcpStream,
+  outgoing_receiver: &mut mpsc::Receiver<Vec<u8>>,
+  incoming_sender: &broadcast::Sender<Vec<u8>>,
+  token: &CancellationToken,
+) {
+  let (mut reader, mut writer) = stream.into_split();
+  let mut buf = [0u8; 1024];
+  loop {
+    select! {
+      data = outgoing_receiver.recv() => {
+        let Some(data) = data else {
+          break;
+        };
+        if let Err(err) = writer.write_all(&data).await {
+          error!("Cannot write to network device {}, disconnecting: {}", address, err);
+          break;
+        }
+      }
+      result = reader.read(&mut buf) => match result {
+        Ok(0) => {
+          info!("Network device {} closed the connection.", address);
+          break;
+        }
+        Ok(len) => {
+          trace!("Got {} bytes from network device {}", len, address);
+          // If no one is listening, ignore input.
+          let _ = incoming_sender.send(buf[..len].to_vec());
+        }
+        Err(err) => {
+          error!("Cannot read from network device {}, disconnecting: {}", address, err);
+          break;
+        }
+      },
+      _ = token.cancelled() => break,
+    }
+  }
+}
+
+async fn run_udp_loop(
+  address: &str,
+  socket: UdpSocket,
+  outgoing_receiver: &mut mpsc::Receiver<Vec<u8>>,
+  incoming_sender: &broadcast::Sender<Vec<u8>>,
+  token: &CancellationToken,
+) {
+  let mut buf = vec![0u8; u16::MAX as usize];
+  loop {
+    select! {
+      data = outgoing_receiver.recv() => {
+        let Some(data) = data else {
+          break;
+        };
+        if let Err(err) = socket.send(&data).await {
+          error!("Cannot send to network device {}, disconnecting: {}", address, err);
+          break;
+        }
+      }
+      // The only errors we'll see here are ICMP unreachable messages from earlier sends, which is
+      // as close as UDP gets to knowing the device is gone.
+      result = socket.recv(&mut buf) => match result {
+        Ok(len) => {
+          trace!("Got {} bytes from network device {}", len, address);
+          let _ = incoming_sender.send(buf[..len].to_vec());
+        }
+        Err(err) => {
+          error!("Cannot receive from network device {}, disconnecting: {}", address, err);
+          break;
+        }
+      },
+      _ = token.cancelled() => break,
+    }
+  }
+}
+
+pub struct NetworkHardware {
+  address: String,
+  connected: Arc<AtomicBool>,
+  outgoing_sender: mpsc::Sender<Vec<u8>>,
+  rx: BufferedRxEndpoint,
+  device_event_sender: broadcast::Sender<HardwareEvent>,
+  connection_token: CancellationToken,
+}
+
+impl NetworkHardware {
+  fn new(address: &str, socket: NetworkSocket, claim: AddressClaim) -> Self {
+    let (device_event_sender, _) = broadcast::channel(256);
+    let (outgoing_sender, mut outgoing_receiver) = mpsc::channel(256);
+    let connected = Arc::new(AtomicBool::new(true));
+    let connection_token = CancellationToken::new();
+    let rx = BufferedRxEndpoint::new(address, &device_event_sender, &connection_token);
+
+    let loop_address = address.to_owned();
+    let loop_incoming_sender = rx.sender();
+    let loop_event_sender = device_event_sender.clone();
+    let loop_connected = connected.clone();
+    let loop_token = connection_token.clone();
+    buttplug_core::spawn!("NetworkHardware connection loop", async move {
+      match socket {
+        NetworkSocket::Tcp(stream) => {
+          run_tcp_loop(
+            &loop_address,
+            stream,
+            &mut outgoing_receiver,
+            &loop_incoming_sender,
+            &loop_token,
+          )
+          .await
+        }
+        NetworkSocket::Udp(socket) => {
+          run_udp_loop(
+            &loop_address,
+            socket,
+            &mut outgoing_receiver,
+            &loop_incoming_sender,
+            &loop_token,
+          )
+          .await
+        }
+      }
+      notify_disconnect(&loop_connected, &loop_event_sender, &loop_address);
+      // Now that the socket is closed, let the next scan connect to the device again.
+      drop(claim);
+      debug!("Exiting network device {} connection loop.", loop_address);
+    });
+
+    Self {
+      address: address.to_owned(),
+      connected,
+      outgoing_sender,
+      rx,
+      device_event_sender,
+      connection_token,
+    }
+  }
+}
+
+impl HardwareInternal for NetworkHardware {
+  fn event_stream(&self) -> broadcast::Receiver<HardwareEvent> {
+    self.device_event_sender.subscribe()
+  }
+
+  fn disconnect(&self) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.connected.store(false, Ordering::Relaxed);
+    self.connection_token.cancel();
+    future::ready(Ok(())).boxed()
+  }
+
+  fn read_value(
+    &self,
+    msg: &HardwareReadCmd,
+  ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
+    self.rx.read_value(msg)
+  }
+
+  fn write_value(
+    &self,
+    msg: &HardwareWriteCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    if msg.endpoint() != Endpoint::Tx {
+      return future::ready(Err(ButtplugDeviceError::InvalidEndpoint(
+        msg.endpoint().to_string(),
+      )))
+      .boxed();
+    }
+    if !self.connected.load(Ordering::Relaxed) {
+      return future::ready(Err(ButtplugDeviceError::DeviceNotConnected(
+        self.address.clone(),
+      )))
+      .boxed();
+    }
+    let sender = self.outgoing_sender.clone();
+    let data = msg.data().clone();
+    let address = self.address.clone();
+    async move {
+      sender
+        .send(data)
+        .await
+        .map_err(|_| ButtplugDeviceError::DeviceNotConnected(address))
+    }
+    .boxed()
+  }
+
+  fn subscribe(
+    &self,
+    msg: &HardwareSubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.rx.subscribe(msg)
+  }
+
+  fn unsubscribe(
+    &self,
+    msg: &HardwareUnsubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    self.rx.unsubscribe(msg)
+  }
+}
+
+impl Drop for NetworkHardware {
+  fn drop(&mut self) {
+    self.connection_token.cancel();
+  }
+}
@@ -21,7 +21,18 @@ use serialport::available_ports;
 use tokio::sync::mpsc::Sender;
 
 #[derive(Default, Clone)]
-pub struct SerialPortCommunicationManagerBuilder {}
+pub struct SerialPortCommunicationManagerBuilder {
+  ports: Vec<String>,
+}
+
+impl SerialPortCommunicationManagerBuilder {
+  /// If not empty, only these ports (e.g. `COM3`, `/dev/ttyUSB0`) are handed to the device manager.
+  /// Otherwise every port on the system is.
+  pub fn ports(mut self, ports: &[String]) -> Self {
+    self.ports = ports.to_vec();
+    self
+  }
+}
 
 impl HardwareCommunicationManagerBuilder for SerialPortCommunicationManagerBuilder {
   fn finish(
@@ -29,19 +40,23 @@ impl HardwareCommunicationManagerBuilder for SerialPortCommunicationManagerBuild
     sender: Sender<HardwareCommunicationManagerEvent>,
   ) -> Box<dyn HardwareCommunicationManager> {
     Box::new(TimedRetryCommunicationManager::new(
-      SerialPortCommunicationManager::new(sender),
+      SerialPortCommunicationManager::new(sender, &self.ports),
     ))
   }
 }
 
 pub struct SerialPortCommunicationManager {
   sender: Sender<HardwareCommunicationManagerEvent>,
+  ports: Vec<String>,
 }
 
 impl SerialPortCommunicationManager {
-  fn new(sender: Sender<HardwareCommunicationManagerEvent>) -> Self {
+  fn new(sender: Sender<HardwareCommunicationManagerEvent>, ports: &[String]) -> Self {
     trace!("Serial port created.");
-    Self { sender }
+    Self {
+      sender,
+      ports: ports.to_vec(),
+    }
   }
 }
 
@@ -60,7 +75,10 @@ impl TimedRetryCommunicationManagerImpl for SerialPortCommunicationManager {
     match available_ports() {
       Ok(ports) => {
         debug!("Got {} serial ports back", ports.len());
-        for p in ports {
+        for p in ports
+          .into_iter()
+          .filter(|p| self.ports.is_empty() || self.ports.contains(&p.port_name))
+        {
           trace!(
             "Sending serial port {:?} for possible device connection.",
             p
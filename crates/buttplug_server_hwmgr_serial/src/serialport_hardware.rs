@@ -20,10 +20,24 @@ use buttplug_server::device::hardware::{
   HardwareWriteCmd,
   communication::HardwareSpecificError,
 };
-use buttplug_server_device_config::{Endpoint, ProtocolCommunicationSpecifier, SerialSpecifier};
+use buttplug_server_device_config::{
+  Endpoint,
+  ProtocolCommunicationSpecifier,
+  SerialFlowControl,
+  SerialSpecifier,
+};
 use futures::future;
 use futures::{FutureExt, future::BoxFuture};
-use serialport::{SerialPort, SerialPortInfo};
+use serialport::{
+  DataBits,
+  FlowControl,
+  Parity,
+  SerialPort,
+  SerialPortBuilder,
+  SerialPortInfo,
+  SerialPortType,
+  StopBits,
+};
 use std::{
   fmt::{self, Debug},
   io::ErrorKind,
@@ -38,16 +52,24 @@ use tokio::sync::{Mutex, broadcast, mpsc};
 use tokio_util::sync::CancellationToken;
 
 pub struct SerialPortHardwareConnector {
-  specifier: ProtocolCommunicationSpecifier,
+  specifier: SerialSpecifier,
   port_info: SerialPortInfo,
 }
 
 impl SerialPortHardwareConnector {
   pub fn new(port_info: &SerialPortInfo) -> Self {
-    Self {
-      specifier: ProtocolCommunicationSpecifier::Serial(SerialSpecifier::new_from_name(
+    let specifier = if let SerialPortType::UsbPort(usb_info) = &port_info.port_type {
+      SerialSpecifier::new_from_usb_port(
         &port_info.port_name,
-      )),
+        usb_info.vid,
+        usb_info.pid,
+        &usb_info.serial_number,
+      )
+    } else {
+      SerialSpecifier::new_from_name(&port_info.port_name)
+    };
+    Self {
+      specifier,
       port_info: port_info.clone(),
     }
   }
@@ -64,24 +86,27 @@ impl Debug for SerialPortHardwareConnector {
 #[async_trait]
 impl HardwareConnector for SerialPortHardwareConnector {
   fn specifier(&self) -> ProtocolCommunicationSpecifier {
-    self.specifier.clone()
+    ProtocolCommunicationSpecifier::Serial(self.specifier.clone())
   }
 
   async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
     Ok(Box::new(SerialPortHardwareSpecialzier::new(
       &self.port_info,
+      &self.specifier,
     )))
   }
 }
 
 pub struct SerialPortHardwareSpecialzier {
   port_info: SerialPortInfo,
+  specifier: SerialSpecifier,
 }
 
 impl SerialPortHardwareSpecialzier {
-  pub fn new(port_info: &SerialPortInfo) -> Self {
+  pub fn new(port_info: &SerialPortInfo, specifier: &SerialSpecifier) -> Self {
     Self {
       port_info: port_info.clone(),
+      specifier: specifier.clone(),
     }
   }
 }
@@ -94,7 +119,8 @@ impl HardwareSpecializer for SerialPortHardwareSpecialzier {
     &mut self,
     specifiers: &[ProtocolCommunicationSpecifier],
   ) -> Result<Hardware, ButtplugDeviceError> {
-    let hardware_internal = SerialPortHardware::try_create(&self.port_info, specifiers).await?;
+    let hardware_internal =
+      SerialPortHardware::try_create(&self.port_info, &self.specifier, specifiers).await?;
     let hardware = Hardware::new(
       &self.port_info.port_name,
       &self.port_info.port_name,
@@ -107,6 +133,57 @@ impl HardwareSpecializer for SerialPortHardwareSpecialzier {
   }
 }
 
+fn serial_config_error(
+  port_name: &str,
+  port_def: &SerialSpecifier,
+  setting: &str,
+) -> ButtplugDeviceError {
+  ButtplugDeviceError::DeviceConfigurationError(format!(
+    "Invalid {} for serial port {}: {:?}",
+    setting, port_name, port_def
+  ))
+}
+
+/// Turns the serial settings from the device config into a port builder.
+fn serial_port_builder(
+  port_name: &str,
+  port_def: &SerialSpecifier,
+) -> Result<SerialPortBuilder, ButtplugDeviceError> {
+  let data_bits = match port_def.data_bits() {
+    5 => DataBits::Five,
+    6 => DataBits::Six,
+    7 => DataBits::Seven,
+    8 => DataBits::Eight,
+    _ => return Err(serial_config_error(port_name, port_def, "data bits")),
+  };
+  let stop_bits = match port_def.stop_bits() {
+    1 => StopBits::One,
+    2 => StopBits::Two,
+    _ => return Err(serial_config_error(port_name, port_def, "stop bits")),
+  };
+  let parity = match port_def.parity().to_ascii_uppercase() {
+    'N' => Parity::None,
+    'E' => Parity::Even,
+    'O' => Parity::Odd,
+    _ => return Err(serial_config_error(port_name, port_def, "parity")),
+  };
+  let flow_control = match port_def.flow_control() {
+    SerialFlowControl::None => FlowControl::None,
+    SerialFlowControl::Software => FlowControl::Software,
+    SerialFlowControl::Hardware => FlowControl::Hardware,
+  };
+  let mut builder = serialport::new(port_name, *port_def.baud_rate())
+    .data_bits(data_bits)
+    .stop_bits(stop_bits)
+    .parity(parity)
+    .flow_control(flow_control)
+    .timeout(Duration::from_millis(100));
+  if let Some(dtr) = port_def.dtr() {
+    builder = builder.dtr_on_open(*dtr);
+  }
+  Ok(builder)
+}
+
 fn serial_write_thread(mut port: Box<dyn SerialPort>, receiver: mpsc::Receiver<Vec<u8>>) {
   let mut recv = receiver;
   // Instead of waiting on a token here, we'll expect that we'll break on our
@@ -176,43 +253,49 @@ pub struct SerialPortHardware {
 }
 
 impl SerialPortHardware {
+  /// Opens the port described by `port_info`, using the settings from whichever of `specifiers`
+  /// matches `port_specifier` (the specifier generated for the port when it was found).
   pub async fn try_create(
     port_info: &SerialPortInfo,
+    port_specifier: &SerialSpecifier,
     specifiers: &[ProtocolCommunicationSpecifier],
   ) -> Result<Self, ButtplugDeviceError> {
     let (device_event_sender, _) = broadcast::channel(256);
     // If we've gotten this far, we can expect we have a serial port definition.
-    let mut port_def = None;
-    for specifier in specifiers {
-      if let ProtocolCommunicationSpecifier::Serial(serial) = specifier
-        && port_info.port_name == *serial.port()
-      {
-        port_def = Some(serial.clone());
-        break;
-      }
-    }
-    let port_def = port_def.expect("We'll always have a port definition by this point");
+    let port_def = specifiers
+      .iter()
+      .find_map(|specifier| match specifier {
+        ProtocolCommunicationSpecifier::Serial(serial) if serial == port_specifier => {
+          Some(serial.clone())
+        }
+        _ => None,
+      })
+      .expect("We'll always have a port definition by this point");
+    let port_name = port_info.port_name.clone();
+    let port_name_clone = port_name.clone();
+    let port_builder = serial_port_builder(&port_name, &port_def)?;
 
     // This seems like it should be a oneshot, but there's no way to await a
     // value on those?
     let (port_sender, mut port_receiver) = mpsc::channel(1);
-    // Mostly just feeling lazy here and don't wanna do the enum conversions.
-    /*
-    settings.stop_bits = port_def.stop_bits;
-    settings.data_bits = port_def.data_bits;
-    settings.parity = port_def.parity;
-    */
-    // TODO for now, assume 8/N/1. Not really sure when/if this would ever change.
-    let port_name = port_info.port_name.clone();
-    let port_name_clone = port_name.clone();
 
     thread::Builder::new()
       .name("Serial Port Connection Thread".to_string())
       .spawn(move || {
         debug!("Starting serial port connection thread for {}", port_name);
-        let port_result = serialport::new(&port_name, *port_def.baud_rate())
-          .timeout(Duration::from_millis(100))
-          .open();
+        let port_result = port_builder.open().map(|mut port| {
+          // Like DTR on open, this is best effort, as some ports (ptys, some USB adapters) don't
+          // have modem control lines.
+          if let Some(rts) = port_def.rts()
+            && let Err(err) = port.write_request_to_send(*rts)
+          {
+            warn!("Cannot set RTS on serial port {}: {}", port_name, err);
+          }
+          if let Some(delay) = port_def.open_delay_ms() {
+            thread::sleep(Duration::from_millis(*delay as u64));
+          }
+          port
+        });
         if port_sender.blocking_send(port_result)
           .is_err() {
             warn!("Serial port open thread did not return before serial device was dropped. Dropping port.");
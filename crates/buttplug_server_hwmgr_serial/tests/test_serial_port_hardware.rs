@@ -0,0 +1,110 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+// These use a pty pair to stand in for a serial device, which is only something we can count on
+// having on Linux.
+#![cfg(target_os = "linux")]
+
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{HardwareConnector, HardwareWriteCmd};
+use buttplug_server_device_config::{
+  Endpoint,
+  ProtocolCommunicationSpecifier,
+  SerialFlowControl,
+  SerialSpecifier,
+};
+use buttplug_server_hwmgr_serial::SerialPortHardwareConnector;
+use serialport::{FlowControl, SerialPort, SerialPortInfo, SerialPortType, StopBits, TTYPort};
+use std::{io::Read, time::Duration};
+
+/// Returns the device side of a pty pair, along with the port info for the other side, which is
+/// what the hardware will open.
+fn pty_port() -> (TTYPort, SerialPortInfo) {
+  let (device, port) = TTYPort::pair().expect("Test, assuming infallible");
+  let port_info = SerialPortInfo {
+    port_name: port.name().expect("Test, assuming infallible"),
+    port_type: SerialPortType::Unknown,
+  };
+  (device, port_info)
+}
+
+#[tokio::test]
+async fn test_serial_port_hardware_line_settings() {
+  let (mut device, port_info) = pty_port();
+  let mut specifier = SerialSpecifier::new(&port_info.port_name, 19200, 7, 2, 'E');
+  specifier.set_flow_control(SerialFlowControl::Software);
+  specifier.set_open_delay_ms(Some(10));
+
+  let mut connector = SerialPortHardwareConnector::new(&port_info);
+  let hardware = connector
+    .connect()
+    .await
+    .unwrap()
+    .specialize(&[ProtocolCommunicationSpecifier::Serial(specifier)])
+    .await
+    .unwrap();
+
+  // Both sides of a pty share line settings, so we can check what the hardware set from here.
+  // Linux ptys always report 8 data bits and no parity, so those can't be checked this way.
+  assert_eq!(device.baud_rate().unwrap(), 19200);
+  assert_eq!(device.stop_bits().unwrap(), StopBits::Two);
+  assert_eq!(device.flow_control().unwrap(), FlowControl::Software);
+
+  hardware
+    .write_value(&HardwareWriteCmd::new(
+      &[],
+      Endpoint::Tx,
+      b"L0500\n".to_vec(),
+      false,
+    ))
+    .await
+    .unwrap();
+  device.set_timeout(Duration::from_secs(1)).unwrap();
+  let mut buf = [0u8; 6];
+  device.read_exact(&mut buf).unwrap();
+  assert_eq!(&buf, b"L0500\n");
+}
+
+#[tokio::test]
+async fn test_serial_port_hardware_skips_unmatched_port_settings() {
+  let (device, port_info) = pty_port();
+  // A specifier for some other port shouldn't be used for ours, even if it comes first.
+  let other_specifier = SerialSpecifier::new("/dev/ttyUSB99", 9600, 8, 2, 'N');
+  let specifier = SerialSpecifier::new(&port_info.port_name, 38400, 8, 1, 'N');
+
+  let mut connector = SerialPortHardwareConnector::new(&port_info);
+  let _hardware = connector
+    .connect()
+    .await
+    .unwrap()
+    .specialize(&[
+      ProtocolCommunicationSpecifier::Serial(other_specifier),
+      ProtocolCommunicationSpecifier::Serial(specifier),
+    ])
+    .await
+    .unwrap();
+  assert_eq!(device.baud_rate().unwrap(), 38400);
+  assert_eq!(device.stop_bits().unwrap(), StopBits::One);
+}
+
+#[tokio::test]
+async fn test_serial_port_hardware_invalid_line_settings() {
+  let (_device, port_info) = pty_port();
+  let specifier = SerialSpecifier::new(&port_info.port_name, 9600, 9, 1, 'N');
+
+  let mut connector = SerialPortHardwareConnector::new(&port_info);
+  let result = connector
+    .connect()
+    .await
+    .unwrap()
+    .specialize(&[ProtocolCommunicationSpecifier::Serial(specifier)])
+    .await;
+  assert!(matches!(
+    result,
+    Err(ButtplugDeviceError::DeviceConfigurationError(_))
+  ));
+}
@@ -0,0 +1,93 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use super::UsbHardwareConnector;
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::communication::{
+  HardwareCommunicationManager,
+  HardwareCommunicationManagerBuilder,
+  HardwareCommunicationManagerEvent,
+  TimedRetryCommunicationManager,
+  TimedRetryCommunicationManagerImpl,
+};
+use tokio::sync::mpsc::Sender;
+
+#[derive(Default, Clone)]
+pub struct UsbCommunicationManagerBuilder {}
+
+impl HardwareCommunicationManagerBuilder for UsbCommunicationManagerBuilder {
+  fn finish(
+    &mut self,
+    sender: Sender<HardwareCommunicationManagerEvent>,
+  ) -> Box<dyn HardwareCommunicationManager> {
+    Box::new(TimedRetryCommunicationManager::new(
+      UsbCommunicationManager::new(sender),
+    ))
+  }
+}
+
+pub struct UsbCommunicationManager {
+  sender: Sender<HardwareCommunicationManagerEvent>,
+}
+
+impl UsbCommunicationManager {
+  fn new(sender: Sender<HardwareCommunicationManagerEvent>) -> Self {
+    trace!("USB manager created.");
+    Self { sender }
+  }
+}
+
+#[async_trait]
+impl TimedRetryCommunicationManagerImpl for UsbCommunicationManager {
+  fn name(&self) -> &'static str {
+    "UsbCommunicationManager"
+  }
+
+  async fn scan(&self) -> Result<(), ButtplugDeviceError> {
+    trace!("USB manager scanning for devices.");
+    // Enumeration only reads descriptors the OS has cached, so it's fine to do inline. We don't
+    // open anything until the device matches a protocol.
+    let connectors: Vec<_> = match rusb::devices() {
+      Ok(devices) => devices
+        .iter()
+        .filter_map(|device| UsbHardwareConnector::try_new(device).ok())
+        .collect(),
+      Err(e) => {
+        warn!("Cannot enumerate USB devices: {:?}", e);
+        return Ok(());
+      }
+    };
+    debug!("Got {} USB devices back", connectors.len());
+    for connector in connectors {
+      trace!(
+        "Sending USB device {:?} for possible device connection.",
+        connector
+      );
+      if self
+        .sender
+        .send(HardwareCommunicationManagerEvent::DeviceFound {
+          name: connector.name().to_owned(),
+          address: connector.address().to_owned(),
+          creator: Box::new(connector),
+        })
+        .await
+        .is_err()
+      {
+        debug!("Device manager disappeared, exiting.");
+        break;
+      }
+    }
+    Ok(())
+  }
+
+  // libusb is available everywhere we build, though devices may still need drivers (WinUSB) or
+  // permissions before they can be opened.
+  fn can_scan(&self) -> bool {
+    true
+  }
+}
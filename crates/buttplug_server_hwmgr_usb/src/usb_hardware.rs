@@ -0,0 +1,557 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use async_trait::async_trait;
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{
+  Hardware,
+  HardwareConnector,
+  HardwareEvent,
+  HardwareInternal,
+  HardwareReadCmd,
+  HardwareReading,
+  HardwareSpecializer,
+  HardwareSubscribeCmd,
+  HardwareUnsubscribeCmd,
+  HardwareWriteCmd,
+  communication::HardwareSpecificError,
+};
+use buttplug_server_device_config::{
+  Endpoint,
+  ProtocolCommunicationSpecifier,
+  USBEndpointAddress,
+  VIDPIDSpecifier,
+};
+use dashmap::DashMap;
+use futures::{
+  FutureExt,
+  future::{self, BoxFuture},
+};
+use rusb::{Device, DeviceHandle, GlobalContext, TransferType};
+use std::{
+  collections::{BTreeSet, HashMap},
+  fmt::{self, Debug},
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+  thread,
+  time::Duration,
+};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+/// Timeout for writes, and reads that don't specify one.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long subscription threads block on a read before checking whether they've been cancelled.
+const SUBSCRIPTION_POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+fn usb_error(e: rusb::Error) -> ButtplugDeviceError {
+  ButtplugDeviceError::DeviceSpecificError(
+    HardwareSpecificError::HardwareSpecificError("USB".to_owned(), e.to_string()).to_string(),
+  )
+}
+
+pub struct UsbHardwareConnector {
+  device: Device<GlobalContext>,
+  name: String,
+  address: String,
+  vendor_id: u16,
+  product_id: u16,
+}
+
+impl UsbHardwareConnector {
+  pub fn try_new(device: Device<GlobalContext>) -> Result<Self, rusb::Error> {
+    let descriptor = device.device_descriptor()?;
+    // Bus addresses change every time a device is plugged in, so use the port path instead. This
+    // stays the same as long as the device goes back into the same port.
+    let ports: Vec<_> = device
+      .port_numbers()?
+      .iter()
+      .map(|port| port.to_string())
+      .collect();
+    Ok(Self {
+      name: format!(
+        "USB Device {:04x}:{:04x}",
+        descriptor.vendor_id(),
+        descriptor.product_id()
+      ),
+      address: format!("usb-{}-{}", device.bus_number(), ports.join(".")),
+      vendor_id: descriptor.vendor_id(),
+      product_id: descriptor.product_id(),
+      device,
+    })
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn address(&self) -> &str {
+    &self.address
+  }
+}
+
+impl Debug for UsbHardwareConnector {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("UsbHardwareConnector")
+      .field("address", &self.address)
+      .field("vid", &self.vendor_id)
+      .field("pid", &self.product_id)
+      .finish()
+  }
+}
+
+#[async_trait]
+impl HardwareConnector for UsbHardwareConnector {
+  fn specifier(&self) -> ProtocolCommunicationSpecifier {
+    ProtocolCommunicationSpecifier::USB(VIDPIDSpecifier::new(self.vendor_id, self.product_id))
+  }
+
+  async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
+    Ok(Box::new(UsbHardwareSpecializer {
+      device: self.device.clone(),
+      name: self.name.clone(),
+      address: self.address.clone(),
+      specifier: VIDPIDSpecifier::new(self.vendor_id, self.product_id),
+    }))
+  }
+}
+
+pub struct UsbHardwareSpecializer {
+  device: Device<GlobalContext>,
+  name: String,
+  address: String,
+  specifier: VIDPIDSpecifier,
+}
+
+#[async_trait]
+impl HardwareSpecializer for UsbHardwareSpecializer {
+  // Like serial, we can't open the device until we know which protocol it matched, as the endpoint
+  // map (and therefore which interfaces to claim) comes from the protocol's specifier.
+  async fn specialize(
+    &mut self,
+    specifiers: &[ProtocolCommunicationSpecifier],
+  ) -> Result<Hardware, ButtplugDeviceError> {
+    let endpoints = specifiers
+      .iter()
+      .find_map(|specifier| match specifier {
+        ProtocolCommunicationSpecifier::USB(usb)
+          if *usb == self.specifier && !usb.endpoints().is_empty() =>
+        {
+          Some(usb.endpoints().clone())
+        }
+        _ => None,
+      })
+      .ok_or_else(|| {
+        ButtplugDeviceError::DeviceConnectionError(format!(
+          "No USB endpoints configured for {}",
+          self.name
+        ))
+      })?;
+    let hardware_internal = UsbHardware::try_create(&self.device, &self.address, &endpoints)?;
+    let endpoint_list: Vec<_> = endpoints.keys().copied().collect();
+    Ok(Hardware::new(
+      &self.name,
+      &self.address,
+      &endpoint_list,
+      &None,
+      false,
+      Box::new(hardware_internal),
+    ))
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UsbEndpoint {
+  address: u8,
+  transfer_type: TransferType,
+  max_packet_size: u16,
+}
+
+impl UsbEndpoint {
+  fn read(
+    &self,
+    handle: &DeviceHandle<GlobalContext>,
+    buf: &mut [u8],
+    timeout: Duration,
+  ) -> Result<usize, rusb::Error> {
+    match self.transfer_type {
+      TransferType::Interrupt => handle.read_interrupt(self.address, buf, timeout),
+      _ => handle.read_bulk(self.address, buf, timeout),
+    }
+  }
+
+  fn write(&self, handle: &DeviceHandle<GlobalContext>, data: &[u8]) -> Result<usize, rusb::Error> {
+    match self.transfer_type {
+      TransferType::Interrupt => handle.write_interrupt(self.address, data, TRANSFER_TIMEOUT),
+      _ => handle.write_bulk(self.address, data, TRANSFER_TIMEOUT),
+    }
+  }
+}
+
+enum UsbTransfer {
+  Write {
+    endpoint: UsbEndpoint,
+    data: Vec<u8>,
+    responder: oneshot::Sender<Result<(), rusb::Error>>,
+  },
+  Read {
+    endpoint: UsbEndpoint,
+    length: usize,
+    timeout: Duration,
+    responder: oneshot::Sender<Result<Vec<u8>, rusb::Error>>,
+  },
+}
+
+/// Marks the device as disconnected and lets the device know, if that hasn't already happened.
+fn notify_disconnect(
+  connected: &AtomicBool,
+  event_sender: &broadcast::Sender<HardwareEvent>,
+  address: &str,
+) {
+  if connected.swap(false, Ordering::Relaxed) && event_sender.receiver_count() != 0 {
+    let _ = event_sender.send(HardwareEvent::Disconnected(address.to_owned()));
+  }
+}
+
+// libusb's synchronous API blocks, so transfers run on their own thread. Running all reads and
+// writes on one thread also keeps them in the order the protocol sent them.
+fn usb_transfer_thread(
+  handle: Arc<DeviceHandle<GlobalContext>>,
+  mut receiver: mpsc::Receiver<UsbTransfer>,
+  connected: Arc<AtomicBool>,
+  event_sender: broadcast::Sender<HardwareEvent>,
+  address: String,
+) {
+  // Exits once the hardware is dropped and the channel closes.
+  while let Some(transfer) = receiver.blocking_recv() {
+    let error = match transfer {
+      UsbTransfer::Write {
+        endpoint,
+        data,
+        responder,
+      } => {
+        let result = endpoint.write(&handle, &data).map(|_| ());
+        let error = result.err();
+        let _ = responder.send(result);
+        error
+      }
+      UsbTransfer::Read {
+        endpoint,
+        length,
+        timeout,
+        responder,
+      } => {
+        let mut buf = vec![0; length];
+        let result = endpoint
+          .read(&handle, &mut buf, timeout)
+          .map(|len| buf[..len].to_vec());
+        let error = result.as_ref().err().copied();
+        let _ = responder.send(result);
+        error
+      }
+    };
+    if error == Some(rusb::Error::NoDevice) {
+      info!("USB device {} gone, exiting transfer thread.", address);
+      notify_disconnect(&connected, &event_sender, &address);
+      break;
+    }
+  }
+}
+
+fn usb_subscription_thread(
+  handle: Arc<DeviceHandle<GlobalContext>>,
+  endpoint: Endpoint,
+  usb_endpoint: UsbEndpoint,
+  connected: Arc<AtomicBool>,
+  event_sender: broadcast::Sender<HardwareEvent>,
+  address: String,
+  token: CancellationToken,
+) {
+  let mut buf = vec![0; usb_endpoint.max_packet_size as usize];
+  while !token.is_cancelled() {
+    match usb_endpoint.read(&handle, &mut buf, SUBSCRIPTION_POLL_TIMEOUT) {
+      Ok(len) => {
+        trace!("Got {} USB bytes on {}", len, endpoint);
+        if event_sender
+          .send(HardwareEvent::Notification(
+            address.clone(),
+            endpoint,
+            buf[..len].to_vec(),
+          ))
+          .is_err()
+        {
+          debug!("No listeners for USB notifications, exiting subscription thread.");
+          break;
+        }
+      }
+      Err(rusb::Error::Timeout) => continue,
+      Err(rusb::Error::NoDevice) => {
+        info!("USB device {} gone, exiting subscription thread.", address);
+        notify_disconnect(&connected, &event_sender, &address);
+        break;
+      }
+      Err(e) => {
+        error!("USB read error on {}: {:?}", endpoint, e);
+        break;
+      }
+    }
+  }
+}
+
+pub struct UsbHardware {
+  address: String,
+  handle: Arc<DeviceHandle<GlobalContext>>,
+  endpoints: HashMap<Endpoint, UsbEndpoint>,
+  transfer_sender: mpsc::Sender<UsbTransfer>,
+  connected: Arc<AtomicBool>,
+  device_event_sender: broadcast::Sender<HardwareEvent>,
+  subscriptions: Arc<DashMap<Endpoint, CancellationToken>>,
+  thread_cancellation_token: CancellationToken,
+}
+
+impl UsbHardware {
+  pub fn try_create(
+    device: &Device<GlobalContext>,
+    address: &str,
+    endpoint_addresses: &HashMap<Endpoint, USBEndpointAddress>,
+  ) -> Result<Self, ButtplugDeviceError> {
+    let config = device.active_config_descriptor().map_err(usb_error)?;
+    let mut endpoints = HashMap::new();
+    for (endpoint, location) in endpoint_addresses {
+      let descriptor = config
+        .interfaces()
+        .filter(|interface| interface.number() == location.interface())
+        .flat_map(|interface| interface.descriptors())
+        .flat_map(|descriptor| descriptor.endpoint_descriptors())
+        .find(|descriptor| descriptor.address() == location.address())
+        .ok_or_else(|| {
+          ButtplugDeviceError::DeviceConnectionError(format!(
+            "USB device has no endpoint {:#04x} on interface {} for {}",
+            location.address(),
+            location.interface(),
+            endpoint
+          ))
+        })?;
+      if !matches!(
+        descriptor.transfer_type(),
+        TransferType::Bulk | TransferType::Interrupt
+      ) {
+        return Err(ButtplugDeviceError::DeviceConnectionError(format!(
+          "USB endpoint {:#04x} for {} is not a bulk or interrupt endpoint",
+          location.address(),
+          endpoint
+        )));
+      }
+      endpoints.insert(
+        *endpoint,
+        UsbEndpoint {
+          address: descriptor.address(),
+          transfer_type: descriptor.transfer_type(),
+          max_packet_size: descriptor.max_packet_size(),
+        },
+      );
+    }
+
+    let handle = device.open().map_err(usb_error)?;
+    // Only supported on Linux. Elsewhere there's no kernel driver for us to detach.
+    let _ = handle.set_auto_detach_kernel_driver(true);
+    let interfaces: BTreeSet<u8> = endpoint_addresses
+      .values()
+      .map(|location| location.interface())
+      .collect();
+    for interface in interfaces {
+      handle.claim_interface(interface).map_err(usb_error)?;
+    }
+    let handle = Arc::new(handle);
+
+    let (device_event_sender, _) = broadcast::channel(256);
+    let connected = Arc::new(AtomicBool::new(true));
+    let (transfer_sender, transfer_receiver) = mpsc::channel(256);
+    let thread_handle = handle.clone();
+    let thread_connected = connected.clone();
+    let thread_event_sender = device_event_sender.clone();
+    let thread_address = address.to_owned();
+    thread::Builder::new()
+      .name("USB Transfer Thread".to_string())
+      .spawn(move || {
+        usb_transfer_thread(
+          thread_handle,
+          transfer_receiver,
+          thread_connected,
+          thread_event_sender,
+          thread_address,
+        )
+      })
+      .expect("Should always be able to create thread");
+
+    Ok(Self {
+      address: address.to_owned(),
+      handle,
+      endpoints,
+      transfer_sender,
+      connected,
+      device_event_sender,
+      subscriptions: Arc::new(DashMap::new()),
+      thread_cancellation_token: CancellationToken::new(),
+    })
+  }
+
+  fn endpoint(&self, endpoint: Endpoint) -> Result<UsbEndpoint, ButtplugDeviceError> {
+    self
+      .endpoints
+      .get(&endpoint)
+      .copied()
+      .ok_or_else(|| ButtplugDeviceError::InvalidEndpoint(endpoint.to_string()))
+  }
+
+  fn transfer<T: Send + 'static>(
+    &self,
+    transfer: UsbTransfer,
+    receiver: oneshot::Receiver<Result<T, rusb::Error>>,
+  ) -> BoxFuture<'static, Result<T, ButtplugDeviceError>> {
+    if !self.connected.load(Ordering::Relaxed) {
+      return future::ready(Err(ButtplugDeviceError::DeviceNotConnected(
+        self.address.clone(),
+      )))
+      .boxed();
+    }
+    let sender = self.transfer_sender.clone();
+    let address = self.address.clone();
+    async move {
+      let disconnected = || ButtplugDeviceError::DeviceNotConnected(address.clone());
+      sender.send(transfer).await.map_err(|_| disconnected())?;
+      receiver
+        .await
+        .map_err(|_| disconnected())?
+        .map_err(usb_error)
+    }
+    .boxed()
+  }
+}
+
+impl HardwareInternal for UsbHardware {
+  fn event_stream(&self) -> broadcast::Receiver<HardwareEvent> {
+    self.device_event_sender.subscribe()
+  }
+
+  fn disconnect(&self) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    let connected = self.connected.clone();
+    self.thread_cancellation_token.cancel();
+    async move {
+      connected.store(false, Ordering::Relaxed);
+      Ok(())
+    }
+    .boxed()
+  }
+
+  fn read_value(
+    &self,
+    msg: &HardwareReadCmd,
+  ) -> BoxFuture<'static, Result<HardwareReading, ButtplugDeviceError>> {
+    let endpoint = match self.endpoint(msg.endpoint()) {
+      Ok(endpoint) => endpoint,
+      Err(e) => return future::ready(Err(e)).boxed(),
+    };
+    let timeout = if msg.timeout_ms() == 0 {
+      TRANSFER_TIMEOUT
+    } else {
+      Duration::from_millis(msg.timeout_ms() as u64)
+    };
+    let (responder, receiver) = oneshot::channel();
+    let read = self.transfer(
+      UsbTransfer::Read {
+        endpoint,
+        length: msg.length() as usize,
+        timeout,
+        responder,
+      },
+      receiver,
+    );
+    let hardware_endpoint = msg.endpoint();
+    async move { Ok(HardwareReading::new(hardware_endpoint, &read.await?)) }.boxed()
+  }
+
+  fn write_value(
+    &self,
+    msg: &HardwareWriteCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    let endpoint = match self.endpoint(msg.endpoint()) {
+      Ok(endpoint) => endpoint,
+      Err(e) => return future::ready(Err(e)).boxed(),
+    };
+    let (responder, receiver) = oneshot::channel();
+    self.transfer(
+      UsbTransfer::Write {
+        endpoint,
+        data: msg.data().clone(),
+        responder,
+      },
+      receiver,
+    )
+  }
+
+  fn subscribe(
+    &self,
+    msg: &HardwareSubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    let endpoint = msg.endpoint();
+    if self.subscriptions.contains_key(&endpoint) {
+      debug!(
+        "Endpoint {} already subscribed, ignoring and returning Ok.",
+        endpoint
+      );
+      return future::ready(Ok(())).boxed();
+    }
+    let usb_endpoint = match self.endpoint(endpoint) {
+      Ok(usb_endpoint) => usb_endpoint,
+      Err(e) => return future::ready(Err(e)).boxed(),
+    };
+    let token = self.thread_cancellation_token.child_token();
+    self.subscriptions.insert(endpoint, token.clone());
+    let handle = self.handle.clone();
+    let connected = self.connected.clone();
+    let event_sender = self.device_event_sender.clone();
+    let address = self.address.clone();
+    thread::Builder::new()
+      .name("USB Subscription Thread".to_string())
+      .spawn(move || {
+        usb_subscription_thread(
+          handle,
+          endpoint,
+          usb_endpoint,
+          connected,
+          event_sender,
+          address,
+          token,
+        )
+      })
+      .expect("Should always be able to create thread");
+    future::ready(Ok(())).boxed()
+  }
+
+  fn unsubscribe(
+    &self,
+    msg: &HardwareUnsubscribeCmd,
+  ) -> BoxFuture<'static, Result<(), ButtplugDeviceError>> {
+    match self.subscriptions.remove(&msg.endpoint()) {
+      Some((_, token)) => token.cancel(),
+      None => debug!(
+        "Endpoint {} already unsubscribed, ignoring and returning Ok.",
+        msg.endpoint()
+      ),
+    }
+    future::ready(Ok(())).boxed()
+  }
+}
+
+impl Drop for UsbHardware {
+  fn drop(&mut self) {
+    self.thread_cancellation_token.cancel();
+  }
+}
@@ -8,8 +8,10 @@
 #[macro_use]
 extern crate log;
 
+pub mod websocket_client_comm_manager;
 pub mod websocket_server_comm_manager;
 pub mod websocket_server_hardware;
 
+pub use websocket_client_comm_manager::*;
 pub use websocket_server_comm_manager::*;
 pub use websocket_server_hardware::*;
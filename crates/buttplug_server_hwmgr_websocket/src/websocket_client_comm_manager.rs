@@ -0,0 +1,315 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Websocket device manager for devices that host their own websocket server, so we have to
+//! connect out to them instead of waiting for them to connect to us.
+//!
+//! Once connected, devices are expected to act exactly like they would with the
+//! [websocket server device manager](crate::websocket_server_comm_manager): the first packet they
+//! send is a [WebsocketServerDeviceCommManagerInitInfo] JSON packet, which we answer with a
+//! [WebsocketServerDeviceCommManagerInitResponse], and after that binary messages map to the Tx/Rx
+//! endpoints.
+
+use super::{
+  websocket_server_comm_manager::{WebsocketDeviceHandshakeConfig, run_handshake},
+  websocket_server_hardware::WebsocketServerHardwareConnector,
+};
+use buttplug_core::ButtplugResultFuture;
+use buttplug_server::device::hardware::communication::{
+  HardwareCommunicationManager,
+  HardwareCommunicationManagerBuilder,
+  HardwareCommunicationManagerEvent,
+};
+use futures::FutureExt;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use tokio::{select, sync::mpsc::Sender, time::sleep};
+use tokio_util::sync::CancellationToken;
+
+/// mDNS service type that devices hosting a websocket server can advertise themselves with. A
+/// `path` TXT record can be used if the websocket isn't served from the root path.
+pub const WEBSOCKET_DEVICE_MDNS_SERVICE_TYPE: &str = "_buttplug-device._tcp.local.";
+
+#[derive(Clone)]
+pub struct WebsocketClientDeviceCommunicationManagerBuilder {
+  device_urls: Vec<String>,
+  use_mdns: bool,
+  reconnect_delay: Duration,
+  handshake: WebsocketDeviceHandshakeConfig,
+}
+
+impl Default for WebsocketClientDeviceCommunicationManagerBuilder {
+  fn default() -> Self {
+    Self {
+      device_urls: vec![],
+      use_mdns: false,
+      reconnect_delay: Duration::from_secs(5),
+      handshake: WebsocketDeviceHandshakeConfig::default(),
+    }
+  }
+}
+
+impl WebsocketClientDeviceCommunicationManagerBuilder {
+  /// Adds a websocket URL (e.g. `ws://192.168.1.50:80/`) to connect to.
+  pub fn add_device_url(mut self, url: &str) -> Self {
+    self.device_urls.push(url.to_owned());
+    self
+  }
+
+  pub fn device_urls(mut self, urls: &[String]) -> Self {
+    self.device_urls = urls.to_vec();
+    self
+  }
+
+  /// If true, also connect to devices advertising [WEBSOCKET_DEVICE_MDNS_SERVICE_TYPE].
+  pub fn use_mdns(mut self, use_mdns: bool) -> Self {
+    self.use_mdns = use_mdns;
+    self
+  }
+
+  /// How long to wait before trying to connect again after a connection fails or drops.
+  pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+    self.reconnect_delay = delay;
+    self
+  }
+
+  /// How long to wait for a device to send its info packet after we connect to it. Defaults to 10s.
+  pub fn handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+    self.handshake.timeout = handshake_timeout;
+    self
+  }
+
+  /// If set, devices must send this secret in their info packet to be accepted.
+  pub fn shared_secret(mut self, secret: &str) -> Self {
+    self.handshake.shared_secret = Some(secret.to_owned());
+    self
+  }
+
+  /// If not empty, only devices with these identifiers are accepted.
+  pub fn allowed_identifiers(mut self, identifiers: &[String]) -> Self {
+    self.handshake.allowed_identifiers = identifiers.iter().cloned().collect();
+    self
+  }
+}
+
+impl HardwareCommunicationManagerBuilder for WebsocketClientDeviceCommunicationManagerBuilder {
+  fn finish(
+    &mut self,
+    sender: Sender<HardwareCommunicationManagerEvent>,
+  ) -> Box<dyn HardwareCommunicationManager> {
+    Box::new(WebsocketClientDeviceCommunicationManager::new(
+      sender,
+      self.clone(),
+    ))
+  }
+}
+
+/// Connects to a device and hands it off to the device manager, then does it all again whenever
+/// the connection fails or drops, until the token is cancelled.
+async fn run_dial_loop(
+  url: String,
+  sender: Sender<HardwareCommunicationManagerEvent>,
+  token: CancellationToken,
+  reconnect_delay: Duration,
+  handshake: WebsocketDeviceHandshakeConfig,
+) {
+  loop {
+    let connection = select! {
+      result = tokio_tungstenite::connect_async(&url) => result,
+      _ = token.cancelled() => return,
+    };
+    match connection {
+      Ok((mut ws_stream, _)) => {
+        info!("Connected to websocket device at {}", url);
+        if let Some(info_packet) = run_handshake(&mut ws_stream, &handshake).await {
+          let connector = WebsocketServerHardwareConnector::new(info_packet.clone(), ws_stream);
+          let connection_token = connector.connection_token();
+          if sender
+            .send(HardwareCommunicationManagerEvent::DeviceFound {
+              name: format!("Websocket Device {}", info_packet.identifier()),
+              address: info_packet.address().clone(),
+              creator: Box::new(connector),
+            })
+            .await
+            .is_err()
+          {
+            error!("Device manager disappeared, exiting.");
+            return;
+          }
+          select! {
+            _ = connection_token.cancelled() => {
+              info!("Connection to websocket device at {} closed.", url);
+            }
+            _ = token.cancelled() => return,
+          }
+        }
+      }
+      Err(err) => {
+        debug!("Cannot connect to websocket device at {}: {}", url, err);
+      }
+    }
+    select! {
+      _ = sleep(reconnect_delay) => {}
+      _ = token.cancelled() => return,
+    }
+  }
+}
+
+fn mdns_service_url(service: &ServiceInfo) -> Option<String> {
+  // Prefer IPv4, as that's what most embedded network stacks will actually be listening on.
+  let address = service
+    .get_addresses()
+    .iter()
+    .find(|address| address.is_ipv4())
+    .or_else(|| service.get_addresses().iter().next())?;
+  let path = service.get_property_val_str("path").unwrap_or("/");
+  Some(format!(
+    "ws://{}{}",
+    SocketAddr::new(*address, service.get_port()),
+    path
+  ))
+}
+
+/// Starts a dial loop for every device that shows up over mDNS, and stops reconnecting to devices
+/// that have stopped advertising.
+async fn run_mdns_discovery(
+  sender: Sender<HardwareCommunicationManagerEvent>,
+  token: CancellationToken,
+  reconnect_delay: Duration,
+  handshake: WebsocketDeviceHandshakeConfig,
+) {
+  let daemon = match ServiceDaemon::new() {
+    Ok(daemon) => daemon,
+    Err(err) => {
+      error!(
+        "Cannot start mDNS discovery for websocket devices: {:?}",
+        err
+      );
+      return;
+    }
+  };
+  let receiver = match daemon.browse(WEBSOCKET_DEVICE_MDNS_SERVICE_TYPE) {
+    Ok(receiver) => receiver,
+    Err(err) => {
+      error!("Cannot browse mDNS for websocket devices: {:?}", err);
+      let _ = daemon.shutdown();
+      return;
+    }
+  };
+  let mut dial_tokens: HashMap<String, CancellationToken> = HashMap::new();
+  loop {
+    let event = select! {
+      event = receiver.recv_async() => match event {
+        Ok(event) => event,
+        Err(_) => break,
+      },
+      _ = token.cancelled() => break,
+    };
+    match event {
+      ServiceEvent::ServiceResolved(service) => {
+        if dial_tokens.contains_key(service.get_fullname()) {
+          continue;
+        }
+        let Some(url) = mdns_service_url(&service) else {
+          continue;
+        };
+        info!(
+          "Found websocket device {} over mDNS at {}",
+          service.get_fullname(),
+          url
+        );
+        let dial_token = token.child_token();
+        dial_tokens.insert(service.get_fullname().to_owned(), dial_token.clone());
+        buttplug_core::spawn!(
+          "WebsocketClientDeviceCommunicationManager dial loop",
+          run_dial_loop(
+            url,
+            sender.clone(),
+            dial_token,
+            reconnect_delay,
+            handshake.clone()
+          )
+        );
+      }
+      ServiceEvent::ServiceRemoved(_, fullname) => {
+        // Connected devices stay connected, we just won't try to reconnect once they drop.
+        if let Some(dial_token) = dial_tokens.remove(&fullname) {
+          debug!("Websocket device {} stopped advertising.", fullname);
+          dial_token.cancel();
+        }
+      }
+      _ => {}
+    }
+  }
+  let _ = daemon.shutdown();
+}
+
+pub struct WebsocketClientDeviceCommunicationManager {
+  cancellation_token: CancellationToken,
+}
+
+impl WebsocketClientDeviceCommunicationManager {
+  fn new(
+    sender: Sender<HardwareCommunicationManagerEvent>,
+    builder: WebsocketClientDeviceCommunicationManagerBuilder,
+  ) -> Self {
+    trace!("Websocket client device manager created.");
+    let cancellation_token = CancellationToken::new();
+    for url in builder.device_urls {
+      buttplug_core::spawn!(
+        "WebsocketClientDeviceCommunicationManager dial loop",
+        run_dial_loop(
+          url,
+          sender.clone(),
+          cancellation_token.child_token(),
+          builder.reconnect_delay,
+          builder.handshake.clone(),
+        )
+      );
+    }
+    if builder.use_mdns {
+      buttplug_core::spawn!(
+        "WebsocketClientDeviceCommunicationManager mDNS discovery",
+        run_mdns_discovery(
+          sender,
+          cancellation_token.child_token(),
+          builder.reconnect_delay,
+          builder.handshake.clone(),
+        )
+      );
+    }
+    Self { cancellation_token }
+  }
+}
+
+impl HardwareCommunicationManager for WebsocketClientDeviceCommunicationManager {
+  fn name(&self) -> &'static str {
+    "WebsocketClientCommunicationManager"
+  }
+
+  // Like the websocket server manager, connections are kept up the whole time the manager exists,
+  // so scanning doesn't change anything.
+  fn start_scanning(&mut self) -> ButtplugResultFuture {
+    debug!("Websocket client manager scanning for devices.");
+    async move { Ok(()) }.boxed()
+  }
+
+  fn stop_scanning(&mut self) -> ButtplugResultFuture {
+    async move { Ok(()) }.boxed()
+  }
+
+  // No restrictions since this is network not hardware.
+  fn can_scan(&self) -> bool {
+    true
+  }
+}
+
+impl Drop for WebsocketClientDeviceCommunicationManager {
+  fn drop(&mut self) {
+    self.cancellation_token.cancel();
+  }
+}
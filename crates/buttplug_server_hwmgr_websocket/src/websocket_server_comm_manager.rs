@@ -12,14 +12,26 @@ use buttplug_server::device::hardware::communication::{
   HardwareCommunicationManagerBuilder,
   HardwareCommunicationManagerEvent,
 };
-use futures::{FutureExt, StreamExt};
+use futures::{FutureExt, SinkExt, StreamExt};
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
-use tokio::{net::TcpListener, select, sync::mpsc::Sender};
+use std::{
+  collections::HashSet,
+  fmt::{self, Debug},
+  time::Duration,
+};
+use tokio::{
+  io::{AsyncRead, AsyncWrite},
+  net::TcpListener,
+  select,
+  sync::mpsc::Sender,
+  time::timeout,
+};
+use tokio_tungstenite::{WebSocketStream, tungstenite::Message};
 use tokio_util::sync::CancellationToken;
 
 // Packet format received from external devices.
-#[derive(Serialize, Deserialize, Debug, Clone, Getters, CopyGetters)]
+#[derive(Serialize, Deserialize, Clone, Getters, CopyGetters)]
 pub struct WebsocketServerDeviceCommManagerInitInfo {
   #[getset(get = "pub")]
   identifier: String,
@@ -27,12 +39,153 @@ pub struct WebsocketServerDeviceCommManagerInitInfo {
   address: String,
   #[getset(get_copy = "pub")]
   version: u32,
+  /// Shared secret, required if the device manager was set up with one.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  #[getset(get = "pub")]
+  secret: Option<String>,
+}
+
+// Hand written so the secret never ends up in logs.
+impl Debug for WebsocketServerDeviceCommManagerInitInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("WebsocketServerDeviceCommManagerInitInfo")
+      .field("identifier", &self.identifier)
+      .field("address", &self.address)
+      .field("version", &self.version)
+      .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+      .finish()
+  }
+}
+
+/// Why a device was turned away during the handshake.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebsocketServerDeviceCommManagerRejectReason {
+  /// First packet was not a valid info packet.
+  InvalidInfo,
+  /// No info packet was received before the handshake timeout.
+  HandshakeTimeout,
+  /// Secret was missing or did not match the one the device manager was set up with.
+  InvalidSecret,
+  /// Identifier is not on the device manager's allowlist.
+  IdentifierNotAllowed,
+}
+
+/// Packet sent back to devices after their info packet, so firmware can show whether it was
+/// accepted. Serialized as e.g. `{"status":"rejected","reason":"invalid_secret"}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum WebsocketServerDeviceCommManagerInitResponse {
+  Accepted,
+  Rejected {
+    reason: WebsocketServerDeviceCommManagerRejectReason,
+  },
+}
+
+/// Rules for accepting devices, shared by the websocket server and client device managers.
+#[derive(Clone, Debug)]
+pub(crate) struct WebsocketDeviceHandshakeConfig {
+  pub(crate) timeout: Duration,
+  pub(crate) shared_secret: Option<String>,
+  /// If empty, any identifier is allowed.
+  pub(crate) allowed_identifiers: HashSet<String>,
+}
+
+impl Default for WebsocketDeviceHandshakeConfig {
+  fn default() -> Self {
+    Self {
+      timeout: Duration::from_secs(10),
+      shared_secret: None,
+      allowed_identifiers: HashSet::new(),
+    }
+  }
+}
+
+// Compare in constant time, so the secret can't be worked out a byte at a time from how long
+// rejections take.
+fn secrets_match(expected: &str, given: &str) -> bool {
+  expected.len() == given.len()
+    && expected
+      .bytes()
+      .zip(given.bytes())
+      .fold(0, |acc, (a, b)| acc | (a ^ b))
+      == 0
+}
+
+impl WebsocketDeviceHandshakeConfig {
+  fn check(
+    &self,
+    info: &WebsocketServerDeviceCommManagerInitInfo,
+  ) -> Result<(), WebsocketServerDeviceCommManagerRejectReason> {
+    if let Some(expected) = &self.shared_secret
+      && !info
+        .secret
+        .as_ref()
+        .is_some_and(|given| secrets_match(expected, given))
+    {
+      return Err(WebsocketServerDeviceCommManagerRejectReason::InvalidSecret);
+    }
+    if !self.allowed_identifiers.is_empty() && !self.allowed_identifiers.contains(&info.identifier)
+    {
+      return Err(WebsocketServerDeviceCommManagerRejectReason::IdentifierNotAllowed);
+    }
+    Ok(())
+  }
+}
+
+/// Waits for the info packet devices send as the first message on a new connection, checks it
+/// against the handshake config, and tells the device whether it was accepted. The connection is
+/// closed if it wasn't.
+pub(crate) async fn run_handshake<S>(
+  ws_stream: &mut WebSocketStream<S>,
+  config: &WebsocketDeviceHandshakeConfig,
+) -> Option<WebsocketServerDeviceCommManagerInitInfo>
+where
+  S: AsyncRead + AsyncWrite + Unpin,
+{
+  let result = match timeout(config.timeout, ws_stream.next()).await {
+    Ok(Some(Ok(Message::Text(info_message)))) => {
+      serde_json::from_str::<WebsocketServerDeviceCommManagerInitInfo>(&info_message)
+        .map_err(|_| WebsocketServerDeviceCommManagerRejectReason::InvalidInfo)
+        .and_then(|info| config.check(&info).map(|_| info))
+    }
+    Ok(Some(Ok(_))) => Err(WebsocketServerDeviceCommManagerRejectReason::InvalidInfo),
+    Ok(_) => {
+      error!("Connection closed before info packet was received.");
+      return None;
+    }
+    Err(_) => Err(WebsocketServerDeviceCommManagerRejectReason::HandshakeTimeout),
+  };
+  let response = match &result {
+    Ok(_) => WebsocketServerDeviceCommManagerInitResponse::Accepted,
+    Err(reason) => WebsocketServerDeviceCommManagerInitResponse::Rejected { reason: *reason },
+  };
+  let response_packet =
+    serde_json::to_string(&response).expect("Response packets always serialize.");
+  if let Err(err) = ws_stream.send(Message::Text(response_packet.into())).await {
+    error!(
+      "Cannot send handshake response, dropping connection: {}",
+      err
+    );
+    return None;
+  }
+  match result {
+    Ok(info) => Some(info),
+    Err(reason) => {
+      warn!("Rejecting websocket device connection: {:?}", reason);
+      if let Err(err) = ws_stream.close(None).await {
+        error!("Error closing connection: {}", err);
+      }
+      None
+    }
+  }
 }
 
 #[derive(Clone)]
 pub struct WebsocketServerDeviceCommunicationManagerBuilder {
   listen_on_all_interfaces: bool,
   server_port: u16,
+  handshake: WebsocketDeviceHandshakeConfig,
 }
 
 impl Default for WebsocketServerDeviceCommunicationManagerBuilder {
@@ -40,6 +193,7 @@ impl Default for WebsocketServerDeviceCommunicationManagerBuilder {
     Self {
       listen_on_all_interfaces: false,
       server_port: 54817,
+      handshake: WebsocketDeviceHandshakeConfig::default(),
     }
   }
 }
@@ -54,6 +208,24 @@ impl WebsocketServerDeviceCommunicationManagerBuilder {
     self.server_port = port;
     self
   }
+
+  /// How long to wait for a device to send its info packet after it connects. Defaults to 10s.
+  pub fn handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+    self.handshake.timeout = handshake_timeout;
+    self
+  }
+
+  /// If set, devices must send this secret in their info packet to be accepted.
+  pub fn shared_secret(mut self, secret: &str) -> Self {
+    self.handshake.shared_secret = Some(secret.to_owned());
+    self
+  }
+
+  /// If not empty, only devices with these identifiers are accepted.
+  pub fn allowed_identifiers(mut self, identifiers: &[String]) -> Self {
+    self.handshake.allowed_identifiers = identifiers.iter().cloned().collect();
+    self
+  }
 }
 
 impl HardwareCommunicationManagerBuilder for WebsocketServerDeviceCommunicationManagerBuilder {
@@ -65,6 +237,7 @@ impl HardwareCommunicationManagerBuilder for WebsocketServerDeviceCommunicationM
       sender,
       self.server_port,
       self.listen_on_all_interfaces,
+      self.handshake.clone(),
     ))
   }
 }
@@ -78,6 +251,7 @@ impl WebsocketServerDeviceCommunicationManager {
     sender: Sender<HardwareCommunicationManagerEvent>,
     port: u16,
     listen_on_all_interfaces: bool,
+    handshake: WebsocketDeviceHandshakeConfig,
   ) -> Self {
     trace!("Websocket server port created.");
     let server_cancellation_token = CancellationToken::new();
@@ -127,21 +301,9 @@ impl WebsocketServerDeviceCommunicationManager {
               // wait for the first packet. We'll have to pass our device event sender off to the newly
               // created event loop, so that it can fire once the info packet is received.
               let sender_clone = sender.clone();
+              let handshake_clone = handshake.clone();
               buttplug_core::spawn!(async move {
-                // TODO Implement a receive timeout here so we don't wait forever
-                if let Some(Ok(tokio_tungstenite::tungstenite::Message::Text(info_message))) =
-                  ws_stream.next().await
-                {
-                  let info_packet: WebsocketServerDeviceCommManagerInitInfo =
-                    if let Ok(packet) = serde_json::from_str(&info_message) {
-                      packet
-                    } else {
-                      error!("Did not receive a valid JSON info packet as the first packet, disconnecting.");
-                      if let Err(err) = ws_stream.close(None).await {
-                        error!("Error closing connection: {}", err);
-                      }
-                      return;
-                    };
+                if let Some(info_packet) = run_handshake(&mut ws_stream, &handshake_clone).await {
                   if sender_clone
                     .send(HardwareCommunicationManagerEvent::DeviceFound {
                       name: format!("Websocket Device {}", info_packet.identifier),
@@ -156,8 +318,6 @@ impl WebsocketServerDeviceCommunicationManager {
                   {
                     error!("Device manager disappeared, exiting.");
                   }
-                } else {
-                  error!("Did not receive info message as first packet, dropping connection.");
                 }
               });
             },
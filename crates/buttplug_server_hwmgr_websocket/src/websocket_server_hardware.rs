@@ -37,7 +37,7 @@ use std::{
   time::Duration,
 };
 use tokio::{
-  net::TcpStream,
+  io::{AsyncRead, AsyncWrite},
   select,
   sync::{
     Mutex,
@@ -46,15 +46,18 @@ use tokio::{
   },
   time::sleep,
 };
+use tokio_tungstenite::WebSocketStream;
 use tokio_util::sync::CancellationToken;
 
-async fn run_connection_loop(
+async fn run_connection_loop<S>(
   address: &str,
   event_sender: broadcast::Sender<HardwareEvent>,
-  ws_stream: tokio_tungstenite::WebSocketStream<TcpStream>,
+  ws_stream: WebSocketStream<S>,
   mut request_receiver: Receiver<Vec<u8>>,
   response_sender: broadcast::Sender<Vec<u8>>,
-) {
+) where
+  S: AsyncRead + AsyncWrite + Unpin,
+{
   info!("Starting websocket server connection event loop.");
 
   let (mut websocket_server_sender, mut websocket_server_receiver) = ws_stream.split();
@@ -160,19 +163,27 @@ pub struct WebsocketServerHardwareConnector {
   outgoing_sender: Sender<Vec<u8>>,
   incoming_broadcaster: broadcast::Sender<Vec<u8>>,
   device_event_sender: broadcast::Sender<HardwareEvent>,
+  connection_token: CancellationToken,
 }
 
 impl WebsocketServerHardwareConnector {
-  pub fn new(
+  /// Takes over an established websocket connection, whether the device connected to us or we
+  /// connected to it.
+  pub fn new<S>(
     info: WebsocketServerDeviceCommManagerInitInfo,
-    ws_stream: tokio_tungstenite::WebSocketStream<TcpStream>,
-  ) -> Self {
+    ws_stream: WebSocketStream<S>,
+  ) -> Self
+  where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+  {
     let (outgoing_sender, outgoing_receiver) = channel(256);
     let (incoming_broadcaster, _) = broadcast::channel(256);
     let incoming_broadcaster_clone = incoming_broadcaster.clone();
     let (device_event_sender, _) = broadcast::channel(256);
     let device_event_sender_clone = device_event_sender.clone();
     let address = info.address().clone();
+    let connection_token = CancellationToken::new();
+    let connection_token_clone = connection_token.clone();
     buttplug_core::spawn!(async move {
       run_connection_loop(
         &address,
@@ -182,14 +193,21 @@ impl WebsocketServerHardwareConnector {
         incoming_broadcaster_clone,
       )
       .await;
+      connection_token_clone.cancel();
     });
     Self {
       info,
       outgoing_sender,
       incoming_broadcaster,
       device_event_sender,
+      connection_token,
     }
   }
+
+  /// Token that is cancelled once the websocket connection closes, for whatever reason.
+  pub fn connection_token(&self) -> CancellationToken {
+    self.connection_token.clone()
+  }
 }
 
 #[async_trait]
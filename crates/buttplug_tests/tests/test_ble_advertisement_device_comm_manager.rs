@@ -0,0 +1,366 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+mod util;
+use async_trait::async_trait;
+use buttplug_client::{
+  ButtplugClientDevice,
+  ButtplugClientEvent,
+  device::{ClientDeviceCommandValue, ClientDeviceOutputCommand},
+};
+use buttplug_core::errors::ButtplugDeviceError;
+use buttplug_server::device::hardware::{
+  HardwareInternal,
+  HardwareWriteCmd,
+  advertisement::{
+    BROADCAST_DEVICE_ADDRESS_PREFIX,
+    BleAdvertisement,
+    BleAdvertisementAdapter,
+    BleAdvertisementCommunicationManagerBuilder,
+    BleAdvertisementHardware,
+  },
+  communication::{HardwareCommunicationManagerBuilder, HardwareCommunicationManagerEvent},
+};
+use buttplug_server_device_config::Endpoint;
+use futures::Stream;
+use std::{
+  collections::HashMap,
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::Duration,
+};
+use tokio::{
+  sync::{broadcast, mpsc},
+  time::{sleep, timeout},
+};
+use util::{test_client_with_comm_manager, wait_for_device_added};
+
+const COMPANY_ID: u16 = 0xfff0;
+const DEVICE_ADDRESS: &str = "AA:BB:CC:DD:EE:FF";
+
+const ADVERTISEMENT_USER_CONFIG: &str = r#"
+{
+  "version": {
+    "major": 4,
+    "minor": 0
+  },
+  "user_configs": {
+    "protocols": {
+      "byte-template": {
+        "communication": [
+          {
+            "btle_advertisement": {
+              "names": [
+                "ADV-*"
+              ],
+              "manufacturer_data": [
+                {
+                  "company": 65520
+                }
+              ]
+            }
+          }
+        ],
+        "defaults": {
+          "name": "Advertisement Vibrator",
+          "id": "5d0a6f2e-93c4-4b8e-a1d7-6e2f0c9b3a10",
+          "byte_template": {
+            "packet": [240, 255, 1, "value"],
+            "battery_offset": 4
+          },
+          "features": [
+            {
+              "index": 0,
+              "id": "5d0a6f2e-93c4-4b8e-a1d7-6e2f0c9b3a11",
+              "output": {
+                "vibrate": {
+                  "value": [0, 100]
+                }
+              }
+            },
+            {
+              "index": 1,
+              "id": "5d0a6f2e-93c4-4b8e-a1d7-6e2f0c9b3a12",
+              "input": {
+                "battery": {
+                  "value": [[0, 100]],
+                  "command": ["Read"]
+                }
+              }
+            }
+          ]
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Stands in for a Bluetooth radio. Advertisements "heard" while not listening are dropped, like a
+/// real radio would.
+struct MockAdvertisementAdapter {
+  can_advertise: bool,
+  listening: AtomicBool,
+  advertisement_sender: broadcast::Sender<BleAdvertisement>,
+  broadcast_sender: mpsc::UnboundedSender<(String, u16, Vec<u8>)>,
+}
+
+impl MockAdvertisementAdapter {
+  fn new() -> (Arc<Self>, mpsc::UnboundedReceiver<(String, u16, Vec<u8>)>) {
+    Self::with_advertising(true)
+  }
+
+  /// Like [MockAdvertisementAdapter::new], but for radios that can only listen when false.
+  fn with_advertising(
+    can_advertise: bool,
+  ) -> (Arc<Self>, mpsc::UnboundedReceiver<(String, u16, Vec<u8>)>) {
+    let (advertisement_sender, _) = broadcast::channel(256);
+    let (broadcast_sender, broadcast_receiver) = mpsc::unbounded_channel();
+    (
+      Arc::new(Self {
+        can_advertise,
+        listening: AtomicBool::new(false),
+        advertisement_sender,
+        broadcast_sender,
+      }),
+      broadcast_receiver,
+    )
+  }
+
+  fn hear(&self, name: &str, data: &[u8]) {
+    if self.listening.load(Ordering::Relaxed) {
+      let _ = self.advertisement_sender.send(BleAdvertisement::new(
+        DEVICE_ADDRESS,
+        name,
+        &HashMap::from([(COMPANY_ID, data.to_vec())]),
+      ));
+    }
+  }
+}
+
+#[async_trait]
+impl BleAdvertisementAdapter for MockAdvertisementAdapter {
+  fn is_available(&self) -> bool {
+    true
+  }
+
+  fn can_advertise(&self) -> bool {
+    self.can_advertise
+  }
+
+  fn advertisements(&self) -> broadcast::Receiver<BleAdvertisement> {
+    self.advertisement_sender.subscribe()
+  }
+
+  async fn start_listening(&self) -> Result<(), ButtplugDeviceError> {
+    self.listening.store(true, Ordering::Relaxed);
+    Ok(())
+  }
+
+  async fn stop_listening(&self) -> Result<(), ButtplugDeviceError> {
+    self.listening.store(false, Ordering::Relaxed);
+    Ok(())
+  }
+
+  async fn advertise(
+    &self,
+    handle: &str,
+    company: u16,
+    data: &[u8],
+  ) -> Result<(), ButtplugDeviceError> {
+    let _ = self
+      .broadcast_sender
+      .send((handle.to_owned(), company, data.to_vec()));
+    Ok(())
+  }
+
+  async fn stop_advertising(&self, _handle: &str) -> Result<(), ButtplugDeviceError> {
+    Ok(())
+  }
+}
+
+// Real devices advertise over and over, so keep doing that until the device shows up.
+async fn hear_until_device_added(
+  event_stream: &mut (impl Stream<Item = ButtplugClientEvent> + Unpin),
+  adapter: &MockAdvertisementAdapter,
+) -> ButtplugClientDevice {
+  timeout(Duration::from_secs(5), async {
+    loop {
+      adapter.hear("ADV-Vibe", &[0x01, 0x02, 75]);
+      if let Ok(device) = timeout(
+        Duration::from_millis(50),
+        wait_for_device_added(event_stream),
+      )
+      .await
+      {
+        return device;
+      }
+    }
+  })
+  .await
+  .expect("Device should be added")
+}
+
+async fn vibrate(device: &ButtplugClientDevice, speed: f64) {
+  device
+    .run_output(&ClientDeviceOutputCommand::Vibrate(
+      ClientDeviceCommandValue::Percent(speed),
+    ))
+    .await
+    .expect("Test, assuming infallible.");
+}
+
+#[tokio::test]
+async fn test_ble_advertisement_device() {
+  let (adapter, mut broadcasts) = MockAdvertisementAdapter::new();
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "BLE Advertisement Test Client",
+    ADVERTISEMENT_USER_CONFIG,
+    BleAdvertisementCommunicationManagerBuilder::new(adapter.clone()),
+  )
+  .await;
+  let device = hear_until_device_added(&mut event_stream, &adapter).await;
+  assert_eq!(device.name(), "Advertisement Vibrator");
+
+  vibrate(&device, 0.5).await;
+  let broadcast = timeout(Duration::from_secs(5), broadcasts.recv())
+    .await
+    .expect("Command should be broadcast")
+    .expect("Test, assuming infallible.");
+  assert_eq!(
+    broadcast,
+    (DEVICE_ADDRESS.to_owned(), COMPANY_ID, vec![0x01, 50])
+  );
+
+  assert_eq!(device.battery().await.unwrap(), 75);
+  // Battery readings follow whatever the device advertised last.
+  adapter.hear("ADV-Vibe", &[0x01, 0x02, 60]);
+  timeout(Duration::from_secs(5), async {
+    while device.battery().await.unwrap() != 60 {
+      sleep(Duration::from_millis(10)).await;
+    }
+  })
+  .await
+  .expect("Battery reading should update");
+}
+
+#[tokio::test]
+async fn test_ble_broadcast_only_device() {
+  let (adapter, mut broadcasts) = MockAdvertisementAdapter::new();
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "BLE Advertisement Test Client",
+    ADVERTISEMENT_USER_CONFIG,
+    BleAdvertisementCommunicationManagerBuilder::new(adapter).broadcast_device("ADV-Listener"),
+  )
+  .await;
+  let device = wait_for_device_added(&mut event_stream).await;
+
+  vibrate(&device, 1.0).await;
+  let broadcast = timeout(Duration::from_secs(5), broadcasts.recv())
+    .await
+    .expect("Command should be broadcast")
+    .expect("Test, assuming infallible.");
+  assert_eq!(
+    broadcast,
+    (
+      format!("{BROADCAST_DEVICE_ADDRESS_PREFIX}ADV-Listener"),
+      COMPANY_ID,
+      vec![0x01, 100]
+    )
+  );
+  // The device has never advertised, so there's nothing to read a battery level from.
+  assert!(device.battery().await.is_err());
+}
+
+#[tokio::test]
+async fn test_ble_listen_only_adapter() {
+  let (adapter, mut broadcasts) = MockAdvertisementAdapter::with_advertising(false);
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "BLE Advertisement Test Client",
+    ADVERTISEMENT_USER_CONFIG,
+    BleAdvertisementCommunicationManagerBuilder::new(adapter.clone())
+      .broadcast_device("ADV-Listener"),
+  )
+  .await;
+  // Broadcast devices are skipped, so the first device added is the one we hear.
+  let device = hear_until_device_added(&mut event_stream, &adapter).await;
+  assert_eq!(device.battery().await.unwrap(), 75);
+
+  // Nothing can be broadcast. Output commands are queued to the device without waiting on the
+  // write, so the failure shows up on the hardware rather than the client.
+  vibrate(&device, 0.5).await;
+  sleep(Duration::from_millis(100)).await;
+  assert!(broadcasts.try_recv().is_err());
+  let hardware = BleAdvertisementHardware::new(
+    &BleAdvertisement::new(DEVICE_ADDRESS, "ADV-Vibe", &HashMap::new()),
+    adapter.clone(),
+  );
+  let write = hardware
+    .write_value(&HardwareWriteCmd::new(
+      &[],
+      Endpoint::Tx,
+      vec![0xf0, 0xff, 0x01, 0x80],
+      false,
+    ))
+    .await;
+  assert!(matches!(
+    write,
+    Err(ButtplugDeviceError::DeviceCommunicationError(_))
+  ));
+}
+
+// Listening starts in the background, so keep advertising until something is reported.
+async fn hear_until_reported(
+  adapter: &MockAdvertisementAdapter,
+  receiver: &mut mpsc::Receiver<HardwareCommunicationManagerEvent>,
+  data: &[u8],
+) -> String {
+  timeout(Duration::from_secs(5), async {
+    loop {
+      adapter.hear("ADV-Vibe", data);
+      if let Ok(Some(HardwareCommunicationManagerEvent::DeviceFound { address, .. })) =
+        timeout(Duration::from_millis(50), receiver.recv()).await
+      {
+        return address;
+      }
+    }
+  })
+  .await
+  .expect("Advertisement should be reported")
+}
+
+#[tokio::test]
+async fn test_ble_advertisement_reported_once_per_scan() {
+  let (adapter, _broadcasts) = MockAdvertisementAdapter::new();
+  let (sender, mut receiver) = mpsc::channel(256);
+  let mut manager =
+    BleAdvertisementCommunicationManagerBuilder::new(adapter.clone()).finish(sender);
+
+  manager.start_scanning().await.unwrap();
+  assert_eq!(
+    hear_until_reported(&adapter, &mut receiver, &[0x01, 0x02, 75]).await,
+    DEVICE_ADDRESS
+  );
+  // Repeats of the last advertisement aren't reported again, but changes are.
+  adapter.hear("ADV-Vibe", &[0x01, 0x02, 75]);
+  sleep(Duration::from_millis(100)).await;
+  assert!(receiver.try_recv().is_err());
+  assert_eq!(
+    hear_until_reported(&adapter, &mut receiver, &[0x01, 0x02, 60]).await,
+    DEVICE_ADDRESS
+  );
+
+  // Stopping forgets what was reported, so the next scan reports the device again.
+  manager.stop_scanning().await.unwrap();
+  manager.start_scanning().await.unwrap();
+  assert_eq!(
+    hear_until_reported(&adapter, &mut receiver, &[0x01, 0x02, 60]).await,
+    DEVICE_ADDRESS
+  );
+}
@@ -6,8 +6,16 @@
 // for full license information.
 
 mod util;
-use buttplug_client::{ButtplugClientDeviceEvent, ButtplugClientError, ButtplugClientEvent};
-use buttplug_core::{errors::ButtplugError, message::OutputType};
+use buttplug_client::{
+  ButtplugClientDeviceEvent,
+  ButtplugClientError,
+  ButtplugClientEvent,
+  device::{ClientDeviceCommandValue, ClientDeviceOutputCommand},
+};
+use buttplug_core::{
+  errors::ButtplugError,
+  message::{DeviceConnectionErrorCode, OutputType, PresetOutputV4},
+};
 use buttplug_server::device::hardware::{HardwareCommand, HardwareWriteCmd};
 use buttplug_server_device_config::{
   Endpoint,
@@ -18,12 +26,19 @@ use buttplug_server_device_config::{
   load_protocol_configs,
 };
 use futures::StreamExt;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+  collections::HashMap,
+  sync::{Arc, atomic::Ordering},
+  time::Duration,
+};
 use tokio::time::sleep;
 use util::test_device_manager::{TestDeviceIdentifier, check_test_recv_value};
 use util::{
+  TestDeviceChannelHost,
   test_client_with_device,
   test_client_with_device_and_custom_dcm,
+  test_client_with_failing_device,
+  test_client_with_reconnecting_device,
   test_device_manager::TestHardwareEvent,
 };
 use uuid::Uuid;
@@ -65,6 +80,193 @@ async fn test_client_device_connected_status() {
   assert!(!client.connected());
 }
 
+#[tokio::test]
+async fn test_client_device_connection_failed() {
+  let identifier = TestDeviceIdentifier::new("Massage Demo", Some("failing-address".to_owned()));
+  let client = test_client_with_failing_device(&identifier).await;
+
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  loop {
+    match event_stream
+      .next()
+      .await
+      .expect("Test, assuming infallible.")
+    {
+      ButtplugClientEvent::DeviceConnectionFailed {
+        name,
+        address,
+        protocols,
+        error_code,
+        error_message,
+      } => {
+        assert_eq!(name, "Massage Demo");
+        assert_eq!(address, "failing-address");
+        assert_eq!(protocols, vec!["aneros".to_owned()]);
+        assert_eq!(error_code, DeviceConnectionErrorCode::Connection);
+        assert_eq!(
+          error_message,
+          "Device connection error: Simulated connection failure"
+        );
+        break;
+      }
+      ButtplugClientEvent::DeviceAdded(_) => panic!("Failing device should never be added"),
+      _ => continue,
+    }
+  }
+  assert!(client.devices().is_empty());
+}
+
+/// Collects the data from the next `count` writes to a test device, sorted so tests don't depend on
+/// the order features were written in.
+async fn recv_write_data(device: &mut TestDeviceChannelHost, count: usize) -> Vec<Vec<u8>> {
+  let mut data = vec![];
+  for _ in 0..count {
+    match tokio::time::timeout(Duration::from_millis(500), device.receiver.recv())
+      .await
+      .expect("No messages received")
+      .expect("Test")
+    {
+      HardwareCommand::Write(cmd) => data.push(cmd.data().clone()),
+      cmd => panic!("Unexpected hardware command {cmd:?}"),
+    }
+  }
+  data.sort();
+  data
+}
+
+#[tokio::test]
+async fn test_client_device_auto_reconnect() {
+  let (client, mut device, mut reconnected_device, _) =
+    test_client_with_reconnecting_device().await;
+
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let mut client_device = None;
+  while let Some(msg) = event_stream.next().await {
+    if let ButtplugClientEvent::DeviceAdded(da) = msg {
+      client_device = Some(da);
+      break;
+    }
+  }
+  let test_device = client_device.expect("Test, assuming infallible.");
+  test_device
+    .run_output(&ClientDeviceOutputCommand::Vibrate(
+      ClientDeviceCommandValue::Steps(10),
+    ))
+    .await
+    .expect("Test, assuming infallible.");
+  assert_eq!(
+    recv_write_data(&mut device, 2).await,
+    vec![vec![0xF1, 10], vec![0xF2, 10]]
+  );
+
+  device
+    .sender
+    .send(TestHardwareEvent::Disconnect)
+    .await
+    .expect("Test, assuming infallible.");
+  let mut removed = false;
+  loop {
+    match tokio::time::timeout(Duration::from_secs(5), event_stream.next())
+      .await
+      .expect("Device should reconnect")
+      .expect("Test, assuming infallible.")
+    {
+      ButtplugClientEvent::DeviceRemoved(_) => removed = true,
+      ButtplugClientEvent::DeviceReconnected(dev) => {
+        assert!(removed);
+        assert_eq!(dev.index(), test_device.index());
+        break;
+      }
+      ButtplugClientEvent::DeviceAdded(_) => panic!("Reconnected device should not be added"),
+      _ => continue,
+    }
+  }
+  // The vibration that was running when the device dropped out is restarted.
+  assert_eq!(
+    recv_write_data(&mut reconnected_device, 2).await,
+    vec![vec![0xF1, 10], vec![0xF2, 10]]
+  );
+}
+
+#[tokio::test]
+async fn test_client_device_auto_reconnect_after_stop() {
+  let (client, mut device, mut reconnected_device, reconnects_held) =
+    test_client_with_reconnecting_device().await;
+
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let mut client_device = None;
+  while let Some(msg) = event_stream.next().await {
+    if let ButtplugClientEvent::DeviceAdded(da) = msg {
+      client_device = Some(da);
+      break;
+    }
+  }
+  let test_device = client_device.expect("Test, assuming infallible.");
+  test_device
+    .run_output(&ClientDeviceOutputCommand::Vibrate(
+      ClientDeviceCommandValue::Steps(10),
+    ))
+    .await
+    .expect("Test, assuming infallible.");
+  assert_eq!(
+    recv_write_data(&mut device, 2).await,
+    vec![vec![0xF1, 10], vec![0xF2, 10]]
+  );
+
+  // Keep the device away until the client has stopped everything.
+  reconnects_held.store(true, Ordering::Relaxed);
+  device
+    .sender
+    .send(TestHardwareEvent::Disconnect)
+    .await
+    .expect("Test, assuming infallible.");
+  loop {
+    if let ButtplugClientEvent::DeviceRemoved(_) = event_stream
+      .next()
+      .await
+      .expect("Test, assuming infallible.")
+    {
+      break;
+    }
+  }
+  client
+    .stop_all_devices()
+    .await
+    .expect("Test, assuming infallible.");
+  reconnects_held.store(false, Ordering::Relaxed);
+  loop {
+    if let ButtplugClientEvent::DeviceReconnected(_) =
+      tokio::time::timeout(Duration::from_secs(5), event_stream.next())
+        .await
+        .expect("Device should reconnect")
+        .expect("Test, assuming infallible.")
+    {
+      break;
+    }
+  }
+  // Stopping devices also covers the ones waiting to reconnect, so nothing is restarted.
+  assert!(
+    tokio::time::timeout(
+      Duration::from_millis(200),
+      reconnected_device.receiver.recv()
+    )
+    .await
+    .is_err()
+  );
+}
+
 #[tokio::test]
 async fn test_client_device_client_disconnected_status() {
   let (client, _) = test_client_with_device().await;
@@ -287,3 +489,38 @@ async fn test_client_range_limits() {
 // TODO Test DeviceList being sent followed by repeat DeviceAdded
 // TODO Test DeviceList being sent multiple times
 // TODO Test sending device return for device that doesn't exist (in client)
+
+#[tokio::test]
+async fn test_client_device_presets() {
+  let (client, mut device) = test_client_with_device().await;
+  let mut event_stream = client.event_stream();
+  assert!(client.start_scanning().await.is_ok());
+
+  while let Some(event) = event_stream.next().await {
+    if let ButtplugClientEvent::DeviceAdded(dev) = event {
+      assert!(
+        dev
+          .store_preset("low", &[PresetOutputV4::new(1, OutputType::Vibrate, 8)])
+          .await
+          .is_ok()
+      );
+      assert!(dev.apply_preset("low").await.is_ok());
+      check_test_recv_value(
+        &Duration::from_millis(150),
+        &mut device,
+        HardwareCommand::Write(HardwareWriteCmd::new(
+          &[Uuid::nil()],
+          Endpoint::Tx,
+          vec![0xF2, 8],
+          false,
+        )),
+      )
+      .await;
+      assert!(matches!(
+        dev.apply_preset("missing").await.unwrap_err(),
+        ButtplugClientError::ButtplugError(ButtplugError::ButtplugDeviceError(..))
+      ));
+      break;
+    }
+  }
+}
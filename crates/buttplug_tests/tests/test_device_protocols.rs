@@ -33,6 +33,8 @@ async fn load_test_case(test_file: &str) -> DeviceTestCase {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -79,6 +81,8 @@ async fn load_test_case(test_file: &str) -> DeviceTestCase {
 #[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 #[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 #[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 #[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_lovense_solace.yaml" ; "Lovense Protocol - Lovense Solace")]
@@ -109,6 +113,7 @@ async fn load_test_case(test_file: &str) -> DeviceTestCase {
 #[test_case("test_satisfyer_triple_vibrator.yaml" ; "Satisfyer Protocol - Triple Vibrator")]
 #[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 #[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 #[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -160,6 +165,8 @@ async fn test_device_protocols_embedded_v4(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -206,6 +213,8 @@ async fn test_device_protocols_embedded_v4(test_file: &str) {
 #[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 #[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 #[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 #[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_lovense_solace.yaml" ; "Lovense Protocol - Lovense Solace")]
@@ -236,6 +245,7 @@ async fn test_device_protocols_embedded_v4(test_file: &str) {
 #[test_case("test_satisfyer_triple_vibrator.yaml" ; "Satisfyer Protocol - Triple Vibrator")]
 #[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 #[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 #[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -286,6 +296,8 @@ async fn test_device_protocols_json_v4(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -332,6 +344,8 @@ async fn test_device_protocols_json_v4(test_file: &str) {
 #[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 #[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 #[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 #[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_lovense_solace.yaml" ; "Lovense Protocol - Lovense Solace")]
@@ -362,6 +376,7 @@ async fn test_device_protocols_json_v4(test_file: &str) {
 #[test_case("test_satisfyer_triple_vibrator.yaml" ; "Satisfyer Protocol - Triple Vibrator")]
 #[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 #[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 #[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -413,6 +428,8 @@ async fn test_device_protocols_embedded_v3(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -459,6 +476,8 @@ async fn test_device_protocols_embedded_v3(test_file: &str) {
 #[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 #[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 #[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 #[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_lovense_solace.yaml" ; "Lovense Protocol - Lovense Solace")]
@@ -489,6 +508,7 @@ async fn test_device_protocols_embedded_v3(test_file: &str) {
 #[test_case("test_satisfyer_triple_vibrator.yaml" ; "Satisfyer Protocol - Triple Vibrator")]
 #[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 #[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 #[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -539,6 +559,8 @@ async fn test_device_protocols_json_v3(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+//#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+//#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -582,6 +604,8 @@ async fn test_device_protocols_json_v3(test_file: &str) {
 //#[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 //#[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 //#[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+//#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+//#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 //#[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_luvmazer_protocol.yaml" ; "Luvmazer Protocol")]
@@ -609,6 +633,7 @@ async fn test_device_protocols_json_v3(test_file: &str) {
 #[test_case("test_sakuraneko_protocol.yaml" ; "Sakuraneko Protocol")]
 //#[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 //#[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+//#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 //#[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -660,6 +685,8 @@ async fn test_device_protocols_embedded_v2(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+//#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+//#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -703,6 +730,8 @@ async fn test_device_protocols_embedded_v2(test_file: &str) {
 //#[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 //#[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 //#[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+//#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+//#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 //#[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_luvmazer_protocol.yaml" ; "Luvmazer Protocol")]
@@ -730,6 +759,7 @@ async fn test_device_protocols_embedded_v2(test_file: &str) {
 #[test_case("test_sakuraneko_protocol.yaml" ; "Sakuraneko Protocol")]
 //#[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 //#[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+//#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 //#[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -781,6 +811,8 @@ async fn test_device_protocols_json_v2(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+//#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+//#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -824,6 +856,8 @@ async fn test_device_protocols_json_v2(test_file: &str) {
 //#[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 //#[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 //#[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+//#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+//#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 //#[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_luvmazer_protocol.yaml" ; "Luvmazer Protocol")]
@@ -851,6 +885,7 @@ async fn test_device_protocols_json_v2(test_file: &str) {
 #[test_case("test_sakuraneko_protocol.yaml" ; "Sakuraneko Protocol")]
 //#[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 //#[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+//#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 //#[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
@@ -901,6 +936,8 @@ async fn test_device_protocols_embedded_v1(test_file: &str) {
 #[test_case("test_ankni_protocol_no_handshake.yaml" ; "Ankni Protocol - No Handshake")]
 #[test_case("test_ankni_protocol.yaml" ; "Ankni Protocol")]
 #[test_case("test_bananasome_protocol.yaml" ; "Bananasome Protocol")]
+//#[test_case("test_byte_template_protocol.yaml" ; "Byte Template Protocol")]
+//#[test_case("test_byte_template_combined_protocol.yaml" ; "Byte Template Protocol Combined Packet")]
 #[test_case("test_cachito_protocol.yaml" ; "Cachito Protocol")]
 #[test_case("test_cowgirl_protocol.yaml" ; "The Cowgirl Protocol")]
 #[test_case("test_cupido_protocol.yaml" ; "Cupido Protocol")]
@@ -944,6 +981,8 @@ async fn test_device_protocols_embedded_v1(test_file: &str) {
 //#[test_case("test_lovense_nora.yaml" ; "Lovense Protocol - Lovense Nora (Vibrate/Rotate)")]
 //#[test_case("test_lovense_osci3.yaml" ; "Lovense Protocol - Osci3")]
 //#[test_case("test_lovense_ridge_user_config.yaml" ; "Lovense Protocol - Lovense Ridge (User Config)")]
+//#[test_case("test_lovense_ridge_response_curve.yaml" ; "Lovense Protocol - Lovense Ridge (Response Curve)")]
+//#[test_case("test_lovense_ridge_safety_limits.yaml" ; "Lovense Protocol - Lovense Ridge (Safety Limits)")]
 //#[test_case("test_lovense_ridge.yaml" ; "Lovense Protocol - Lovense Ridge (Oscillate)")]
 #[test_case("test_lovense_single_vibrator.yaml" ; "Lovense Protocol - Single Vibrator Device")]
 #[test_case("test_luvmazer_protocol.yaml" ; "Luvmazer Protocol")]
@@ -971,6 +1010,7 @@ async fn test_device_protocols_embedded_v1(test_file: &str) {
 #[test_case("test_sakuraneko_protocol.yaml" ; "Sakuraneko Protocol")]
 //#[test_case("test_satisfyer_dual_vibrator.yaml" ; "Satisfyer Protocol - Dual Vibrator")]
 //#[test_case("test_satisfyer_single_vibrator.yaml" ; "Satisfyer Protocol - Single Vibrator")]
+//#[test_case("test_scripted_protocol.yaml" ; "Scripted Protocol")]
 //#[test_case("test_sensee_capsule.yaml" ; "Sensee Capsule Protocol")]
 #[test_case("test_sensee_protocol.yaml" ; "Sensee Diandou Protocol - Rabbit")]
 #[test_case("test_serveu_protocol.yaml" ; "ServeU")]
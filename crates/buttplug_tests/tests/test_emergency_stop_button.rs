@@ -0,0 +1,280 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+mod util;
+use buttplug_client::{
+  ButtplugClient,
+  ButtplugClientDevice,
+  ButtplugClientEvent,
+  device::{ClientDeviceCommandValue, ClientDeviceOutputCommand},
+};
+use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
+use buttplug_core::{
+  errors::ButtplugDeviceError,
+  message::{InputReadingV4, InputType, InputTypeReading, InputValue},
+  util::stream::convert_broadcast_receiver_to_stream,
+};
+use buttplug_server::{
+  ButtplugServer,
+  ButtplugServerBuilder,
+  device::{
+    ServerDeviceManagerBuilder,
+    hardware::{Hardware, HardwareCommand, HardwareEvent, HardwareSubscribeCmd, HardwareWriteCmd},
+    protocol::{
+      GenericProtocolIdentifier,
+      ProtocolHandler,
+      ProtocolIdentifier,
+      ProtocolIdentifierFactory,
+    },
+  },
+  message::ButtplugServerDeviceMessage,
+};
+use buttplug_server_device_config::{Endpoint, load_protocol_configs};
+use futures::{
+  FutureExt,
+  Stream,
+  StreamExt,
+  future::{self, BoxFuture},
+};
+use std::{pin::Pin, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, time::timeout};
+use util::test_device_manager::{
+  TestDeviceChannelHost,
+  TestDeviceCommunicationManagerBuilder,
+  TestDeviceIdentifier,
+  TestHardwareEvent,
+  TestHardwareNotification,
+};
+use uuid::Uuid;
+
+const ESTOP_PROTOCOL_NAME: &str = "estop-button";
+
+/// Vibrator with a button that reports its state via notifications on the rx endpoint.
+struct EStopButtonProtocol {
+  event_stream: broadcast::Sender<ButtplugServerDeviceMessage>,
+}
+
+impl Default for EStopButtonProtocol {
+  fn default() -> Self {
+    let (sender, _) = broadcast::channel(256);
+    Self {
+      event_stream: sender,
+    }
+  }
+}
+
+impl ProtocolHandler for EStopButtonProtocol {
+  fn event_stream(&self) -> Pin<Box<dyn Stream<Item = ButtplugServerDeviceMessage> + Send>> {
+    convert_broadcast_receiver_to_stream(self.event_stream.subscribe()).boxed()
+  }
+
+  fn handle_output_vibrate_cmd(
+    &self,
+    _feature_index: u32,
+    feature_id: Uuid,
+    speed: u32,
+  ) -> Result<Vec<HardwareCommand>, ButtplugDeviceError> {
+    Ok(vec![
+      HardwareWriteCmd::new(&[feature_id], Endpoint::Tx, vec![0xa0, speed as u8], false).into(),
+    ])
+  }
+
+  fn handle_input_subscribe_cmd(
+    &self,
+    device_index: u32,
+    device: Arc<Hardware>,
+    feature_index: u32,
+    feature_id: Uuid,
+    _sensor_type: InputType,
+  ) -> BoxFuture<'_, Result<(), ButtplugDeviceError>> {
+    let sender = self.event_stream.clone();
+    async move {
+      let mut hardware_stream = device.event_stream();
+      device
+        .subscribe(&HardwareSubscribeCmd::new(feature_id, Endpoint::Rx))
+        .await?;
+      tokio::spawn(async move {
+        while let Ok(event) = hardware_stream.recv().await {
+          if let HardwareEvent::Notification(_, Endpoint::Rx, data) = event
+            && sender
+              .send(
+                InputReadingV4::new(
+                  device_index,
+                  feature_index,
+                  InputTypeReading::Button(InputValue::new(data[0])),
+                )
+                .into(),
+              )
+              .is_err()
+          {
+            return;
+          }
+        }
+      });
+      Ok(())
+    }
+    .boxed()
+  }
+
+  fn handle_input_unsubscribe_cmd(
+    &self,
+    _device: Arc<Hardware>,
+    _feature_index: u32,
+    _feature_id: Uuid,
+    _sensor_type: InputType,
+  ) -> BoxFuture<'_, Result<(), ButtplugDeviceError>> {
+    future::ready(Ok(())).boxed()
+  }
+}
+
+struct EStopButtonProtocolFactory;
+
+impl ProtocolIdentifierFactory for EStopButtonProtocolFactory {
+  fn identifier(&self) -> &str {
+    ESTOP_PROTOCOL_NAME
+  }
+
+  fn create(&self) -> Box<dyn ProtocolIdentifier> {
+    Box::new(GenericProtocolIdentifier::new(
+      Arc::new(EStopButtonProtocol::default()),
+      ESTOP_PROTOCOL_NAME,
+    ))
+  }
+}
+
+fn setup_estop_server() -> (ButtplugServer, TestDeviceChannelHost) {
+  let user_config = std::fs::read_to_string(
+    std::path::Path::new(&std::env::var("CARGO_MANIFEST_DIR").expect("Should have manifest path"))
+      .join("tests/util/device_test/device_test_case/config/estop_button_user_config.json"),
+  )
+  .expect("Should be able to load user config");
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .expect("Test, assuming infallible.")
+    .finish()
+    .expect("Test, assuming infallible.");
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  let device = builder.add_test_device(&TestDeviceIdentifier::new(
+    "EStop-Button",
+    Some("estop-button-test".to_owned()),
+  ));
+  let mut dm_builder = ServerDeviceManagerBuilder::new(dcm);
+  dm_builder
+    .comm_manager(builder)
+    .protocol_factory(EStopButtonProtocolFactory);
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  (server, device)
+}
+
+async fn connect_estop_device(
+  server: ButtplugServer,
+) -> (
+  ButtplugClient,
+  impl Stream<Item = ButtplugClientEvent>,
+  ButtplugClientDevice,
+) {
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server)
+    .finish();
+  let client = ButtplugClient::new("Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let client_device = timeout(Duration::from_secs(5), async {
+    loop {
+      if let Some(ButtplugClientEvent::DeviceAdded(device)) = event_stream.next().await {
+        return device;
+      }
+    }
+  })
+  .await
+  .expect("Device should be added");
+  (client, event_stream, client_device)
+}
+
+async fn recv_write_data(device: &mut TestDeviceChannelHost) -> Vec<u8> {
+  loop {
+    match timeout(Duration::from_secs(1), device.receiver.recv())
+      .await
+      .expect("Should receive a write")
+      .expect("Test, assuming infallible.")
+    {
+      HardwareCommand::Write(cmd) => return cmd.data().clone(),
+      HardwareCommand::Subscribe(_) => continue,
+      cmd => panic!("Expected a write, got {cmd:?}"),
+    }
+  }
+}
+
+async fn press_button(device: &TestDeviceChannelHost, value: u8) {
+  device
+    .sender
+    .send(TestHardwareEvent::Notifications(vec![
+      TestHardwareNotification::new(Endpoint::Rx, &[value]),
+    ]))
+    .await
+    .expect("Test, assuming infallible.");
+}
+
+#[tokio::test]
+async fn test_emergency_stop_button_engages_stop() {
+  let (server, mut device) = setup_estop_server();
+  let device_manager = server.device_manager();
+  let (_client, mut event_stream, client_device) = connect_estop_device(server).await;
+
+  client_device
+    .run_output(&ClientDeviceOutputCommand::Vibrate(
+      ClientDeviceCommandValue::Percent(0.5),
+    ))
+    .await
+    .expect("Test, assuming infallible.");
+  let mut data = recv_write_data(&mut device).await;
+  while data == vec![0xa0, 0] {
+    data = recv_write_data(&mut device).await;
+  }
+  assert_eq!(data, vec![0xa0, 50]);
+
+  // Releases aren't presses, and leave the device running.
+  press_button(&device, 0).await;
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert!(!device_manager.emergency_stop_engaged());
+
+  press_button(&device, 1).await;
+  timeout(Duration::from_secs(1), async {
+    loop {
+      match event_stream.next().await {
+        Some(ButtplugClientEvent::EmergencyStopStateChanged(engaged)) => {
+          assert!(engaged);
+          return;
+        }
+        _ => continue,
+      }
+    }
+  })
+  .await
+  .expect("Button press should engage the emergency stop");
+  assert!(device_manager.emergency_stop_engaged());
+  assert_eq!(recv_write_data(&mut device).await, vec![0xa0, 0]);
+
+  let error = client_device
+    .run_output(&ClientDeviceOutputCommand::Vibrate(
+      ClientDeviceCommandValue::Percent(0.5),
+    ))
+    .await
+    .expect_err("Output commands should be rejected while the emergency stop is engaged");
+  assert!(
+    format!("{error:?}").contains("EmergencyStopEngaged"),
+    "{error:?}"
+  );
+}
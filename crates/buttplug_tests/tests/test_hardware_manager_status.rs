@@ -0,0 +1,181 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Tests for hardware manager status reporting, and adding/removing comm managers while the
+//! server is running.
+
+mod util;
+
+use async_trait::async_trait;
+use buttplug_client::{ButtplugClient, ButtplugClientEvent};
+use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
+use buttplug_core::{errors::ButtplugDeviceError, message::HardwareManagerStatus};
+use buttplug_server::{
+  ButtplugServerBuilder,
+  ButtplugServerError,
+  device::{
+    ServerDeviceManagerBuilder,
+    hardware::communication::{
+      HardwareCommunicationManager,
+      TimedRetryCommunicationManager,
+      TimedRetryCommunicationManagerImpl,
+    },
+  },
+};
+use futures::StreamExt;
+use std::time::Duration;
+use util::{
+  create_test_dcm,
+  delay_device_communication_manager::DelayDeviceCommunicationManagerBuilder,
+  long_running_scan_comm_manager::{
+    LongRunningScanCommunicationManagerBuilder,
+    LongRunningScanState,
+  },
+};
+
+const MANAGER_NAME: &str = "LongRunningScanCommunicationManager";
+
+#[tokio::test]
+async fn test_status_sent_when_scanning_starts() {
+  let state = LongRunningScanState::default();
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder.comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+    state.clone(),
+  ));
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server)
+    .finish();
+  let client = ButtplugClient::new("Status Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  let mut event_stream = client.event_stream();
+  client.start_scanning().await.unwrap();
+
+  let status = tokio::time::timeout(Duration::from_secs(5), async {
+    while let Some(event) = event_stream.next().await {
+      if let ButtplugClientEvent::HardwareManagerStatusChanged { name, status } = event
+        && name == MANAGER_NAME
+      {
+        return status;
+      }
+    }
+    panic!("Client event stream ended before status was received");
+  })
+  .await
+  .expect("Test, assuming infallible.");
+  assert_eq!(status, HardwareManagerStatus::Scanning);
+}
+
+#[tokio::test]
+async fn test_add_and_remove_comm_manager_at_runtime() {
+  let device_manager = ServerDeviceManagerBuilder::new(create_test_dcm())
+    .finish()
+    .unwrap();
+  assert!(device_manager.comm_manager_statuses().is_empty());
+
+  let state = LongRunningScanState::default();
+  device_manager
+    .add_comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+      state.clone(),
+    ))
+    .await
+    .unwrap();
+  assert_eq!(
+    device_manager.comm_manager_statuses().get(MANAGER_NAME),
+    Some(&HardwareManagerStatus::Available)
+  );
+  assert!(matches!(
+    device_manager
+      .add_comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+        state.clone()
+      ))
+      .await,
+    Err(ButtplugServerError::DeviceCommunicationManagerTypeAlreadyAdded(_))
+  ));
+
+  device_manager
+    .remove_comm_manager(MANAGER_NAME)
+    .await
+    .unwrap();
+  assert!(device_manager.comm_manager_statuses().is_empty());
+  assert!(matches!(
+    device_manager.remove_comm_manager(MANAGER_NAME).await,
+    Err(ButtplugServerError::DeviceCommunicationManagerDoesNotExist(
+      _
+    ))
+  ));
+}
+
+#[tokio::test]
+async fn test_added_comm_manager_joins_running_scan() {
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder.comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+    LongRunningScanState::default(),
+  ));
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  let device_manager = server.device_manager();
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server)
+    .finish();
+  let client = ButtplugClient::new("Status Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  client.start_scanning().await.unwrap();
+
+  // The delayed manager has a different name, so it can sit alongside the long running one.
+  device_manager
+    .add_comm_manager(DelayDeviceCommunicationManagerBuilder::default())
+    .await
+    .unwrap();
+  let statuses = device_manager.comm_manager_statuses();
+  assert_eq!(
+    statuses.get("DelayDeviceCommunicationManager"),
+    Some(&HardwareManagerStatus::Scanning)
+  );
+}
+
+#[derive(Default)]
+struct FailingScanCommunicationManager {}
+
+#[async_trait]
+impl TimedRetryCommunicationManagerImpl for FailingScanCommunicationManager {
+  fn name(&self) -> &'static str {
+    "FailingScanCommunicationManager"
+  }
+
+  fn can_scan(&self) -> bool {
+    true
+  }
+
+  async fn scan(&self) -> Result<(), ButtplugDeviceError> {
+    Err(ButtplugDeviceError::DeviceConnectionError(
+      "Port is busy".to_owned(),
+    ))
+  }
+}
+
+#[tokio::test]
+async fn test_timed_retry_scan_error_reported_as_unavailable() {
+  let mut manager = TimedRetryCommunicationManager::new(FailingScanCommunicationManager::default());
+  assert_eq!(manager.status(), HardwareManagerStatus::Available);
+  manager.start_scanning().await.unwrap();
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  assert!(matches!(
+    manager.status(),
+    HardwareManagerStatus::Unavailable(reason) if reason.contains("Port is busy")
+  ));
+  manager.stop_scanning().await.unwrap();
+}
@@ -0,0 +1,459 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+mod util;
+use buttplug_client::ButtplugClientEvent;
+use buttplug_server::device::hardware::{
+  HardwareEvent,
+  HardwareReadCmd,
+  HardwareSubscribeCmd,
+  communication::{HardwareCommunicationManagerBuilder, HardwareCommunicationManagerEvent},
+};
+use buttplug_server_device_config::{Endpoint, MqttSpecifier, ProtocolCommunicationSpecifier};
+use buttplug_server_hwmgr_mqtt::MqttCommunicationManagerBuilder;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use rumqttc::{
+  ConnAck,
+  ConnectReturnCode,
+  Packet,
+  PubAck,
+  Publish,
+  QoS,
+  SubAck,
+  SubscribeReasonCode,
+  UnsubAck,
+  matches,
+};
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::{TcpListener, TcpStream},
+  select,
+  sync::{broadcast, mpsc},
+  time::{sleep, timeout},
+};
+use util::{
+  TCODE_POSITION_500,
+  send_position,
+  test_client_with_comm_manager,
+  wait_for_device_added,
+};
+use uuid::Uuid;
+
+const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+struct TestBrokerClient {
+  filters: Vec<String>,
+  sender: mpsc::UnboundedSender<Packet>,
+}
+
+#[derive(Default)]
+struct TestBrokerState {
+  retained: Mutex<HashMap<String, Bytes>>,
+  clients: Mutex<HashMap<usize, TestBrokerClient>>,
+}
+
+impl TestBrokerState {
+  fn route(&self, publish: &Publish) {
+    if publish.retain {
+      let mut retained = self.retained.lock().unwrap();
+      if publish.payload.is_empty() {
+        retained.remove(&publish.topic);
+      } else {
+        retained.insert(publish.topic.clone(), publish.payload.clone());
+      }
+    }
+    for client in self.clients.lock().unwrap().values() {
+      if client
+        .filters
+        .iter()
+        .any(|filter| matches(&publish.topic, filter))
+      {
+        let _ = client.sender.send(Packet::Publish(Publish::new(
+          &publish.topic,
+          QoS::AtMostOnce,
+          publish.payload.to_vec(),
+        )));
+      }
+    }
+  }
+
+  fn handle_packet(
+    &self,
+    id: usize,
+    packet: Packet,
+    sender: &mpsc::UnboundedSender<Packet>,
+    published: &broadcast::Sender<Publish>,
+  ) -> bool {
+    match packet {
+      Packet::Connect(_) => {
+        self.clients.lock().unwrap().insert(
+          id,
+          TestBrokerClient {
+            filters: vec![],
+            sender: sender.clone(),
+          },
+        );
+        let _ = sender.send(Packet::ConnAck(ConnAck::new(
+          ConnectReturnCode::Success,
+          false,
+        )));
+      }
+      Packet::Subscribe(subscribe) => {
+        let filters: Vec<String> = subscribe
+          .filters
+          .iter()
+          .map(|filter| filter.path.clone())
+          .collect();
+        let _ = sender.send(Packet::SubAck(SubAck::new(
+          subscribe.pkid,
+          filters
+            .iter()
+            .map(|_| SubscribeReasonCode::Success(QoS::AtMostOnce))
+            .collect(),
+        )));
+        for (topic, payload) in self.retained.lock().unwrap().iter() {
+          if filters.iter().any(|filter| matches(topic, filter)) {
+            let mut publish = Publish::new(topic, QoS::AtMostOnce, payload.to_vec());
+            publish.retain = true;
+            let _ = sender.send(Packet::Publish(publish));
+          }
+        }
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+          client.filters.extend(filters);
+        }
+      }
+      Packet::Unsubscribe(unsubscribe) => {
+        if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+          client
+            .filters
+            .retain(|filter| !unsubscribe.topics.contains(filter));
+        }
+        let _ = sender.send(Packet::UnsubAck(UnsubAck::new(unsubscribe.pkid)));
+      }
+      Packet::Publish(publish) => {
+        if publish.qos == QoS::AtLeastOnce {
+          let _ = sender.send(Packet::PubAck(PubAck::new(publish.pkid)));
+        }
+        let _ = published.send(publish.clone());
+        self.route(&publish);
+      }
+      Packet::PingReq => {
+        let _ = sender.send(Packet::PingResp);
+      }
+      Packet::Disconnect => return false,
+      _ => {}
+    }
+    true
+  }
+}
+
+/// Just enough of an MQTT broker to test against: retained messages, topic filters, and QoS 0/1
+/// publishes (which are always forwarded as QoS 0).
+struct TestBroker {
+  port: u16,
+  state: Arc<TestBrokerState>,
+  published: broadcast::Sender<Publish>,
+}
+
+impl TestBroker {
+  async fn start() -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0")
+      .await
+      .expect("Test, assuming infallible.");
+    let port = listener
+      .local_addr()
+      .expect("Test, assuming infallible.")
+      .port();
+    let state = Arc::new(TestBrokerState::default());
+    let (published, _) = broadcast::channel(256);
+    let accept_state = state.clone();
+    let accept_published = published.clone();
+    tokio::spawn(async move {
+      let mut next_id = 0;
+      while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(Self::run_client(
+          next_id,
+          stream,
+          accept_state.clone(),
+          accept_published.clone(),
+        ));
+        next_id += 1;
+      }
+    });
+    Self {
+      port,
+      state,
+      published,
+    }
+  }
+
+  async fn run_client(
+    id: usize,
+    stream: TcpStream,
+    state: Arc<TestBrokerState>,
+    published: broadcast::Sender<Publish>,
+  ) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Packet>();
+    let mut buf = BytesMut::new();
+    'connection: loop {
+      select! {
+        packet = receiver.recv() => {
+          let Some(packet) = packet else {
+            break;
+          };
+          let mut out = BytesMut::new();
+          packet.write(&mut out, MAX_PACKET_SIZE).expect("Test, assuming infallible.");
+          if writer.write_all(&out).await.is_err() {
+            break;
+          }
+        }
+        read = reader.read_buf(&mut buf) => {
+          if !matches!(read, Ok(len) if len > 0) {
+            break;
+          }
+          while let Ok(packet) = Packet::read(&mut buf, MAX_PACKET_SIZE) {
+            if !state.handle_packet(id, packet, &sender, &published) {
+              break 'connection;
+            }
+          }
+        }
+      }
+    }
+    state.clients.lock().unwrap().remove(&id);
+  }
+
+  /// Publishes a message as if it came from a device.
+  fn publish(&self, topic: &str, payload: &[u8], retain: bool) {
+    let mut publish = Publish::new(topic, QoS::AtMostOnce, payload);
+    publish.retain = retain;
+    self.state.route(&publish);
+  }
+
+  fn announce(&self, device_id: &str, announcement: &str) {
+    self.publish(
+      &format!("buttplug/devices/{device_id}"),
+      announcement.as_bytes(),
+      true,
+    );
+  }
+
+  async fn wait_for_subscription(&self, topic: &str) {
+    timeout(Duration::from_secs(5), async {
+      while !self
+        .state
+        .clients
+        .lock()
+        .unwrap()
+        .values()
+        .any(|client| client.filters.iter().any(|filter| matches(topic, filter)))
+      {
+        sleep(Duration::from_millis(10)).await;
+      }
+    })
+    .await
+    .expect("Manager should subscribe to topic");
+  }
+
+  async fn wait_for_publish(&self, receiver: &mut broadcast::Receiver<Publish>) -> Publish {
+    timeout(Duration::from_secs(5), receiver.recv())
+      .await
+      .expect("Manager should publish")
+      .expect("Test, assuming infallible.")
+  }
+
+  fn manager_builder(&self) -> MqttCommunicationManagerBuilder {
+    MqttCommunicationManagerBuilder::default().broker("127.0.0.1", self.port)
+  }
+}
+
+fn mqtt_user_config(identifier: &str) -> String {
+  format!(
+    r#"
+{{
+  "version": {{
+    "major": 4,
+    "minor": 0
+  }},
+  "user_configs": {{
+    "protocols": {{
+      "tcode-v03": {{
+        "communication": [
+          {{
+            "mqtt": {{
+              "identifier": "{}"
+            }}
+          }}
+        ]
+      }}
+    }}
+  }}
+}}
+"#,
+    identifier
+  )
+}
+
+async fn wait_for_device_removed(
+  event_stream: &mut (impl Stream<Item = ButtplugClientEvent> + Unpin),
+) {
+  loop {
+    if let ButtplugClientEvent::DeviceRemoved(_) =
+      timeout(Duration::from_secs(5), event_stream.next())
+        .await
+        .expect("Device should be removed")
+        .expect("Test, assuming infallible.")
+    {
+      return;
+    }
+  }
+}
+
+#[tokio::test]
+async fn test_mqtt_device() {
+  let broker = TestBroker::start().await;
+  // Announcements are retained, so devices that announced before we connected should show up.
+  broker.announce("stroker-1", r#"{"identifier": "mqtt-stroker"}"#);
+  let mut published = broker.published.subscribe();
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "MQTT DCM Test Client",
+    &mqtt_user_config("mqtt-stroker"),
+    broker.manager_builder(),
+  )
+  .await;
+
+  let device = wait_for_device_added(&mut event_stream).await;
+  send_position(&device).await;
+  let publish = broker.wait_for_publish(&mut published).await;
+  assert_eq!(publish.topic, "buttplug/devices/stroker-1/tx");
+  assert_eq!(publish.payload, TCODE_POSITION_500);
+
+  // Clearing the announcement removes the device.
+  broker.announce("stroker-1", "");
+  wait_for_device_removed(&mut event_stream).await;
+
+  // Devices can pick their own topics, and come back by announcing themselves again.
+  broker.announce(
+    "stroker-1",
+    r#"{"identifier": "mqtt-stroker", "tx_topic": "stroker/commands"}"#,
+  );
+  let device = wait_for_device_added(&mut event_stream).await;
+  send_position(&device).await;
+  let publish = broker.wait_for_publish(&mut published).await;
+  assert_eq!(publish.topic, "stroker/commands");
+  assert_eq!(publish.payload, TCODE_POSITION_500);
+}
+
+#[tokio::test]
+async fn test_mqtt_device_unknown_identifier_ignored() {
+  let broker = TestBroker::start().await;
+  broker.announce("lamp-1", r#"{"identifier": "smart-lamp"}"#);
+  broker.announce("not-json", "this is not an announcement");
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "MQTT DCM Test Client",
+    &mqtt_user_config("mqtt-stroker"),
+    broker.manager_builder(),
+  )
+  .await;
+  assert!(
+    timeout(Duration::from_secs(2), async {
+      loop {
+        if let Some(ButtplugClientEvent::DeviceAdded(_)) = event_stream.next().await {
+          return;
+        }
+      }
+    })
+    .await
+    .is_err()
+  );
+}
+
+#[tokio::test]
+async fn test_mqtt_hardware_rx() {
+  let broker = TestBroker::start().await;
+  broker.announce(
+    "sensor-1",
+    r#"{"identifier": "mqtt-sensor", "rx_topic": "sensor/data"}"#,
+  );
+  let (sender, mut receiver) = mpsc::channel(256);
+  let mut manager = broker.manager_builder().finish(sender);
+  manager
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let HardwareCommunicationManagerEvent::DeviceFound {
+    address,
+    mut creator,
+    ..
+  } = timeout(Duration::from_secs(5), receiver.recv())
+    .await
+    .expect("Device should be found")
+    .expect("Test, assuming infallible.")
+  else {
+    panic!("Expected a device to be found");
+  };
+  assert_eq!(address, "sensor-1");
+  assert_eq!(
+    creator.specifier(),
+    ProtocolCommunicationSpecifier::Mqtt(MqttSpecifier::new("mqtt-sensor"))
+  );
+  let hardware = creator
+    .connect()
+    .await
+    .expect("Test, assuming infallible.")
+    .specialize(&[])
+    .await
+    .expect("Test, assuming infallible.");
+  broker.wait_for_subscription("sensor/data").await;
+
+  // Reads see data that arrived before they were called.
+  broker.publish("sensor/data", b"first", false);
+  let reading = hardware
+    .read_value(&HardwareReadCmd::new(Uuid::nil(), Endpoint::Rx, 0, 1000))
+    .await
+    .expect("Test, assuming infallible.");
+  assert_eq!(reading.data(), b"first");
+
+  let mut events = hardware.event_stream();
+  hardware
+    .subscribe(&HardwareSubscribeCmd::new(Uuid::nil(), Endpoint::Rx))
+    .await
+    .expect("Test, assuming infallible.");
+  broker.publish("sensor/data", b"second", false);
+  loop {
+    match timeout(Duration::from_secs(5), events.recv())
+      .await
+      .expect("Notification should arrive")
+      .expect("Test, assuming infallible.")
+    {
+      HardwareEvent::Notification(_, Endpoint::Rx, data) => {
+        assert_eq!(data, b"second");
+        break;
+      }
+      _ => continue,
+    }
+  }
+
+  // Clearing the announcement disconnects the hardware.
+  broker.announce("sensor-1", "");
+  loop {
+    if let HardwareEvent::Disconnected(address) = timeout(Duration::from_secs(5), events.recv())
+      .await
+      .expect("Device should disconnect")
+      .expect("Test, assuming infallible.")
+    {
+      assert_eq!(address, "sensor-1");
+      break;
+    }
+  }
+}
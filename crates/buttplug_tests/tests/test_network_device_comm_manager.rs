@@ -0,0 +1,149 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+mod util;
+use buttplug_client::ButtplugClientEvent;
+use buttplug_server_device_config::{NetworkSpecifier, NetworkTransport};
+use buttplug_server_hwmgr_network::NetworkCommunicationManagerBuilder;
+use futures::StreamExt;
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+  io::AsyncReadExt,
+  net::{TcpListener, TcpStream, UdpSocket},
+  time::timeout,
+};
+use util::{
+  TCODE_POSITION_500,
+  send_position,
+  test_client_with_comm_manager,
+  wait_for_device_added,
+};
+
+fn network_user_config(specifier: &NetworkSpecifier) -> String {
+  format!(
+    r#"
+{{
+  "version": {{
+    "major": 4,
+    "minor": 0
+  }},
+  "user_configs": {{
+    "protocols": {{
+      "tcode-v03": {{
+        "communication": [
+          {{
+            "network": {{
+              "host": "{}",
+              "port": {},
+              "transport": "{}"
+            }}
+          }}
+        ]
+      }}
+    }}
+  }}
+}}
+"#,
+    specifier.host(),
+    specifier.port(),
+    specifier.transport()
+  )
+}
+
+async fn accept_connection(listener: &TcpListener) -> TcpStream {
+  timeout(Duration::from_secs(5), listener.accept())
+    .await
+    .expect("Manager should connect to device")
+    .expect("Test, assuming infallible.")
+    .0
+}
+
+#[tokio::test]
+async fn test_network_tcp_device() {
+  let listener = TcpListener::bind("127.0.0.1:0")
+    .await
+    .expect("Test, assuming infallible.");
+  let specifier = NetworkSpecifier::new(
+    "127.0.0.1",
+    listener
+      .local_addr()
+      .expect("Test, assuming infallible.")
+      .port(),
+    NetworkTransport::Tcp,
+  );
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "Network DCM Test Client",
+    &network_user_config(&specifier),
+    NetworkCommunicationManagerBuilder::default().add_device(&specifier),
+  )
+  .await;
+
+  let mut stream = accept_connection(&listener).await;
+  let device = wait_for_device_added(&mut event_stream).await;
+  send_position(&device).await;
+  let mut buf = vec![0; TCODE_POSITION_500.len()];
+  timeout(Duration::from_secs(1), stream.read_exact(&mut buf))
+    .await
+    .expect("No data received")
+    .expect("Test, assuming infallible.");
+  assert_eq!(buf, TCODE_POSITION_500);
+
+  // Rescans shouldn't open another connection to a device we're already connected to.
+  assert!(
+    timeout(Duration::from_millis(1500), listener.accept())
+      .await
+      .is_err()
+  );
+
+  // Once the device closes the connection, the next scan should connect to it again.
+  drop(stream);
+  let _stream = accept_connection(&listener).await;
+  let mut removed = false;
+  loop {
+    match timeout(Duration::from_secs(5), event_stream.next())
+      .await
+      .expect("Device should be added again")
+      .expect("Test, assuming infallible.")
+    {
+      ButtplugClientEvent::DeviceRemoved(_) => removed = true,
+      ButtplugClientEvent::DeviceAdded(_) => break,
+      _ => {}
+    }
+  }
+  assert!(removed);
+}
+
+#[tokio::test]
+async fn test_network_udp_device() {
+  let socket = UdpSocket::bind("127.0.0.1:0")
+    .await
+    .expect("Test, assuming infallible.");
+  let specifier = NetworkSpecifier::new(
+    "127.0.0.1",
+    socket
+      .local_addr()
+      .expect("Test, assuming infallible.")
+      .port(),
+    NetworkTransport::Udp,
+  );
+  let (_client, mut event_stream) = test_client_with_comm_manager(
+    "Network DCM Test Client",
+    &network_user_config(&specifier),
+    NetworkCommunicationManagerBuilder::default().add_device(&specifier),
+  )
+  .await;
+
+  // There's no handshake for UDP, so the device shows up before it's heard from us.
+  let device = wait_for_device_added(&mut event_stream).await;
+  send_position(&device).await;
+  let mut buf = vec![0; 1024];
+  let (len, _): (usize, SocketAddr) = timeout(Duration::from_secs(1), socket.recv_from(&mut buf))
+    .await
+    .expect("No data received")
+    .expect("Test, assuming infallible.");
+  assert_eq!(&buf[..len], TCODE_POSITION_500);
+}
@@ -0,0 +1,242 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+mod util;
+use buttplug_client::{
+  ButtplugClient,
+  ButtplugClientDevice,
+  ButtplugClientEvent,
+  device::{ClientDeviceCommandValue, ClientDeviceOutputCommand},
+};
+use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
+use buttplug_server::{
+  ButtplugServerBuilder,
+  device::{
+    ServerDeviceManagerBuilder,
+    hardware::HardwareCommand,
+    protocol_script::ScriptProtocolIdentifierFactory,
+  },
+};
+use buttplug_server_device_config::load_protocol_configs;
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::time::timeout;
+use util::{
+  TestDeviceChannelHost,
+  TestDeviceCommunicationManagerBuilder,
+  test_device_manager::TestDeviceIdentifier,
+};
+
+const SCRIPT_PROTOCOL_NAME: &str = "scripted-vibrator";
+
+async fn setup_script_client(
+  factory: ScriptProtocolIdentifierFactory,
+) -> (ButtplugClient, TestDeviceChannelHost) {
+  let user_config = std::fs::read_to_string(
+    std::path::Path::new(&std::env::var("CARGO_MANIFEST_DIR").expect("Should have manifest path"))
+      .join("tests/util/device_test/device_test_case/config/scripted_vibrator_user_config.json"),
+  )
+  .expect("Should be able to load user config");
+  let dcm = load_protocol_configs(&None, &Some(user_config), false)
+    .expect("Test, assuming infallible.")
+    .finish()
+    .expect("Test, assuming infallible.");
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  let device = builder.add_test_device(&TestDeviceIdentifier::new("SCR-Vibe", None));
+  let mut dm_builder = ServerDeviceManagerBuilder::new(dcm);
+  dm_builder.comm_manager(builder).protocol_factory(factory);
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(
+      ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+        .finish()
+        .unwrap(),
+    )
+    .finish();
+  let client = ButtplugClient::new("Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  (client, device)
+}
+
+async fn connect_script_device(
+  source: &str,
+) -> (ButtplugClient, ButtplugClientDevice, TestDeviceChannelHost) {
+  let factory = ScriptProtocolIdentifierFactory::new(SCRIPT_PROTOCOL_NAME, source)
+    .expect("Script should compile");
+  let (client, device) = setup_script_client(factory).await;
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let client_device = timeout(Duration::from_secs(5), async {
+    loop {
+      if let Some(ButtplugClientEvent::DeviceAdded(device)) = event_stream.next().await {
+        return device;
+      }
+    }
+  })
+  .await
+  .expect("Device should be added");
+  (client, client_device, device)
+}
+
+async fn vibrate(device: &ButtplugClientDevice, speed: f64) -> Result<(), String> {
+  device
+    .run_output(&ClientDeviceOutputCommand::Vibrate(
+      ClientDeviceCommandValue::Percent(speed),
+    ))
+    .await
+    .map_err(|e| format!("{e:?}"))
+}
+
+async fn recv_write_data(device: &mut TestDeviceChannelHost) -> Vec<u8> {
+  match timeout(Duration::from_secs(1), device.receiver.recv())
+    .await
+    .expect("Should receive a write")
+    .expect("Test, assuming infallible.")
+  {
+    HardwareCommand::Write(cmd) => cmd.data().clone(),
+    cmd => panic!("Expected a write, got {cmd:?}"),
+  }
+}
+
+#[tokio::test]
+async fn test_script_keepalive_repeats_last_packet() {
+  let (_client, client_device, mut device) = connect_script_device(
+    r#"
+      fn keepalive_strategy() {
+        #{ repeat_last_packet_ms: 50 }
+      }
+
+      fn handle_output_vibrate_cmd(feature_index, speed) {
+        [#{ endpoint: "tx", data: [0xb0, speed] }]
+      }
+    "#,
+  )
+  .await;
+  // The stop written on connect seeds the keepalive, and goes out before anything else.
+  assert_eq!(recv_write_data(&mut device).await, vec![0xb0, 0]);
+
+  vibrate(&client_device, 0.5)
+    .await
+    .expect("Test, assuming infallible.");
+  // The stop packet may have been repeated while the client was sending the command.
+  let mut data = recv_write_data(&mut device).await;
+  while data == vec![0xb0, 0] {
+    data = recv_write_data(&mut device).await;
+  }
+  assert_eq!(data, vec![0xb0, 50]);
+  // With nothing else sent, the keepalive repeats the last packet.
+  assert_eq!(recv_write_data(&mut device).await, vec![0xb0, 50]);
+}
+
+#[tokio::test]
+async fn test_script_keepalive_seed_write_failure() {
+  // The device has no txmode endpoint, so the stop written on connect to seed the keepalive fails.
+  let factory = ScriptProtocolIdentifierFactory::new(
+    SCRIPT_PROTOCOL_NAME,
+    r#"
+      fn keepalive_strategy() {
+        #{ repeat_last_packet_ms: 50 }
+      }
+
+      fn handle_output_vibrate_cmd(feature_index, speed) {
+        [#{ endpoint: "txmode", data: [0xb0, speed] }]
+      }
+    "#,
+  )
+  .expect("Script should compile");
+  let (client, _device) = setup_script_client(factory).await;
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let error = timeout(Duration::from_secs(5), async {
+    loop {
+      match event_stream.next().await {
+        Some(ButtplugClientEvent::DeviceConnectionFailed { error_message, .. }) => {
+          return error_message;
+        }
+        Some(ButtplugClientEvent::DeviceAdded(_)) => {
+          panic!("Device should not be added when its keepalive can't be written")
+        }
+        _ => continue,
+      }
+    }
+  })
+  .await
+  .expect("Connection should fail when the keepalive can't be written");
+  assert!(error.contains("Error setting up keepalive"), "{error}");
+}
+
+#[tokio::test]
+async fn test_script_hardware_call_timeout() {
+  // Nothing reads from the test device, so its write channel fills up and the script blocks.
+  let factory = ScriptProtocolIdentifierFactory::new_with_hardware_timeout(
+    SCRIPT_PROTOCOL_NAME,
+    r#"
+      fn initialize(hardware, device) {
+        for i in 0..1000 {
+          hardware.write("tx", [0xa0]);
+        }
+      }
+    "#,
+    Duration::from_millis(100),
+  )
+  .expect("Script should compile");
+  let (client, _device) = setup_script_client(factory).await;
+  let mut event_stream = client.event_stream();
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  let error = timeout(Duration::from_secs(5), async {
+    loop {
+      match event_stream.next().await {
+        Some(ButtplugClientEvent::DeviceConnectionFailed { error_message, .. }) => {
+          return error_message;
+        }
+        Some(ButtplugClientEvent::DeviceAdded(_)) => {
+          panic!("Device should not be added when its script times out")
+        }
+        _ => continue,
+      }
+    }
+  })
+  .await
+  .expect("Connection should fail once the script times out");
+  assert!(error.contains("timed out"), "{error}");
+}
+
+#[tokio::test]
+async fn test_script_operation_limit() {
+  let (_client, client_device, mut device) = connect_script_device(
+    r#"
+      fn handle_output_vibrate_cmd(feature_index, speed) {
+        if speed == 100 {
+          loop {}
+        }
+        [#{ endpoint: "tx", data: [0xb0, speed] }]
+      }
+    "#,
+  )
+  .await;
+  let error = vibrate(&client_device, 1.0)
+    .await
+    .expect_err("Runaway script should be stopped");
+  assert!(error.contains("operations"), "{error}");
+
+  // Hitting the limit only fails that call, the device keeps working.
+  vibrate(&client_device, 0.5)
+    .await
+    .expect("Test, assuming infallible.");
+  assert_eq!(recv_write_data(&mut device).await, vec![0xb0, 50]);
+}
@@ -0,0 +1,125 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Tests for per comm manager scan policies.
+
+mod util;
+
+use buttplug_client::{ButtplugClient, ButtplugClientEvent};
+use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
+use buttplug_server::{
+  ButtplugServerBuilder,
+  device::{ScanMode, ScanPolicy, ServerDeviceManagerBuilder},
+};
+use futures::{Stream, StreamExt, pin_mut};
+use std::{sync::atomic::Ordering, time::Duration};
+use util::{
+  create_test_dcm,
+  long_running_scan_comm_manager::{
+    LongRunningScanCommunicationManagerBuilder,
+    LongRunningScanState,
+  },
+  test_device_manager::{TestDeviceCommunicationManagerBuilder, TestDeviceIdentifier},
+};
+
+const LONG_RUNNING_MANAGER: &str = "LongRunningScanCommunicationManager";
+
+async fn connect_client(dm_builder: &mut ServerDeviceManagerBuilder) -> ButtplugClient {
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server)
+    .finish();
+  let client = ButtplugClient::new("Scan Policy Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  client
+}
+
+async fn wait_for_scanning_finished(event_stream: impl Stream<Item = ButtplugClientEvent>) {
+  pin_mut!(event_stream);
+  tokio::time::timeout(Duration::from_secs(5), async {
+    while let Some(event) = event_stream.next().await {
+      if matches!(event, ButtplugClientEvent::ScanningFinished) {
+        return;
+      }
+    }
+    panic!("Client event stream ended before scanning finished");
+  })
+  .await
+  .expect("Test, assuming infallible.");
+}
+
+#[tokio::test]
+async fn test_continuous_manager_ignores_client_scanning() {
+  let state = LongRunningScanState::default();
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder
+    .comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+      state.clone(),
+    ))
+    .scan_policy(
+      LONG_RUNNING_MANAGER,
+      ScanPolicy::new(ScanMode::Continuous, None),
+    );
+  let client = connect_client(&mut dm_builder).await;
+  // Continuous managers start with the device manager, no client needed.
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert!(state.is_scanning.load(Ordering::Relaxed));
+
+  client.start_scanning().await.unwrap();
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  client.stop_scanning().await.unwrap();
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert_eq!(state.start_count.load(Ordering::Relaxed), 1);
+  assert_eq!(state.stop_count.load(Ordering::Relaxed), 0);
+  assert!(state.is_scanning.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn test_continuous_manager_does_not_hold_up_scanning_finished() {
+  let mut test_builder = TestDeviceCommunicationManagerBuilder::default();
+  let _ = test_builder.add_test_device(&TestDeviceIdentifier::new("Massage Demo", None));
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder
+    .comm_manager(test_builder)
+    .comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+      LongRunningScanState::default(),
+    ))
+    .scan_policy(
+      LONG_RUNNING_MANAGER,
+      ScanPolicy::new(ScanMode::Continuous, None),
+    );
+  let client = connect_client(&mut dm_builder).await;
+  let event_stream = client.event_stream();
+  client.start_scanning().await.unwrap();
+  wait_for_scanning_finished(event_stream).await;
+}
+
+#[tokio::test]
+async fn test_scan_duration_limit_finishes_scan() {
+  let state = LongRunningScanState::default();
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder
+    .comm_manager(LongRunningScanCommunicationManagerBuilder::new(
+      state.clone(),
+    ))
+    .scan_policy(
+      LONG_RUNNING_MANAGER,
+      ScanPolicy::new(ScanMode::OnDemand, Some(Duration::from_millis(100))),
+    );
+  let client = connect_client(&mut dm_builder).await;
+  // This manager never sends ScanningFinished on its own, so only the limit can end the scan.
+  let event_stream = client.event_stream();
+  client.start_scanning().await.unwrap();
+  wait_for_scanning_finished(event_stream).await;
+  assert_eq!(state.stop_count.load(Ordering::Relaxed), 1);
+  assert!(!state.is_scanning.load(Ordering::Relaxed));
+}
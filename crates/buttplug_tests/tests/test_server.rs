@@ -6,11 +6,18 @@
 // for full license information.
 
 mod util;
-use buttplug_server_device_config::Endpoint;
+use buttplug_server_device_config::{
+  DevicePreset,
+  Endpoint,
+  PresetOutput,
+  load_protocol_configs,
+  save_user_config,
+};
 use util::test_server;
 pub use util::{
   create_test_dcm,
   test_device_manager::{
+    TestDeviceChannelHost,
     TestDeviceCommunicationManagerBuilder,
     TestDeviceIdentifier,
     check_test_recv_value,
@@ -22,6 +29,7 @@ pub use util::{
 use buttplug_core::{
   errors::{ButtplugDeviceError, ButtplugError, ButtplugHandshakeError},
   message::{
+    ApplyPresetCmdV4,
     BUTTPLUG_CURRENT_API_MAJOR_VERSION,
     BUTTPLUG_CURRENT_API_MINOR_VERSION,
     ButtplugClientMessageV4,
@@ -31,18 +39,25 @@ use buttplug_core::{
     ErrorCode,
     OutputCmdV4,
     OutputCommand,
+    OutputType,
     OutputValue,
     PingV0,
+    PresetOutputV4,
     RequestDeviceListV0,
     RequestServerInfoV4,
     ServerInfoV4,
     StartScanningV0,
+    StopCmdV4,
+    StorePresetCmdV4,
   },
 };
 use buttplug_server::{
   ButtplugServer,
   ButtplugServerBuilder,
   device::{
+    OutputArbitrationPolicy,
+    OutputBlendMode,
+    OutputMixerConfig,
     ServerDeviceManagerBuilder,
     hardware::{HardwareCommand, HardwareWriteCmd},
   },
@@ -59,8 +74,8 @@ use buttplug_server::{
   },
 };
 use futures::{Stream, StreamExt, pin_mut};
-use std::time::Duration;
-use tokio::time::sleep;
+use std::{collections::HashSet, time::Duration};
+use tokio::time::{sleep, timeout};
 use uuid::Uuid;
 
 async fn setup_test_server(
@@ -245,7 +260,11 @@ async fn test_device_stop_on_ping_timeout() {
   // Check that we got an event back about a new device.
   let mut device_index = 100;
   while let Some(msg) = recv.next().await {
-    if let ButtplugServerMessageV4::ScanningFinished(_) = msg {
+    if matches!(
+      msg,
+      ButtplugServerMessageV4::ScanningFinished(_)
+        | ButtplugServerMessageV4::HardwareManagerStatus(_)
+    ) {
       continue;
     } else if let ButtplugServerMessageV4::DeviceList(list) = msg {
       let da = &list.devices()[&0];
@@ -380,7 +399,11 @@ async fn test_device_index_generation() {
   // Check that we got an event back about a new device.
   let mut index = 0u32;
   while let Some(msg) = recv.next().await {
-    if let ButtplugServerMessageV4::ScanningFinished(_) = msg {
+    if matches!(
+      msg,
+      ButtplugServerMessageV4::ScanningFinished(_)
+        | ButtplugServerMessageV4::HardwareManagerStatus(_)
+    ) {
       continue;
     } else if let ButtplugServerMessageV4::DeviceList(list) = msg {
       let da = &list.devices()[&0];
@@ -488,7 +511,11 @@ async fn test_device_list_message_id_on_device_event_vs_request() {
   // Collect the DeviceList event from the event stream (device added notification).
   // This is a system message, so its id must be 0.
   while let Some(msg) = recv.next().await {
-    if let ButtplugServerMessageV4::ScanningFinished(_) = msg {
+    if matches!(
+      msg,
+      ButtplugServerMessageV4::ScanningFinished(_)
+        | ButtplugServerMessageV4::HardwareManagerStatus(_)
+    ) {
       continue;
     } else if let ButtplugServerMessageV4::DeviceList(device_list) = msg {
       assert_eq!(
@@ -528,6 +555,689 @@ async fn test_device_list_message_id_on_device_event_vs_request() {
   }
 }
 
+#[tokio::test]
+async fn test_emergency_stop_latch() {
+  let (server, mut device) = test_server_with_device("Massage Demo");
+  let recv = server.server_version_event_stream();
+  pin_mut!(recv);
+
+  let msg = RequestServerInfoV4::new(
+    "Test Client",
+    BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+    BUTTPLUG_CURRENT_API_MINOR_VERSION,
+  );
+  assert!(
+    server
+      .parse_checked_message(ButtplugCheckedClientMessageV4::from(msg))
+      .await
+      .is_ok()
+  );
+  assert!(
+    server
+      .parse_checked_message(StartScanningV0::default().into())
+      .await
+      .is_ok()
+  );
+  let mut device_index = 100;
+  while let Some(msg) = recv.next().await {
+    if let ButtplugServerMessageV4::DeviceList(list) = msg {
+      device_index = list.devices()[&0].device_index();
+      break;
+    }
+  }
+  let vibrate = |level| {
+    ButtplugCheckedClientMessageV4::from(CheckedOutputCmdV4::new(
+      1,
+      device_index,
+      0,
+      "f50a528b-b023-40f0-9906-df037443950a".try_into().unwrap(),
+      OutputCommand::Vibrate(OutputValue::new(level)),
+    ))
+  };
+  let vibrate_write = |level| {
+    HardwareCommand::Write(HardwareWriteCmd::new(
+      &[Uuid::nil()],
+      Endpoint::Tx,
+      vec![0xF1, level],
+      false,
+    ))
+  };
+
+  assert!(server.parse_checked_message(vibrate(64)).await.is_ok());
+  check_test_recv_value(&Duration::from_millis(150), &mut device, vibrate_write(64)).await;
+
+  // Engaging stops the device and notifies clients.
+  let device_manager = server.device_manager();
+  assert!(device_manager.emergency_stop().await.is_ok());
+  assert!(device_manager.emergency_stop_engaged());
+  check_test_recv_value(&Duration::from_millis(150), &mut device, vibrate_write(0)).await;
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    HardwareCommand::Write(HardwareWriteCmd::new(
+      &[Uuid::nil()],
+      Endpoint::Tx,
+      vec![0xF2, 0],
+      false,
+    )),
+  )
+  .await;
+  match recv.next().await {
+    Some(ButtplugServerMessageV4::EmergencyStopState(state)) => assert!(state.engaged()),
+    msg => panic!("Expected emergency stop state, got {:?}", msg),
+  }
+
+  // Output commands are rejected until reset, but stopping is still allowed.
+  let err = server
+    .parse_checked_message(vibrate(64))
+    .await
+    .expect_err("Output commands should be rejected while the emergency stop is engaged");
+  assert!(matches!(
+    err.original_error(),
+    ButtplugError::ButtplugDeviceError(ButtplugDeviceError::EmergencyStopEngaged)
+  ));
+  assert!(
+    server
+      .parse_checked_message(StopCmdV4::default().into())
+      .await
+      .is_ok()
+  );
+
+  device_manager.reset_emergency_stop();
+  assert!(!device_manager.emergency_stop_engaged());
+  match recv.next().await {
+    Some(ButtplugServerMessageV4::EmergencyStopState(state)) => assert!(!state.engaged()),
+    msg => panic!("Expected emergency stop state, got {:?}", msg),
+  }
+  assert!(server.parse_checked_message(vibrate(32)).await.is_ok());
+  check_test_recv_value(&Duration::from_millis(150), &mut device, vibrate_write(32)).await;
+}
+
+#[tokio::test]
+async fn test_emergency_stop_older_spec_error() {
+  let server = test_server();
+  let recv = server.event_stream();
+  pin_mut!(recv);
+  let msg = ButtplugClientMessageVariant::V3(
+    RequestServerInfoV1::new("Test Client", ButtplugMessageSpecVersion::Version3).into(),
+  );
+  assert!(server.parse_message(msg).await.is_ok());
+  let check_engaged_error = |msg| match msg {
+    Some(ButtplugServerMessageVariant::V3(ButtplugServerMessageV3::Error(e))) => {
+      assert_eq!(e.id(), 0);
+      assert!(matches!(
+        e.original_error(),
+        ButtplugError::ButtplugDeviceError(ButtplugDeviceError::EmergencyStopEngaged)
+      ));
+    }
+    msg => panic!("Expected error, got {:?}", msg),
+  };
+  assert!(server.device_manager().emergency_stop().await.is_ok());
+  check_engaged_error(recv.next().await);
+  // Resets can't be described to older clients, so the next thing they see is the stop engaging
+  // again.
+  server.device_manager().reset_emergency_stop();
+  assert!(server.device_manager().emergency_stop().await.is_ok());
+  check_engaged_error(recv.next().await);
+}
+
+#[tokio::test]
+async fn test_device_connection_failed_older_spec_dropped() {
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  builder.add_failing_test_device(&TestDeviceIdentifier::new("Massage Demo", None));
+  let server = test_server_with_comm_manager(builder);
+  let recv = server.event_stream();
+  pin_mut!(recv);
+  let msg = ButtplugClientMessageVariant::V3(
+    RequestServerInfoV1::new("Test Client", ButtplugMessageSpecVersion::Version3).into(),
+  );
+  assert!(server.parse_message(msg).await.is_ok());
+  let msg = ButtplugClientMessageVariant::V3(ButtplugClientMessageV3::StartScanning(
+    StartScanningV0::default(),
+  ));
+  assert!(server.parse_message(msg).await.is_ok());
+  // Failed connections aren't part of older specs, so nothing but scanning finishing should show
+  // up.
+  let result = timeout(Duration::from_millis(500), async {
+    loop {
+      match recv.next().await {
+        Some(ButtplugServerMessageVariant::V3(ButtplugServerMessageV3::ScanningFinished(_))) => {
+          continue;
+        }
+        msg => panic!("Expected no messages, got {:?}", msg),
+      }
+    }
+  })
+  .await;
+  assert!(result.is_err());
+}
+
+/// Builds two servers sharing a device manager with a connected Massage Demo device, and returns
+/// them along with the device index.
+async fn setup_shared_servers(
+  configure: impl FnOnce(&mut ServerDeviceManagerBuilder),
+) -> (ButtplugServer, ButtplugServer, TestDeviceChannelHost, u32) {
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  let device = builder.add_test_device(&TestDeviceIdentifier::new("Massage Demo", None));
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder.comm_manager(builder);
+  configure(&mut dm_builder);
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  let other_server = ButtplugServerBuilder::with_shared_device_manager(server.device_manager())
+    .finish()
+    .unwrap();
+  let recv = server.server_version_event_stream();
+  pin_mut!(recv);
+  for s in [&server, &other_server] {
+    let msg = RequestServerInfoV4::new(
+      "Test Client",
+      BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+      BUTTPLUG_CURRENT_API_MINOR_VERSION,
+    );
+    assert!(
+      s.parse_checked_message(ButtplugCheckedClientMessageV4::from(msg))
+        .await
+        .is_ok()
+    );
+  }
+  assert!(
+    server
+      .parse_checked_message(StartScanningV0::default().into())
+      .await
+      .is_ok()
+  );
+  let mut device_index = 100;
+  while let Some(msg) = recv.next().await {
+    if let ButtplugServerMessageV4::DeviceList(list) = msg {
+      device_index = list.devices()[&0].device_index();
+      break;
+    }
+  }
+  (server, other_server, device, device_index)
+}
+
+fn massage_demo_vibrate(device_index: u32, level: i32) -> ButtplugCheckedClientMessageV4 {
+  ButtplugCheckedClientMessageV4::from(CheckedOutputCmdV4::new(
+    1,
+    device_index,
+    0,
+    "f50a528b-b023-40f0-9906-df037443950a".try_into().unwrap(),
+    OutputCommand::Vibrate(OutputValue::new(level)),
+  ))
+}
+
+fn massage_demo_write(level: u8) -> HardwareCommand {
+  HardwareCommand::Write(HardwareWriteCmd::new(
+    &[Uuid::nil()],
+    Endpoint::Tx,
+    vec![0xF1, level],
+    false,
+  ))
+}
+
+#[tokio::test]
+async fn test_multiple_sessions_max_arbitration() {
+  let (server, other_server, mut device, device_index) = setup_shared_servers(|builder| {
+    builder.arbitration_policy(OutputArbitrationPolicy::Max);
+  })
+  .await;
+
+  assert!(
+    server
+      .parse_checked_message(massage_demo_vibrate(device_index, 32))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(32),
+  )
+  .await;
+  assert!(
+    other_server
+      .parse_checked_message(massage_demo_vibrate(device_index, 64))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(64),
+  )
+  .await;
+
+  // Disconnecting a session only drops its own contribution.
+  assert!(other_server.disconnect().await.is_ok());
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(32),
+  )
+  .await;
+  assert!(server.disconnect().await.is_ok());
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(0),
+  )
+  .await;
+}
+
+#[tokio::test]
+async fn test_multiple_sessions_exclusive_lock() {
+  let (server, other_server, mut device, device_index) = setup_shared_servers(|builder| {
+    builder.arbitration_policy(OutputArbitrationPolicy::ExclusiveLock {
+      lease: Duration::from_secs(60),
+    });
+  })
+  .await;
+
+  assert!(
+    server
+      .parse_checked_message(massage_demo_vibrate(device_index, 32))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(32),
+  )
+  .await;
+  let err = other_server
+    .parse_checked_message(massage_demo_vibrate(device_index, 64))
+    .await
+    .expect_err("Locked outputs should reject commands from other sessions");
+  assert!(matches!(
+    err.original_error(),
+    ButtplugError::ButtplugDeviceError(ButtplugDeviceError::DeviceFeatureLocked(_, 0))
+  ));
+
+  // Stopping the output releases the lock.
+  assert!(
+    server
+      .parse_checked_message(massage_demo_vibrate(device_index, 0))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(0),
+  )
+  .await;
+  assert!(
+    other_server
+      .parse_checked_message(massage_demo_vibrate(device_index, 64))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(64),
+  )
+  .await;
+}
+
+#[tokio::test]
+async fn test_session_device_visibility() {
+  let (server, _, _device, device_index) = setup_shared_servers(|_| {}).await;
+  let hidden_server = ButtplugServerBuilder::with_shared_device_manager(server.device_manager())
+    .visible_devices(HashSet::new())
+    .finish()
+    .unwrap();
+  let msg = RequestServerInfoV4::new(
+    "Test Client",
+    BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+    BUTTPLUG_CURRENT_API_MINOR_VERSION,
+  );
+  assert!(
+    hidden_server
+      .parse_checked_message(ButtplugCheckedClientMessageV4::from(msg))
+      .await
+      .is_ok()
+  );
+  match hidden_server
+    .parse_checked_message(RequestDeviceListV0::default().into())
+    .await
+  {
+    Ok(ButtplugServerMessageV4::DeviceList(list)) => assert!(list.devices().is_empty()),
+    msg => panic!("Expected device list, got {:?}", msg),
+  }
+  let err = hidden_server
+    .parse_checked_message(massage_demo_vibrate(device_index, 64))
+    .await
+    .expect_err("Hidden devices should not accept commands");
+  assert!(matches!(
+    err.original_error(),
+    ButtplugError::ButtplugDeviceError(ButtplugDeviceError::DeviceNotAvailable(_))
+  ));
+}
+
+#[tokio::test]
+async fn test_new_session_keeps_server_settings() {
+  let (server, _, _device, _) = setup_shared_servers(|_| {}).await;
+  let hidden_server = ButtplugServerBuilder::with_shared_device_manager(server.device_manager())
+    .name("Hidden Server")
+    .visible_devices(HashSet::new())
+    .finish()
+    .unwrap();
+  let session_server = hidden_server.new_session().unwrap();
+  assert_ne!(session_server.session().id(), hidden_server.session().id());
+  let msg = RequestServerInfoV4::new(
+    "Test Client",
+    BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+    BUTTPLUG_CURRENT_API_MINOR_VERSION,
+  );
+  match session_server
+    .parse_checked_message(ButtplugCheckedClientMessageV4::from(msg))
+    .await
+  {
+    Ok(ButtplugServerMessageV4::ServerInfo(info)) => {
+      assert_eq!(info.server_name(), "Hidden Server")
+    }
+    msg => panic!("Expected server info, got {:?}", msg),
+  }
+  match session_server
+    .parse_checked_message(RequestDeviceListV0::default().into())
+    .await
+  {
+    Ok(ButtplugServerMessageV4::DeviceList(list)) => assert!(list.devices().is_empty()),
+    msg => panic!("Expected device list, got {:?}", msg),
+  }
+}
+
+#[tokio::test]
+async fn test_output_layer_mixing() {
+  let (server, _, mut device, device_index) = setup_shared_servers(|builder| {
+    builder.output_mixer(OutputMixerConfig::new(
+      OutputBlendMode::AdditiveClamp,
+      Some(Duration::from_millis(200)),
+    ));
+  })
+  .await;
+  let device_manager = server.device_manager();
+  let pattern = |level| {
+    CheckedOutputCmdV4::new(
+      1,
+      device_index,
+      0,
+      "f50a528b-b023-40f0-9906-df037443950a".try_into().unwrap(),
+      OutputCommand::Vibrate(OutputValue::new(level)),
+    )
+  };
+
+  assert!(
+    server
+      .parse_checked_message(massage_demo_vibrate(device_index, 32))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(32),
+  )
+  .await;
+  assert!(
+    device_manager
+      .set_output_layer("pattern", 0, &pattern(16))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(48),
+  )
+  .await;
+  // The pattern layer expires and the client's command is all that's left.
+  check_test_recv_value(
+    &Duration::from_millis(500),
+    &mut device,
+    massage_demo_write(32),
+  )
+  .await;
+
+  assert!(
+    device_manager
+      .set_output_layer("pattern", 0, &pattern(8))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(40),
+  )
+  .await;
+  assert!(
+    device_manager
+      .clear_output_layer(device_index, "pattern")
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write(32),
+  )
+  .await;
+}
+
+/// User config for a Massage Demo at `aneros-test` with a preset that is applied on connect.
+const PRESET_USER_CONFIG_JSON: &str = r#"
+{
+  "version": {
+    "major": 4,
+    "minor": 0
+  },
+  "user_configs": {
+    "devices": [
+      {
+        "identifier": {
+          "address": "aneros-test",
+          "protocol": "aneros",
+          "identifier": "Massage Demo"
+        },
+        "config": {
+          "id": "3d4bc3a8-3b11-4e7c-a1c6-2f4b1a6e7f01",
+          "base_id": "f023f0f4-6629-469e-84c4-171ed4939f3d",
+          "features": [
+            {
+              "id": "3d4bc3a8-3b11-4e7c-a1c6-2f4b1a6e7f02",
+              "base_id": "a980bc1a-5554-4293-a75f-6d17bf25ebee"
+            },
+            {
+              "id": "3d4bc3a8-3b11-4e7c-a1c6-2f4b1a6e7f03",
+              "base_id": "811d7d6e-6a75-4925-943a-a06042223e3a"
+            }
+          ],
+          "user_config": {
+            "allow": false,
+            "deny": false,
+            "index": 0,
+            "presets": {
+              "gentle": [
+                {
+                  "feature_index": 0,
+                  "output_type": "vibrate",
+                  "value": 16
+                }
+              ]
+            },
+            "connect_preset": "gentle"
+          }
+        }
+      }
+    ]
+  }
+}
+"#;
+
+fn massage_demo_write_feature(feature: u8, level: u8) -> HardwareCommand {
+  HardwareCommand::Write(HardwareWriteCmd::new(
+    &[Uuid::nil()],
+    Endpoint::Tx,
+    vec![0xF1 + feature, level],
+    false,
+  ))
+}
+
+/// Builds a server with a Massage Demo using [PRESET_USER_CONFIG_JSON], and returns it along with
+/// the device index once the connect preset has been written.
+async fn setup_preset_server() -> (ButtplugServer, TestDeviceChannelHost, u32) {
+  let dcm = load_protocol_configs(&None, &Some(PRESET_USER_CONFIG_JSON.to_owned()), false)
+    .expect("Test, assuming infallible.")
+    .finish()
+    .expect("Test, assuming infallible.");
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  let mut device = builder.add_test_device(&TestDeviceIdentifier::new(
+    "Massage Demo",
+    Some("aneros-test".to_owned()),
+  ));
+  let mut dm_builder = ServerDeviceManagerBuilder::new(dcm);
+  dm_builder.comm_manager(builder);
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  let recv = server.server_version_event_stream();
+  pin_mut!(recv);
+  let msg = RequestServerInfoV4::new(
+    "Test Client",
+    BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+    BUTTPLUG_CURRENT_API_MINOR_VERSION,
+  );
+  assert!(
+    server
+      .parse_checked_message(ButtplugCheckedClientMessageV4::from(msg))
+      .await
+      .is_ok()
+  );
+  assert!(
+    server
+      .parse_checked_message(StartScanningV0::default().into())
+      .await
+      .is_ok()
+  );
+  let mut device_index = 100;
+  while let Some(msg) = recv.next().await {
+    if let ButtplugServerMessageV4::DeviceList(list) = msg {
+      device_index = list.devices()[&0].device_index();
+      break;
+    }
+  }
+
+  // The connect preset is applied as soon as the device is added.
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write_feature(0, 16),
+  )
+  .await;
+  (server, device, device_index)
+}
+
+#[tokio::test]
+async fn test_device_presets() {
+  let (server, mut device, device_index) = setup_preset_server().await;
+  let device_manager = server.device_manager();
+  let err = device_manager
+    .apply_preset(device_index, "missing")
+    .await
+    .expect_err("Unknown presets should be rejected");
+  assert!(matches!(
+    err,
+    ButtplugError::ButtplugDeviceError(ButtplugDeviceError::DeviceConfigurationError(_))
+  ));
+
+  let low = DevicePreset::new(&[PresetOutput::new(1, OutputType::Vibrate, 8)]);
+  assert!(
+    device_manager
+      .store_preset(device_index, "low", &low)
+      .is_ok()
+  );
+  assert!(
+    device_manager
+      .apply_preset(device_index, "low")
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write_feature(1, 8),
+  )
+  .await;
+
+  // Presets are checked against the device's outputs before they are stored.
+  let too_high = DevicePreset::new(&[PresetOutput::new(1, OutputType::Vibrate, 1000)]);
+  assert!(matches!(
+    device_manager.store_preset(device_index, "too_high", &too_high),
+    Err(ButtplugDeviceError::DeviceConfigurationError(_))
+  ));
+  let wrong_type = DevicePreset::new(&[PresetOutput::new(0, OutputType::Rotate, 8)]);
+  assert!(
+    device_manager
+      .store_preset(device_index, "wrong_type", &wrong_type)
+      .is_err()
+  );
+}
+
+#[tokio::test]
+async fn test_device_preset_messages() {
+  let (server, mut device, device_index) = setup_preset_server().await;
+  let config_changes = server.device_manager().user_config_event_stream();
+  pin_mut!(config_changes);
+
+  let msg = StorePresetCmdV4::new(
+    device_index,
+    "low",
+    &[PresetOutputV4::new(1, OutputType::Vibrate, 8)],
+  );
+  assert!(
+    server
+      .parse_message(ButtplugClientMessageVariant::V4(msg.into()))
+      .await
+      .is_ok()
+  );
+  let identifier = timeout(Duration::from_secs(1), config_changes.next())
+    .await
+    .expect("Storing a preset should report a user config change")
+    .expect("Test, assuming infallible.");
+  assert_eq!(identifier.address(), "aneros-test");
+  // Stored presets are saved with the rest of the user config.
+  let saved_config = save_user_config(server.device_manager().device_configuration_manager())
+    .expect("Test, assuming infallible.");
+  assert!(saved_config.contains("\"low\""));
+
+  let msg = ApplyPresetCmdV4::new(device_index, "low");
+  assert!(
+    server
+      .parse_message(ButtplugClientMessageVariant::V4(msg.into()))
+      .await
+      .is_ok()
+  );
+  check_test_recv_value(
+    &Duration::from_millis(150),
+    &mut device,
+    massage_demo_write_feature(1, 8),
+  )
+  .await;
+
+  let msg = ApplyPresetCmdV4::new(device_index + 1, "low");
+  assert!(
+    server
+      .parse_message(ButtplugClientMessageVariant::V4(msg.into()))
+      .await
+      .is_err()
+  );
+}
+
 // TODO Test sending system message (Id 0)
 // TODO Test sending system message (Ok but Id > 0)
 // TODO Test scan with no comm managers
@@ -0,0 +1,131 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+mod util;
+use buttplug_core::message::{
+  BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+  BUTTPLUG_CURRENT_API_MINOR_VERSION,
+  ButtplugClientMessageV4,
+  ButtplugServerMessageV4,
+  OutputCmdV4,
+  OutputCommand,
+  OutputValue,
+  RequestServerInfoV4,
+  StartScanningV0,
+  StopCmdV4,
+};
+use buttplug_server::{
+  ButtplugServerBuilder,
+  device::ServerDeviceManagerBuilder,
+  message::ButtplugClientMessageVariant,
+  recorder::{RecordedEvent, SessionRecorder, read_recording},
+};
+use futures::{StreamExt, pin_mut};
+use std::time::Duration;
+use util::{
+  create_test_dcm,
+  replay::replay_recording,
+  test_device_manager::{TestDeviceCommunicationManagerBuilder, TestDeviceIdentifier},
+};
+
+fn client_message(msg: impl Into<ButtplugClientMessageV4>) -> ButtplugClientMessageVariant {
+  ButtplugClientMessageVariant::V4(msg.into())
+}
+
+#[tokio::test]
+async fn test_record_and_replay_session() {
+  let path = std::env::temp_dir().join(format!("buttplug-session-{}.jsonl", uuid::Uuid::new_v4()));
+  let recorder = SessionRecorder::to_file(&path).unwrap();
+  let session;
+  {
+    let mut builder = TestDeviceCommunicationManagerBuilder::default();
+    let mut device = builder.add_test_device(&TestDeviceIdentifier::new("Massage Demo", None));
+    let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+    dm_builder.comm_manager(builder).recorder(recorder.clone());
+    let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+      .finish()
+      .unwrap();
+    session = server.session().id();
+    let recv = server.server_version_event_stream();
+    pin_mut!(recv);
+
+    let messages = [
+      client_message(RequestServerInfoV4::new(
+        "Test Client",
+        BUTTPLUG_CURRENT_API_MAJOR_VERSION,
+        BUTTPLUG_CURRENT_API_MINOR_VERSION,
+      )),
+      client_message(StartScanningV0::default()),
+    ];
+    for msg in messages {
+      assert!(server.parse_message(msg).await.is_ok());
+    }
+    while let Some(msg) = recv.next().await {
+      if let ButtplugServerMessageV4::DeviceList(_) = msg {
+        break;
+      }
+    }
+    // Commands are sent to the hardware asynchronously and may be coalesced, so wait for each
+    // message's writes to go out before sending the next.
+    let messages = [
+      (
+        client_message(OutputCmdV4::new(
+          0,
+          0,
+          OutputCommand::Vibrate(OutputValue::new(32)),
+        )),
+        1,
+      ),
+      (
+        client_message(OutputCmdV4::new(
+          0,
+          1,
+          OutputCommand::Vibrate(OutputValue::new(64)),
+        )),
+        1,
+      ),
+      (client_message(StopCmdV4::default()), 2),
+    ];
+    for (msg, writes) in messages {
+      assert!(server.parse_message(msg).await.is_ok());
+      for _ in 0..writes {
+        assert!(
+          tokio::time::timeout(Duration::from_secs(1), device.receiver.recv())
+            .await
+            .is_ok()
+        );
+      }
+    }
+  }
+
+  // Recordings are written on a background thread.
+  recorder.flush();
+  let recording = read_recording(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  assert_eq!(
+    recording
+      .iter()
+      .filter(
+        |e| matches!(e.event(), RecordedEvent::ClientMessage { session: s, .. } if *s == session)
+      )
+      .count(),
+    5
+  );
+  assert!(
+    recording
+      .iter()
+      .any(|e| matches!(e.event(), RecordedEvent::DeviceConnected { .. }))
+  );
+  assert_eq!(
+    recording
+      .iter()
+      .filter(|e| matches!(e.event(), RecordedEvent::HardwareCommand { .. }))
+      .count(),
+    4
+  );
+  replay_recording(&recording).await;
+}
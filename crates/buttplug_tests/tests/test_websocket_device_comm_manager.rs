@@ -9,14 +9,33 @@ mod util;
 
 #[cfg(feature = "websocket-server-manager")]
 mod test {
-
-  use buttplug::{
-    client::ButtplugClient,
-    client::connector::ButtplugInProcessClientConnectorBuilder,
-    server::device::hardware::communication::websocket_server::websocket_server_comm_manager::WebsocketServerDeviceCommunicationManagerBuilder,
+  use buttplug_client::{
+    ButtplugClient,
+    ButtplugClientEvent,
+    device::{ClientDeviceCommandValue, ClientDeviceOutputCommand},
+  };
+  use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
+  use buttplug_server::{ButtplugServerBuilder, device::ServerDeviceManagerBuilder};
+  use buttplug_server_device_config::{DeviceConfigurationManager, load_protocol_configs};
+  use buttplug_server_hwmgr_websocket::{
+    WebsocketClientDeviceCommunicationManagerBuilder,
+    WebsocketServerDeviceCommunicationManagerBuilder,
   };
+  use futures::{SinkExt, StreamExt};
+  use std::time::Duration;
+  use test_case::test_case;
+  use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+  };
+  use tokio_tungstenite::{WebSocketStream, accept_async, connect_async, tungstenite::Message};
 
-  use crate::util::test_server_with_comm_manager;
+  use crate::util::{
+    test_client_with_comm_manager,
+    test_server_with_comm_manager,
+    wait_for_device_added,
+  };
 
   async fn setup_test_client() -> ButtplugClient {
     let server = test_server_with_comm_manager(
@@ -41,4 +60,230 @@ mod test {
     let client = setup_test_client().await;
     assert!(client.connected());
   }
+
+  const WEBSOCKET_USER_CONFIG_JSON: &str = r#"
+{
+  "version": {
+    "major": 4,
+    "minor": 0
+  },
+  "user_configs": {
+    "protocols": {
+      "aneros": {
+        "communication": [
+          {
+            "websocket": {
+              "name": "Massage Demo"
+            }
+          }
+        ]
+      }
+    }
+  }
+}
+"#;
+
+  const MASSAGE_DEMO_INFO: &str =
+    r#"{"identifier": "Massage Demo", "address": "websocket-client-test", "version": 1}"#;
+  const ACCEPTED_RESPONSE: &str = r#"{"status":"accepted"}"#;
+
+  // Acts like a device hosting its own websocket server, waiting for the manager to dial in.
+  async fn accept_device_connection(
+    listener: &TcpListener,
+    info: Option<&str>,
+  ) -> WebSocketStream<TcpStream> {
+    let (stream, _) = timeout(Duration::from_secs(5), listener.accept())
+      .await
+      .expect("Manager should connect to device")
+      .expect("Test, assuming infallible.");
+    let mut ws_stream = accept_async(stream)
+      .await
+      .expect("Test, assuming infallible.");
+    if let Some(info) = info {
+      ws_stream
+        .send(Message::Text(info.into()))
+        .await
+        .expect("Test, assuming infallible.");
+    }
+    ws_stream
+  }
+
+  async fn recv_message<S>(ws_stream: &mut WebSocketStream<S>) -> Message
+  where
+    S: AsyncRead + AsyncWrite + Unpin,
+  {
+    loop {
+      match timeout(Duration::from_millis(500), ws_stream.next())
+        .await
+        .expect("No messages received")
+        .expect("Test, assuming infallible.")
+        .expect("Test, assuming infallible.")
+      {
+        Message::Ping(_) | Message::Pong(_) => continue,
+        msg => return msg,
+      }
+    }
+  }
+
+  async fn recv_binary(ws_stream: &mut WebSocketStream<TcpStream>) -> Vec<u8> {
+    match recv_message(ws_stream).await {
+      Message::Binary(data) => data.to_vec(),
+      msg => panic!("Unexpected websocket message {msg:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_websocket_client_dcm_dials_and_reconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0")
+      .await
+      .expect("Test, assuming infallible.");
+    let url = format!(
+      "ws://{}",
+      listener.local_addr().expect("Test, assuming infallible.")
+    );
+
+    let (_client, mut event_stream) = test_client_with_comm_manager(
+      "Websocket Client DCM Test Client",
+      WEBSOCKET_USER_CONFIG_JSON,
+      WebsocketClientDeviceCommunicationManagerBuilder::default()
+        .add_device_url(&url)
+        .reconnect_delay(Duration::from_millis(100)),
+    )
+    .await;
+
+    let mut ws_stream = accept_device_connection(&listener, Some(MASSAGE_DEMO_INFO)).await;
+    assert_eq!(
+      recv_message(&mut ws_stream).await,
+      Message::Text(ACCEPTED_RESPONSE.into())
+    );
+    let device = wait_for_device_added(&mut event_stream).await;
+    device
+      .run_output(&ClientDeviceOutputCommand::Vibrate(
+        ClientDeviceCommandValue::Steps(10),
+      ))
+      .await
+      .expect("Test, assuming infallible.");
+    let mut writes = vec![
+      recv_binary(&mut ws_stream).await,
+      recv_binary(&mut ws_stream).await,
+    ];
+    writes.sort();
+    assert_eq!(writes, vec![vec![0xF1, 10], vec![0xF2, 10]]);
+
+    // Once the device drops, the manager should dial it again and hand it back to the server.
+    ws_stream
+      .close(None)
+      .await
+      .expect("Test, assuming infallible.");
+    let _ws_stream = accept_device_connection(&listener, Some(MASSAGE_DEMO_INFO)).await;
+    let mut removed = false;
+    loop {
+      match timeout(Duration::from_secs(5), event_stream.next())
+        .await
+        .expect("Device should be added again")
+        .expect("Test, assuming infallible.")
+      {
+        ButtplugClientEvent::DeviceRemoved(_) => removed = true,
+        ButtplugClientEvent::DeviceAdded(_) => break,
+        _ => {}
+      }
+    }
+    assert!(removed);
+  }
+
+  fn websocket_test_dcm() -> DeviceConfigurationManager {
+    load_protocol_configs(&None, &Some(WEBSOCKET_USER_CONFIG_JSON.to_owned()), false)
+      .expect("Test, assuming infallible.")
+      .finish()
+      .expect("Test, assuming infallible.")
+  }
+
+  #[test_case(None, &[], Some("not json"), "invalid_info" ; "Invalid Info")]
+  #[test_case(None, &[], None, "handshake_timeout" ; "Handshake Timeout")]
+  #[test_case(Some("hunter2"), &[], Some(MASSAGE_DEMO_INFO), "invalid_secret" ; "Missing Secret")]
+  #[test_case(
+    Some("hunter2"),
+    &[],
+    Some(r#"{"identifier": "Massage Demo", "address": "websocket-client-test", "version": 1, "secret": "hunter3"}"#),
+    "invalid_secret" ;
+    "Wrong Secret"
+  )]
+  #[test_case(None, &["Other Device"], Some(MASSAGE_DEMO_INFO), "identifier_not_allowed" ; "Identifier Not Allowed")]
+  #[tokio::test]
+  async fn test_websocket_device_handshake_rejected(
+    secret: Option<&str>,
+    allowed_identifiers: &[&str],
+    info: Option<&str>,
+    reason: &str,
+  ) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+      .await
+      .expect("Test, assuming infallible.");
+    let url = format!(
+      "ws://{}",
+      listener.local_addr().expect("Test, assuming infallible.")
+    );
+    let mut comm_builder = WebsocketClientDeviceCommunicationManagerBuilder::default()
+      .add_device_url(&url)
+      .handshake_timeout(Duration::from_millis(100))
+      .allowed_identifiers(
+        &allowed_identifiers
+          .iter()
+          .map(|x| x.to_string())
+          .collect::<Vec<_>>(),
+      );
+    if let Some(secret) = secret {
+      comm_builder = comm_builder.shared_secret(secret);
+    }
+    let mut dm_builder = ServerDeviceManagerBuilder::new(websocket_test_dcm());
+    dm_builder.comm_manager(comm_builder);
+    let _server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+      .finish()
+      .unwrap();
+
+    let mut ws_stream = accept_device_connection(&listener, info).await;
+    assert_eq!(
+      recv_message(&mut ws_stream).await,
+      Message::Text(format!(r#"{{"status":"rejected","reason":"{reason}"}}"#).into())
+    );
+    assert!(matches!(
+      recv_message(&mut ws_stream).await,
+      Message::Close(_)
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_websocket_server_dcm_accepts_device_with_secret() {
+    let (_client, mut event_stream) = test_client_with_comm_manager(
+      "Websocket Server DCM Test Client",
+      WEBSOCKET_USER_CONFIG_JSON,
+      WebsocketServerDeviceCommunicationManagerBuilder::default()
+        .server_port(51284)
+        .shared_secret("hunter2")
+        .allowed_identifiers(&["Massage Demo".to_owned()]),
+    )
+    .await;
+
+    // The server socket comes up in the background, so give it a few tries.
+    let mut ws_stream = None;
+    for _ in 0..50 {
+      if let Ok((stream, _)) = connect_async("ws://127.0.0.1:51284").await {
+        ws_stream = Some(stream);
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let mut ws_stream = ws_stream.expect("Websocket device server should be listening");
+    ws_stream
+      .send(Message::Text(
+        r#"{"identifier": "Massage Demo", "address": "websocket-server-test", "version": 1, "secret": "hunter2"}"#.into(),
+      ))
+      .await
+      .expect("Test, assuming infallible.");
+    assert_eq!(
+      recv_message(&mut ws_stream).await,
+      Message::Text(ACCEPTED_RESPONSE.into())
+    );
+    wait_for_device_added(&mut event_stream).await;
+  }
 }
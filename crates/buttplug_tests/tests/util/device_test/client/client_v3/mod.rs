@@ -148,10 +148,12 @@ fn build_server(test_case: &DeviceTestCase) -> (ButtplugServer, Vec<TestDeviceCh
     info!("identifier: {:?}", device.identifier);
     device_channels.push(builder.add_test_device(&device.identifier));
   }
-  let dm = ServerDeviceManagerBuilder::new(dcm)
-    .comm_manager(builder)
-    .finish()
-    .unwrap();
+  let mut dm_builder = ServerDeviceManagerBuilder::new(dcm);
+  dm_builder.comm_manager(builder);
+  for factory in test_case.protocol_script_factories() {
+    dm_builder.protocol_factory(factory);
+  }
+  let dm = dm_builder.finish().unwrap();
 
   (
     ButtplugServerBuilder::new(dm)
@@ -10,7 +10,10 @@
 pub mod client;
 pub mod connector;
 use super::{TestDeviceIdentifier, TestHardwareEvent};
-use buttplug_server::device::hardware::HardwareCommand;
+use buttplug_server::device::{
+  hardware::HardwareCommand,
+  protocol_script::ScriptProtocolIdentifierFactory,
+};
 use buttplug_server::message::{
   RotationSubcommandV1,
   ScalarSubcommandV3,
@@ -65,10 +68,39 @@ pub struct DeviceTestCase {
   devices: Vec<TestDevice>,
   device_config_file: Option<String>,
   user_device_config_file: Option<String>,
+  protocol_script_files: Option<Vec<String>>,
   device_init: Option<Vec<TestCommand>>,
   device_commands: Vec<TestCommand>,
 }
 
+impl DeviceTestCase {
+  fn protocol_script_factories(&self) -> Vec<ScriptProtocolIdentifierFactory> {
+    let Some(script_files) = &self.protocol_script_files else {
+      return vec![];
+    };
+    script_files
+      .iter()
+      .map(|script_file| {
+        let script_path = std::path::Path::new(
+          &std::env::var("CARGO_MANIFEST_DIR").expect("Should have manifest path"),
+        )
+        .join("tests")
+        .join("util")
+        .join("device_test")
+        .join("device_test_case")
+        .join("scripts")
+        .join(script_file);
+        let protocol_name = script_path
+          .file_stem()
+          .and_then(|x| x.to_str())
+          .expect("Script files should have a name");
+        let source = std::fs::read_to_string(&script_path).expect("Should be able to load script");
+        ScriptProtocolIdentifierFactory::new(protocol_name, &source).expect("Script should compile")
+      })
+      .collect()
+  }
+}
+
 /// Flattens `device_commands` for a given spec version, expanding `VersionGated` groups only
 /// when `spec_version >= min_spec_version`. Skipped groups (and their nested commands) are
 /// dropped entirely, so version-specific `Messages`+`Commands` pairs never get separated.
@@ -5,7 +5,7 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
-mod delay_device_communication_manager;
+pub mod delay_device_communication_manager;
 pub mod long_running_scan_comm_manager;
 pub mod test_server;
 pub use test_server::ButtplugTestServer;
@@ -13,7 +13,13 @@ pub mod device_test;
 pub mod test_device_manager;
 pub use delay_device_communication_manager::DelayDeviceCommunicationManagerBuilder;
 pub mod channel_transport;
-use buttplug_client::ButtplugClient;
+pub mod replay;
+use buttplug_client::{
+  ButtplugClient,
+  ButtplugClientDevice,
+  ButtplugClientEvent,
+  device::{ClientDeviceCommandValue, ClientDeviceOutputCommand},
+};
 use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
 use buttplug_server::{
   ButtplugServer,
@@ -24,14 +30,24 @@ use buttplug_server::{
   },
 };
 use buttplug_server_device_config::{DeviceConfigurationManager, load_protocol_configs};
+use futures::{Stream, StreamExt};
+use std::{
+  sync::{Arc, atomic::AtomicBool},
+  time::Duration,
+};
 pub use test_device_manager::{
   TestDeviceChannelHost,
   TestDeviceCommunicationManagerBuilder,
   TestHardwareEvent,
 };
+use tokio::time::timeout;
 
 use crate::util::test_device_manager::TestDeviceIdentifier;
 
+/// What a TCode v0.3 device is sent for [send_position].
+#[allow(dead_code)]
+pub const TCODE_POSITION_500: &[u8] = b"L0500\nR0500\n";
+
 pub fn create_test_dcm() -> DeviceConfigurationManager {
   load_protocol_configs(&None, &None, false)
     .expect("If this fails, the whole library goes with it.")
@@ -122,6 +138,112 @@ pub async fn test_client_with_device_and_custom_dcm(
   (client, device)
 }
 
+#[allow(dead_code)]
+pub async fn test_client_with_failing_device(identifier: &TestDeviceIdentifier) -> ButtplugClient {
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  builder.add_failing_test_device(identifier);
+
+  let mut dm_builder = ServerDeviceManagerBuilder::new(create_test_dcm());
+  dm_builder.comm_manager(builder);
+
+  let server_builder = ButtplugServerBuilder::new(dm_builder.finish().unwrap());
+
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server_builder.finish().unwrap())
+    .finish();
+
+  let client = ButtplugClient::new("Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  client
+}
+
+/// User config giving the Massage Demo at `aneros-test` an auto-reconnect policy.
+const AUTO_RECONNECT_USER_CONFIG_JSON: &str = r#"
+{
+  "version": {
+    "major": 4,
+    "minor": 0
+  },
+  "user_configs": {
+    "devices": [
+      {
+        "identifier": {
+          "address": "aneros-test",
+          "protocol": "aneros",
+          "identifier": "Massage Demo"
+        },
+        "config": {
+          "id": "3d4bc3a8-3b11-4e7c-a1c6-2f4b1a6e7f01",
+          "base_id": "f023f0f4-6629-469e-84c4-171ed4939f3d",
+          "features": [
+            {
+              "id": "3d4bc3a8-3b11-4e7c-a1c6-2f4b1a6e7f02",
+              "base_id": "a980bc1a-5554-4293-a75f-6d17bf25ebee"
+            },
+            {
+              "id": "3d4bc3a8-3b11-4e7c-a1c6-2f4b1a6e7f03",
+              "base_id": "811d7d6e-6a75-4925-943a-a06042223e3a"
+            }
+          ],
+          "user_config": {
+            "allow": false,
+            "deny": false,
+            "index": 0,
+            "auto_reconnect": {
+              "timeout_ms": 10000
+            }
+          }
+        }
+      }
+    ]
+  }
+}
+"#;
+
+/// Client connected to a server with a Massage Demo that is set to auto-reconnect. The first host
+/// channel is the device's initial connection, and the second is the connection it comes back on.
+/// The device doesn't come back while the returned flag is set.
+#[allow(dead_code)]
+pub async fn test_client_with_reconnecting_device() -> (
+  ButtplugClient,
+  TestDeviceChannelHost,
+  TestDeviceChannelHost,
+  Arc<AtomicBool>,
+) {
+  let dcm = load_protocol_configs(
+    &None,
+    &Some(AUTO_RECONNECT_USER_CONFIG_JSON.to_owned()),
+    false,
+  )
+  .expect("Test, assuming infallible.")
+  .finish()
+  .expect("Test, assuming infallible.");
+  let identifier = TestDeviceIdentifier::new("Massage Demo", Some("aneros-test".to_owned()));
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  let device = builder.add_test_device(&identifier);
+  let reconnected_device = builder.add_reconnecting_test_device(&identifier);
+  let reconnects_held = builder.reconnects_held();
+
+  let mut dm_builder = ServerDeviceManagerBuilder::new(dcm);
+  dm_builder.comm_manager(builder);
+
+  let server_builder = ButtplugServerBuilder::new(dm_builder.finish().unwrap());
+
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server_builder.finish().unwrap())
+    .finish();
+
+  let client = ButtplugClient::new("Test Client");
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  (client, device, reconnected_device, reconnects_held)
+}
+
 #[allow(dead_code)]
 pub async fn test_client_with_delayed_device_manager() -> ButtplugClient {
   let builder = DelayDeviceCommunicationManagerBuilder::default();
@@ -173,3 +295,69 @@ pub fn test_server_v4_with_device(device_type: &str) -> (ButtplugServer, TestDev
 
   (test_server_with_comm_manager(builder), device)
 }
+
+/// Connects a client to a server using the user config and comm manager, and starts scanning. The
+/// client's event stream is taken before connecting, so no events are missed.
+#[allow(dead_code)]
+pub async fn test_client_with_comm_manager<T>(
+  client_name: &str,
+  user_config: &str,
+  comm_manager: T,
+) -> (
+  ButtplugClient,
+  impl Stream<Item = ButtplugClientEvent> + Unpin + use<T>,
+)
+where
+  T: HardwareCommunicationManagerBuilder + 'static,
+{
+  let dcm = load_protocol_configs(&None, &Some(user_config.to_owned()), false)
+    .expect("Test, assuming infallible.")
+    .finish()
+    .expect("Test, assuming infallible.");
+  let mut dm_builder = ServerDeviceManagerBuilder::new(dcm);
+  dm_builder.comm_manager(comm_manager);
+  let server = ButtplugServerBuilder::new(dm_builder.finish().unwrap())
+    .finish()
+    .unwrap();
+  let connector = ButtplugInProcessClientConnectorBuilder::default()
+    .server(server)
+    .finish();
+  let client = ButtplugClient::new(client_name);
+  let event_stream = client.event_stream();
+  client
+    .connect(connector)
+    .await
+    .expect("Test, assuming infallible.");
+  client
+    .start_scanning()
+    .await
+    .expect("Test, assuming infallible.");
+  (client, event_stream)
+}
+
+#[allow(dead_code)]
+pub async fn wait_for_device_added(
+  event_stream: &mut (impl Stream<Item = ButtplugClientEvent> + Unpin),
+) -> ButtplugClientDevice {
+  loop {
+    if let ButtplugClientEvent::DeviceAdded(device) =
+      timeout(Duration::from_secs(5), event_stream.next())
+        .await
+        .expect("Device should be added")
+        .expect("Test, assuming infallible.")
+    {
+      return device;
+    }
+  }
+}
+
+/// Moves a linear device to the middle of its range.
+#[allow(dead_code)]
+pub async fn send_position(device: &ButtplugClientDevice) {
+  device
+    .run_output(&ClientDeviceOutputCommand::Position(
+      ClientDeviceCommandValue::Steps(500),
+    ))
+    .await
+    .expect("Test, assuming infallible.");
+}
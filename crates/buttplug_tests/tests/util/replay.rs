@@ -0,0 +1,107 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+//! Replays session recordings against simulated test devices.
+
+// Not every test binary that includes the util module replays recordings.
+#![allow(dead_code)]
+
+use super::{
+  test_device_manager::{
+    TestDeviceChannelHost,
+    TestDeviceCommunicationManagerBuilder,
+    TestDeviceIdentifier,
+    TestHardwareEvent,
+    TestHardwareNotification,
+  },
+  test_server_with_comm_manager,
+};
+use buttplug_core::message::ButtplugServerMessageV4;
+use buttplug_server::recorder::{RecordedEntry, RecordedEvent};
+use futures::{StreamExt, pin_mut};
+use std::{collections::HashMap, time::Duration};
+
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Feeds the client messages in a recording into a server backed by simulated versions of the
+/// recorded devices, and checks that the hardware receives the same commands in the same order.
+/// Recorded hardware reads and notifications are played back from the simulated devices.
+pub async fn replay_recording(recording: &[RecordedEntry]) {
+  let mut builder = TestDeviceCommunicationManagerBuilder::default();
+  let mut devices: HashMap<String, TestDeviceChannelHost> = HashMap::new();
+  for entry in recording {
+    if let RecordedEvent::DeviceConnected { name, address } = entry.event() {
+      let identifier = TestDeviceIdentifier::new(name, Some(address.clone()));
+      devices.insert(address.clone(), builder.add_test_device(&identifier));
+    }
+  }
+  let server = test_server_with_comm_manager(builder);
+  let events = server.server_version_event_stream();
+  pin_mut!(events);
+
+  for (i, entry) in recording.iter().enumerate() {
+    match entry.event() {
+      RecordedEvent::ClientMessage { message, .. } => {
+        // Reads happen while the message is being handled, so queue up everything the hardware
+        // returned before sending it.
+        for next in recording[i + 1..]
+          .iter()
+          .take_while(|e| !matches!(e.event(), RecordedEvent::ClientMessage { .. }))
+        {
+          if let RecordedEvent::HardwareRead {
+            address,
+            endpoint,
+            data,
+          } = next.event()
+          {
+            devices[address]
+              .sender
+              .send(TestHardwareEvent::Reads(vec![
+                TestHardwareNotification::new(*endpoint, data),
+              ]))
+              .await
+              .expect("Test device should be alive");
+          }
+        }
+        // Errors are part of the recording too, so the result doesn't matter here.
+        let _ = server.parse_message(message.clone()).await;
+      }
+      RecordedEvent::DeviceConnected { address, .. } => {
+        // Wait for the device to be added before sending it any client messages.
+        loop {
+          match tokio::time::timeout(REPLAY_TIMEOUT, events.next()).await {
+            Ok(Some(ButtplugServerMessageV4::DeviceList(_))) => break,
+            Ok(Some(_)) => continue,
+            _ => panic!("Recorded device {address} never connected during replay"),
+          }
+        }
+      }
+      RecordedEvent::HardwareCommand { address, command } => {
+        let device = devices.get_mut(address).expect("Device was recorded");
+        let received = tokio::time::timeout(REPLAY_TIMEOUT, device.receiver.recv())
+          .await
+          .unwrap_or_else(|_| panic!("Entry {i}: expected {command:?}, got nothing"))
+          .expect("Test device should be alive");
+        assert_eq!(&received, command, "Replay diverged at entry {i}");
+      }
+      RecordedEvent::HardwareNotification {
+        address,
+        endpoint,
+        data,
+      } => {
+        devices[address]
+          .sender
+          .send(TestHardwareEvent::Notifications(vec![
+            TestHardwareNotification::new(*endpoint, data),
+          ]))
+          .await
+          .expect("Test device should be alive");
+      }
+      RecordedEvent::HardwareRead { .. } => {}
+    }
+  }
+}
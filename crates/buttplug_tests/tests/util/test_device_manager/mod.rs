@@ -11,7 +11,12 @@ mod test_device_comm_manager;
 
 use buttplug_server::device::hardware::HardwareCommand;
 use std::time::Duration;
-pub use test_device::{TestDevice, TestDeviceChannelHost, TestHardwareEvent};
+pub use test_device::{
+  TestDevice,
+  TestDeviceChannelHost,
+  TestHardwareEvent,
+  TestHardwareNotification,
+};
 
 pub use test_device_comm_manager::{
   //new_bluetoothle_test_device,
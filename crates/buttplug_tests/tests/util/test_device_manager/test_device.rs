@@ -39,6 +39,16 @@ pub struct TestHardwareNotification {
   data: Vec<u8>,
 }
 
+impl TestHardwareNotification {
+  #[allow(dead_code)]
+  pub fn new(endpoint: Endpoint, data: &[u8]) -> Self {
+    Self {
+      endpoint,
+      data: data.to_vec(),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum TestHardwareEvent {
   // Values to be emitted from subscriptions
@@ -61,6 +71,15 @@ impl TestHardwareConnector {
       hardware: Some(hardware),
     }
   }
+
+  /// Connector for a device that is found during scanning but always fails to connect.
+  #[allow(dead_code)]
+  pub fn new_failing(specifier: ProtocolCommunicationSpecifier) -> Self {
+    Self {
+      specifier,
+      hardware: None,
+    }
+  }
 }
 
 impl Debug for TestHardwareConnector {
@@ -78,9 +97,10 @@ impl HardwareConnector for TestHardwareConnector {
   }
 
   async fn connect(&mut self) -> Result<Box<dyn HardwareSpecializer>, ButtplugDeviceError> {
-    Ok(Box::new(TestHardwareSpecializer::new(
-      self.hardware.take().expect("Test"),
-    )))
+    let hardware = self.hardware.take().ok_or_else(|| {
+      ButtplugDeviceError::DeviceConnectionError("Simulated connection failure".to_owned())
+    })?;
+    Ok(Box::new(TestHardwareSpecializer::new(hardware)))
   }
 }
 
@@ -74,12 +74,18 @@ impl TestDeviceIdentifier {
 
 pub struct TestDeviceCommunicationManagerBuilder {
   devices: Option<Vec<(TestDeviceIdentifier, TestDeviceChannelDevice)>>,
+  failing_devices: Vec<TestDeviceIdentifier>,
+  reconnecting_devices: Vec<(TestDeviceIdentifier, TestDeviceChannelDevice)>,
+  reconnects_held: Arc<AtomicBool>,
 }
 
 impl Default for TestDeviceCommunicationManagerBuilder {
   fn default() -> Self {
     Self {
       devices: Some(vec![]),
+      failing_devices: vec![],
+      reconnecting_devices: vec![],
+      reconnects_held: Arc::new(AtomicBool::new(false)),
     }
   }
 }
@@ -94,6 +100,33 @@ impl TestDeviceCommunicationManagerBuilder {
       .push((device.clone(), device_channel));
     host_channel
   }
+
+  /// Adds a device that is found during scanning, but fails when the server tries to connect.
+  #[allow(dead_code)]
+  pub fn add_failing_test_device(&mut self, device: &TestDeviceIdentifier) {
+    self.failing_devices.push(device.clone());
+  }
+
+  /// Adds a second connection for a device added with [Self::add_test_device], which is found on
+  /// any scan after the first one. Used for testing devices that drop out and come back.
+  #[allow(dead_code)]
+  pub fn add_reconnecting_test_device(
+    &mut self,
+    device: &TestDeviceIdentifier,
+  ) -> TestDeviceChannelHost {
+    let (host_channel, device_channel) = new_device_channel();
+    self
+      .reconnecting_devices
+      .push((device.clone(), device_channel));
+    host_channel
+  }
+
+  /// Flag that keeps devices added with [Self::add_reconnecting_test_device] from being found
+  /// while it is set.
+  #[allow(dead_code)]
+  pub fn reconnects_held(&self) -> Arc<AtomicBool> {
+    self.reconnects_held.clone()
+  }
 }
 
 impl HardwareCommunicationManagerBuilder for TestDeviceCommunicationManagerBuilder {
@@ -101,24 +134,34 @@ impl HardwareCommunicationManagerBuilder for TestDeviceCommunicationManagerBuild
     &mut self,
     sender: Sender<HardwareCommunicationManagerEvent>,
   ) -> Box<dyn HardwareCommunicationManager> {
-    Box::new(TestDeviceCommunicationManager::new(
+    let mut manager = TestDeviceCommunicationManager::new(
       sender,
       self
         .devices
         .take()
         .expect("Devices vec does not exist, is this running twice?"),
-    ))
+    );
+    manager.failing_devices = std::mem::take(&mut self.failing_devices);
+    manager.reconnecting_devices = std::mem::take(&mut self.reconnecting_devices);
+    manager.reconnects_held = self.reconnects_held.clone();
+    Box::new(manager)
   }
 }
 
+fn ble_test_specifier(identifier: &TestDeviceIdentifier) -> ProtocolCommunicationSpecifier {
+  ProtocolCommunicationSpecifier::BluetoothLE(BluetoothLESpecifier::new_from_device(
+    &identifier.name,
+    &HashMap::new(),
+    &[],
+  ))
+}
+
 fn new_uninitialized_ble_test_device(
   identifier: &TestDeviceIdentifier,
   device_channel: TestDeviceChannelDevice,
 ) -> TestHardwareConnector {
   let address = identifier.address.clone();
-  let specifier = ProtocolCommunicationSpecifier::BluetoothLE(
-    BluetoothLESpecifier::new_from_device(&identifier.name, &HashMap::new(), &[]),
-  );
+  let specifier = ble_test_specifier(identifier);
   let hardware = TestDevice::new(&identifier.name, &address, device_channel);
   TestHardwareConnector::new(specifier, hardware)
 }
@@ -126,6 +169,10 @@ fn new_uninitialized_ble_test_device(
 pub struct TestDeviceCommunicationManager {
   device_sender: Sender<HardwareCommunicationManagerEvent>,
   devices: Vec<(TestDeviceIdentifier, TestDeviceChannelDevice)>,
+  failing_devices: Vec<TestDeviceIdentifier>,
+  reconnecting_devices: Vec<(TestDeviceIdentifier, TestDeviceChannelDevice)>,
+  reconnects_held: Arc<AtomicBool>,
+  has_scanned: bool,
   is_scanning: Arc<AtomicBool>,
 }
 
@@ -137,6 +184,10 @@ impl TestDeviceCommunicationManager {
     Self {
       device_sender,
       devices,
+      failing_devices: vec![],
+      reconnecting_devices: vec![],
+      reconnects_held: Arc::new(AtomicBool::new(false)),
+      has_scanned: false,
       is_scanning: Arc::new(AtomicBool::new(false)),
     }
   }
@@ -148,12 +199,17 @@ impl HardwareCommunicationManager for TestDeviceCommunicationManager {
   }
 
   fn start_scanning(&mut self) -> ButtplugResultFuture {
-    if self.devices.is_empty() {
+    if self.devices.is_empty() && self.failing_devices.is_empty() {
       warn!("No devices for test device comm manager to emit, did you mean to do this?");
     }
 
     let mut events = vec![];
 
+    if std::mem::replace(&mut self.has_scanned, true)
+      && !self.reconnects_held.load(Ordering::Relaxed)
+    {
+      self.devices.append(&mut self.reconnecting_devices);
+    }
     while let Some((device, test_channel)) = self.devices.pop() {
       let device_creator = new_uninitialized_ble_test_device(&device, test_channel);
 
@@ -163,6 +219,15 @@ impl HardwareCommunicationManager for TestDeviceCommunicationManager {
         creator: Box::new(device_creator),
       });
     }
+    for device in self.failing_devices.drain(..) {
+      events.push(HardwareCommunicationManagerEvent::DeviceFound {
+        name: device.name.clone(),
+        address: device.address.clone(),
+        creator: Box::new(TestHardwareConnector::new_failing(ble_test_specifier(
+          &device,
+        ))),
+      });
+    }
     let device_sender = self.device_sender.clone();
     let is_scanning = self.is_scanning.clone();
     async move {
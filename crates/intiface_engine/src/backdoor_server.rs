@@ -6,7 +6,7 @@
 // for full license information.
 
 use buttplug_core::{
-  connector::transport::stream::ButtplugStreamTransport,
+  connector::transport::stream::ButtplugStreamTransport, errors::ButtplugError,
   message::serializer::ButtplugSerializedMessage,
   util::stream::convert_broadcast_receiver_to_stream,
 };
@@ -29,6 +29,7 @@ pub struct BackdoorServer {
   //server: ButtplugRemoteServer,
   sender: Sender<ButtplugSerializedMessage>,
   broadcaster: broadcast::Sender<String>,
+  device_manager: Arc<ServerDeviceManager>,
 }
 
 impl BackdoorServer {
@@ -70,6 +71,7 @@ impl BackdoorServer {
     Self {
       sender: s_in,
       broadcaster: s_stream,
+      device_manager,
     }
   }
 
@@ -77,6 +79,15 @@ impl BackdoorServer {
     convert_broadcast_receiver_to_stream(self.broadcaster.subscribe())
   }
 
+  /// Engages the latched emergency stop on the shared device manager.
+  pub async fn emergency_stop(&self) -> Result<(), ButtplugError> {
+    self.device_manager.emergency_stop().await.map(|_| ())
+  }
+
+  pub fn reset_emergency_stop(&self) {
+    self.device_manager.reset_emergency_stop();
+  }
+
   pub async fn parse_message(&self, msg: &str) {
     self
       .sender
@@ -9,11 +9,12 @@
 extern crate log;
 
 use argh::FromArgs;
+use buttplug_server::device::OutputArbitrationPolicy;
 use getset::{CopyGetters, Getters};
 use intiface_engine::{
   EngineOptions, EngineOptionsBuilder, IntifaceEngine, IntifaceEngineError, IntifaceError,
 };
-use std::fs;
+use std::{fs, time::Duration};
 use tokio::{select, signal::ctrl_c};
 use tracing::Level;
 use tracing_subscriber::{
@@ -23,6 +24,7 @@ use tracing_subscriber::{
 };
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const DEFAULT_OUTPUT_LEASE_MS: u32 = 10000;
 
 /// command line interface for intiface/buttplug.
 ///
@@ -80,6 +82,11 @@ pub struct IntifaceCLIArguments {
   #[getset(get = "pub")]
   user_device_config_file: Option<String>,
 
+  /// path to a directory of protocol scripts (.rhai files) to load
+  #[argh(option)]
+  #[getset(get = "pub")]
+  protocol_script_path: Option<String>,
+
   /// ping timeout maximum for server (in milliseconds)
   #[argh(option)]
   #[argh(default = "0")]
@@ -97,11 +104,23 @@ pub struct IntifaceCLIArguments {
   #[getset(get_copy = "pub")]
   use_bluetooth_le: bool,
 
+  /// turn on bluetooth le advertisement-only device support (off by default). Devices can be
+  /// found and read, but not commanded, as advertisements can't be sent yet.
+  #[argh(switch)]
+  #[getset(get_copy = "pub")]
+  use_bluetooth_le_advertisements: bool,
+
   /// turn off serial device support
   #[argh(switch)]
   #[getset(get_copy = "pub")]
   use_serial: bool,
 
+  /// serial port to look for devices on. if used, all other serial ports are ignored (can be used
+  /// multiple times)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  serial_port: Vec<String>,
+
   /// turn off hid device support
   #[allow(dead_code)]
   #[argh(switch)]
@@ -123,6 +142,11 @@ pub struct IntifaceCLIArguments {
   #[getset(get_copy = "pub")]
   use_xinput: bool,
 
+  /// turn on evdev gamepad rumble support (linux only, off by default)
+  #[argh(switch)]
+  #[getset(get_copy = "pub")]
+  use_evdev: bool,
+
   /// turn on lovense connect app device support (off by default)
   #[argh(switch)]
   #[getset(get_copy = "pub")]
@@ -138,6 +162,82 @@ pub struct IntifaceCLIArguments {
   #[getset(get_copy = "pub")]
   device_websocket_server_port: Option<u16>,
 
+  /// url of a device hosting its own websocket server to connect to (can be used multiple times)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  device_websocket_client_url: Vec<String>,
+
+  /// if set, connect to devices advertising a websocket server via mdns
+  #[argh(switch)]
+  #[getset(get_copy = "pub")]
+  use_device_websocket_client_mdns: bool,
+
+  /// how long websocket devices have to send their info packet, in milliseconds (defaults to 10000)
+  #[argh(option)]
+  #[getset(get_copy = "pub")]
+  device_websocket_handshake_timeout: Option<u32>,
+
+  /// shared secret websocket devices must send in their info packet to be accepted
+  #[argh(option)]
+  #[getset(get = "pub")]
+  device_websocket_secret: Option<String>,
+
+  /// identifier of a websocket device to accept. if used, devices with any other identifier are
+  /// rejected (can be used multiple times)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  device_websocket_allowed_identifier: Vec<String>,
+
+  /// address of a device that takes raw tcp/udp connections, in transport://host:port form (e.g.
+  /// tcp://192.168.1.50:8000, can be used multiple times)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  network_device: Vec<String>,
+
+  /// name of a device comm manager that should scan all the time instead of only when a client
+  /// asks (e.g. HIDCommunicationManager, can be used multiple times)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  continuous_scan: Vec<String>,
+
+  /// longest a device comm manager scans each time a client asks, in name=milliseconds form (e.g.
+  /// BtlePlugCommunicationManager=30000, can be used multiple times)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  scan_duration_limit: Vec<String>,
+
+  /// how output commands from multiple connected clients to the same device output are combined:
+  /// last-writer-wins, max, sum-clamped, or exclusive-lock (defaults to last-writer-wins)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  output_arbitration: Option<String>,
+
+  /// how long a client holds an output under exclusive-lock arbitration without sending it a
+  /// command, in milliseconds (defaults to 10000)
+  #[argh(option)]
+  #[getset(get_copy = "pub")]
+  output_lease: Option<u32>,
+
+  /// mqtt broker to look for devices on, in host or host:port form (port defaults to 1883)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  mqtt_broker: Option<String>,
+
+  /// topic prefix mqtt devices announce themselves under (defaults to buttplug/devices)
+  #[argh(option)]
+  #[getset(get = "pub")]
+  mqtt_discovery_topic: Option<String>,
+
+  /// username for the mqtt broker. requires --mqtt-password
+  #[argh(option)]
+  #[getset(get = "pub")]
+  mqtt_username: Option<String>,
+
+  /// password for the mqtt broker. requires --mqtt-username
+  #[argh(option)]
+  #[getset(get = "pub")]
+  mqtt_password: Option<String>,
+
   /// if set, broadcast server port/service info via mdns
   #[argh(switch)]
   #[getset(get_copy = "pub")]
@@ -163,6 +263,16 @@ pub struct IntifaceCLIArguments {
   #[getset(get = "pub")]
   rest_api_port: Option<u16>,
 
+  /// record client messages and hardware traffic to a file, for bug reports
+  #[argh(option)]
+  #[getset(get = "pub")]
+  session_recording_path: Option<String>,
+
+  /// serve prometheus metrics on this port at /metrics
+  #[argh(option)]
+  #[getset(get = "pub")]
+  metrics_port: Option<u16>,
+
   #[cfg(debug_assertions)]
   /// crash the main thread (that holds the runtime)
   #[argh(switch)]
@@ -238,16 +348,24 @@ impl TryFrom<IntifaceCLIArguments> for EngineOptions {
       };
     }
 
+    if let Some(scriptpath) = args.protocol_script_path() {
+      info!("Intiface CLI Options: Protocol Scripts {}", scriptpath);
+      builder.protocol_script_path(scriptpath);
+    }
+
     builder
       .websocket_use_all_interfaces(args.websocket_use_all_interfaces())
       .use_bluetooth_le(args.use_bluetooth_le())
+      .use_bluetooth_le_advertisements(args.use_bluetooth_le_advertisements())
       .use_serial_port(args.use_serial())
       .use_hid(args.use_hid())
       .use_lovense_dongle_serial(args.use_lovense_dongle_serial())
       .use_lovense_dongle_hid(args.use_lovense_dongle_hid())
       .use_xinput(args.use_xinput())
+      .use_evdev(args.use_evdev())
       .use_lovense_connect(args.use_lovense_connect())
       .use_device_websocket_server(args.use_device_websocket_server())
+      .use_device_websocket_client_mdns(args.use_device_websocket_client_mdns())
       .max_ping_time(args.max_ping_time())
       .server_name(args.server_name())
       .broadcast_server_mdns(args.broadcast_server_mdns());
@@ -271,9 +389,85 @@ impl TryFrom<IntifaceCLIArguments> for EngineOptions {
     if let Some(value) = args.device_websocket_server_port() {
       builder.device_websocket_server_port(value);
     }
+    for url in args.device_websocket_client_url() {
+      builder.add_device_websocket_client_url(url);
+    }
+    if let Some(value) = args.device_websocket_handshake_timeout() {
+      builder.device_websocket_handshake_timeout_ms(value);
+    }
+    if let Some(value) = args.device_websocket_secret() {
+      builder.device_websocket_secret(value);
+    }
+    for identifier in args.device_websocket_allowed_identifier() {
+      builder.add_device_websocket_allowed_identifier(identifier);
+    }
+    for address in args.network_device() {
+      builder.add_network_device_address(address);
+    }
+    for manager_name in args.continuous_scan() {
+      builder.add_continuous_scan_manager(manager_name);
+    }
+    for limit in args.scan_duration_limit() {
+      match limit
+        .split_once('=')
+        .and_then(|(name, ms)| Some((name, ms.parse::<u32>().ok()?)))
+      {
+        Some((manager_name, ms)) => {
+          builder.scan_duration_limit_ms(manager_name, ms);
+        }
+        None => {
+          return Err(IntifaceError::new(&format!(
+            "Invalid scan duration limit {}, expected name=milliseconds",
+            limit
+          )));
+        }
+      }
+    }
+    if let Some(policy) = args.output_arbitration() {
+      let policy = match policy.as_str() {
+        "last-writer-wins" => OutputArbitrationPolicy::LastWriterWins,
+        "max" => OutputArbitrationPolicy::Max,
+        "sum-clamped" => OutputArbitrationPolicy::SumClamped,
+        "exclusive-lock" => OutputArbitrationPolicy::ExclusiveLock {
+          lease: Duration::from_millis(
+            args.output_lease().unwrap_or(DEFAULT_OUTPUT_LEASE_MS) as u64
+          ),
+        },
+        _ => {
+          return Err(IntifaceError::new(&format!(
+            "Invalid output arbitration policy {}, expected last-writer-wins, max, sum-clamped or exclusive-lock",
+            policy
+          )));
+        }
+      };
+      builder.output_arbitration_policy(policy);
+    }
+    if args.output_lease().is_some()
+      && !matches!(args.output_arbitration().as_deref(), Some("exclusive-lock"))
+    {
+      warn!("Output lease only applies to exclusive-lock arbitration, ignoring");
+    }
+    if let Some(value) = args.mqtt_broker() {
+      builder.mqtt_broker_address(value);
+    }
+    if let Some(value) = args.mqtt_discovery_topic() {
+      builder.mqtt_discovery_topic(value);
+    }
+    if let (Some(username), Some(password)) = (args.mqtt_username(), args.mqtt_password()) {
+      builder.mqtt_credentials(username, password);
+    }
+    for port in args.serial_port() {
+      builder.add_serial_port(port);
+    }
     if let Some(value) = args.rest_api_port() {
       builder.rest_api_port(*value);
     }
+    if let Some(value) = args.session_recording_path() {
+      builder.session_recording_path(value);
+    }
+    if let Some(value) = args.metrics_port() {
+      builder.metrics_port(*value);
+    }
     if args.broadcast_server_mdns()
       && let Some(value) = args.mdns_suffix()
     {
@@ -5,27 +5,41 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc, time::Duration};
 
 use crate::{
   BackdoorServer, ButtplugRemoteServer, ButtplugServerConnectorError, EngineOptions,
-  IntifaceEngineError, IntifaceError, remote_server::ButtplugRemoteServerEvent,
+  IntifaceEngineError, IntifaceError,
 };
 use buttplug_server::{
   ButtplugServer, ButtplugServerBuilder,
   connector::ButtplugRemoteServerConnector,
-  device::{ServerDeviceManager, ServerDeviceManagerBuilder},
+  device::{
+    ScanMode, ScanPolicy, ServerDeviceManagerBuilder,
+    hardware::advertisement::BleAdvertisementCommunicationManagerBuilder,
+    protocol_script::load_protocol_scripts,
+  },
   message::serializer::ButtplugServerJSONSerializer,
+  recorder::SessionRecorder,
+};
+use buttplug_server_device_config::{
+  DeviceConfigurationManager, NetworkSpecifier, load_protocol_configs,
+};
+use buttplug_server_hwmgr_btleplug::{
+  BtlePlugCommunicationManagerBuilder, BtleplugAdvertisementAdapter,
 };
-use buttplug_server_device_config::{DeviceConfigurationManager, load_protocol_configs};
-use buttplug_server_hwmgr_btleplug::BtlePlugCommunicationManagerBuilder;
 use buttplug_server_hwmgr_lovense_connect::LovenseConnectServiceCommunicationManagerBuilder;
-use buttplug_server_hwmgr_websocket::WebsocketServerDeviceCommunicationManagerBuilder;
+use buttplug_server_hwmgr_mqtt::MqttCommunicationManagerBuilder;
+use buttplug_server_hwmgr_network::NetworkCommunicationManagerBuilder;
+use buttplug_server_hwmgr_websocket::{
+  WebsocketClientDeviceCommunicationManagerBuilder,
+  WebsocketServerDeviceCommunicationManagerBuilder,
+};
 use buttplug_transport_websocket_tungstenite::{
   ButtplugWebsocketClientTransport, ButtplugWebsocketServerTransportBuilder,
 };
+use futures::future::BoxFuture;
 use once_cell::sync::OnceCell;
-use tokio::sync::broadcast::Sender;
 // Device communication manager setup gets its own module because the includes and platform
 // specifics are such a mess.
 
@@ -42,6 +56,12 @@ pub fn setup_server_device_comm_managers(
     command_manager_builder.requires_keepalive(false);
     server_builder.comm_manager(command_manager_builder);
   }
+  if args.use_bluetooth_le_advertisements() {
+    info!("Including Bluetooth LE Advertisement Device Comm Manager Support");
+    server_builder.comm_manager(BleAdvertisementCommunicationManagerBuilder::new(Arc::new(
+      BtleplugAdvertisementAdapter::default(),
+    )));
+  }
   if args.use_lovense_connect() {
     info!("Including Lovense Connect App Support");
     server_builder.comm_manager(LovenseConnectServiceCommunicationManagerBuilder::default());
@@ -57,7 +77,8 @@ pub fn setup_server_device_comm_managers(
     }
     if args.use_serial_port() {
       info!("Including Serial Port Support");
-      server_builder.comm_manager(SerialPortCommunicationManagerBuilder::default());
+      server_builder
+        .comm_manager(SerialPortCommunicationManagerBuilder::default().ports(args.serial_ports()));
     }
     if args.use_hid() {
       info!("Including Hid Support");
@@ -71,33 +92,99 @@ pub fn setup_server_device_comm_managers(
         server_builder.comm_manager(XInputDeviceCommunicationManagerBuilder::default());
       }
     }
+    #[cfg(target_os = "linux")]
+    {
+      use buttplug_server_hwmgr_evdev::EvdevCommunicationManagerBuilder;
+      if args.use_evdev() {
+        info!("Including Evdev Gamepad Support");
+        server_builder.comm_manager(EvdevCommunicationManagerBuilder::default());
+      }
+    }
   }
   if args.use_device_websocket_server() {
     info!("Including Websocket Server Device Support");
-    let mut builder =
-      WebsocketServerDeviceCommunicationManagerBuilder::default().listen_on_all_interfaces(true);
+    let mut builder = WebsocketServerDeviceCommunicationManagerBuilder::default()
+      .listen_on_all_interfaces(true)
+      .allowed_identifiers(args.device_websocket_allowed_identifiers());
     if let Some(port) = args.device_websocket_server_port() {
       builder = builder.server_port(port);
     }
+    if let Some(timeout) = args.device_websocket_handshake_timeout_ms() {
+      builder = builder.handshake_timeout(Duration::from_millis(timeout as u64));
+    }
+    if let Some(secret) = args.device_websocket_secret() {
+      builder = builder.shared_secret(secret);
+    }
     server_builder.comm_manager(builder);
   }
-}
 
-pub async fn reset_buttplug_server(
-  options: &EngineOptions,
-  device_manager: &Arc<ServerDeviceManager>,
-  sender: &Sender<ButtplugRemoteServerEvent>,
-) -> Result<ButtplugRemoteServer, IntifaceEngineError> {
-  match ButtplugServerBuilder::with_shared_device_manager(device_manager.clone())
-    .name(options.server_name())
-    .max_ping_time(options.max_ping_time())
-    .finish()
-  {
-    Ok(server) => Ok(ButtplugRemoteServer::new(server, &Some(sender.clone()))),
-    Err(e) => {
-      error!("Error starting server: {:?}", e);
-      Err(IntifaceEngineError::ButtplugServerError(e))
+  if !args.device_websocket_client_urls().is_empty() || args.use_device_websocket_client_mdns() {
+    info!("Including Websocket Client Device Support");
+    let mut builder = WebsocketClientDeviceCommunicationManagerBuilder::default()
+      .device_urls(args.device_websocket_client_urls())
+      .use_mdns(args.use_device_websocket_client_mdns())
+      .allowed_identifiers(args.device_websocket_allowed_identifiers());
+    if let Some(timeout) = args.device_websocket_handshake_timeout_ms() {
+      builder = builder.handshake_timeout(Duration::from_millis(timeout as u64));
     }
+    if let Some(secret) = args.device_websocket_secret() {
+      builder = builder.shared_secret(secret);
+    }
+    server_builder.comm_manager(builder);
+  }
+
+  if !args.network_device_addresses().is_empty() {
+    info!("Including Network Device Support");
+    let mut builder = NetworkCommunicationManagerBuilder::default();
+    for address in args.network_device_addresses() {
+      match address.parse::<NetworkSpecifier>() {
+        Ok(specifier) => builder = builder.add_device(&specifier),
+        Err(e) => warn!("Ignoring network device address {}: {}", address, e),
+      }
+    }
+    server_builder.comm_manager(builder);
+  }
+
+  if let Some(address) = args.mqtt_broker_address() {
+    info!("Including MQTT Device Support");
+    let (host, port) = match address.rsplit_once(':') {
+      Some((host, port)) => match port.parse() {
+        Ok(port) => (host, port),
+        Err(_) => {
+          warn!("Invalid MQTT broker port {}, using 1883", port);
+          (host, 1883)
+        }
+      },
+      None => (address.as_str(), 1883),
+    };
+    let mut builder = MqttCommunicationManagerBuilder::default().broker(host, port);
+    if let Some(topic) = args.mqtt_discovery_topic() {
+      builder = builder.discovery_topic(topic);
+    }
+    if let (Some(username), Some(password)) = (args.mqtt_username(), args.mqtt_password()) {
+      builder = builder.credentials(username, password);
+    }
+    server_builder.comm_manager(builder);
+  }
+
+  for manager_name in args.continuous_scan_managers() {
+    server_builder.scan_policy(manager_name, ScanPolicy::new(ScanMode::Continuous, None));
+  }
+  for (manager_name, limit) in args.scan_duration_limits_ms() {
+    if args.continuous_scan_managers().contains(manager_name) {
+      warn!(
+        "{} scans continuously, ignoring its scan duration limit",
+        manager_name
+      );
+      continue;
+    }
+    server_builder.scan_policy(
+      manager_name,
+      ScanPolicy::new(
+        ScanMode::OnDemand,
+        Some(Duration::from_millis(*limit as u64)),
+      ),
+    );
   }
 }
 
@@ -123,7 +210,23 @@ pub async fn setup_buttplug_server(
     )
   };
 
+  if let Some(path) = options.protocol_script_path() {
+    info!("Loading protocol scripts from {}", path);
+    for factory in load_protocol_scripts(Path::new(path))
+      .map_err(|e| IntifaceEngineError::ButtplugError(e.into()))?
+    {
+      dm_builder.protocol_factory(factory);
+    }
+  }
   setup_server_device_comm_managers(options, &mut dm_builder);
+  dm_builder.arbitration_policy(options.output_arbitration_policy());
+  if let Some(path) = options.session_recording_path() {
+    info!("Recording session to {}", path);
+    dm_builder.recorder(
+      SessionRecorder::to_file(Path::new(path))
+        .map_err(IntifaceEngineError::ButtplugServerError)?,
+    );
+  }
   let mut server_builder = ButtplugServerBuilder::new(
     dm_builder
       .finish()
@@ -154,13 +257,14 @@ pub async fn setup_buttplug_server(
   }
 }
 
-pub async fn run_server(
+/// Waits for a client to connect, returning the loop that serves its session.
+pub async fn connect_server(
   server: &ButtplugRemoteServer,
   options: &EngineOptions,
-) -> Result<(), ButtplugServerConnectorError> {
+) -> Result<BoxFuture<'static, ()>, ButtplugServerConnectorError> {
   if let Some(port) = options.websocket_port() {
     server
-      .start(ButtplugRemoteServerConnector::<
+      .connect(ButtplugRemoteServerConnector::<
         _,
         ButtplugServerJSONSerializer,
       >::new(
@@ -172,7 +276,7 @@ pub async fn run_server(
       .await
   } else if let Some(addr) = options.websocket_client_address() {
     server
-      .start(ButtplugRemoteServerConnector::<
+      .connect(ButtplugRemoteServerConnector::<
         _,
         ButtplugServerJSONSerializer,
       >::new(
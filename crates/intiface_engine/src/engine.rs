@@ -8,13 +8,14 @@
 use crate::{
   ButtplugRemoteServer, ButtplugRepeater,
   backdoor_server::BackdoorServer,
-  buttplug_server::{reset_buttplug_server, run_server, setup_buttplug_server},
+  buttplug_server::{connect_server, setup_buttplug_server},
   error::IntifaceEngineError,
   frontend::{
     Frontend, frontend_external_event_loop, frontend_server_event_loop,
     process_messages::EngineMessage,
   },
   mdns::IntifaceMdns,
+  metrics_server::IntifaceMetricsServer,
   options::EngineOptions,
   remote_server::ButtplugRemoteServerEvent,
   rest_server::IntifaceRestServer,
@@ -110,6 +111,20 @@ impl IntifaceEngine {
 
     // Set up Engine (if in engine mode)
 
+    if let Some(metrics_port) = options.metrics_port() {
+      let stop_token = self.stop_token.child_token();
+      tokio::spawn(async move {
+        select! {
+          _ = stop_token.cancelled() => {}
+          res = IntifaceMetricsServer::run(metrics_port) => {
+            if let Err(e) = res {
+              error!("Error running metrics endpoint: {:?}", e);
+            }
+          }
+        }
+      });
+    }
+
     // At this point we will have received and validated options.
 
     // Hang out until those listeners get sick of listening.
@@ -140,7 +155,7 @@ impl IntifaceEngine {
       return Ok(());
     }
 
-    let mut server = ButtplugRemoteServer::new(server, &None);
+    let server = ButtplugRemoteServer::new(server, &None);
 
     if let Some(config_path) = options.user_device_config_path() {
       let stream = server.event_stream();
@@ -157,7 +172,8 @@ impl IntifaceEngine {
                   name: _,
                   display_name: _,
                   needs_keepalive: _,
-                } => {
+                }
+                | ButtplugRemoteServerEvent::UserDeviceConfigChanged { identifier: _ } => {
                   if let Ok(config_str) = save_user_config(&dcm) {
                     // Should probably at least log if we fail to write the config file
                     if let Err(e) = fs::write(&Path::new(&config_path), config_str).await {
@@ -183,23 +199,43 @@ impl IntifaceEngine {
     }
 
     loop {
-      let session_connection_token = CancellationToken::new();
-      info!("Starting server");
+      info!("Waiting for client connection");
 
       // Let everything spin up, then try crashing.
 
       #[cfg(debug_assertions)]
       maybe_crash_main_thread(options);
 
-      let mut exit_requested = false;
       select! {
         _ = self.stop_token.cancelled() => {
           info!("Owner requested process exit, exiting.");
-          exit_requested = true;
+          break;
         }
-        result = run_server(&server, options) => {
+        result = connect_server(&server, options) => {
           match result {
-            Ok(_) => info!("Connection dropped, restarting stay open loop."),
+            Ok(session) => {
+              let frontend = frontend.clone();
+              let session = async move {
+                session.await;
+                info!("Client connection dropped.");
+                if let Some(frontend) = &frontend {
+                  frontend.send(EngineMessage::ClientDisconnected {}).await;
+                }
+              };
+              // When we're the websocket server, keep listening so other clients can connect while
+              // this one is running. When we connect out to a client, there's only ever one.
+              if options.websocket_port().is_some() {
+                tokio::spawn(session);
+              } else {
+                select! {
+                  _ = self.stop_token.cancelled() => {
+                    info!("Owner requested process exit, exiting.");
+                    break;
+                  }
+                  _ = session => {}
+                }
+              }
+            }
             Err(e) => {
               error!("{}", format!("Process Error: {:?}", e));
 
@@ -212,24 +248,9 @@ impl IntifaceEngine {
           }
         }
       };
-      match server.disconnect().await {
-        Ok(_) => {
-          info!("Client forcefully disconnected from server.");
-          if let Some(frontend) = &frontend {
-            frontend.send(EngineMessage::ClientDisconnected {}).await;
-          }
-        }
-        Err(_) => info!("Client already disconnected from server."),
-      };
-      session_connection_token.cancel();
-      if exit_requested {
-        info!("Breaking out of event loop in order to exit");
-        break;
-      }
-      // We're not exiting, rebuild our server.
-      let dm = server.server().device_manager();
-      server = reset_buttplug_server(options, &dm, server.event_sender()).await?;
-      info!("Server connection dropped, restarting");
+    }
+    if let Err(e) = server.disconnect().await {
+      error!("Error disconnecting clients: {:?}", e);
     }
     info!("Shutting down server...");
     if let Err(e) = server.shutdown().await {
@@ -100,6 +100,61 @@ pub async fn frontend_server_event_loop(
                 .send(EngineMessage::DeviceDisconnected{index: device_id})
                 .await;
             }
+            ButtplugRemoteServerEvent::DeviceReconnected { index: device_id } => {
+              info!("Device Reconnected: {}", device_id);
+              frontend
+                .send(EngineMessage::DeviceReconnected{index: device_id})
+                .await;
+            }
+            ButtplugRemoteServerEvent::SafetyViolation(violation) => {
+              frontend
+                .send(EngineMessage::DeviceSafetyLimitApplied {
+                  identifier: violation.identifier().clone(),
+                  feature_index: violation.feature_index(),
+                  output_type: violation.output_type(),
+                  kind: violation.kind().clone(),
+                })
+                .await;
+            }
+            ButtplugRemoteServerEvent::EmergencyStopStateChanged { engaged } => {
+              info!("Emergency stop engaged: {}", engaged);
+              frontend
+                .send(EngineMessage::EmergencyStopStateChanged { engaged })
+                .await;
+            }
+            ButtplugRemoteServerEvent::DeviceConnectionFailed {
+              name,
+              address,
+              protocols,
+              error_code,
+              error_message,
+            } => {
+              info!(
+                "Device connection failed: {} ({}) - {}",
+                name, address, error_message
+              );
+              frontend
+                .send(EngineMessage::DeviceConnectionFailed {
+                  name,
+                  address,
+                  protocols,
+                  error_code,
+                  error: error_message,
+                })
+                .await;
+            }
+            ButtplugRemoteServerEvent::HardwareManagerStatusChanged { name, status } => {
+              info!("Hardware manager {} status: {:?}", name, status);
+              frontend
+                .send(EngineMessage::HardwareManagerStatusChanged { name, status })
+                .await;
+            }
+            ButtplugRemoteServerEvent::UserDeviceConfigChanged { identifier } => {
+              info!("User device config changed: {:?}", identifier);
+              frontend
+                .send(EngineMessage::UserDeviceConfigChanged { identifier })
+                .await;
+            }
           },
           None => {
             info!("Lost connection with main thread, breaking.");
@@ -5,6 +5,8 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
+use buttplug_core::message::{DeviceConnectionErrorCode, HardwareManagerStatus, OutputType};
+use buttplug_server::device::SafetyViolationKind;
 use buttplug_server_device_config::UserDeviceIdentifier;
 use serde::{Deserialize, Serialize};
 
@@ -36,6 +38,32 @@ pub enum EngineMessage {
   DeviceDisconnected {
     index: u32,
   },
+  DeviceReconnected {
+    index: u32,
+  },
+  DeviceSafetyLimitApplied {
+    identifier: UserDeviceIdentifier,
+    feature_index: u32,
+    output_type: OutputType,
+    kind: SafetyViolationKind,
+  },
+  EmergencyStopStateChanged {
+    engaged: bool,
+  },
+  DeviceConnectionFailed {
+    name: String,
+    address: String,
+    protocols: Vec<String>,
+    error_code: DeviceConnectionErrorCode,
+    error: String,
+  },
+  HardwareManagerStatusChanged {
+    name: String,
+    status: HardwareManagerStatus,
+  },
+  UserDeviceConfigChanged {
+    identifier: UserDeviceIdentifier,
+  },
   ClientRejected {
     reason: String,
   },
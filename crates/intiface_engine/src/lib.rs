@@ -14,6 +14,7 @@ mod engine;
 mod error;
 mod frontend;
 mod mdns;
+mod metrics_server;
 mod options;
 mod remote_server;
 mod repeater;
@@ -23,5 +24,7 @@ pub use engine::IntifaceEngine;
 pub use error::*;
 pub use frontend::{EngineMessage, Frontend, IntifaceMessage};
 pub use options::{EngineOptions, EngineOptionsBuilder, EngineOptionsExternal};
-pub use remote_server::{ButtplugRemoteServer, ButtplugServerConnectorError};
+pub use remote_server::{
+  ButtplugRemoteServer, ButtplugRemoteServerEvent, ButtplugServerConnectorError,
+};
 pub use repeater::ButtplugRepeater;
@@ -0,0 +1,114 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use axum::{Router, extract::State, routing::get};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use once_cell::sync::OnceCell;
+use tokio::net::TcpListener;
+
+/// Counter of client connections that completed a handshake.
+pub const CLIENT_CONNECTIONS: &str = "intiface_client_connections_total";
+/// Counter of messages received from clients.
+pub const CLIENT_MESSAGES: &str = "intiface_client_messages_total";
+/// Counter of client messages that resulted in an error reply.
+pub const CLIENT_MESSAGE_ERRORS: &str = "intiface_client_message_errors_total";
+
+// The metrics recorder is process global, so it can only be installed once even if the engine is
+// restarted.
+static PROMETHEUS_HANDLE: OnceCell<PrometheusHandle> = OnceCell::new();
+
+async fn render_metrics(State(handle): State<PrometheusHandle>) -> String {
+  handle.render()
+}
+
+pub struct IntifaceMetricsServer {}
+
+impl IntifaceMetricsServer {
+  /// Installs the Prometheus recorder and serves its text exposition format on `/metrics`.
+  pub async fn run(port: u16) -> Result<(), io::Error> {
+    let handle = PROMETHEUS_HANDLE
+      .get_or_try_init(|| PrometheusBuilder::new().install_recorder())
+      .map_err(io::Error::other)?
+      .clone();
+    let app = Router::new()
+      .route("/metrics", get(render_metrics))
+      .with_state(handle.clone());
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    info!("Metrics endpoint now listening on {:?}", addr);
+    let listener = TcpListener::bind(addr).await?;
+    // Without the exporter's own listener, histogram upkeep is up to us.
+    let upkeep = tokio::spawn(async move {
+      loop {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        handle.run_upkeep();
+      }
+    });
+    let result = axum::serve(listener, app.into_make_service()).await;
+    upkeep.abort();
+    result
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+  };
+
+  async fn get_metrics(port: u16) -> String {
+    // The endpoint comes up asynchronously, so retry until it accepts connections.
+    let mut stream = loop {
+      match TcpStream::connect(("127.0.0.1", port)).await {
+        Ok(stream) => break stream,
+        Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+      }
+    };
+    stream
+      .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+      .await
+      .expect("Test, assuming infallible.");
+    let mut response = String::new();
+    stream
+      .read_to_string(&mut response)
+      .await
+      .expect("Test, assuming infallible.");
+    response
+  }
+
+  #[tokio::test]
+  async fn test_metrics_endpoint() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+      .and_then(|listener| listener.local_addr())
+      .expect("Should be able to find a free port")
+      .port();
+    let server = tokio::spawn(IntifaceMetricsServer::run(port));
+    let response = tokio::time::timeout(Duration::from_secs(5), async {
+      loop {
+        // Metrics recorded before the recorder is installed are lost, so keep bumping the counter
+        // until it shows up.
+        metrics::counter!(CLIENT_CONNECTIONS).increment(1);
+        let response = get_metrics(port).await;
+        if response.contains(CLIENT_CONNECTIONS) {
+          return response;
+        }
+      }
+    })
+    .await
+    .expect("Metrics endpoint should report recorded metrics");
+    server.abort();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(
+      response.contains(&format!("# TYPE {CLIENT_CONNECTIONS} counter")),
+      "{response}"
+    );
+  }
+}
@@ -5,7 +5,9 @@
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
 
+use buttplug_server::device::OutputArbitrationPolicy;
 use getset::{CopyGetters, Getters};
+use std::collections::HashMap;
 
 #[derive(CopyGetters, Getters, Default, Debug, Clone)]
 pub struct EngineOptions {
@@ -16,6 +18,8 @@ pub struct EngineOptions {
   #[getset(get = "pub")]
   user_device_config_path: Option<String>,
   #[getset(get = "pub")]
+  protocol_script_path: Option<String>,
+  #[getset(get = "pub")]
   server_name: String,
   #[getset(get_copy = "pub")]
   websocket_use_all_interfaces: bool,
@@ -32,7 +36,11 @@ pub struct EngineOptions {
   #[getset(get_copy = "pub")]
   use_bluetooth_le: bool,
   #[getset(get_copy = "pub")]
+  use_bluetooth_le_advertisements: bool,
+  #[getset(get_copy = "pub")]
   use_serial_port: bool,
+  #[getset(get = "pub")]
+  serial_ports: Vec<String>,
   #[getset(get_copy = "pub")]
   use_hid: bool,
   #[getset(get_copy = "pub")]
@@ -42,11 +50,39 @@ pub struct EngineOptions {
   #[getset(get_copy = "pub")]
   use_xinput: bool,
   #[getset(get_copy = "pub")]
+  use_evdev: bool,
+  #[getset(get_copy = "pub")]
   use_lovense_connect: bool,
   #[getset(get_copy = "pub")]
   use_device_websocket_server: bool,
   #[getset(get_copy = "pub")]
   device_websocket_server_port: Option<u16>,
+  #[getset(get = "pub")]
+  device_websocket_client_urls: Vec<String>,
+  #[getset(get_copy = "pub")]
+  use_device_websocket_client_mdns: bool,
+  #[getset(get = "pub")]
+  network_device_addresses: Vec<String>,
+  #[getset(get_copy = "pub")]
+  device_websocket_handshake_timeout_ms: Option<u32>,
+  #[getset(get = "pub")]
+  device_websocket_secret: Option<String>,
+  #[getset(get = "pub")]
+  device_websocket_allowed_identifiers: Vec<String>,
+  #[getset(get = "pub")]
+  mqtt_broker_address: Option<String>,
+  #[getset(get = "pub")]
+  mqtt_discovery_topic: Option<String>,
+  #[getset(get = "pub")]
+  mqtt_username: Option<String>,
+  #[getset(get = "pub")]
+  mqtt_password: Option<String>,
+  #[getset(get = "pub")]
+  continuous_scan_managers: Vec<String>,
+  #[getset(get = "pub")]
+  scan_duration_limits_ms: HashMap<String, u32>,
+  #[getset(get_copy = "pub")]
+  output_arbitration_policy: OutputArbitrationPolicy,
   #[getset(get_copy = "pub")]
   crash_main_thread: bool,
   #[getset(get_copy = "pub")]
@@ -63,6 +99,10 @@ pub struct EngineOptions {
   repeater_remote_address: Option<String>,
   #[getset(get_copy = "pub")]
   rest_api_port: Option<u16>,
+  #[getset(get = "pub")]
+  session_recording_path: Option<String>,
+  #[getset(get_copy = "pub")]
+  metrics_port: Option<u16>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -70,6 +110,7 @@ pub struct EngineOptionsExternal {
   pub device_config_json: Option<String>,
   pub user_device_config_json: Option<String>,
   pub user_device_config_path: Option<String>,
+  pub protocol_script_path: Option<String>,
   pub server_name: String,
   pub websocket_use_all_interfaces: bool,
   pub websocket_port: Option<u16>,
@@ -78,14 +119,30 @@ pub struct EngineOptionsExternal {
   pub frontend_in_process_channel: bool,
   pub max_ping_time: u32,
   pub use_bluetooth_le: bool,
+  pub use_bluetooth_le_advertisements: bool,
   pub use_serial_port: bool,
+  pub serial_ports: Vec<String>,
   pub use_hid: bool,
   pub use_lovense_dongle_serial: bool,
   pub use_lovense_dongle_hid: bool,
   pub use_xinput: bool,
+  pub use_evdev: bool,
   pub use_lovense_connect: bool,
   pub use_device_websocket_server: bool,
   pub device_websocket_server_port: Option<u16>,
+  pub device_websocket_client_urls: Vec<String>,
+  pub use_device_websocket_client_mdns: bool,
+  pub network_device_addresses: Vec<String>,
+  pub device_websocket_handshake_timeout_ms: Option<u32>,
+  pub device_websocket_secret: Option<String>,
+  pub device_websocket_allowed_identifiers: Vec<String>,
+  pub mqtt_broker_address: Option<String>,
+  pub mqtt_discovery_topic: Option<String>,
+  pub mqtt_username: Option<String>,
+  pub mqtt_password: Option<String>,
+  pub continuous_scan_managers: Vec<String>,
+  pub scan_duration_limits_ms: HashMap<String, u32>,
+  pub output_arbitration_policy: OutputArbitrationPolicy,
   pub crash_main_thread: bool,
   pub crash_task_thread: bool,
   pub broadcast_server_mdns: bool,
@@ -94,6 +151,8 @@ pub struct EngineOptionsExternal {
   pub repeater_local_port: Option<u16>,
   pub repeater_remote_address: Option<String>,
   pub rest_api_port: Option<u16>,
+  pub session_recording_path: Option<String>,
+  pub metrics_port: Option<u16>,
 }
 
 impl From<EngineOptionsExternal> for EngineOptions {
@@ -102,6 +161,7 @@ impl From<EngineOptionsExternal> for EngineOptions {
       device_config_json: other.device_config_json,
       user_device_config_json: other.user_device_config_json,
       user_device_config_path: other.user_device_config_path,
+      protocol_script_path: other.protocol_script_path,
       server_name: other.server_name,
       websocket_use_all_interfaces: other.websocket_use_all_interfaces,
       websocket_port: other.websocket_port,
@@ -110,14 +170,30 @@ impl From<EngineOptionsExternal> for EngineOptions {
       frontend_in_process_channel: other.frontend_in_process_channel,
       max_ping_time: other.max_ping_time,
       use_bluetooth_le: other.use_bluetooth_le,
+      use_bluetooth_le_advertisements: other.use_bluetooth_le_advertisements,
       use_serial_port: other.use_serial_port,
+      serial_ports: other.serial_ports,
       use_hid: other.use_hid,
       use_lovense_dongle_serial: other.use_lovense_dongle_serial,
       use_lovense_dongle_hid: other.use_lovense_dongle_hid,
       use_xinput: other.use_xinput,
+      use_evdev: other.use_evdev,
       use_lovense_connect: other.use_lovense_connect,
       use_device_websocket_server: other.use_device_websocket_server,
       device_websocket_server_port: other.device_websocket_server_port,
+      device_websocket_client_urls: other.device_websocket_client_urls,
+      use_device_websocket_client_mdns: other.use_device_websocket_client_mdns,
+      network_device_addresses: other.network_device_addresses,
+      device_websocket_handshake_timeout_ms: other.device_websocket_handshake_timeout_ms,
+      device_websocket_secret: other.device_websocket_secret,
+      device_websocket_allowed_identifiers: other.device_websocket_allowed_identifiers,
+      mqtt_broker_address: other.mqtt_broker_address,
+      mqtt_discovery_topic: other.mqtt_discovery_topic,
+      mqtt_username: other.mqtt_username,
+      mqtt_password: other.mqtt_password,
+      continuous_scan_managers: other.continuous_scan_managers,
+      scan_duration_limits_ms: other.scan_duration_limits_ms,
+      output_arbitration_policy: other.output_arbitration_policy,
       crash_main_thread: other.crash_main_thread,
       crash_task_thread: other.crash_task_thread,
       broadcast_server_mdns: other.broadcast_server_mdns,
@@ -126,6 +202,8 @@ impl From<EngineOptionsExternal> for EngineOptions {
       repeater_local_port: other.repeater_local_port,
       repeater_remote_address: other.repeater_remote_address,
       rest_api_port: other.rest_api_port,
+      session_recording_path: other.session_recording_path,
+      metrics_port: other.metrics_port,
     }
   }
 }
@@ -151,6 +229,11 @@ impl EngineOptionsBuilder {
     self
   }
 
+  pub fn protocol_script_path(&mut self, value: &str) -> &mut Self {
+    self.options.protocol_script_path = Some(value.to_owned());
+    self
+  }
+
   pub fn server_name(&mut self, value: &str) -> &mut Self {
     self.options.server_name = value.to_owned();
     self
@@ -184,11 +267,21 @@ impl EngineOptionsBuilder {
     self
   }
 
+  pub fn use_bluetooth_le_advertisements(&mut self, value: bool) -> &mut Self {
+    self.options.use_bluetooth_le_advertisements = value;
+    self
+  }
+
   pub fn use_serial_port(&mut self, value: bool) -> &mut Self {
     self.options.use_serial_port = value;
     self
   }
 
+  pub fn add_serial_port(&mut self, port: &str) -> &mut Self {
+    self.options.serial_ports.push(port.to_owned());
+    self
+  }
+
   pub fn use_hid(&mut self, value: bool) -> &mut Self {
     self.options.use_hid = value;
     self
@@ -209,6 +302,11 @@ impl EngineOptionsBuilder {
     self
   }
 
+  pub fn use_evdev(&mut self, value: bool) -> &mut Self {
+    self.options.use_evdev = value;
+    self
+  }
+
   pub fn use_lovense_connect(&mut self, value: bool) -> &mut Self {
     self.options.use_lovense_connect = value;
     self
@@ -244,6 +342,82 @@ impl EngineOptionsBuilder {
     self
   }
 
+  pub fn add_device_websocket_client_url(&mut self, url: &str) -> &mut Self {
+    self
+      .options
+      .device_websocket_client_urls
+      .push(url.to_owned());
+    self
+  }
+
+  pub fn use_device_websocket_client_mdns(&mut self, value: bool) -> &mut Self {
+    self.options.use_device_websocket_client_mdns = value;
+    self
+  }
+
+  pub fn device_websocket_handshake_timeout_ms(&mut self, value: u32) -> &mut Self {
+    self.options.device_websocket_handshake_timeout_ms = Some(value);
+    self
+  }
+
+  pub fn device_websocket_secret(&mut self, secret: &str) -> &mut Self {
+    self.options.device_websocket_secret = Some(secret.to_owned());
+    self
+  }
+
+  pub fn add_device_websocket_allowed_identifier(&mut self, identifier: &str) -> &mut Self {
+    self
+      .options
+      .device_websocket_allowed_identifiers
+      .push(identifier.to_owned());
+    self
+  }
+
+  pub fn mqtt_broker_address(&mut self, address: &str) -> &mut Self {
+    self.options.mqtt_broker_address = Some(address.to_owned());
+    self
+  }
+
+  pub fn mqtt_discovery_topic(&mut self, topic: &str) -> &mut Self {
+    self.options.mqtt_discovery_topic = Some(topic.to_owned());
+    self
+  }
+
+  pub fn mqtt_credentials(&mut self, username: &str, password: &str) -> &mut Self {
+    self.options.mqtt_username = Some(username.to_owned());
+    self.options.mqtt_password = Some(password.to_owned());
+    self
+  }
+
+  pub fn add_continuous_scan_manager(&mut self, manager_name: &str) -> &mut Self {
+    self
+      .options
+      .continuous_scan_managers
+      .push(manager_name.to_owned());
+    self
+  }
+
+  pub fn scan_duration_limit_ms(&mut self, manager_name: &str, value: u32) -> &mut Self {
+    self
+      .options
+      .scan_duration_limits_ms
+      .insert(manager_name.to_owned(), value);
+    self
+  }
+
+  pub fn output_arbitration_policy(&mut self, policy: OutputArbitrationPolicy) -> &mut Self {
+    self.options.output_arbitration_policy = policy;
+    self
+  }
+
+  pub fn add_network_device_address(&mut self, address: &str) -> &mut Self {
+    self
+      .options
+      .network_device_addresses
+      .push(address.to_owned());
+    self
+  }
+
   pub fn max_ping_time(&mut self, value: u32) -> &mut Self {
     self.options.max_ping_time = value;
     self
@@ -279,6 +453,16 @@ impl EngineOptionsBuilder {
     self
   }
 
+  pub fn session_recording_path(&mut self, path: &str) -> &mut Self {
+    self.options.session_recording_path = Some(path.to_owned());
+    self
+  }
+
+  pub fn metrics_port(&mut self, port: u16) -> &mut Self {
+    self.options.metrics_port = Some(port);
+    self
+  }
+
   pub fn finish(&mut self) -> EngineOptions {
     self.options.clone()
   }
@@ -4,19 +4,28 @@
 //
 // Licensed under the BSD 3-Clause license. See LICENSE file in the project root
 // for full license information.
+use crate::metrics_server::{CLIENT_CONNECTIONS, CLIENT_MESSAGE_ERRORS, CLIENT_MESSAGES};
 use buttplug_core::{
   connector::ButtplugConnector,
   errors::{ButtplugError, ButtplugHandshakeError},
-  message::{ButtplugMessageSpecVersion, ButtplugServerMessageV4},
+  message::{
+    ButtplugMessageSpecVersion, ButtplugServerMessageV4, DeviceConnectionErrorCode,
+    HardwareManagerStatus,
+  },
   util::stream::convert_broadcast_receiver_to_stream,
 };
 use buttplug_server::{
   ButtplugServer, ButtplugServerBuilder,
+  device::SafetyViolation,
   message::{ButtplugClientMessageVariant, ButtplugServerMessageVariant},
 };
 use buttplug_server_device_config::UserDeviceIdentifier;
 use dashmap::DashSet;
-use futures::{FutureExt, Stream, StreamExt, future::Future, pin_mut, select};
+use futures::{
+  FutureExt, Stream, StreamExt,
+  future::{BoxFuture, Future},
+  pin_mut, select,
+};
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -42,6 +51,30 @@ pub enum ButtplugRemoteServerEvent {
   DeviceRemoved {
     index: u32,
   },
+  /// Sent after the DeviceAdded event for a device the server automatically reconnected.
+  DeviceReconnected {
+    index: u32,
+  },
+  SafetyViolation(SafetyViolation),
+  EmergencyStopStateChanged {
+    engaged: bool,
+  },
+  DeviceConnectionFailed {
+    name: String,
+    address: String,
+    protocols: Vec<String>,
+    error_code: DeviceConnectionErrorCode,
+    error_message: String,
+  },
+  HardwareManagerStatusChanged {
+    name: String,
+    status: HardwareManagerStatus,
+  },
+  /// A device's user configuration changed while the server was running, e.g. a client stored a
+  /// preset.
+  UserDeviceConfigChanged {
+    identifier: UserDeviceIdentifier,
+  },
   //DeviceCommand(ButtplugDeviceCommandMessageUnion)
 }
 
@@ -51,8 +84,14 @@ pub enum ButtplugServerConnectorError {
   ConnectorError(String),
 }
 
+/// Serves [ButtplugServer] connections over connectors.
+///
+/// Each connection gets its own session, created from [ButtplugRemoteServer::server] with
+/// [ButtplugServer::new_session], so any number of clients can be connected at once. Outputs they
+/// share are arbitrated by the device manager.
 #[derive(Getters)]
 pub struct ButtplugRemoteServer {
+  /// Server that connection sessions are created from. Device events are forwarded from it.
   #[getset(get = "pub")]
   server: Arc<ButtplugServer>,
   #[getset(get = "pub")]
@@ -66,6 +105,7 @@ async fn run_device_event_stream(
 ) {
   let server_receiver = server.server_version_event_stream();
   let known_indexes = DashSet::<u32>::default();
+  let reconnecting_indexes = DashSet::<u32>::default();
 
   pin_mut!(server_receiver);
   loop {
@@ -75,6 +115,41 @@ async fn run_device_event_stream(
         break;
       }
       Some(msg) => {
+        if let ButtplugServerMessageV4::EmergencyStopState(state) = &msg
+          && remote_event_sender
+            .send(ButtplugRemoteServerEvent::EmergencyStopStateChanged {
+              engaged: state.engaged(),
+            })
+            .is_err()
+        {
+          debug!("No remote event listeners, dropping emergency stop state event.");
+        }
+        if let ButtplugServerMessageV4::DeviceConnectionFailed(failure) = &msg
+          && remote_event_sender
+            .send(ButtplugRemoteServerEvent::DeviceConnectionFailed {
+              name: failure.device_name().clone(),
+              address: failure.device_address().clone(),
+              protocols: failure.protocols().clone(),
+              error_code: failure.error_code(),
+              error_message: failure.error_message().clone(),
+            })
+            .is_err()
+        {
+          debug!("No remote event listeners, dropping device connection failure event.");
+        }
+        if let ButtplugServerMessageV4::HardwareManagerStatus(status) = &msg
+          && remote_event_sender
+            .send(ButtplugRemoteServerEvent::HardwareManagerStatusChanged {
+              name: status.manager_name().clone(),
+              status: status.status(),
+            })
+            .is_err()
+        {
+          debug!("No remote event listeners, dropping hardware manager status event.");
+        }
+        if let ButtplugServerMessageV4::DeviceReconnected(reconnected) = &msg {
+          reconnecting_indexes.insert(reconnected.device_index());
+        }
         if let ButtplugServerMessageV4::DeviceList(dl) = msg
           && remote_event_sender.receiver_count() > 0
         {
@@ -96,6 +171,17 @@ async fn run_device_event_stream(
                 );
               }
               known_indexes.insert(da.1.device_index());
+              if reconnecting_indexes.remove(&da.1.device_index()).is_some()
+                && remote_event_sender
+                  .send(ButtplugRemoteServerEvent::DeviceReconnected {
+                    index: da.1.device_index(),
+                  })
+                  .is_err()
+              {
+                error!(
+                  "Cannot send event to owner, dropping and assuming local server thread has exited."
+                );
+              }
             }
           }
           let indexes = known_indexes.clone();
@@ -146,6 +232,7 @@ async fn run_server<ConnectorType>(
         }
         Some(client_message) => {
           trace!("Got message from connector: {:?}", client_message);
+          metrics::counter!(CLIENT_MESSAGES).increment(1);
           let server_clone = server.clone();
           let connected = server_clone.connected();
           let connector_clone = shared_connector.clone();
@@ -156,6 +243,7 @@ async fn run_server<ConnectorType>(
               Ok(ret_msg) => {
                 // Only send event if we just connected. Sucks to check it on every message but the boolean check should be quick.
                 if !connected && server_clone.connected() {
+                  metrics::counter!(CLIENT_CONNECTIONS).increment(1);
                   if remote_event_sender_clone.receiver_count() > 0
                     && remote_event_sender_clone.send(ButtplugRemoteServerEvent::ClientConnected(server_clone.client_name().unwrap_or("Buttplug Client (No name specified)".to_owned()).clone())).is_err() {
                       error!("Cannot send event to owner, dropping and assuming local server thread has exited.");
@@ -166,6 +254,7 @@ async fn run_server<ConnectorType>(
                 }
               },
               Err(err_msg) => {
+                metrics::counter!(CLIENT_MESSAGE_ERRORS).increment(1);
                 if connector_clone.send(err_msg).await.is_err() {
                   error!("Cannot send reply to server, dropping and assuming remote server thread has exited.");
                 }
@@ -262,6 +351,42 @@ impl ButtplugRemoteServer {
         }
       });
     }
+    {
+      let safety_stream = server.device_manager().safety_event_stream();
+      let event_sender = event_sender.clone();
+      tokio::spawn(async move {
+        pin_mut!(safety_stream);
+        while let Some(violation) = safety_stream.next().await {
+          if event_sender.receiver_count() > 0
+            && event_sender
+              .send(ButtplugRemoteServerEvent::SafetyViolation(violation))
+              .is_err()
+          {
+            error!(
+              "Cannot send event to owner, dropping and assuming local server thread has exited."
+            );
+          }
+        }
+      });
+    }
+    {
+      let user_config_stream = server.device_manager().user_config_event_stream();
+      let event_sender = event_sender.clone();
+      tokio::spawn(async move {
+        pin_mut!(user_config_stream);
+        while let Some(identifier) = user_config_stream.next().await {
+          if event_sender.receiver_count() > 0
+            && event_sender
+              .send(ButtplugRemoteServerEvent::UserDeviceConfigChanged { identifier })
+              .is_err()
+          {
+            error!(
+              "Cannot send event to owner, dropping and assuming local server thread has exited."
+            );
+          }
+        }
+      });
+    }
     Self {
       event_sender,
       server,
@@ -273,10 +398,30 @@ impl ButtplugRemoteServer {
     convert_broadcast_receiver_to_stream(self.event_sender.subscribe())
   }
 
+  /// Connects the connector, then serves the connection until it closes or the server is
+  /// disconnected.
   pub fn start<ConnectorType>(
     &self,
-    mut connector: ConnectorType,
+    connector: ConnectorType,
   ) -> impl Future<Output = Result<(), ButtplugServerConnectorError>> + use<ConnectorType>
+  where
+    ConnectorType:
+      ButtplugConnector<ButtplugServerMessageVariant, ButtplugClientMessageVariant> + 'static,
+  {
+    let connect_fut = self.connect(connector);
+    async move {
+      connect_fut.await?.await;
+      Ok(())
+    }
+  }
+
+  /// Connects the connector and creates a session for the connection, returning the loop that
+  /// serves it. Other connections can be accepted while the loop runs.
+  pub fn connect<ConnectorType>(
+    &self,
+    mut connector: ConnectorType,
+  ) -> impl Future<Output = Result<BoxFuture<'static, ()>, ButtplugServerConnectorError>>
+  + use<ConnectorType>
   where
     ConnectorType:
       ButtplugConnector<ButtplugServerMessageVariant, ButtplugClientMessageVariant> + 'static,
@@ -293,18 +438,23 @@ impl ButtplugRemoteServer {
         .connect(connector_sender)
         .await
         .map_err(|e| ButtplugServerConnectorError::ConnectorError(format!("{:?}", e)))?;
-      run_server(
-        server,
-        event_sender,
-        connector,
-        connector_receiver,
-        disconnect_notifier,
+      let session = server
+        .new_session()
+        .map_err(|e| ButtplugServerConnectorError::ConnectorError(format!("{:?}", e)))?;
+      Ok(
+        run_server(
+          Arc::new(session),
+          event_sender,
+          connector,
+          connector_receiver,
+          disconnect_notifier,
+        )
+        .boxed(),
       )
-      .await;
-      Ok(())
     }
   }
 
+  /// Disconnects every connection being served.
   pub async fn disconnect(&self) -> Result<(), ButtplugError> {
     self.disconnect_notifier.notify_waiters();
     Ok(())
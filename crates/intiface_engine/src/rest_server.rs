@@ -22,8 +22,11 @@ use buttplug_client::{
   device::{ClientDeviceFeature, ClientDeviceOutputCommand},
 };
 use buttplug_client_in_process::ButtplugInProcessClientConnectorBuilder;
-use buttplug_core::message::{DeviceFeature, OutputType};
-use buttplug_server::ButtplugServer;
+use buttplug_core::{
+  errors::ButtplugError,
+  message::{DeviceFeature, OutputType},
+};
+use buttplug_server::{ButtplugServer, device::ServerDeviceManager};
 use futures::{Stream, StreamExt};
 use serde::Serialize;
 use thiserror::Error;
@@ -35,6 +38,8 @@ enum IntifaceRestError {
   JsonRejection(JsonRejection),
   #[error("Library Error: {0}")]
   ButtplugClientError(ButtplugClientError),
+  #[error("Server Error: {0}")]
+  ButtplugServerError(ButtplugError),
   #[error("Device index {0} does not refer to a currently connected device.")]
   InvalidDevice(u32),
   #[error("Device index {0} feature index {1} does not refer to a valid device feature.")]
@@ -152,6 +157,20 @@ async fn stop_all_devices(
     .map_err(IntifaceRestError::ButtplugClientError)
 }
 
+async fn emergency_stop(
+  State(device_manager): State<Arc<ServerDeviceManager>>,
+) -> Result<(), IntifaceRestError> {
+  device_manager
+    .emergency_stop()
+    .await
+    .map(|_| ())
+    .map_err(IntifaceRestError::ButtplugServerError)
+}
+
+async fn reset_emergency_stop(State(device_manager): State<Arc<ServerDeviceManager>>) {
+  device_manager.reset_emergency_stop();
+}
+
 async fn stop_device(
   State(client): State<Arc<ButtplugClient>>,
   Path(index): Path<u32>,
@@ -273,6 +292,8 @@ async fn server_sse(
 
 impl IntifaceRestServer {
   pub async fn run(port: u16, server: ButtplugServer) -> Result<(), io::Error> {
+    // The emergency stop isn't part of the client API, so drive it through the device manager.
+    let device_manager = server.device_manager();
     let connector = ButtplugInProcessClientConnectorBuilder::default()
       .server(server)
       .finish();
@@ -312,7 +333,13 @@ impl IntifaceRestServer {
          */
         .route("/events", get(server_sse))
         //.route("/devices/{*index}/vibrate", post(set_feature_vibrate_speed))
-        .with_state(Arc::new(client)),
+        .with_state(Arc::new(client))
+        .merge(
+          Router::new()
+            .route("/emergency-stop", put(emergency_stop))
+            .route("/emergency-stop/reset", put(reset_emergency_stop))
+            .with_state(device_manager),
+        ),
     );
 
     // write address like this to not make typos
@@ -0,0 +1,98 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+use buttplug_client::{
+  ButtplugClient, connector::ButtplugRemoteClientConnector,
+  serializer::ButtplugClientJSONSerializer,
+};
+use buttplug_core::connector::transport::stream::ButtplugStreamTransport;
+use buttplug_server::{
+  connector::ButtplugRemoteServerConnector, message::serializer::ButtplugServerJSONSerializer,
+};
+use futures::{StreamExt, pin_mut};
+use intiface_engine::{ButtplugRemoteServer, ButtplugRemoteServerEvent};
+use std::time::Duration;
+use tokio::{sync::mpsc, task::JoinHandle, time::timeout};
+
+/// Connects a client to the server over a pair of stream transports, returning the client and the
+/// task serving its session.
+async fn connect_client(
+  server: &ButtplugRemoteServer,
+  name: &str,
+) -> (ButtplugClient, JoinHandle<()>) {
+  let (client_sender, server_receiver) = mpsc::channel(256);
+  let (server_sender, client_receiver) = mpsc::channel(256);
+  let session = server
+    .connect(ButtplugRemoteServerConnector::<
+      _,
+      ButtplugServerJSONSerializer,
+    >::new(ButtplugStreamTransport::new(
+      server_sender,
+      server_receiver,
+    )))
+    .await
+    .expect("Test, assuming infallible.");
+  let session = tokio::spawn(session);
+  let client = ButtplugClient::new(name);
+  client
+    .connect(ButtplugRemoteClientConnector::<
+      _,
+      ButtplugClientJSONSerializer,
+    >::new(ButtplugStreamTransport::new(
+      client_sender,
+      client_receiver,
+    )))
+    .await
+    .expect("Test, assuming infallible.");
+  (client, session)
+}
+
+#[tokio::test]
+async fn test_remote_server_multiple_connections() {
+  let server = ButtplugRemoteServer::default();
+  let events = server.event_stream();
+  pin_mut!(events);
+  let (client, session) = connect_client(&server, "First Client").await;
+  let (other_client, other_session) = connect_client(&server, "Second Client").await;
+  assert!(client.connected());
+  assert!(other_client.connected());
+
+  let mut connected_clients = vec![];
+  while connected_clients.len() < 2 {
+    if let Some(ButtplugRemoteServerEvent::ClientConnected(name)) =
+      timeout(Duration::from_secs(1), events.next())
+        .await
+        .expect("Both connections should be reported")
+    {
+      connected_clients.push(name);
+    }
+  }
+  connected_clients.sort();
+  assert_eq!(connected_clients, ["First Client", "Second Client"]);
+
+  // Each connection is its own session, so one client leaving doesn't affect the other.
+  client
+    .disconnect()
+    .await
+    .expect("Test, assuming infallible.");
+  timeout(Duration::from_secs(1), session)
+    .await
+    .expect("Session should end when its client disconnects")
+    .expect("Test, assuming infallible.");
+  assert!(other_client.connected());
+  assert!(other_client.start_scanning().await.is_ok());
+  assert!(other_client.stop_scanning().await.is_ok());
+
+  server
+    .disconnect()
+    .await
+    .expect("Test, assuming infallible.");
+  timeout(Duration::from_secs(1), other_session)
+    .await
+    .expect("Disconnecting the server should end every session")
+    .expect("Test, assuming infallible.");
+}
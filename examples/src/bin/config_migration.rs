@@ -0,0 +1,97 @@
+// Buttplug Rust Source Code File - See https://buttplug.io for more info.
+//
+// Copyright 2016-2026 Nonpolynomial Labs LLC. All rights reserved.
+//
+// Licensed under the BSD 3-Clause license. See LICENSE file in the project root
+// for full license information.
+
+// Previews what happens to a user device config when the base device config changes.
+//
+// Usage: config_migration <user config> [old base config] [new base config] [--write <output>]
+//
+// If the old base config is left out, features can only be matched by the output types that were
+// customized. If the new base config is left out, the device config built into the library is
+// used. Use "-" to skip the old base config while still passing a new one.
+
+use anyhow::{Context, bail};
+use buttplug_server_device_config::{
+  DeviceMigrationResult,
+  FeatureMigrationResult,
+  migrate_user_config,
+};
+use std::fs;
+
+fn read_optional_config(path: Option<&String>) -> anyhow::Result<Option<String>> {
+  match path.map(|x| x.as_str()) {
+    None | Some("-") => Ok(None),
+    Some(path) => Ok(Some(
+      fs::read_to_string(path).with_context(|| format!("Cannot read {path}"))?,
+    )),
+  }
+}
+
+fn main() -> anyhow::Result<()> {
+  let mut args: Vec<String> = std::env::args().skip(1).collect();
+  let output_path = if let Some(pos) = args.iter().position(|x| x == "--write") {
+    let Some(path) = args.get(pos + 1).cloned() else {
+      bail!("--write requires an output path");
+    };
+    args.drain(pos..=pos + 1);
+    Some(path)
+  } else {
+    None
+  };
+  let Some(user_config_path) = args.first() else {
+    bail!(
+      "Usage: config_migration <user config> [old base config] [new base config] [--write <output>]"
+    );
+  };
+
+  let user_config = fs::read_to_string(user_config_path)
+    .with_context(|| format!("Cannot read {user_config_path}"))?;
+  let old_base_config = read_optional_config(args.get(1))?;
+  let new_base_config = read_optional_config(args.get(2))?;
+
+  let migration = migrate_user_config(&user_config, &old_base_config, &new_base_config)?;
+
+  for device in migration.devices() {
+    let identifier = device.identifier();
+    print!(
+      "{} {} ({}): ",
+      identifier.protocol(),
+      identifier.identifier().as_deref().unwrap_or("default"),
+      identifier.address()
+    );
+    match device.result() {
+      DeviceMigrationResult::Unchanged => println!("base device unchanged"),
+      DeviceMigrationResult::Migrated {
+        old_base_id,
+        new_base_id,
+      } => println!("migrated from base device {old_base_id} to {new_base_id}"),
+      DeviceMigrationResult::Dropped(reason) => println!("DROPPED - {reason}"),
+    }
+    for feature in device.features() {
+      let index = feature.index().map_or("-".to_owned(), |x| x.to_string());
+      let result = match feature.result() {
+        FeatureMigrationResult::Unchanged => "unchanged".to_owned(),
+        FeatureMigrationResult::Migrated {
+          old_base_id,
+          new_base_id,
+        } => format!("migrated from base feature {old_base_id} to {new_base_id}"),
+        FeatureMigrationResult::Conflicted(reason) => format!("CONFLICT - {reason}"),
+        FeatureMigrationResult::Dropped(reason) => format!("DROPPED - {reason}"),
+        FeatureMigrationResult::Added => "added".to_owned(),
+      };
+      println!("  [{index}] {}: {result}", feature.id());
+    }
+  }
+
+  if !migration.has_changes() {
+    println!("No changes needed.");
+  } else if let Some(path) = output_path {
+    fs::write(&path, migration.user_config()).with_context(|| format!("Cannot write {path}"))?;
+    println!("Migrated user config written to {path}");
+  }
+
+  Ok(())
+}